@@ -1,6 +1,8 @@
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::time::Duration;
 
+use crate::repositories::catalogo_repository::UMBRAL_TRIGRAMAS;
+
 #[derive(Clone)]
 pub struct DatabaseConfig {
     pub pool: PgPool,
@@ -11,6 +13,15 @@ impl DatabaseConfig {
         let pool = PgPoolOptions::new()
             .max_connections(10)
             .acquire_timeout(Duration::from_secs(5))
+            // Umbral del operador <% de la búsqueda por trigramas del catálogo
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    sqlx::query(&format!("SET pg_trgm.word_similarity_threshold = {}", UMBRAL_TRIGRAMAS))
+                        .execute(conn)
+                        .await
+                        .map(|_| ())
+                })
+            })
             .connect(database_url)
             .await?;
 
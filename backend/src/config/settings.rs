@@ -1,20 +1,52 @@
 use std::env;
 
+/// Longitud mínima del secreto HMAC (256 bits)
+const JWT_SECRET_MIN_LENGTH: usize = 32;
+
+/// Secretos de ejemplo publicados en la documentación: cumplen el largo mínimo pero
+/// cualquiera podría firmar tokens con ellos
+const JWT_SECRETOS_DE_EJEMPLO: &[&str] = &[
+    "tu_secreto_super_seguro_aqui_cambiar_en_produccion",
+    "super_secret_dev_key_change_in_production_123456789",
+];
+
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub database_url: String,
-    pub jwt_secret: String,
+    pub jwt: JwtSettings,
     pub port: u16,
     pub host: String,
 }
 
+/// Configuración de las claves de firma de JWT.
+///
+/// - `JWT_SECRET` (obligatorio): secreto HS256, identificado con `JWT_SECRET_KID` (por defecto "principal").
+///   Los tokens sin `kid` emitidos antes de la rotación se verifican con este secreto.
+/// - `JWT_ALGORITHM`: algoritmo de firma activo: HS256 (defecto), EdDSA o RS256.
+/// - `JWT_PRIVATE_KEY_FILE` y `JWT_KID`: clave privada PEM (PKCS#8) y su kid, obligatorios con EdDSA/RS256.
+/// - `JWT_SECRETOS_ANTERIORES`: secretos HS256 retirados, solo para verificar: "kid1:secreto1,kid2:secreto2".
+/// - `JWT_CLAVES_ANTERIORES`: claves públicas PEM retiradas (Ed25519, EC P-256/P-384 o RSA), solo para verificar:
+///   "kid3:/ruta/clave_publica.pem,...".
+///
+/// Cada kid identifica una sola clave: el servidor no inicia si se repite.
+#[derive(Debug, Clone)]
+pub struct JwtSettings {
+    pub secreto: String,
+    pub secreto_kid: String,
+    pub algoritmo: String,
+    pub clave_privada_archivo: Option<String>,
+    pub kid: Option<String>,
+    pub secretos_anteriores: Vec<(String, String)>,
+    pub claves_anteriores: Vec<(String, String)>,
+}
+
 impl Settings {
-    pub fn new() -> Result<Self, env::VarError> {
+    pub fn new() -> Result<Self, String> {
         dotenv::dotenv().ok();
 
         Ok(Self {
-            database_url: env::var("DATABASE_URL")?,
-            jwt_secret: env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret".to_string()),
+            database_url: env::var("DATABASE_URL").map_err(|_| "DATABASE_URL no está configurada".to_string())?,
+            jwt: JwtSettings::new()?,
             port: env::var("PORT")
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
@@ -28,6 +60,56 @@ impl Settings {
     }
 }
 
+impl JwtSettings {
+    pub fn new() -> Result<Self, String> {
+        let secreto = env::var("JWT_SECRET")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or("JWT_SECRET no está configurada. Genera una con: openssl rand -base64 32".to_string())?;
+
+        if secreto.len() < JWT_SECRET_MIN_LENGTH {
+            return Err(format!(
+                "JWT_SECRET debe tener al menos {} caracteres",
+                JWT_SECRET_MIN_LENGTH
+            ));
+        }
+        if JWT_SECRETOS_DE_EJEMPLO.contains(&secreto.trim()) {
+            return Err("JWT_SECRET es el valor de ejemplo de la documentación. Genera uno con: openssl rand -base64 32"
+                .to_string());
+        }
+
+        Ok(Self {
+            secreto,
+            secreto_kid: env::var("JWT_SECRET_KID").unwrap_or_else(|_| "principal".to_string()),
+            algoritmo: env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            clave_privada_archivo: env::var("JWT_PRIVATE_KEY_FILE").ok().filter(|v| !v.is_empty()),
+            kid: env::var("JWT_KID").ok().filter(|v| !v.is_empty()),
+            secretos_anteriores: Self::parse_lista("JWT_SECRETOS_ANTERIORES")?,
+            claves_anteriores: Self::parse_lista("JWT_CLAVES_ANTERIORES")?,
+        })
+    }
+
+    // Lista "kid:valor,kid:valor"
+    fn parse_lista(variable: &str) -> Result<Vec<(String, String)>, String> {
+        let Ok(valor) = env::var(variable) else {
+            return Ok(Vec::new());
+        };
+
+        valor
+            .split(',')
+            .map(str::trim)
+            .filter(|entrada| !entrada.is_empty())
+            .map(|entrada| {
+                entrada
+                    .split_once(':')
+                    .map(|(kid, valor)| (kid.trim().to_string(), valor.trim().to_string()))
+                    .filter(|(kid, valor)| !kid.is_empty() && !valor.is_empty())
+                    .ok_or(format!("{} tiene una entrada inválida (formato kid:valor)", variable))
+            })
+            .collect()
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self::new().expect("Failed to load settings")
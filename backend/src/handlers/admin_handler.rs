@@ -1,5 +1,5 @@
 use axum::{
-    extract::{State, Path, Query},
+    extract::{ConnectInfo, State, Path, Query},
     http::{StatusCode, HeaderMap},
     Json,
     response::IntoResponse,
@@ -7,8 +7,13 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, FromRow};
 use chrono::NaiveDateTime;
+use std::net::SocketAddr;
 
-use crate::services::AuthService;
+use crate::middleware::rate_limit::ip_cliente;
+use crate::models::{ActualizarApiKeyRequest, CrearApiKeyRequest, ImpersonarUsuarioRequest};
+use crate::services::{ApiKeyService, AuthService, DosFactoresService};
+use crate::utils::password::{self, ParametrosHash};
+use crate::utils::validacion::{validar_email, ErroresCampo, PoliticaPassword};
 
 // ==================== RESPONSES ====================
 
@@ -23,6 +28,8 @@ pub struct ApiResponse<T> {
 pub struct ErrorResponse {
     pub success: bool,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errores: Option<ErroresCampo>,
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -40,6 +47,27 @@ pub struct UsuarioAdmin {
     pub ultima_conexion: Option<NaiveDateTime>,
 }
 
+/// Conteo de cuentas por esquema de hash de contraseña
+#[derive(Debug, Serialize, FromRow)]
+pub struct HashesPorRol {
+    pub rol: String,
+    pub total: i64,
+    pub argon2id: i64,
+    pub bcrypt: i64,
+    pub otros: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReporteHashesPassword {
+    pub total: i64,
+    pub argon2id: i64,
+    /// Cuentas en el esquema legado; se migran al iniciar sesión
+    pub bcrypt: i64,
+    pub otros: i64,
+    pub porcentaje_migrado: f64,
+    pub por_rol: Vec<HashesPorRol>,
+}
+
 // ==================== REQUESTS ====================
 
 #[derive(Debug, Deserialize)]
@@ -78,6 +106,7 @@ fn verify_super_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorRespons
             Json(ErrorResponse {
                 success: false,
                 message: e,
+                errores: None,
             }),
         )
     })?;
@@ -88,6 +117,7 @@ fn verify_super_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorRespons
             Json(ErrorResponse {
                 success: false,
                 message: "Acceso denegado. Solo super_admin puede acceder a este recurso".to_string(),
+                errores: None,
             }),
         ));
     }
@@ -113,6 +143,7 @@ fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorRes
                 Json(ErrorResponse {
                     success: false,
                     message: "Token no proporcionado".to_string(),
+                    errores: None,
                 }),
             )
         })
@@ -211,6 +242,7 @@ pub async fn listar_usuarios_handler(
             Json(ErrorResponse {
                 success: false,
                 message: format!("Error al listar usuarios: {}", err),
+                errores: None,
             }),
         )),
     }
@@ -234,6 +266,7 @@ pub async fn actualizar_usuario_admin_handler(
             Json(ErrorResponse {
                 success: false,
                 message: "No puedes desactivar tu propia cuenta".to_string(),
+                errores: None,
             }),
         ));
     }
@@ -249,6 +282,7 @@ pub async fn actualizar_usuario_admin_handler(
             Json(ErrorResponse {
                 success: false,
                 message: "No se proporcionaron campos para actualizar".to_string(),
+                errores: None,
             }),
         ));
     }
@@ -261,6 +295,7 @@ pub async fn actualizar_usuario_admin_handler(
                 Json(ErrorResponse {
                     success: false,
                     message: "Rol inválido".to_string(),
+                    errores: None,
                 }),
             ));
         }
@@ -281,6 +316,7 @@ pub async fn actualizar_usuario_admin_handler(
             Json(ErrorResponse {
                 success: false,
                 message: format!("Error al buscar usuario: {}", e),
+                errores: None,
             }),
         )
     })?;
@@ -290,6 +326,7 @@ pub async fn actualizar_usuario_admin_handler(
         Json(ErrorResponse {
             success: false,
             message: "Usuario no encontrado".to_string(),
+            errores: None,
         }),
     ))?;
 
@@ -329,6 +366,7 @@ pub async fn actualizar_usuario_admin_handler(
             Json(ErrorResponse {
                 success: false,
                 message: format!("Error al actualizar usuario: {}", err),
+                errores: None,
             }),
         )),
     }
@@ -351,28 +389,29 @@ pub async fn crear_administrador_handler(
             Json(ErrorResponse {
                 success: false,
                 message: "Rol inválido. Debe ser 'administrador' o 'super_admin'".to_string(),
+                errores: None,
             }),
         ));
     }
 
-    // Validar email
-    if !payload.email.contains('@') || !payload.email.contains('.') {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                success: false,
-                message: "Email inválido".to_string(),
-            }),
-        ));
+    // Validar email y contraseña según la política configurada
+    let mut errores = ErroresCampo::new();
+    if let Err(e) = validar_email(&payload.email) {
+        errores.agregar("email", e);
     }
+    let politica = PoliticaPassword::cargar();
+    errores.agregar_todos(
+        "password",
+        politica.validar(&payload.password, &[&payload.nombre, &payload.apellido, &payload.email]),
+    );
 
-    // Validar contraseña
-    if payload.password.len() < 6 {
+    if let Err(errores) = errores.into_result() {
         return Err((
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse {
                 success: false,
-                message: "La contraseña debe tener al menos 6 caracteres".to_string(),
+                message: errores.to_string(),
+                errores: Some(errores),
             }),
         ));
     }
@@ -388,6 +427,7 @@ pub async fn crear_administrador_handler(
                 Json(ErrorResponse {
                     success: false,
                     message: format!("Error al verificar email: {}", e),
+                    errores: None,
                 }),
             )
         })?;
@@ -398,18 +438,20 @@ pub async fn crear_administrador_handler(
             Json(ErrorResponse {
                 success: false,
                 message: "El email ya está registrado".to_string(),
+                errores: None,
             }),
         ));
     }
 
     // Hash de la contraseña
-    let password_hash = bcrypt::hash(&payload.password, bcrypt::DEFAULT_COST)
+    let password_hash = password::hash_password(&payload.password, &ParametrosHash::cargar())
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
                     success: false,
                     message: format!("Error al encriptar contraseña: {}", e),
+                    errores: None,
                 }),
             )
         })?;
@@ -441,7 +483,263 @@ pub async fn crear_administrador_handler(
             Json(ErrorResponse {
                 success: false,
                 message: format!("Error al crear administrador: {}", err),
+                errores: None,
+            }),
+        )),
+    }
+}
+
+/// DELETE /api/admin/usuarios/:id/2fa
+/// Restablecer la autenticación en dos pasos de un usuario (solo super_admin)
+pub async fn restablecer_dos_factores_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id_usuario): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let admin_id = verify_super_admin(token)?;
+
+    match DosFactoresService::restablecer_por_admin(&pool, admin_id, id_usuario, Some(ip_cliente(&headers, Some(addr.ip())))).await {
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::<()> {
+                success: true,
+                data: None,
+                message: Some("Autenticación en dos pasos restablecida. El usuario deberá configurarla nuevamente".to_string()),
+            }),
+        )),
+        Err(err) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+                errores: None,
+            }),
+        )),
+    }
+}
+
+/// POST /api/admin/usuarios/:id/impersonar
+/// Emitir un token de suplantación de corta duración para ver la cuenta de un cliente (solo super_admin)
+pub async fn impersonar_usuario_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id_usuario): Path<i32>,
+    Json(payload): Json<ImpersonarUsuarioRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let admin_id = verify_super_admin(token)?;
+
+    match AuthService::emitir_token_impersonacion(&pool, admin_id, id_usuario, payload, Some(ip_cliente(&headers, Some(addr.ip())))).await {
+        Ok(response) => Ok((
+            StatusCode::CREATED,
+            Json(ApiResponse {
+                success: true,
+                message: Some(format!(
+                    "Suplantación de {} válida por {} minutos",
+                    response.usuario.email,
+                    response.expira_en_segundos / 60
+                )),
+                data: Some(response),
+            }),
+        )),
+        Err(err) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+                errores: None,
+            }),
+        )),
+    }
+}
+
+/// GET /api/admin/api-keys
+/// Listar las API keys de integraciones (solo super_admin)
+pub async fn listar_api_keys_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_super_admin(token)?;
+
+    match ApiKeyService::listar(&pool).await {
+        Ok(keys) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(keys),
+            message: None,
+        })),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+                errores: None,
+            }),
+        )),
+    }
+}
+
+/// POST /api/admin/api-keys
+/// Crear una API key; la clave completa solo se devuelve en esta respuesta (solo super_admin)
+pub async fn crear_api_key_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearApiKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let admin_id = verify_super_admin(token)?;
+
+    match ApiKeyService::crear(&pool, admin_id, payload, Some(ip_cliente(&headers, Some(addr.ip())))).await {
+        Ok(response) => Ok((
+            StatusCode::CREATED,
+            Json(ApiResponse {
+                success: true,
+                data: Some(response),
+                message: Some("API key creada. Guárdala ahora: no se volverá a mostrar".to_string()),
+            }),
+        )),
+        Err(err) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+                errores: None,
             }),
         )),
     }
 }
+
+/// PUT /api/admin/api-keys/{id}
+/// Cambiar nombre, scopes, IPs permitidas, límite o expiración de una API key (solo super_admin)
+pub async fn actualizar_api_key_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id_api_key): Path<i32>,
+    Json(payload): Json<ActualizarApiKeyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let admin_id = verify_super_admin(token)?;
+
+    match ApiKeyService::actualizar(&pool, admin_id, id_api_key, payload, Some(ip_cliente(&headers, Some(addr.ip())))).await {
+        Ok(api_key) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(api_key),
+            message: Some("API key actualizada".to_string()),
+        })),
+        Err(err) => {
+            let status = if err.contains("no encontrada") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            Err((
+                status,
+                Json(ErrorResponse {
+                    success: false,
+                    message: err,
+                    errores: None,
+                }),
+            ))
+        }
+    }
+}
+
+/// DELETE /api/admin/api-keys/{id}
+/// Revocar una API key (solo super_admin)
+pub async fn revocar_api_key_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id_api_key): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let admin_id = verify_super_admin(token)?;
+
+    match ApiKeyService::revocar(&pool, admin_id, id_api_key, Some(ip_cliente(&headers, Some(addr.ip())))).await {
+        Ok(()) => Ok(Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some("API key revocada".to_string()),
+        })),
+        Err(err) => {
+            let status = if err.contains("no encontrada") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            Err((
+                status,
+                Json(ErrorResponse {
+                    success: false,
+                    message: err,
+                    errores: None,
+                }),
+            ))
+        }
+    }
+}
+
+/// GET /api/admin/seguridad/hashes-password
+/// Cuántas cuentas siguen con hash bcrypt (solo super_admin)
+pub async fn reporte_hashes_password_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_super_admin(token)?;
+
+    let por_rol = sqlx::query_as::<_, HashesPorRol>(
+        "SELECT rol::TEXT as rol,
+                COUNT(*) as total,
+                COUNT(*) FILTER (WHERE contrasena LIKE '$argon2id$%') as argon2id,
+                COUNT(*) FILTER (WHERE contrasena LIKE '$2_$%') as bcrypt,
+                COUNT(*) FILTER (WHERE contrasena NOT LIKE '$argon2id$%' AND contrasena NOT LIKE '$2_$%') as otros
+         FROM usuario
+         GROUP BY rol
+         ORDER BY rol"
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                success: false,
+                message: format!("Error al generar reporte: {}", e),
+                errores: None,
+            }),
+        )
+    })?;
+
+    let total: i64 = por_rol.iter().map(|r| r.total).sum();
+    let argon2id: i64 = por_rol.iter().map(|r| r.argon2id).sum();
+    let bcrypt: i64 = por_rol.iter().map(|r| r.bcrypt).sum();
+    let otros: i64 = por_rol.iter().map(|r| r.otros).sum();
+
+    let porcentaje_migrado = if total > 0 {
+        ((argon2id as f64 / total as f64) * 10_000.0).round() / 100.0
+    } else {
+        100.0
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(ReporteHashesPassword {
+                total,
+                argon2id,
+                bcrypt,
+                otros,
+                porcentaje_migrado,
+                por_rol,
+            }),
+            message: None,
+        }),
+    ))
+}
@@ -0,0 +1,325 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::models::armado_pc::{
+    AsignarComponenteRequest, CrearArmadoRequest, SugerenciasArmadoQuery, SugerenciasArmadoRequest,
+    ValidarArmadoRequest,
+};
+use crate::services::armado_service::ArmadoError;
+use crate::services::{ArmadoService, AuthService};
+
+// ==================== RESPONSES ====================
+
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// ==================== HELPER FUNCTIONS ====================
+
+// Extraer ID de usuario del token JWT
+fn extract_user_id(headers: &HeaderMap) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    let token = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                }),
+            )
+        })?;
+
+    let claims = AuthService::verify_token(token).map_err(|_| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                success: false,
+                message: "Token inválido o expirado".to_string(),
+            }),
+        )
+    })?;
+
+    Ok(claims.sub)
+}
+
+fn armado_error_response(err: ArmadoError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        ArmadoError::NoEncontrado(_) => StatusCode::NOT_FOUND,
+        ArmadoError::Invalida(_) => StatusCode::BAD_REQUEST,
+        ArmadoError::Conflicto(_) => StatusCode::CONFLICT,
+        ArmadoError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    armado_error_response(e.into())
+}
+
+fn ranura_requerida(ranura: Option<String>) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    ranura.filter(|r| !r.trim().is_empty()).ok_or_else(|| {
+        armado_error_response(ArmadoError::Invalida("Indica la ranura (ranura=cpu, gpu, ...)".to_string()))
+    })
+}
+
+// ==================== PÚBLICOS ====================
+
+/// GET /api/armados/ranuras
+/// Ranuras de un armado y la categoría que admite cada una
+pub async fn get_ranuras_handler(
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let ranuras = ArmadoService::ranuras(&pool).await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(ranuras),
+            message: None,
+        }),
+    ))
+}
+
+/// POST /api/armados/validar
+/// Verificar la compatibilidad de un armado sin guardarlo
+pub async fn validar_armado_handler(
+    State(pool): State<PgPool>,
+    Json(payload): Json<ValidarArmadoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let validado = ArmadoService::validar(&pool, &payload.componentes)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(validado),
+            message: None,
+        }),
+    ))
+}
+
+/// POST /api/armados/sugerencias
+/// Productos compatibles para una ranura de un armado sin guardar
+pub async fn sugerencias_armado_handler(
+    State(pool): State<PgPool>,
+    Json(payload): Json<SugerenciasArmadoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let sugerencias = ArmadoService::sugerencias(&pool, &payload.componentes, &payload.ranura, payload.limit)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(sugerencias),
+            message: None,
+        }),
+    ))
+}
+
+// ==================== ARMADOS DEL USUARIO ====================
+
+// GET /api/armados
+pub async fn get_armados_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let armados = ArmadoService::listar(&pool, id_usuario).await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(armados),
+            message: None,
+        }),
+    ))
+}
+
+// POST /api/armados
+pub async fn crear_armado_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearArmadoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let armado = ArmadoService::crear(&pool, id_usuario, payload)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse {
+            success: true,
+            data: Some(armado),
+            message: Some("Armado creado exitosamente".to_string()),
+        }),
+    ))
+}
+
+// GET /api/armados/:id
+pub async fn get_armado_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_armado): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let armado = ArmadoService::obtener(&pool, id_usuario, id_armado)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(armado),
+            message: None,
+        }),
+    ))
+}
+
+// DELETE /api/armados/:id
+pub async fn eliminar_armado_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_armado): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    ArmadoService::eliminar(&pool, id_usuario, id_armado)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some("Armado eliminado exitosamente".to_string()),
+        }),
+    ))
+}
+
+// PUT /api/armados/:id/componentes/:ranura
+pub async fn asignar_componente_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path((id_armado, ranura)): Path<(i32, String)>,
+    Json(payload): Json<AsignarComponenteRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let armado = ArmadoService::asignar_componente(&pool, id_usuario, id_armado, &ranura, payload)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(armado),
+            message: Some("Componente actualizado".to_string()),
+        }),
+    ))
+}
+
+// DELETE /api/armados/:id/componentes/:ranura
+pub async fn quitar_componente_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path((id_armado, ranura)): Path<(i32, String)>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let armado = ArmadoService::quitar_componente(&pool, id_usuario, id_armado, &ranura)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(armado),
+            message: Some("Componente eliminado del armado".to_string()),
+        }),
+    ))
+}
+
+// GET /api/armados/:id/sugerencias?ranura=gpu&limit=10
+pub async fn get_sugerencias_armado_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_armado): Path<i32>,
+    Query(query): Query<SugerenciasArmadoQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+    let ranura = ranura_requerida(query.ranura)?;
+
+    let sugerencias = ArmadoService::sugerencias_armado(&pool, id_usuario, id_armado, &ranura, query.limit)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(sugerencias),
+            message: None,
+        }),
+    ))
+}
+
+// POST /api/armados/:id/carrito
+pub async fn agregar_armado_carrito_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_armado): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id_usuario = extract_user_id(&headers)?;
+
+    let carrito = ArmadoService::agregar_al_carrito(&pool, id_usuario, id_armado)
+        .await
+        .map_err(armado_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(carrito),
+            message: Some("Armado agregado al carrito".to_string()),
+        }),
+    ))
+}
@@ -1,14 +1,23 @@
 use axum::{
-    extract::State,
-    http::{StatusCode, HeaderMap},
+    extract::{ConnectInfo, Query, State},
+    http::{header, StatusCode, HeaderMap},
     Json,
     response::IntoResponse,
 };
 use serde::Serialize;
 use sqlx::PgPool;
+use std::net::SocketAddr;
 
-use crate::models::{LoginRequest, RegisterRequest};
-use crate::services::AuthService;
+use crate::models::{
+    LoginRequest, RegisterRequest, RecuperarPasswordRequest, RestablecerPasswordRequest,
+    ActivarDosFactoresRequest, DesactivarDosFactoresRequest, VerificarDosFactoresRequest,
+    ExportarDatosQuery, SolicitarEliminacionRequest,
+};
+use crate::middleware::rate_limit::ip_cliente;
+use crate::services::{AuthService, DatosPersonalesService, DosFactoresService};
+use crate::services::auth_service::AuthError;
+use crate::utils::jwt;
+use crate::utils::validacion::ErroresCampo;
 
 // ==================== RESPONSES ====================
 
@@ -23,6 +32,30 @@ pub struct ApiResponse<T> {
 pub struct ErrorResponse {
     pub success: bool,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errores: Option<ErroresCampo>,
+}
+
+// Convertir un AuthError en respuesta, incluyendo los errores por campo si los hay
+fn auth_error_response(status: StatusCode, err: AuthError) -> (StatusCode, Json<ErrorResponse>) {
+    match err {
+        AuthError::Validacion(errores) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                success: false,
+                message: errores.to_string(),
+                errores: Some(errores),
+            }),
+        ),
+        AuthError::Mensaje(message) => (
+            status,
+            Json(ErrorResponse {
+                success: false,
+                message,
+                errores: None,
+            }),
+        ),
+    }
 }
 
 // ==================== HANDLERS ====================
@@ -41,22 +74,21 @@ pub async fn register_handler(
                 message: None,
             }),
         )),
-        Err(err) => Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                success: false,
-                message: err,
-            }),
-        )),
+        Err(err) => Err(auth_error_response(StatusCode::BAD_REQUEST, err)),
     }
 }
 
 // POST /api/auth/login
 pub async fn login_handler(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    match AuthService::login(&pool, payload).await {
+    // IP resuelta con los proxies de confianza: la detección de anomalías agrupa por ella
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match AuthService::login(&pool, payload, Some(ip_cliente)).await {
         Ok(response) => Ok((
             StatusCode::OK,
             Json(ApiResponse {
@@ -70,6 +102,7 @@ pub async fn login_handler(
             Json(ErrorResponse {
                 success: false,
                 message: err,
+                errores: None,
             }),
         )),
     }
@@ -97,6 +130,7 @@ pub async fn get_current_user_handler(
                 Json(ErrorResponse {
                     success: false,
                     message: "Token no proporcionado".to_string(),
+                    errores: None,
                 }),
             )
         })?;
@@ -115,6 +149,7 @@ pub async fn get_current_user_handler(
             Json(ErrorResponse {
                 success: false,
                 message: err,
+                errores: None,
             }),
         )),
     }
@@ -134,6 +169,16 @@ pub async fn logout_handler() -> impl IntoResponse {
     )
 }
 
+// GET /api/auth/jwks
+// Claves públicas para que otros servicios verifiquen nuestros tokens (RFC 7517)
+pub async fn jwks_handler() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(jwt::jwks()),
+    )
+}
+
 // ==================== PERFIL ====================
 
 use serde::Deserialize;
@@ -169,6 +214,7 @@ pub async fn actualizar_perfil_handler(
                 Json(ErrorResponse {
                     success: false,
                     message: "Token no proporcionado".to_string(),
+                    errores: None,
                 }),
             )
         })?;
@@ -187,6 +233,7 @@ pub async fn actualizar_perfil_handler(
             Json(ErrorResponse {
                 success: false,
                 message: err,
+                errores: None,
             }),
         )),
     }
@@ -221,6 +268,7 @@ pub async fn cambiar_password_handler(
                 Json(ErrorResponse {
                     success: false,
                     message: "Token no proporcionado".to_string(),
+                    errores: None,
                 }),
             )
         })?;
@@ -234,12 +282,374 @@ pub async fn cambiar_password_handler(
                 message: Some("Contraseña actualizada exitosamente".to_string()),
             }),
         )),
+        Err(err) => Err(auth_error_response(StatusCode::BAD_REQUEST, err)),
+    }
+}
+
+// ==================== RECUPERACIÓN DE CONTRASEÑA ====================
+
+// POST /api/auth/recuperar-password
+// Siempre responde lo mismo exista o no el email, para no revelar cuentas registradas
+pub async fn recuperar_password_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<RecuperarPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    if AuthService::recuperacion_ip_bloqueada(&pool, &ip_cliente).await {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse {
+                success: false,
+                message: "Demasiadas solicitudes. Intenta nuevamente más tarde".to_string(),
+                errores: None,
+            }),
+        ));
+    }
+
+    if let Err(err) = AuthService::solicitar_recuperacion(&pool, payload, Some(ip_cliente)).await {
+        eprintln!("⚠️  Error en recuperación de contraseña: {}", err);
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some(
+                "Si el email está registrado, recibirás un enlace para restablecer tu contraseña".to_string(),
+            ),
+        }),
+    ))
+}
+
+// POST /api/auth/restablecer-password
+pub async fn restablecer_password_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<RestablecerPasswordRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match AuthService::restablecer_password(&pool, payload, Some(ip_cliente)).await {
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::<()> {
+                success: true,
+                data: None,
+                message: Some("Contraseña restablecida. Inicia sesión con tu nueva contraseña".to_string()),
+            }),
+        )),
+        Err(err) => Err(auth_error_response(StatusCode::BAD_REQUEST, err)),
+    }
+}
+
+// ==================== AUTENTICACIÓN EN DOS PASOS ====================
+
+/// Extraer el token Bearer del header Authorization
+fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                    errores: None,
+                }),
+            )
+        })
+}
+
+fn bad_request(message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            success: false,
+            message,
+            errores: None,
+        }),
+    )
+}
+
+// POST /api/auth/2fa/verificar
+// Segundo paso del login: token de desafío + código TOTP o de recuperación
+pub async fn verificar_dos_factores_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<VerificarDosFactoresRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DosFactoresService::verificar_login(&pool, payload, Some(ip_cliente)).await {
+        Ok(response) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(response),
+                message: None,
+            }),
+        )),
         Err(err) => Err((
-            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
             Json(ErrorResponse {
                 success: false,
                 message: err,
+                errores: None,
+            }),
+        )),
+    }
+}
+
+// GET /api/auth/2fa
+pub async fn estado_dos_factores_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+
+    match DosFactoresService::estado(&pool, token).await {
+        Ok(estado) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(estado),
+                message: None,
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// POST /api/auth/2fa/configurar
+// Acepta un token de sesión o el token de enrolamiento devuelto por el login
+pub async fn configurar_dos_factores_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+
+    match DosFactoresService::configurar(&pool, token).await {
+        Ok(configuracion) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(configuracion),
+                message: Some("Escanea el código QR y confirma con un código de tu app autenticadora".to_string()),
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// POST /api/auth/2fa/activar
+pub async fn activar_dos_factores_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<ActivarDosFactoresRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DosFactoresService::activar(&pool, token, payload, Some(ip_cliente)).await {
+        Ok(response) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(response),
+                message: Some(
+                    "Autenticación en dos pasos activada. Guarda los códigos de recuperación en un lugar seguro".to_string(),
+                ),
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// POST /api/auth/2fa/desactivar
+pub async fn desactivar_dos_factores_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<DesactivarDosFactoresRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DosFactoresService::desactivar(&pool, token, payload, Some(ip_cliente)).await {
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::<()> {
+                success: true,
+                data: None,
+                message: Some("Autenticación en dos pasos desactivada".to_string()),
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// POST /api/auth/2fa/codigos-recuperacion
+pub async fn regenerar_codigos_recuperacion_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<ActivarDosFactoresRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DosFactoresService::regenerar_codigos(&pool, token, payload, Some(ip_cliente)).await {
+        Ok(codigos) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(codigos),
+                message: Some("Se generaron nuevos códigos de recuperación; los anteriores ya no son válidos".to_string()),
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// ==================== DATOS PERSONALES (LEY 29733) ====================
+
+// GET /api/auth/mis-datos/export?formato=json|zip
+// Descarga todos los datos vinculados a la cuenta
+pub async fn exportar_mis_datos_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<ExportarDatosQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    let formato = query.formato.as_deref().unwrap_or("json").to_lowercase();
+    if formato != "json" && formato != "zip" {
+        return Err(bad_request("Formato no soportado. Usa json o zip".to_string()));
+    }
+
+    let datos = DatosPersonalesService::exportar(&pool, token, &formato, Some(ip_cliente))
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: err,
+                    errores: None,
+                }),
+            )
+        })?;
+
+    let (contenido, tipo) = if formato == "zip" {
+        let zip = DatosPersonalesService::empaquetar_zip(&datos).map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    success: false,
+                    message: err,
+                    errores: None,
+                }),
+            )
+        })?;
+        (zip, "application/zip")
+    } else {
+        let json = serde_json::to_vec_pretty(&datos).unwrap_or_default();
+        (json, "application/json; charset=utf-8")
+    };
+
+    let archivo = format!(
+        "attachment; filename=\"kronostech-mis-datos-{}.{}\"",
+        chrono::Utc::now().format("%Y%m%d"),
+        formato
+    );
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, tipo.to_string()),
+            (header::CONTENT_DISPOSITION, archivo),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        contenido,
+    ))
+}
+
+// GET /api/auth/mis-datos/eliminacion
+pub async fn estado_eliminacion_cuenta_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+
+    match DatosPersonalesService::estado_eliminacion(&pool, token).await {
+        Ok(estado) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(estado),
+                message: None,
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// POST /api/auth/mis-datos/eliminacion
+// Programa la eliminación de la cuenta al final del periodo de gracia
+pub async fn solicitar_eliminacion_cuenta_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<SolicitarEliminacionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DatosPersonalesService::solicitar_eliminacion(&pool, token, payload, Some(ip_cliente)).await {
+        Ok(solicitud) => Ok((
+            StatusCode::CREATED,
+            Json(ApiResponse {
+                success: true,
+                message: Some(format!(
+                    "Tu cuenta se eliminará el {} UTC. Puedes cancelar la solicitud hasta esa fecha",
+                    solicitud.fecha_programada.format("%Y-%m-%d %H:%M")
+                )),
+                data: Some(solicitud),
+            }),
+        )),
+        Err(err) => Err(bad_request(err)),
+    }
+}
+
+// DELETE /api/auth/mis-datos/eliminacion
+pub async fn cancelar_eliminacion_cuenta_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let ip_cliente = ip_cliente(&headers, Some(addr.ip()));
+
+    match DatosPersonalesService::cancelar_eliminacion(&pool, token, Some(ip_cliente)).await {
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::<()> {
+                success: true,
+                data: None,
+                message: Some("Solicitud de eliminación cancelada".to_string()),
             }),
         )),
+        Err(err) => Err(bad_request(err)),
     }
 }
@@ -0,0 +1,157 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{StatusCode, HeaderMap},
+    Json,
+    response::IntoResponse,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::models::busqueda::{BloquearTerminoRequest, SugerenciasQuery, TerminosBusquedaQuery};
+use crate::services::{AuthService, BusquedaService};
+
+// ==================== RESPONSES ====================
+
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// ==================== HELPER FUNCTIONS ====================
+
+fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                }),
+            )
+        })
+}
+
+fn verify_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    let claims = AuthService::verify_token(token).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                success: false,
+                message: e,
+            }),
+        )
+    })?;
+
+    if claims.rol != "super_admin" && claims.rol != "administrador" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: "Acceso denegado. Solo administradores pueden acceder".to_string(),
+            }),
+        ));
+    }
+
+    Ok(claims.sub)
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            success: false,
+            message: format!("Error en la base de datos: {}", e),
+        }),
+    )
+}
+
+// ==================== HANDLERS ====================
+
+/// GET /api/busqueda/sugerencias?q=
+/// Autocompletado del buscador: productos, marcas, categorías y búsquedas populares (público)
+pub async fn get_sugerencias_handler(
+    State(pool): State<PgPool>,
+    Query(query): Query<SugerenciasQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let sugerencias = BusquedaService::sugerencias(&pool, query.q.as_deref().unwrap_or_default())
+        .await
+        .map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(sugerencias),
+            message: None,
+        }),
+    ))
+}
+
+/// GET /api/busqueda/terminos?sin_resultados=true&limit=
+/// Términos más buscados en el catálogo, o los que no encontraron productos (solo admin)
+pub async fn get_terminos_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Query(query): Query<TerminosBusquedaQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let terminos = BusquedaService::terminos(&pool, &query).await.map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(terminos),
+            message: None,
+        }),
+    ))
+}
+
+/// PUT /api/busqueda/terminos/:termino/bloqueo
+/// Excluir un término de las sugerencias populares, o volver a permitirlo (solo admin)
+pub async fn bloquear_termino_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Path(termino): Path<String>,
+    Json(payload): Json<BloquearTerminoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let termino = BusquedaService::bloquear_termino(&pool, &termino, payload.bloqueado)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Término no encontrado".to_string(),
+                }),
+            )
+        })?;
+
+    let message = if termino.bloqueado { "Término bloqueado" } else { "Término desbloqueado" };
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(termino),
+            message: Some(message.to_string()),
+        }),
+    ))
+}
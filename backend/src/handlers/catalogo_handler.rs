@@ -1,16 +1,26 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
     Json,
     response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 
+use crate::middleware::rate_limit::ip_cliente;
 use crate::repositories::ProductoFilters;
 use crate::services::{CatalogoService, AuthService};
+use crate::models::busqueda::Facetas;
 use crate::models::valoracion::CrearValoracionRequest;
 
+/// Prefijo de los filtros por especificación en la query: `esp.socket=AM5,LGA1700`
+const PREFIJO_ESPECIFICACION: &str = "esp.";
+
+/// Máximo de atributos de especificación filtrados a la vez
+const MAXIMO_ATRIBUTOS_FILTRO: usize = 10;
+
 // ==================== RESPONSES ====================
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
@@ -24,6 +34,8 @@ pub struct PaginatedResponse<T> {
     pub success: bool,
     pub data: Vec<T>,
     pub pagination: Pagination,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facetas: Option<Facetas>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +64,8 @@ pub struct ProductoQuery {
     pub order_by: Option<String>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Incluir las facetas con conteos (por defecto no: son varias consultas de agregación)
+    pub facetas: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,6 +78,37 @@ pub struct SubcategoriaQuery {
     pub categoria: Option<i32>,
 }
 
+// ==================== HELPERS ====================
+
+/// Filtros por especificación de la query (`esp.<atributo>=valor`), repetidos o
+/// separados por comas; varios valores del mismo atributo se combinan con O
+fn filtros_especificacion(pares: Vec<(String, String)>) -> BTreeMap<String, Vec<String>> {
+    let mut especificaciones: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (clave, valor) in pares {
+        let Some(atributo) = clave
+            .strip_prefix(PREFIJO_ESPECIFICACION)
+            .map(str::trim)
+            .filter(|atributo| !atributo.is_empty())
+        else {
+            continue;
+        };
+        if especificaciones.len() >= MAXIMO_ATRIBUTOS_FILTRO && !especificaciones.contains_key(atributo) {
+            continue;
+        }
+
+        let valores = especificaciones.entry(atributo.to_string()).or_default();
+        for valor in valor.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            if !valores.iter().any(|v| v == valor) {
+                valores.push(valor.to_string());
+            }
+        }
+    }
+
+    especificaciones.retain(|_, valores| !valores.is_empty());
+    especificaciones
+}
+
 // ==================== HANDLERS ====================
 
 /// GET /api/familias - Obtener todas las familias
@@ -124,10 +169,13 @@ pub async fn get_marcas(
     }
 }
 
-/// GET /api/productos - Obtener productos con filtros
+/// GET /api/productos - Obtener productos con filtros y facetas
 pub async fn get_productos(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Query(params): Query<ProductoQuery>,
+    Query(pares): Query<Vec<(String, String)>>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let filters = ProductoFilters {
         search: params.search,
@@ -141,6 +189,7 @@ pub async fn get_productos(
         nuevos: params.nuevos,
         ofertas: params.ofertas,
         en_stock: params.en_stock,
+        especificaciones: filtros_especificacion(pares),
         order_by: params.order_by,
         limit: params.limit,
         offset: params.offset,
@@ -149,8 +198,18 @@ pub async fn get_productos(
     let limit = filters.limit.unwrap_or(20);
     let offset = filters.offset.unwrap_or(0);
 
-    match CatalogoService::get_productos(&pool, filters).await {
-        Ok((productos, total)) => {
+    // Las facetas se piden explícitamente y se calculan a la vez que el listado
+    let facetas = async {
+        match params.facetas.unwrap_or(false) {
+            true => CatalogoService::get_facetas(&pool, &filters).await.map(Some),
+            false => Ok(None),
+        }
+    };
+    let ip = ip_cliente(&headers, Some(addr.ip()));
+    let listado = CatalogoService::get_productos(&pool, filters.clone(), &ip);
+
+    match tokio::try_join!(facetas, listado) {
+        Ok((facetas, (productos, total))) => {
             let total_pages = (total as f64 / limit as f64).ceil() as i64;
             let current_page = (offset / limit) as i64 + 1;
 
@@ -164,6 +223,7 @@ pub async fn get_productos(
                     total_pages,
                     current_page,
                 },
+                facetas,
             }))
         }
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -1,14 +1,18 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
 
+use crate::middleware::funcionalidad::sujeto_funcionalidad;
 use crate::models::ProcesarCheckoutRequest;
-use crate::services::{AuthService, CheckoutService};
+use crate::services::feature_flag_service::Funcionalidad;
+use crate::services::{AuthService, CheckoutService, FeatureFlagService};
+use crate::services::auth_service::{Claims, ACCION_NO_PERMITIDA_SUPLANTACION};
 
 // ==================== RESPONSES ====================
 
@@ -42,6 +46,10 @@ pub struct CalcularTotalQuery {
 // ==================== HELPER FUNCTIONS ====================
 
 fn extract_user_id(headers: &HeaderMap) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    extract_claims(headers).map(|claims| claims.sub)
+}
+
+fn extract_claims(headers: &HeaderMap) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
     let token = headers
         .get("Authorization")
         .and_then(|value| value.to_str().ok())
@@ -72,7 +80,29 @@ fn extract_user_id(headers: &HeaderMap) -> Result<i32, (StatusCode, Json<ErrorRe
         )
     })?;
 
-    Ok(claims.sub)
+    Ok(claims)
+}
+
+// Un código de cupón solo se acepta si la funcionalidad de cupones está activa para el usuario.
+// El sujeto se arma igual que en /api/config/funcionalidades, con la IP del cliente.
+fn verificar_cupones(
+    headers: &HeaderMap,
+    peer: IpAddr,
+    codigo_cupon: Option<&str>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if codigo_cupon.is_none_or(|codigo| codigo.trim().is_empty()) {
+        return Ok(());
+    }
+
+    FeatureFlagService::verificar(Funcionalidad::Cupones, &sujeto_funcionalidad(headers, Some(peer))).map_err(|error| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: error.message,
+            }),
+        )
+    })
 }
 
 fn extract_ip_and_user_agent(headers: &HeaderMap) -> (Option<String>, Option<String>) {
@@ -118,10 +148,12 @@ pub async fn get_metodos_pago_handler(
 /// GET /api/checkout/calcular-total - Calcular total del checkout
 pub async fn calcular_total_handler(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Query(params): Query<CalcularTotalQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let id_usuario = extract_user_id(&headers)?;
+    verificar_cupones(&headers, addr.ip(), params.codigo_cupon.as_deref())?;
 
     match CheckoutService::calcular_total(&pool, id_usuario, params.id_direccion, params.codigo_cupon).await {
         Ok(totales) => Ok((
@@ -145,13 +177,27 @@ pub async fn calcular_total_handler(
 /// POST /api/checkout/procesar - Procesar checkout y crear pedido
 pub async fn procesar_checkout_handler(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(payload): Json<ProcesarCheckoutRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let id_usuario = extract_user_id(&headers)?;
+    let claims = extract_claims(&headers)?;
     let (ip_cliente, user_agent) = extract_ip_and_user_agent(&headers);
 
-    match CheckoutService::procesar_checkout(&pool, id_usuario, payload, ip_cliente, user_agent).await {
+    // Un administrador que suplanta al cliente nunca puede comprar en su nombre
+    if claims.impersonacion.is_some() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: ACCION_NO_PERMITIDA_SUPLANTACION.to_string(),
+            }),
+        ));
+    }
+
+    verificar_cupones(&headers, addr.ip(), payload.codigo_cupon.as_deref())?;
+
+    match CheckoutService::procesar_checkout(&pool, claims.sub, payload, ip_cliente, user_agent).await {
         Ok(venta) => Ok((
             StatusCode::CREATED,
             Json(ApiResponse {
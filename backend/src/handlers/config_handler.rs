@@ -1,5 +1,5 @@
 use axum::{
-    extract::{State, Path},
+    extract::{ConnectInfo, Path, Query, State},
     http::{StatusCode, HeaderMap},
     Json,
     response::IntoResponse,
@@ -7,9 +7,19 @@ use axum::{
 use serde::Serialize;
 use sqlx::PgPool;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 
-use crate::models::configuracion::{ConfiguracionSistema, ActualizarConfigRequest, ActualizarConfigBatchRequest, ConfigValue};
-use crate::services::AuthService;
+use crate::middleware::funcionalidad::sujeto_funcionalidad;
+use crate::middleware::rate_limit::ip_cliente;
+use crate::models::configuracion::{
+    ActualizarConfigRequest, ActualizarConfigBatchRequest, ConfigValue, HistorialConfigQuery, RevertirConfigRequest,
+    RevertirLoteConfigRequest,
+};
+use crate::services::config_service::ConfigError;
+use crate::services::{AuthService, ConfigService, FeatureFlagService, MantenimientoService};
+
+/// Categorías que solo ven los administradores
+const CATEGORIAS_PRIVADAS: &[&str] = &["email", "seguridad"];
 
 // ==================== RESPONSES ====================
 
@@ -74,12 +84,29 @@ fn verify_admin(token: &str) -> Result<(i32, String), (StatusCode, Json<ErrorRes
     Ok((claims.sub, claims.rol))
 }
 
+fn config_error_response(err: ConfigError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        ConfigError::NoEncontrada(_) => StatusCode::NOT_FOUND,
+        ConfigError::Invalida(_) => StatusCode::BAD_REQUEST,
+        ConfigError::Conflicto(_) => StatusCode::CONFLICT,
+        ConfigError::Prohibida(_) => StatusCode::FORBIDDEN,
+        ConfigError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
 // ==================== HANDLERS ====================
 
 /// GET /api/config
 /// Obtener toda la configuración (público - algunas claves, admin - todas)
 pub async fn get_all_config_handler(
-    State(pool): State<PgPool>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     // Intentar verificar si es admin para mostrar todas las configs
@@ -89,68 +116,47 @@ pub async fn get_all_config_handler(
         false
     };
 
-    let configs = if is_admin {
-        // Admin ve toda la configuración
-        sqlx::query_as::<_, ConfiguracionSistema>(
-            "SELECT * FROM configuracion_sistema ORDER BY categoria, clave"
-        )
-        .fetch_all(&pool)
-        .await
-    } else {
+    let mut config_map: HashMap<String, ConfigValue> = HashMap::new();
+    for config in ConfigService::listar() {
         // Público solo ve configuración pública (no email ni seguridad interna)
-        sqlx::query_as::<_, ConfiguracionSistema>(
-            "SELECT * FROM configuracion_sistema 
-             WHERE categoria NOT IN ('email', 'seguridad')
-             ORDER BY categoria, clave"
-        )
-        .fetch_all(&pool)
-        .await
-    };
-
-    match configs {
-        Ok(configs) => {
-            let mut config_map: HashMap<String, ConfigValue> = HashMap::new();
-            for config in configs {
-                config_map.insert(config.clave.clone(), ConfigValue {
-                    valor: config.valor,
-                    tipo: config.tipo,
-                    categoria: config.categoria,
-                });
-            }
-            
-            Ok((
-                StatusCode::OK,
-                Json(ApiResponse {
-                    success: true,
-                    data: Some(config_map),
-                    message: None,
-                }),
-            ))
+        if !is_admin && CATEGORIAS_PRIVADAS.contains(&config.categoria.as_str()) {
+            continue;
         }
-        Err(err) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Error al obtener configuración: {}", err),
-            }),
-        )),
+        config_map.insert(config.clave.clone(), ConfigValue {
+            valor: config.valor,
+            tipo: config.tipo,
+            categoria: config.categoria,
+        });
     }
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(config_map),
+            message: None,
+        }),
+    ))
 }
 
 /// GET /api/config/:clave
-/// Obtener una configuración específica
+/// Obtener una configuración específica. Las claves privadas se responden como
+/// inexistentes si no las pide un admin.
 pub async fn get_config_handler(
-    State(pool): State<PgPool>,
+    headers: HeaderMap,
     Path(clave): Path<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    match sqlx::query_as::<_, ConfiguracionSistema>(
-        "SELECT * FROM configuracion_sistema WHERE clave = $1"
-    )
-    .bind(&clave)
-    .fetch_optional(&pool)
-    .await
-    {
-        Ok(Some(config)) => Ok((
+    let is_admin = if let Ok(token) = extract_token(&headers) {
+        verify_admin(token).is_ok()
+    } else {
+        false
+    };
+
+    let visible = ConfigService::obtener(&clave)
+        .filter(|config| is_admin || !CATEGORIAS_PRIVADAS.contains(&config.categoria.as_str()));
+
+    match visible {
+        Some(config) => Ok((
             StatusCode::OK,
             Json(ApiResponse {
                 success: true,
@@ -158,25 +164,18 @@ pub async fn get_config_handler(
                 message: None,
             }),
         )),
-        Ok(None) => Err((
+        None => Err((
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
                 success: false,
                 message: format!("Configuración '{}' no encontrada", clave),
             }),
         )),
-        Err(err) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Error: {}", err),
-            }),
-        )),
     }
 }
 
 /// PUT /api/config/:clave
-/// Actualizar una configuración (solo admin)
+/// Actualizar una configuración (solo admin). El valor se valida contra el tipo de la clave.
 pub async fn update_config_handler(
     State(pool): State<PgPool>,
     headers: HeaderMap,
@@ -184,48 +183,9 @@ pub async fn update_config_handler(
     Json(payload): Json<ActualizarConfigRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let token = extract_token(&headers)?;
-    let (user_id, _) = verify_admin(token)?;
-
-    // Validar que la clave existe
-    let existing = sqlx::query_as::<_, ConfiguracionSistema>(
-        "SELECT * FROM configuracion_sistema WHERE clave = $1"
-    )
-    .bind(&clave)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Error: {}", e),
-            }),
-        )
-    })?;
+    let (user_id, rol) = verify_admin(token)?;
 
-    if existing.is_none() {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Configuración '{}' no encontrada", clave),
-            }),
-        ));
-    }
-
-    // Actualizar
-    match sqlx::query_as::<_, ConfiguracionSistema>(
-        "UPDATE configuracion_sistema 
-         SET valor = $1, fecha_actualizacion = CURRENT_TIMESTAMP, actualizado_por = $2
-         WHERE clave = $3
-         RETURNING *"
-    )
-    .bind(&payload.valor)
-    .bind(user_id)
-    .bind(&clave)
-    .fetch_one(&pool)
-    .await
-    {
+    match ConfigService::actualizar(&pool, user_id, &rol, &clave, &payload.valor, payload.motivo).await {
         Ok(config) => Ok((
             StatusCode::OK,
             Json(ApiResponse {
@@ -234,112 +194,175 @@ pub async fn update_config_handler(
                 message: Some("Configuración actualizada".to_string()),
             }),
         )),
-        Err(err) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Error al actualizar: {}", err),
-            }),
-        )),
+        Err(err) => Err(config_error_response(err)),
     }
 }
 
 /// PUT /api/config
-/// Actualizar múltiples configuraciones (solo admin)
+/// Actualizar múltiples configuraciones (solo admin). Se aplican todas o ninguna.
 pub async fn update_config_batch_handler(
     State(pool): State<PgPool>,
     headers: HeaderMap,
     Json(payload): Json<ActualizarConfigBatchRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let token = extract_token(&headers)?;
-    let (user_id, _) = verify_admin(token)?;
-
-    let mut updated_count = 0;
-    let mut errors: Vec<String> = Vec::new();
+    let (user_id, rol) = verify_admin(token)?;
 
-    for config in payload.configuraciones {
-        match sqlx::query(
-            "UPDATE configuracion_sistema 
-             SET valor = $1, fecha_actualizacion = CURRENT_TIMESTAMP, actualizado_por = $2
-             WHERE clave = $3"
-        )
-        .bind(&config.valor)
-        .bind(user_id)
-        .bind(&config.clave)
-        .execute(&pool)
-        .await
-        {
-            Ok(result) => {
-                if result.rows_affected() > 0 {
-                    updated_count += 1;
-                } else {
-                    errors.push(format!("Clave '{}' no encontrada", config.clave));
-                }
-            }
-            Err(e) => {
-                errors.push(format!("Error en '{}': {}", config.clave, e));
-            }
-        }
+    match ConfigService::actualizar_lote(&pool, user_id, &rol, &payload.configuraciones, payload.motivo.as_deref()).await {
+        Ok((id_lote, configs)) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(serde_json::json!({ "id_lote": id_lote })),
+                message: Some(format!("{} configuraciones actualizadas", configs.len())),
+            }),
+        )),
+        Err(err) => Err(config_error_response(err)),
     }
+}
+
+/// GET /api/config/historial
+/// Historial de cambios de configuración, filtrable por clave o lote (solo admin)
+pub async fn get_config_history_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Query(filtros): Query<HistorialConfigQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
 
-    if errors.is_empty() {
-        Ok((
+    match ConfigService::historial(&pool, &filtros).await {
+        Ok(versiones) => Ok((
             StatusCode::OK,
-            Json(ApiResponse::<()> {
+            Json(ApiResponse {
                 success: true,
-                data: None,
-                message: Some(format!("{} configuraciones actualizadas", updated_count)),
+                data: Some(versiones),
+                message: None,
             }),
-        ))
-    } else {
-        Ok((
+        )),
+        Err(err) => Err(config_error_response(err)),
+    }
+}
+
+/// GET /api/config/:clave/historial
+/// Versiones de una clave, de la más reciente a la más antigua (solo admin)
+pub async fn get_config_key_history_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(clave): Path<String>,
+    Query(filtros): Query<HistorialConfigQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let filtros = HistorialConfigQuery { clave: Some(clave), ..filtros };
+    match ConfigService::historial(&pool, &filtros).await {
+        Ok(versiones) => Ok((
             StatusCode::OK,
-            Json(ApiResponse::<()> {
+            Json(ApiResponse {
                 success: true,
-                data: None,
-                message: Some(format!("{} actualizadas. Errores: {}", updated_count, errors.join(", "))),
+                data: Some(versiones),
+                message: None,
             }),
-        ))
+        )),
+        Err(err) => Err(config_error_response(err)),
     }
 }
 
-/// GET /api/config/session-timeout
-/// Obtener el timeout de sesión (usado por el servicio de auth)
-pub async fn get_session_timeout_handler(
+/// POST /api/config/:clave/rollback
+/// Devolver una clave al valor de una versión del historial (solo admin)
+pub async fn rollback_config_handler(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(clave): Path<String>,
+    Json(payload): Json<RevertirConfigRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    match sqlx::query_scalar::<_, String>(
-        "SELECT valor FROM configuracion_sistema WHERE clave = 'session_timeout'"
-    )
-    .fetch_optional(&pool)
-    .await
-    {
-        Ok(Some(valor)) => {
-            let hours: i64 = valor.parse().unwrap_or(24);
-            Ok((
-                StatusCode::OK,
-                Json(ApiResponse {
-                    success: true,
-                    data: Some(hours),
-                    message: None,
-                }),
-            ))
-        }
-        Ok(None) => Ok((
+    let token = extract_token(&headers)?;
+    let (user_id, rol) = verify_admin(token)?;
+    let ip = ip_cliente(&headers, Some(addr.ip()));
+
+    match ConfigService::revertir_clave(&pool, user_id, &rol, &clave, payload.id_historial, payload.motivo, Some(ip)).await {
+        Ok(config) => Ok((
             StatusCode::OK,
             Json(ApiResponse {
                 success: true,
-                data: Some(24i64), // Default 24 horas
-                message: None,
+                data: Some(config),
+                message: Some(format!("Configuración revertida a la versión #{}", payload.id_historial)),
             }),
         )),
-        Err(err) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                success: false,
-                message: format!("Error: {}", err),
+        Err(err) => Err(config_error_response(err)),
+    }
+}
+
+/// POST /api/config/historial/lotes/:id_lote/rollback
+/// Deshacer todos los cambios de un lote (solo admin). Si alguna clave cambió
+/// después se responde 409, salvo que se envíe `forzar`.
+pub async fn rollback_config_batch_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(id_lote): Path<i32>,
+    Json(payload): Json<RevertirLoteConfigRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let (user_id, rol) = verify_admin(token)?;
+    let ip = ip_cliente(&headers, Some(addr.ip()));
+
+    match ConfigService::revertir_lote(&pool, user_id, &rol, id_lote, payload.motivo, payload.forzar, Some(ip)).await {
+        Ok((id_lote_nuevo, configs)) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(serde_json::json!({ "id_lote": id_lote_nuevo, "configuraciones": configs })),
+                message: Some(format!("Lote #{} revertido ({} configuraciones)", id_lote, configs.len())),
             }),
         )),
+        Err(err) => Err(config_error_response(err)),
     }
 }
 
+/// GET /api/config/funcionalidades
+/// Funcionalidades activas para quien consulta (público). Aplica el interruptor,
+/// el porcentaje de despliegue y los roles de cada una.
+pub async fn get_funcionalidades_handler(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let sujeto = sujeto_funcionalidad(&headers, Some(addr.ip()));
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(FeatureFlagService::evaluar(&sujeto)),
+            message: None,
+        }),
+    ))
+}
+
+/// GET /api/config/estado
+/// Estado público de la tienda: modo de mantenimiento vigente, mensaje y ventana programada
+pub async fn get_estado_handler() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({ "mantenimiento": MantenimientoService::estado() })),
+            message: None,
+        }),
+    ))
+}
+
+/// GET /api/config/session-timeout
+/// Obtener el timeout de sesión (usado por el servicio de auth)
+pub async fn get_session_timeout_handler() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(AuthService::get_session_timeout()),
+            message: None,
+        }),
+    ))
+}
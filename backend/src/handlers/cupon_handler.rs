@@ -7,9 +7,11 @@ use axum::{
 use chrono::NaiveDateTime;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, FromRow};
+use sqlx::{PgPool, FromRow, Postgres, QueryBuilder};
 
+use crate::handlers::descuento_handler::ProductoDropdownQuery;
 use crate::models::cupon::{Cupon, CuponListItem, CuponStats};
+use crate::repositories::catalogo_repository::{AlcanceFiltro, ProductoFilters, FROM_PRODUCTOS};
 use crate::services::AuthService;
 
 #[derive(Debug, Deserialize)]
@@ -564,14 +566,22 @@ pub async fn delete_cupon(
 
 pub async fn get_productos_dropdown(
     State(pool): State<PgPool>,
+    Query(query): Query<ProductoDropdownQuery>,
 ) -> Result<Json<Vec<ProductoDropdown>>, StatusCode> {
-    match sqlx::query_as::<_, ProductoDropdown>(
-        r#"
-        SELECT id_producto_detalle, nombre
-        FROM producto_detalle
-        ORDER BY nombre ASC
-        "#
-    )
+    let filtros = ProductoFilters {
+        search: query.search,
+        ..Default::default()
+    };
+
+    let mut sql = QueryBuilder::<Postgres>::new("SELECT pd.id_producto_detalle, pd.nombre");
+    sql.push(FROM_PRODUCTOS);
+    filtros.push_where(&mut sql, AlcanceFiltro::Administracion);
+    sql.push(" ORDER BY pd.nombre ASC");
+    if let Some(limit) = query.limit {
+        sql.push(" LIMIT ").push_bind(limit.clamp(1, 500));
+    }
+
+    match sql.build_query_as::<ProductoDropdown>()
     .fetch_all(&pool)
     .await
     {
@@ -4,11 +4,12 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use rust_decimal::Decimal;
 use chrono::NaiveDateTime;
 
 use crate::models::descuento::Descuento;
+use crate::repositories::catalogo_repository::{AlcanceFiltro, ProductoFilters, FROM_PRODUCTOS};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct DescuentoListItem {
@@ -62,6 +63,13 @@ pub struct ProductoDropdown {
     pub nombre: String,
 }
 
+/// Búsqueda opcional para los selectores de productos del panel
+#[derive(Debug, Deserialize)]
+pub struct ProductoDropdownQuery {
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+}
+
 pub async fn get_descuentos(
     State(pool): State<PgPool>,
     Query(query): Query<DescuentoQuery>,
@@ -291,14 +299,22 @@ pub async fn create_descuento(
 
 pub async fn get_productos_dropdown(
     State(pool): State<PgPool>,
+    Query(query): Query<ProductoDropdownQuery>,
 ) -> Result<Json<Vec<ProductoDropdown>>, StatusCode> {
-    match sqlx::query_as::<_, ProductoDropdown>(
-        r#"
-        SELECT id_producto_detalle, nombre
-        FROM producto_detalle
-        ORDER BY nombre ASC
-        "#
-    )
+    let filtros = ProductoFilters {
+        search: query.search,
+        ..Default::default()
+    };
+
+    let mut sql = QueryBuilder::<Postgres>::new("SELECT pd.id_producto_detalle, pd.nombre");
+    sql.push(FROM_PRODUCTOS);
+    filtros.push_where(&mut sql, AlcanceFiltro::Administracion);
+    sql.push(" ORDER BY pd.nombre ASC");
+    if let Some(limit) = query.limit {
+        sql.push(" LIMIT ").push_bind(limit.clamp(1, 500));
+    }
+
+    match sql.build_query_as::<ProductoDropdown>()
     .fetch_all(&pool)
     .await
     {
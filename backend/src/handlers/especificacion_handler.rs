@@ -0,0 +1,264 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{StatusCode, HeaderMap},
+    Json,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::models::especificacion_producto::{
+    ActualizarAtributoRequest, ActualizarEspecificacionesRequest, CompararQuery, CrearAtributoRequest,
+};
+use crate::services::especificacion_service::EspecificacionError;
+use crate::services::{AuthService, EspecificacionService};
+
+// ==================== RESPONSES ====================
+
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EliminarAtributoQuery {
+    /// Eliminar también los valores cargados en productos
+    pub forzar: Option<bool>,
+}
+
+// ==================== HELPER FUNCTIONS ====================
+
+fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                }),
+            )
+        })
+}
+
+fn verify_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    let claims = AuthService::verify_token(token).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                success: false,
+                message: e,
+            }),
+        )
+    })?;
+
+    if claims.rol != "super_admin" && claims.rol != "administrador" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: "Acceso denegado. Solo administradores pueden acceder".to_string(),
+            }),
+        ));
+    }
+
+    Ok(claims.sub)
+}
+
+fn especificacion_error_response(err: EspecificacionError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        EspecificacionError::NoEncontrado(_) => StatusCode::NOT_FOUND,
+        EspecificacionError::Invalida(_) => StatusCode::BAD_REQUEST,
+        EspecificacionError::Conflicto(_) => StatusCode::CONFLICT,
+        EspecificacionError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    especificacion_error_response(e.into())
+}
+
+// ==================== ATRIBUTOS ====================
+
+/// GET /api/categorias/:id/atributos
+/// Esquema de especificaciones de la categoría (público)
+pub async fn get_atributos_handler(
+    State(pool): State<PgPool>,
+    Path(id_categoria): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let atributos = EspecificacionService::listar_atributos(&pool, id_categoria)
+        .await
+        .map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(atributos),
+            message: None,
+        }),
+    ))
+}
+
+/// POST /api/categorias/:id/atributos
+/// Agregar un atributo al esquema de la categoría (solo admin)
+pub async fn create_atributo_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Path(id_categoria): Path<i32>,
+    Json(payload): Json<CrearAtributoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let atributo = EspecificacionService::crear_atributo(&pool, id_categoria, payload)
+        .await
+        .map_err(especificacion_error_response)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse {
+            success: true,
+            data: Some(atributo),
+            message: Some("Atributo creado exitosamente".to_string()),
+        }),
+    ))
+}
+
+/// PUT /api/atributos/:id
+/// Actualizar un atributo (solo admin)
+pub async fn update_atributo_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Path(id_atributo): Path<i32>,
+    Json(payload): Json<ActualizarAtributoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let atributo = EspecificacionService::actualizar_atributo(&pool, id_atributo, payload)
+        .await
+        .map_err(especificacion_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(atributo),
+            message: Some("Atributo actualizado exitosamente".to_string()),
+        }),
+    ))
+}
+
+/// DELETE /api/atributos/:id?forzar=true
+/// Eliminar un atributo; en uso solo con `forzar` (solo admin)
+pub async fn delete_atributo_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Path(id_atributo): Path<i32>,
+    Query(query): Query<EliminarAtributoQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let valores_eliminados = EspecificacionService::eliminar_atributo(&pool, id_atributo, query.forzar.unwrap_or(false))
+        .await
+        .map_err(especificacion_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({ "valores_eliminados": valores_eliminados })),
+            message: Some("Atributo eliminado exitosamente".to_string()),
+        }),
+    ))
+}
+
+// ==================== VALORES POR VARIANTE ====================
+
+/// GET /api/productos/:id/especificaciones
+/// Especificaciones de una variante (público)
+pub async fn get_especificaciones_handler(
+    State(pool): State<PgPool>,
+    Path(id_producto_detalle): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let especificaciones = EspecificacionService::especificaciones(&pool, id_producto_detalle)
+        .await
+        .map_err(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(especificaciones),
+            message: None,
+        }),
+    ))
+}
+
+/// PUT /api/productos/:id/especificaciones
+/// Reemplazar las especificaciones de una variante, validadas contra el esquema (solo admin)
+pub async fn update_especificaciones_handler(
+    headers: HeaderMap,
+    State(pool): State<PgPool>,
+    Path(id_producto_detalle): Path<i32>,
+    Json(payload): Json<ActualizarEspecificacionesRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    let especificaciones = EspecificacionService::reemplazar(&pool, id_producto_detalle, &payload.especificaciones)
+        .await
+        .map_err(especificacion_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(especificaciones),
+            message: Some("Especificaciones actualizadas exitosamente".to_string()),
+        }),
+    ))
+}
+
+// ==================== COMPARACIÓN ====================
+
+/// GET /api/productos/comparar?ids=1,2,3
+/// Comparar variantes lado a lado: precio, stock, valoración, garantía y especificaciones (público)
+pub async fn comparar_productos_handler(
+    State(pool): State<PgPool>,
+    Query(query): Query<CompararQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let comparacion = EspecificacionService::comparar(&pool, query.ids.as_deref().unwrap_or_default())
+        .await
+        .map_err(especificacion_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            message: comparacion.advertencia.clone(),
+            data: Some(comparacion),
+        }),
+    ))
+}
@@ -4,11 +4,13 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use rust_decimal::Decimal;
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
+use crate::repositories::catalogo_repository::{AlcanceFiltro, ProductoFilters, FROM_PRODUCTOS};
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct InventarioItem {
     pub id_inventario: i32,
@@ -83,11 +85,11 @@ pub async fn get_inventario(
     State(pool): State<PgPool>,
     Query(query): Query<InventarioQuery>,
 ) -> Result<Json<InventarioResponse>, StatusCode> {
-    let page = query.page.unwrap_or(1);
-    let limit = query.limit.unwrap_or(10);
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
     let offset = (page - 1) * limit;
 
-    let mut sql = String::from(
+    let mut sql = QueryBuilder::<Postgres>::new(
         r#"
         SELECT 
             i.id_inventario,
@@ -108,65 +110,51 @@ pub async fn get_inventario(
                 WHEN i.cantidad_disponible < 10 THEN 'bajo'
                 ELSE 'ok'
             END as stock_estado
-        FROM inventario i
-        JOIN producto_detalle pd ON i.id_producto_detalle = pd.id_producto_detalle
-        JOIN producto p ON pd.id_producto = p.id_producto
-        JOIN categoria c ON p.id_categoria = c.id_categoria
-        JOIN marca m ON pd.id_marca = m.id_marca
-        WHERE 1=1
         "#,
     );
+    let mut count_sql = QueryBuilder::<Postgres>::new("SELECT COUNT(*) as count");
 
-    let mut count_sql = String::from(
-        r#"
-        SELECT COUNT(*) as count
+    // Mismos filtros para el listado y el conteo
+    let filtros = ProductoFilters {
+        search: query.search.clone(),
+        ..Default::default()
+    };
+    for builder in [&mut sql, &mut count_sql] {
+        builder.push(
+            r#"
         FROM inventario i
         JOIN producto_detalle pd ON i.id_producto_detalle = pd.id_producto_detalle
         JOIN producto p ON pd.id_producto = p.id_producto
         JOIN categoria c ON p.id_categoria = c.id_categoria
         JOIN marca m ON pd.id_marca = m.id_marca
-        WHERE 1=1
         "#,
-    );
-
-    // Search filter
-    if let Some(search) = &query.search {
-        if !search.is_empty() {
-            let filter = format!(
-                " AND (pd.nombre ILIKE '%{}%' OR pd.sku ILIKE '%{}%')",
-                search, search
-            );
-            sql.push_str(&filter);
-            count_sql.push_str(&filter);
+        );
+        filtros.push_where(builder, AlcanceFiltro::Administracion);
+
+        // Stock status filter
+        if let Some(estado) = &query.stock_estado {
+            let filter = match estado.as_str() {
+                "bajo" => " AND i.cantidad_disponible < 10 AND i.cantidad_disponible > 0",
+                "agotado" => " AND i.cantidad_disponible = 0",
+                "ok" => " AND i.cantidad_disponible >= 10",
+                _ => "" // "todos" or any other value - no filter
+            };
+            builder.push(filter);
         }
-    }
 
-    // Stock status filter
-    if let Some(estado) = &query.stock_estado {
-        let filter = match estado.as_str() {
-            "bajo" => " AND i.cantidad_disponible < 10 AND i.cantidad_disponible > 0",
-            "agotado" => " AND i.cantidad_disponible = 0",
-            "ok" => " AND i.cantidad_disponible >= 10",
-            _ => "" // "todos" or any other value - no filter
-        };
-        sql.push_str(filter);
-        count_sql.push_str(filter);
-    }
-
-    // Brand filter
-    if let Some(marca) = &query.marca {
-        if marca != "Todas" && !marca.is_empty() {
-            let filter = format!(" AND m.nombre = '{}'", marca);
-            sql.push_str(&filter);
-            count_sql.push_str(&filter);
+        // Brand filter
+        if let Some(marca) = &query.marca {
+            if marca != "Todas" && !marca.is_empty() {
+                builder.push(" AND m.nombre = ").push_bind(marca.clone());
+            }
         }
     }
 
-    sql.push_str(" ORDER BY i.fecha_actualizacion DESC NULLS LAST");
-    sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset));
+    sql.push(" ORDER BY i.fecha_actualizacion DESC NULLS LAST");
+    sql.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
 
     // Get total count
-    let total_count: (i64,) = match sqlx::query_as(&count_sql)
+    let total_count: (i64,) = match count_sql.build_query_as()
         .fetch_one(&pool)
         .await
     {
@@ -178,7 +166,7 @@ pub async fn get_inventario(
     };
 
     // Get items
-    let items = match sqlx::query_as::<_, InventarioItem>(&sql)
+    let items = match sql.build_query_as::<InventarioItem>()
         .fetch_all(&pool)
         .await
     {
@@ -224,9 +212,13 @@ pub async fn search_products(
     State(pool): State<PgPool>,
     Query(query): Query<InventarioQuery>,
 ) -> Result<Json<Vec<ProductSearchResult>>, StatusCode> {
-    let search_term = query.search.unwrap_or_default();
-    
-    let sql = r#"
+    let filtros = ProductoFilters {
+        search: query.search,
+        ..Default::default()
+    };
+
+    let mut sql = QueryBuilder::<Postgres>::new(
+        r#"
         SELECT 
             pd.id_producto_detalle,
             pd.nombre,
@@ -234,17 +226,13 @@ pub async fn search_products(
             m.nombre as marca,
             COALESCE(i.cantidad_disponible, 0) as stock_actual,
             pd.imagen_principal
-        FROM producto_detalle pd
-        JOIN marca m ON pd.id_marca = m.id_marca
-        LEFT JOIN inventario i ON pd.id_producto_detalle = i.id_producto_detalle
-        WHERE pd.nombre ILIKE $1 OR pd.sku ILIKE $1
-        LIMIT 10
-    "#;
-
-    let search_pattern = format!("%{}%", search_term);
+        "#,
+    );
+    sql.push(FROM_PRODUCTOS);
+    filtros.push_where(&mut sql, AlcanceFiltro::Administracion);
+    sql.push(" ORDER BY pd.nombre LIMIT 10");
 
-    match sqlx::query_as::<_, ProductSearchResult>(sql)
-        .bind(&search_pattern)
+    match sql.build_query_as::<ProductSearchResult>()
         .fetch_all(&pool)
         .await
     {
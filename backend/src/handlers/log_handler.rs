@@ -1,14 +1,21 @@
 use axum::{
-    extract::{State, Query},
-    http::{StatusCode, HeaderMap},
+    body::Body,
+    extract::{ConnectInfo, State, Query},
+    http::{header, StatusCode, HeaderMap},
     Json,
     response::IntoResponse,
 };
+use futures_util::stream;
 use serde::Serialize;
 use sqlx::PgPool;
+use std::net::SocketAddr;
 
-use crate::models::log_auditoria::{LogAuditoria, CrearLogRequest, FiltrarLogsQuery, LogResponse};
-use crate::services::AuthService;
+use crate::middleware::rate_limit::ip_cliente;
+use crate::models::log_auditoria::{
+    LogAuditoria, CrearLogRequest, ExportarLogsQuery, FiltrarLogsQuery, LogResponse, NivelLog,
+};
+use crate::services::log_service::{FormatoExportacion, COLUMNAS_LOG};
+use crate::services::{AuthService, LogService};
 
 // ==================== RESPONSES ====================
 
@@ -25,6 +32,14 @@ pub struct ErrorResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct LogsPaginadosResponse {
+    pub success: bool,
+    pub data: Vec<LogResponse>,
+    /// Cursor para pedir la página siguiente (None si es la última)
+    pub siguiente_cursor: Option<i32>,
+}
+
 // ==================== HELPER FUNCTIONS ====================
 
 /// Verificar si el usuario es admin o super_admin
@@ -75,18 +90,31 @@ fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorRes
         })
 }
 
-/// Extraer IP del cliente desde headers
-fn extract_client_ip(headers: &HeaderMap) -> Option<String> {
-    headers
-        .get("X-Forwarded-For")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            headers
-                .get("X-Real-IP")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
+/// Resumen legible de los filtros usados en una exportación
+fn describir_filtros(filtros: &FiltrarLogsQuery) -> String {
+    let texto = [
+        ("nivel", filtros.nivel.clone()),
+        ("modulo", filtros.modulo.clone()),
+        ("desde", filtros.fecha_inicio.clone()),
+        ("hasta", filtros.fecha_fin.clone()),
+        ("q", filtros.q.clone()),
+        ("id_usuario", filtros.id_usuario.map(|id| id.to_string())),
+        ("email", filtros.email.clone()),
+        ("ip", filtros.ip.clone()),
+        ("origen", filtros.origen.clone()),
+        ("entidad", filtros.entidad.clone()),
+        ("id_entidad", filtros.id_entidad.clone()),
+    ]
+    .into_iter()
+    .filter_map(|(campo, valor)| valor.map(|v| format!("{}={}", campo, v)))
+    .collect::<Vec<_>>()
+    .join(", ");
+
+    if texto.is_empty() {
+        "ninguno".to_string()
+    } else {
+        texto
+    }
 }
 
 /// Extraer User-Agent del cliente
@@ -100,7 +128,8 @@ fn extract_user_agent(headers: &HeaderMap) -> Option<String> {
 // ==================== HANDLERS ====================
 
 /// GET /api/logs
-/// Listar logs con filtros (solo admin/super_admin)
+/// Listar logs con filtros y búsqueda de texto completo (solo admin/super_admin).
+/// Para paginar, enviar `cursor` con el `siguiente_cursor` de la respuesta anterior.
 pub async fn listar_logs_handler(
     State(pool): State<PgPool>,
     headers: HeaderMap,
@@ -109,84 +138,114 @@ pub async fn listar_logs_handler(
     let token = extract_token(&headers)?;
     verify_admin(token)?;
 
-    let limit = query.limit.unwrap_or(100).min(500);
-    let offset = query.offset.unwrap_or(0);
-
-    // Construir query dinámicamente
-    let mut sql = String::from(
-        "SELECT id_log, nivel::TEXT as nivel, accion, detalles, modulo, 
-         id_usuario, email_usuario, ip_cliente, user_agent, fecha_creacion
-         FROM log_auditoria WHERE 1=1"
-    );
-    
-    let mut param_count = 0;
-    
-    if query.nivel.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND nivel = ${}::nivel_log", param_count));
-    }
-    
-    if query.modulo.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND modulo = ${}", param_count));
-    }
-    
-    if query.fecha_inicio.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND fecha_creacion >= ${}::timestamp", param_count));
-    }
-    
-    if query.fecha_fin.is_some() {
-        param_count += 1;
-        sql.push_str(&format!(" AND fecha_creacion <= ${}::timestamp", param_count));
-    }
-    
-    sql.push_str(&format!(" ORDER BY fecha_creacion DESC LIMIT ${} OFFSET ${}", param_count + 1, param_count + 2));
-
-    // Ejecutar query con parámetros dinámicos
-    let mut query_builder = sqlx::query_as::<_, LogAuditoria>(&sql);
-    
-    if let Some(nivel) = &query.nivel {
-        query_builder = query_builder.bind(nivel);
-    }
-    if let Some(modulo) = &query.modulo {
-        query_builder = query_builder.bind(modulo);
-    }
-    if let Some(fecha_inicio) = &query.fecha_inicio {
-        query_builder = query_builder.bind(fecha_inicio);
-    }
-    if let Some(fecha_fin) = &query.fecha_fin {
-        query_builder = query_builder.bind(fecha_fin);
-    }
-    
-    query_builder = query_builder.bind(limit).bind(offset);
-
-    match query_builder.fetch_all(&pool).await {
-        Ok(logs) => {
+    match LogService::buscar(&pool, &query).await {
+        Ok((logs, siguiente_cursor)) => {
             let logs_response: Vec<LogResponse> = logs.into_iter().map(LogResponse::from).collect();
             Ok((
                 StatusCode::OK,
-                Json(ApiResponse {
+                Json(LogsPaginadosResponse {
                     success: true,
-                    data: Some(logs_response),
-                    message: None,
+                    data: logs_response,
+                    siguiente_cursor,
                 }),
             ))
         }
-        Err(err) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
+        Err(err) => {
+            let status = if err.starts_with("Error al") {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            Err((
+                status,
+                Json(ErrorResponse {
+                    success: false,
+                    message: err,
+                }),
+            ))
+        }
+    }
+}
+
+/// GET /api/logs/export?formato=csv|jsonl
+/// Exportar los logs que cumplen los filtros de GET /api/logs (sin límite de filas).
+/// La respuesta se transmite en streaming mientras se leen las filas (solo admin/super_admin).
+pub async fn exportar_logs_handler(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(filtros): Query<FiltrarLogsQuery>,
+    Query(exportar): Query<ExportarLogsQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    let (id_admin, email, _) = verify_admin(token)?;
+
+    let (formato, content_type, extension) = match exportar.formato.as_deref().unwrap_or("csv") {
+        "csv" => (FormatoExportacion::Csv, "text/csv; charset=utf-8", "csv"),
+        "jsonl" => (FormatoExportacion::Jsonl, "application/x-ndjson", "jsonl"),
+        otro => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    success: false,
+                    message: format!("Formato no soportado: {}. Use csv o jsonl", otro),
+                }),
+            ));
+        }
+    };
+
+    let receptor = LogService::exportar(pool.clone(), &filtros, formato).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
             Json(ErrorResponse {
                 success: false,
-                message: format!("Error al listar logs: {}", err),
+                message: err,
             }),
-        )),
-    }
+        )
+    })?;
+
+    // La exportación también queda auditada
+    LogService::registrar(
+        &pool,
+        Some(id_admin),
+        LogService::nuevo(
+            NivelLog::Security,
+            "Logs exportados",
+            "Sistema",
+            Some(format!("Exportación {} con filtros: {}", extension, describir_filtros(&filtros))),
+            Some(email),
+            Some(ip_cliente(&headers, Some(addr.ip()))),
+        ),
+    )
+    .await;
+
+    let cuerpo = Body::from_stream(stream::unfold(receptor, |mut receptor| async move {
+        receptor.recv().await.map(|chunk| (chunk, receptor))
+    }));
+
+    let nombre = format!(
+        "attachment; filename=\"logs_auditoria_{}.{}\"",
+        chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        extension
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, nombre),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        cuerpo,
+    ))
 }
 
 /// POST /api/logs
-/// Crear nuevo log (autenticado - admin/super_admin o sistema)
+/// Crear nuevo log (autenticado - admin/super_admin o sistema).
+/// Se guarda con origen 'cliente': el contenido lo decide el frontend, así que no
+/// sustituye a la auditoría que registra el servidor.
 pub async fn crear_log_handler(
     State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(payload): Json<CrearLogRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -201,7 +260,7 @@ pub async fn crear_log_handler(
         (None, payload.email_usuario.clone())
     };
 
-    let ip_cliente = payload.ip_cliente.clone().or_else(|| extract_client_ip(&headers));
+    let ip_cliente = payload.ip_cliente.clone().or_else(|| Some(ip_cliente(&headers, Some(addr.ip()))));
     let user_agent = payload.user_agent.clone().or_else(|| extract_user_agent(&headers));
 
     // Validar nivel
@@ -216,11 +275,12 @@ pub async fn crear_log_handler(
         ));
     }
 
-    match sqlx::query_as::<_, LogAuditoria>(
-        "INSERT INTO log_auditoria (nivel, accion, detalles, modulo, id_usuario, email_usuario, ip_cliente, user_agent)
-         VALUES ($1::nivel_log, $2, $3, $4, $5, $6, $7, $8)
-         RETURNING id_log, nivel::TEXT as nivel, accion, detalles, modulo, id_usuario, email_usuario, ip_cliente, user_agent, fecha_creacion"
-    )
+    match sqlx::query_as::<_, LogAuditoria>(&format!(
+        "INSERT INTO log_auditoria (nivel, accion, detalles, modulo, id_usuario, email_usuario, ip_cliente, user_agent, origen)
+         VALUES ($1::nivel_log, $2, $3, $4, $5, $6, $7, $8, 'cliente')
+         RETURNING {}",
+        COLUMNAS_LOG
+    ))
     .bind(&payload.nivel)
     .bind(&payload.accion)
     .bind(&payload.detalles)
@@ -250,50 +310,80 @@ pub async fn crear_log_handler(
     }
 }
 
-/// DELETE /api/logs
-/// Limpiar todos los logs (solo super_admin)
+/// DELETE /api/logs/limpiar
+/// Aplicar la política de retención (solo super_admin): archiva en disco las entradas
+/// con más de `audit_retention_days` días y las purga. Ya no se borra el log completo.
 pub async fn limpiar_logs_handler(
     State(pool): State<PgPool>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let token = extract_token(&headers)?;
-    let (_, email, rol) = verify_admin(token)?;
+    let (id_admin, email, rol) = verify_admin(token)?;
 
-    // Solo super_admin puede limpiar logs
+    // Solo super_admin puede purgar logs
     if rol != "super_admin" {
         return Err((
             StatusCode::FORBIDDEN,
             Json(ErrorResponse {
                 success: false,
-                message: "Solo super_admin puede eliminar logs".to_string(),
+                message: "Solo super_admin puede purgar logs".to_string(),
             }),
         ));
     }
 
-    // Eliminar todos los logs
-    match sqlx::query("DELETE FROM log_auditoria")
-        .execute(&pool)
-        .await
-    {
-        Ok(result) => {
-            let deleted_count = result.rows_affected();
-            
-            // Crear log de la acción de limpieza
-            let _ = sqlx::query(
-                "INSERT INTO log_auditoria (nivel, accion, detalles, modulo, email_usuario)
-                 VALUES ('warning'::nivel_log, 'Logs eliminados', $1, 'Sistema', $2)"
-            )
-            .bind(format!("Se eliminaron {} logs del sistema", deleted_count))
-            .bind(&email)
-            .execute(&pool)
-            .await;
+    match LogService::aplicar_retencion(&pool, Some(id_admin), Some(email)).await {
+        Ok(resultado) => {
+            let message = match (resultado.archivos.as_slice(), resultado.dias_retencion) {
+                (_, 0) => "La retención de logs está desactivada (audit_retention_days = 0)".to_string(),
+                ([], dias) => format!("No hay logs con más de {} días", dias),
+                (archivos, dias) => format!(
+                    "Se archivaron {} logs con más de {} días en {}",
+                    resultado.entradas_archivadas,
+                    dias,
+                    archivos.iter().map(|a| a.nombre_archivo.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            };
+            Ok((
+                StatusCode::OK,
+                Json(ApiResponse {
+                    success: true,
+                    data: Some(resultado),
+                    message: Some(message),
+                }),
+            ))
+        }
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+            }),
+        )),
+    }
+}
 
+/// GET /api/logs/verificar
+/// Verificar la cadena de hashes del log de auditoría (solo admin/super_admin)
+pub async fn verificar_logs_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
+
+    match LogService::verificar_cadena(&pool).await {
+        Ok(verificacion) => {
+            let message = if verificacion.valida {
+                format!("Cadena íntegra: {} entradas verificadas", verificacion.total_entradas)
+            } else {
+                format!("Se detectaron {} problemas en la cadena de logs", verificacion.problemas.len())
+            };
             Ok((
                 StatusCode::OK,
-                Json(ApiResponse::<()> {
+                Json(ApiResponse {
                     success: true,
-                    data: None,
-                    message: Some(format!("Se eliminaron {} logs", deleted_count)),
+                    data: Some(verificacion),
+                    message: Some(message),
                 }),
             ))
         }
@@ -301,10 +391,36 @@ pub async fn limpiar_logs_handler(
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
                 success: false,
-                message: format!("Error al eliminar logs: {}", err),
+                message: err,
             }),
         )),
     }
 }
 
+/// GET /api/logs/archivos
+/// Listar los archivos generados por la política de retención (solo admin/super_admin)
+pub async fn listar_archivos_logs_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = extract_token(&headers)?;
+    verify_admin(token)?;
 
+    match LogService::listar_archivos(&pool).await {
+        Ok(archivos) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                data: Some(archivos),
+                message: None,
+            }),
+        )),
+        Err(err) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                success: false,
+                message: err,
+            }),
+        )),
+    }
+}
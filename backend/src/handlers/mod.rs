@@ -1,5 +1,6 @@
 // Handlers - Catálogo
 pub mod catalogo_handler;
+pub mod busqueda_handler;
 
 // Handlers - Autenticación y Carrito (H3nr7)
 pub mod auth_handler;
@@ -7,12 +8,15 @@ pub mod carrito_handler;
 pub mod direccion_handler;
 pub mod checkout_handler;
 pub mod metodo_pago_cliente_handler;
+pub mod armado_handler;
 pub mod admin_handler;
 
 // Handlers - Administración (main)
 pub mod venta;
 pub mod inventario_handler;
 pub mod producto_handler;
+pub mod taxonomia_handler;
+pub mod especificacion_handler;
 pub mod descuento_handler;
 pub mod cupon_handler;
 pub mod reembolso_handler;
@@ -1,12 +1,21 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use rust_decimal::Decimal;
 
+use crate::models::especificacion_producto::ValorEspecificacionRequest;
+use crate::models::producto::ActualizarProductoRequest;
+use crate::models::producto_detalle::{ActualizarVarianteRequest, CrearVarianteRequest};
+use crate::services::especificacion_service::EspecificacionError;
+use crate::services::producto_service::ProductoError;
+use crate::services::{AuthService, EspecificacionService, ProductoService};
+use crate::utils::slug;
+
 #[derive(Debug, Deserialize)]
 pub struct CreateProductoRequest {
     pub nombre: String,
@@ -30,6 +39,8 @@ pub struct CreateProductoRequest {
     pub es_nuevo: Option<bool>,
     pub es_oferta: Option<bool>,
     pub imagen_principal: Option<String>,
+    /// Valores de especificación, validados contra el esquema de la categoría
+    pub especificaciones: Option<Vec<ValorEspecificacionRequest>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,27 +50,144 @@ pub struct CreateProductoResponse {
     pub id_inventario: Option<i32>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                }),
+            )
+        })
+}
+
+fn verify_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    let claims = AuthService::verify_token(token).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                success: false,
+                message: e,
+            }),
+        )
+    })?;
+
+    if claims.rol != "super_admin" && claims.rol != "administrador" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: "Acceso denegado. Solo administradores pueden acceder".to_string(),
+            }),
+        ));
+    }
+
+    Ok(claims.sub)
+}
+
+fn error_interno() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            success: false,
+            message: "Error interno del servidor".to_string(),
+        }),
+    )
+}
+
+fn producto_error_response(err: ProductoError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        ProductoError::NoEncontrado(_) => StatusCode::NOT_FOUND,
+        ProductoError::Invalida(_) => StatusCode::BAD_REQUEST,
+        ProductoError::Conflicto(_) => StatusCode::CONFLICT,
+        ProductoError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
+fn especificacion_error_response(err: EspecificacionError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        EspecificacionError::NoEncontrado(_) => StatusCode::NOT_FOUND,
+        EspecificacionError::Invalida(_) => StatusCode::BAD_REQUEST,
+        EspecificacionError::Conflicto(_) => StatusCode::CONFLICT,
+        EspecificacionError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
 pub async fn create_producto(
     State(pool): State<PgPool>,
+    headers: HeaderMap,
     Json(payload): Json<CreateProductoRequest>,
-) -> Result<Json<CreateProductoResponse>, StatusCode> {
-    let mut tx = pool.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<Json<CreateProductoResponse>, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    // Especificaciones válidas para la categoría antes de crear nada
+    let especificaciones = EspecificacionService::validar(
+        &pool,
+        payload.id_categoria,
+        payload.especificaciones.as_deref().unwrap_or_default(),
+    )
+    .await
+    .map_err(especificacion_error_response)?;
+
+    let mut tx = pool.begin().await.map_err(|_| error_interno())?;
+
+    let slug_producto = slug::slug_unico(&mut tx, "producto", "id_producto", &payload.nombre, None)
+        .await
+        .map_err(|_| error_interno())?;
+    let slug_detalle = slug::slug_unico(&mut tx, "producto_detalle", "id_producto_detalle", &payload.nombre, None)
+        .await
+        .map_err(|_| error_interno())?;
 
     // 1. Crear producto base
     let producto_result = sqlx::query!(
         r#"
-        INSERT INTO producto (id_categoria, nombre)
-        VALUES ($1, $2)
+        INSERT INTO producto (id_categoria, nombre, slug)
+        VALUES ($1, $2, $3)
         RETURNING id_producto
         "#,
         payload.id_categoria,
-        payload.nombre
+        payload.nombre,
+        slug_producto
     )
     .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
         eprintln!("Error creating producto: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        error_interno()
     })?;
 
     let id_producto = producto_result.id_producto;
@@ -70,9 +198,9 @@ pub async fn create_producto(
         INSERT INTO producto_detalle (
             id_producto, id_marca, nombre, descripcion, modelo, sku, codigo_barras,
             precio_base, precio_venta, costo, peso, dimensiones, garantia_meses,
-            es_destacado, es_nuevo, es_oferta, imagen_principal
+            es_destacado, es_nuevo, es_oferta, imagen_principal, slug
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
         RETURNING id_producto_detalle
         "#,
         id_producto,
@@ -91,13 +219,23 @@ pub async fn create_producto(
         payload.es_destacado.unwrap_or(false),
         payload.es_nuevo.unwrap_or(false),
         payload.es_oferta.unwrap_or(false),
-        payload.imagen_principal
+        payload.imagen_principal,
+        slug_detalle
     )
     .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        eprintln!("Error creating producto_detalle: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+    .map_err(|e| match e.as_database_error() {
+        Some(db) if db.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                success: false,
+                message: format!("El SKU '{}' ya está en uso", payload.sku),
+            }),
+        ),
+        _ => {
+            eprintln!("Error creating producto_detalle: {:?}", e);
+            error_interno()
+        }
     })?;
 
     let id_producto_detalle = producto_detalle_result.id_producto_detalle;
@@ -121,7 +259,7 @@ pub async fn create_producto(
         .await
         .map_err(|e| {
             eprintln!("Error creating inventario: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            error_interno()
         })?;
 
         Some(inventario_result.id_inventario)
@@ -129,8 +267,13 @@ pub async fn create_producto(
         None
     };
 
+    // 4. Especificaciones de la variante
+    EspecificacionService::guardar(&mut tx, id_producto_detalle, &especificaciones)
+        .await
+        .map_err(especificacion_error_response)?;
+
     // Commit transaction
-    tx.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tx.commit().await.map_err(|_| error_interno())?;
 
     Ok(Json(CreateProductoResponse {
         id_producto,
@@ -153,3 +296,147 @@ pub async fn check_sku_availability(
 
     Ok(Json(result.count.unwrap_or(0) == 0))
 }
+
+// ==================== PRODUCTO BASE ====================
+
+/// GET /api/productos-base/:id
+/// Producto base con todas sus variantes, incluidas las inactivas
+pub async fn get_producto_base(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let producto = ProductoService::obtener_producto(&pool, id_producto)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(producto),
+            message: None,
+        }),
+    ))
+}
+
+/// PUT /api/productos-base/:id
+/// Al cambiar de categoría se revalidan las especificaciones de todas las variantes
+pub async fn update_producto_base(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto): Path<i32>,
+    Json(payload): Json<ActualizarProductoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let producto = ProductoService::actualizar_producto(&pool, id_producto, payload)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(producto),
+            message: Some("Producto actualizado exitosamente".to_string()),
+        }),
+    ))
+}
+
+/// DELETE /api/productos-base/:id
+/// Desactiva el producto y todas sus variantes
+pub async fn delete_producto_base(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    ProductoService::eliminar_producto(&pool, id_producto)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some("Producto desactivado exitosamente".to_string()),
+        }),
+    ))
+}
+
+// ==================== VARIANTES ====================
+
+// POST /api/productos-base/:id/variantes
+pub async fn create_variante(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto): Path<i32>,
+    Json(payload): Json<CrearVarianteRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let variante = ProductoService::crear_variante(&pool, id_producto, payload)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse {
+            success: true,
+            data: Some(variante),
+            message: Some("Variante creada exitosamente".to_string()),
+        }),
+    ))
+}
+
+/// PUT /api/productos/:id
+/// Actualiza una variante (`producto_detalle`), el mismo id que GET /api/productos/:id
+pub async fn update_producto(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto_detalle): Path<i32>,
+    Json(payload): Json<ActualizarVarianteRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let variante = ProductoService::actualizar_variante(&pool, id_producto_detalle, payload)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(variante),
+            message: Some("Producto actualizado exitosamente".to_string()),
+        }),
+    ))
+}
+
+/// DELETE /api/productos/:id
+/// Desactiva la variante; el producto base y sus otras variantes no cambian
+pub async fn delete_producto(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_producto_detalle): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    ProductoService::eliminar_variante(&pool, id_producto_detalle)
+        .await
+        .map_err(producto_error_response)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some("Producto desactivado exitosamente".to_string()),
+        }),
+    ))
+}
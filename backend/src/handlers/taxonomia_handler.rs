@@ -0,0 +1,316 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::models::categoria::{ActualizarCategoriaRequest, CrearCategoriaRequest};
+use crate::models::familia::{ActualizarFamiliaRequest, CrearFamiliaRequest};
+use crate::models::marca::{ActualizarMarcaRequest, CrearMarcaRequest};
+use crate::models::subcategoria::{ActualizarSubcategoriaRequest, CrearSubcategoriaRequest};
+use crate::services::taxonomia_service::TaxonomiaError;
+use crate::services::{AuthService, TaxonomiaService};
+
+// ==================== RESPONSES ====================
+
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// ==================== HELPER FUNCTIONS ====================
+
+fn extract_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    success: false,
+                    message: "Token no proporcionado".to_string(),
+                }),
+            )
+        })
+}
+
+fn verify_admin(token: &str) -> Result<i32, (StatusCode, Json<ErrorResponse>)> {
+    let claims = AuthService::verify_token(token).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                success: false,
+                message: e,
+            }),
+        )
+    })?;
+
+    if claims.rol != "super_admin" && claims.rol != "administrador" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                success: false,
+                message: "Acceso denegado. Solo administradores pueden acceder".to_string(),
+            }),
+        ));
+    }
+
+    Ok(claims.sub)
+}
+
+fn taxonomia_error_response(err: TaxonomiaError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match err {
+        TaxonomiaError::NoEncontrado(_) => StatusCode::NOT_FOUND,
+        TaxonomiaError::Invalida(_) => StatusCode::BAD_REQUEST,
+        TaxonomiaError::Conflicto(_) => StatusCode::CONFLICT,
+        TaxonomiaError::BaseDatos(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            success: false,
+            message: err.to_string(),
+        }),
+    )
+}
+
+fn creado<T: Serialize>(data: T, message: &str) -> impl IntoResponse {
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse {
+            success: true,
+            data: Some(data),
+            message: Some(message.to_string()),
+        }),
+    )
+}
+
+fn actualizado<T: Serialize>(data: T, message: &str) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(ApiResponse {
+            success: true,
+            data: Some(data),
+            message: Some(message.to_string()),
+        }),
+    )
+}
+
+fn desactivado(message: &str) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::<()> {
+            success: true,
+            data: None,
+            message: Some(message.to_string()),
+        }),
+    )
+}
+
+// ==================== FAMILIAS ====================
+
+// POST /api/familias
+pub async fn crear_familia_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearFamiliaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let familia = TaxonomiaService::crear_familia(&pool, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(creado(familia, "Familia creada exitosamente"))
+}
+
+// PUT /api/familias/:id
+pub async fn actualizar_familia_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_familia): Path<i32>,
+    Json(payload): Json<ActualizarFamiliaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let familia = TaxonomiaService::actualizar_familia(&pool, id_familia, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(actualizado(familia, "Familia actualizada exitosamente"))
+}
+
+/// DELETE /api/familias/:id
+/// Desactiva la familia; falla si todavía tiene categorías activas
+pub async fn eliminar_familia_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_familia): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    TaxonomiaService::eliminar_familia(&pool, id_familia)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(desactivado("Familia desactivada exitosamente"))
+}
+
+// ==================== CATEGORÍAS ====================
+
+// POST /api/categorias
+pub async fn crear_categoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearCategoriaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let categoria = TaxonomiaService::crear_categoria(&pool, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(creado(categoria, "Categoría creada exitosamente"))
+}
+
+// PUT /api/categorias/:id
+pub async fn actualizar_categoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_categoria): Path<i32>,
+    Json(payload): Json<ActualizarCategoriaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let categoria = TaxonomiaService::actualizar_categoria(&pool, id_categoria, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(actualizado(categoria, "Categoría actualizada exitosamente"))
+}
+
+/// DELETE /api/categorias/:id
+/// Desactiva la categoría y sus subcategorías; falla si todavía tiene productos activos
+pub async fn eliminar_categoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_categoria): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    TaxonomiaService::eliminar_categoria(&pool, id_categoria)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(desactivado("Categoría desactivada exitosamente"))
+}
+
+// ==================== SUBCATEGORÍAS ====================
+
+// POST /api/subcategorias
+pub async fn crear_subcategoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearSubcategoriaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let subcategoria = TaxonomiaService::crear_subcategoria(&pool, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(creado(subcategoria, "Subcategoría creada exitosamente"))
+}
+
+// PUT /api/subcategorias/:id
+pub async fn actualizar_subcategoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_subcategoria): Path<i32>,
+    Json(payload): Json<ActualizarSubcategoriaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let subcategoria = TaxonomiaService::actualizar_subcategoria(&pool, id_subcategoria, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(actualizado(subcategoria, "Subcategoría actualizada exitosamente"))
+}
+
+// DELETE /api/subcategorias/:id
+pub async fn eliminar_subcategoria_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_subcategoria): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    TaxonomiaService::eliminar_subcategoria(&pool, id_subcategoria)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(desactivado("Subcategoría desactivada exitosamente"))
+}
+
+// ==================== MARCAS ====================
+
+// POST /api/marcas
+pub async fn crear_marca_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<CrearMarcaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let marca = TaxonomiaService::crear_marca(&pool, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(creado(marca, "Marca creada exitosamente"))
+}
+
+// PUT /api/marcas/:id
+pub async fn actualizar_marca_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_marca): Path<i32>,
+    Json(payload): Json<ActualizarMarcaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    let marca = TaxonomiaService::actualizar_marca(&pool, id_marca, payload)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(actualizado(marca, "Marca actualizada exitosamente"))
+}
+
+// DELETE /api/marcas/:id
+pub async fn eliminar_marca_handler(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(id_marca): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    verify_admin(extract_token(&headers)?)?;
+
+    TaxonomiaService::eliminar_marca(&pool, id_marca)
+        .await
+        .map_err(taxonomia_error_response)?;
+
+    Ok(desactivado("Marca desactivada exitosamente"))
+}
@@ -1,5 +1,6 @@
 mod config;
 mod handlers;
+mod middleware;
 mod models;
 mod repositories;
 mod routes;
@@ -14,17 +15,22 @@ use axum::{
     Router,
 };
 use config::{DatabaseConfig, Settings};
+use std::net::SocketAddr;
 use routes::{
     catalogo_routes,
+    busqueda_routes,
     auth_routes,
     carrito_routes,
     direccion_routes,
     checkout_routes,
     metodo_pago_cliente_routes,
+    armado_routes,
     admin_routes,
     venta_routes,
     inventario_routes,
     producto_routes,
+    taxonomia_routes,
+    especificacion_routes,
     descuento_routes,
     cupon_routes,
     reembolso_routes,
@@ -38,6 +44,10 @@ async fn main() {
     // Cargar configuración
     let settings = Settings::new().expect("Failed to load settings");
 
+    // Cargar claves de firma JWT (falla si no hay secreto configurado)
+    let (kid_activo, algoritmo, total_claves) =
+        utils::jwt::inicializar(&settings.jwt).expect("Failed to load JWT keys");
+
     println!("🚀 Starting KronosTech E-Commerce Backend");
     println!("📊 Connecting to database...");
 
@@ -47,38 +57,74 @@ async fn main() {
         .expect("Failed to connect to database");
 
     println!("✅ Database connected successfully");
+    println!("🔑 {} claves JWT cargadas (activa: '{}', {})", total_claves, kid_activo, algoritmo);
 
     let pool = db_config.pool().clone();
 
+    // Cargar la configuración del sistema y escuchar sus cambios
+    match services::ConfigService::iniciar(pool.clone()).await {
+        Ok(total) => println!("⚙️  {} claves de configuración cargadas", total),
+        Err(e) => eprintln!("⚠️  {}", e),
+    }
+
+    // Cargar revocaciones de sesión para la validación de tokens
+    match services::AuthService::cargar_sesiones_revocadas(&pool).await {
+        Ok(total) => println!("🔐 {} usuarios con sesiones revocadas cargados", total),
+        Err(e) => eprintln!("⚠️  {}", e),
+    }
+
+    // Anonimizar cuentas cuyo periodo de gracia de eliminación venció
+    services::DatosPersonalesService::iniciar_tarea_eliminaciones(pool.clone());
+
+    // Archivar y purgar los logs de auditoría según la política de retención
+    services::LogService::iniciar_tarea_retencion(pool.clone());
+
+    // Reenviar los logs de auditoría al SIEM (si está habilitado en la configuración)
+    services::SiemService::iniciar_tarea_reenvio(pool.clone());
+
+    // Reglas de detección de anomalías sobre los logs de autenticación y administración
+    services::AnomaliaService::iniciar_tarea_deteccion(pool.clone());
+
     // Configurar CORS
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:5173".parse::<HeaderValue>().unwrap())
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::PATCH])
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE])
+        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE, middleware::autenticacion::HEADER_API_KEY])
+        .expose_headers([middleware::impersonacion::HEADER_SUPLANTACION])
         .allow_credentials(true);
 
     // Construir rutas
     let app = Router::new()
         // Rutas de catálogo (público)
         .nest("/api", catalogo_routes(pool.clone()))
+        .nest("/api/busqueda", busqueda_routes(pool.clone()))
         // Rutas de autenticación y carrito (H3nr7)
         .nest("/api/auth", auth_routes(pool.clone()))
         .nest("/api", carrito_routes(pool.clone()))
         .nest("/api", direccion_routes(pool.clone()))
         .nest("/api", checkout_routes(pool.clone()))
         .nest("/api/metodos-pago-cliente", metodo_pago_cliente_routes(pool.clone()))
+        .nest("/api", armado_routes(pool.clone()))
         .nest("/api/admin", admin_routes(pool.clone()))
         // Rutas de administración (main)
         .nest("/api", venta_routes(pool.clone()))
         .nest("/api", inventario_routes(pool.clone()))
         .nest("/api", producto_routes(pool.clone()))
+        .nest("/api", taxonomia_routes(pool.clone()))
+        .nest("/api", especificacion_routes(pool.clone()))
         .nest("/api", descuento_routes(pool.clone()))
         .nest("/api", cupon_routes(pool.clone()))
         .nest("/api", reembolso_routes(pool.clone()))
         // Rutas de logs y auditoría
         .nest("/api/logs", log_routes(pool.clone()))
         // Rutas de configuración del sistema
-        .nest("/api/config", config_routes(pool))
+        .nest("/api/config", config_routes(pool.clone()))
+        // Auditoría de las escrituras administrativas (actor, entidad y diff de la fila)
+        .layer(middleware::AuditoriaLayer::new(pool.clone()))
+        // Tokens de suplantación: bloqueo de escrituras y auditoría de cada solicitud
+        .layer(middleware::ImpersonacionLayer::new(pool))
+        // Modo mantenimiento: 503 según el modo vigente, salvo administradores
+        .layer(middleware::MantenimientoLayer::new())
         .layer(cors);
 
     let addr = settings.server_address();
@@ -92,11 +138,26 @@ async fn main() {
     println!("   GET  /api/productos");
     println!("   GET  /api/productos/{{id}}");
     println!("   GET  /api/productos/slug/{{slug}}");
+    println!("   GET  /api/busqueda/sugerencias");
+    println!("   GET  /api/busqueda/terminos");
     println!("   === Autenticación ===");
     println!("   POST /api/auth/register");
     println!("   POST /api/auth/login");
     println!("   GET  /api/auth/me");
     println!("   POST /api/auth/logout");
+    println!("   GET  /api/auth/jwks");
+    println!("   POST /api/auth/recuperar-password");
+    println!("   POST /api/auth/restablecer-password");
+    println!("   GET  /api/auth/2fa");
+    println!("   POST /api/auth/2fa/verificar");
+    println!("   POST /api/auth/2fa/configurar");
+    println!("   POST /api/auth/2fa/activar");
+    println!("   POST /api/auth/2fa/desactivar");
+    println!("   POST /api/auth/2fa/codigos-recuperacion");
+    println!("   GET  /api/auth/mis-datos/export");
+    println!("   GET  /api/auth/mis-datos/eliminacion");
+    println!("   POST /api/auth/mis-datos/eliminacion");
+    println!("   DELETE /api/auth/mis-datos/eliminacion");
     println!("   === Carrito de Compras ===");
     println!("   GET    /api/carrito");
     println!("   POST   /api/carrito/items");
@@ -114,6 +175,18 @@ async fn main() {
     println!("   POST   /api/checkout/procesar");
     println!("   GET    /api/pedidos");
     println!("   GET    /api/pedidos/{{id}}");
+    println!("   === Arma tu PC ===");
+    println!("   GET    /api/armados/ranuras");
+    println!("   POST   /api/armados/validar");
+    println!("   POST   /api/armados/sugerencias");
+    println!("   GET    /api/armados");
+    println!("   POST   /api/armados");
+    println!("   GET    /api/armados/{{id}}");
+    println!("   DELETE /api/armados/{{id}}");
+    println!("   PUT    /api/armados/{{id}}/componentes/{{ranura}}");
+    println!("   DELETE /api/armados/{{id}}/componentes/{{ranura}}");
+    println!("   GET    /api/armados/{{id}}/sugerencias");
+    println!("   POST   /api/armados/{{id}}/carrito");
     println!("   === Administración - Ventas ===");
     println!("   GET    /api/ventas");
     println!("   GET    /api/ventas/{{id}}");
@@ -126,6 +199,30 @@ async fn main() {
     println!("   POST   /api/productos");
     println!("   PUT    /api/productos/{{id}}");
     println!("   DELETE /api/productos/{{id}}");
+    println!("   GET    /api/productos-base/{{id}}");
+    println!("   PUT    /api/productos-base/{{id}}");
+    println!("   DELETE /api/productos-base/{{id}}");
+    println!("   POST   /api/productos-base/{{id}}/variantes");
+    println!("   GET    /api/productos/comparar");
+    println!("   GET    /api/productos/{{id}}/especificaciones");
+    println!("   PUT    /api/productos/{{id}}/especificaciones");
+    println!("   GET    /api/categorias/{{id}}/atributos");
+    println!("   POST   /api/categorias/{{id}}/atributos");
+    println!("   PUT    /api/atributos/{{id}}");
+    println!("   DELETE /api/atributos/{{id}}");
+    println!("   === Administración - Catálogo ===");
+    println!("   POST   /api/familias");
+    println!("   PUT    /api/familias/{{id}}");
+    println!("   DELETE /api/familias/{{id}}");
+    println!("   POST   /api/categorias");
+    println!("   PUT    /api/categorias/{{id}}");
+    println!("   DELETE /api/categorias/{{id}}");
+    println!("   POST   /api/subcategorias");
+    println!("   PUT    /api/subcategorias/{{id}}");
+    println!("   DELETE /api/subcategorias/{{id}}");
+    println!("   POST   /api/marcas");
+    println!("   PUT    /api/marcas/{{id}}");
+    println!("   DELETE /api/marcas/{{id}}");
     println!("   === Administración - Descuentos ===");
     println!("   GET    /api/descuentos");
     println!("   POST   /api/descuentos");
@@ -144,19 +241,28 @@ async fn main() {
     println!("   GET    /api/logs");
     println!("   POST   /api/logs");
     println!("   DELETE /api/logs/limpiar");
+    println!("   GET    /api/logs/export");
+    println!("   GET    /api/logs/verificar");
+    println!("   GET    /api/logs/archivos");
     println!("   === Configuración del Sistema ===");
     println!("   GET    /api/config");
     println!("   PUT    /api/config");
     println!("   GET    /api/config/session-timeout");
+    println!("   GET    /api/config/funcionalidades");
+    println!("   GET    /api/config/estado");
     println!("   GET    /api/config/:clave");
     println!("   PUT    /api/config/:clave");
+    println!("   GET    /api/config/historial");
+    println!("   GET    /api/config/:clave/historial");
+    println!("   POST   /api/config/:clave/rollback");
+    println!("   POST   /api/config/historial/lotes/:id_lote/rollback");
 
     // Iniciar servidor
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .expect("Failed to bind server");
 
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .expect("Failed to start server");
 }
@@ -0,0 +1,489 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::models::SolicitudAuditada;
+use crate::services::{AuthService, LogService};
+
+use super::autenticacion::HEADER_API_KEY;
+use super::rate_limit::ip_cliente;
+
+/// Tamaño máximo del cuerpo que se lee para buscar el id de la entidad
+/// (el mismo límite por defecto del extractor Json de axum)
+const LIMITE_CUERPO: usize = 2 * 1024 * 1024;
+
+/// Tamaño máximo de la respuesta que se lee para buscar el id de una fila creada;
+/// las respuestas más grandes o de tamaño desconocido pasan sin leerse
+const LIMITE_RESPUESTA: u64 = 256 * 1024;
+
+/// Columnas que nunca se copian al diff: las que contienen alguno de estos fragmentos
+/// (token_verificacion, clave_hash, codigo_hash, secreto_totp, ...)
+const CAMPOS_SENSIBLES: &[&str] = &["contrasena", "password", "hash", "secreto", "token"];
+
+/// Ruta administrativa cuyas escrituras se auditan.
+/// `patron` usa `{id}` para el segmento con el id de la entidad; otros `{...}` aceptan cualquier valor.
+struct RutaAuditada {
+    patron: &'static str,
+    entidad: &'static str,
+    modulo: &'static str,
+    /// Consulta que devuelve la fila como JSON; recibe el id en $1 si la ruta lo tiene
+    consulta: Option<&'static str>,
+    /// Campo donde buscar el id en la respuesta o en el cuerpo, si no va en la ruta
+    campo_id: Option<&'static str>,
+}
+
+const FILA_USUARIO: &str = "SELECT to_jsonb(t) FROM usuario t WHERE id_usuario::text = $1";
+const FILA_API_KEY: &str = "SELECT to_jsonb(t) FROM api_key t WHERE id_api_key::text = $1";
+const FILA_CUPON: &str = "SELECT to_jsonb(t) FROM cupon t WHERE id_cupon::text = $1";
+const ASIGNACIONES_CUPON: &str = "SELECT jsonb_build_object('usuarios_asignados', COALESCE(jsonb_agg(id_usuario ORDER BY id_usuario), '[]'::jsonb))
+     FROM asignacion_cupon WHERE id_cupon::text = $1";
+const FILA_DESCUENTO: &str = "SELECT to_jsonb(t) FROM descuento t WHERE id_descuento::text = $1";
+const FILA_INVENTARIO: &str = "SELECT to_jsonb(t) FROM inventario t WHERE id_producto_detalle::text = $1";
+const FILA_ATRIBUTO: &str = "SELECT to_jsonb(t) FROM atributo_especificacion t WHERE id_atributo::text = $1";
+const FILA_VENTA: &str = "SELECT to_jsonb(t) FROM venta t WHERE id_venta::text = $1";
+const FILA_PRODUCTO: &str = "SELECT to_jsonb(t) FROM producto t WHERE id_producto::text = $1";
+// Sin las columnas de búsqueda que mantienen los triggers
+const FILA_VARIANTE: &str = "SELECT to_jsonb(t) - 'documento_busqueda' - 'texto_busqueda'
+     FROM producto_detalle t WHERE id_producto_detalle::text = $1";
+const FILA_FAMILIA: &str = "SELECT to_jsonb(t) FROM familia t WHERE id_familia::text = $1";
+const FILA_CATEGORIA: &str = "SELECT to_jsonb(t) FROM categoria t WHERE id_categoria::text = $1";
+const FILA_SUBCATEGORIA: &str = "SELECT to_jsonb(t) FROM subcategoria t WHERE id_subcategoria::text = $1";
+const FILA_MARCA: &str = "SELECT to_jsonb(t) FROM marca t WHERE id_marca::text = $1";
+
+/// POST que solo consultan y que de otro modo coincidirían con un patrón auditado
+const RUTAS_NO_AUDITADAS: &[&str] = &["/api/productos/check-sku"];
+
+const RUTAS_AUDITADAS: &[RutaAuditada] = &[
+    // Administración de usuarios e integraciones
+    RutaAuditada { patron: "/api/admin/usuarios/{id}", entidad: "usuario", modulo: "Usuarios", consulta: Some(FILA_USUARIO), campo_id: None },
+    RutaAuditada {
+        patron: "/api/admin/usuarios/{id}/2fa",
+        entidad: "usuario",
+        modulo: "Seguridad",
+        consulta: Some("SELECT to_jsonb(t) FROM autenticacion_dos_factores t WHERE id_usuario::text = $1"),
+        campo_id: None,
+    },
+    RutaAuditada { patron: "/api/admin/usuarios/{id}/impersonar", entidad: "usuario", modulo: "Suplantación", consulta: None, campo_id: None },
+    RutaAuditada { patron: "/api/admin/administradores", entidad: "usuario", modulo: "Usuarios", consulta: Some(FILA_USUARIO), campo_id: Some("id_usuario") },
+    RutaAuditada { patron: "/api/admin/api-keys", entidad: "api_key", modulo: "Integraciones", consulta: Some(FILA_API_KEY), campo_id: Some("id_api_key") },
+    RutaAuditada { patron: "/api/admin/api-keys/{id}", entidad: "api_key", modulo: "Integraciones", consulta: Some(FILA_API_KEY), campo_id: None },
+    // Configuración
+    RutaAuditada {
+        patron: "/api/config",
+        entidad: "configuracion_sistema",
+        modulo: "Configuración",
+        consulta: Some("SELECT jsonb_object_agg(clave, valor) FROM configuracion_sistema"),
+        campo_id: None,
+    },
+    RutaAuditada {
+        patron: "/api/config/{id}",
+        entidad: "configuracion_sistema",
+        modulo: "Configuración",
+        consulta: Some("SELECT to_jsonb(t) FROM configuracion_sistema t WHERE clave = $1"),
+        campo_id: None,
+    },
+    RutaAuditada {
+        patron: "/api/config/{id}/rollback",
+        entidad: "configuracion_sistema",
+        modulo: "Configuración",
+        consulta: Some("SELECT to_jsonb(t) FROM configuracion_sistema t WHERE clave = $1"),
+        campo_id: None,
+    },
+    RutaAuditada {
+        patron: "/api/config/historial/lotes/{id}/rollback",
+        entidad: "configuracion_sistema",
+        modulo: "Configuración",
+        consulta: Some(
+            "SELECT jsonb_object_agg(clave, valor) FROM configuracion_sistema
+             WHERE clave IN (SELECT clave FROM historial_configuracion WHERE id_lote::text = $1)",
+        ),
+        campo_id: None,
+    },
+    // Cupones y descuentos
+    RutaAuditada { patron: "/api/cupones", entidad: "cupon", modulo: "Cupones", consulta: Some(FILA_CUPON), campo_id: Some("id_cupon") },
+    RutaAuditada { patron: "/api/cupones/assign", entidad: "cupon", modulo: "Cupones", consulta: Some(ASIGNACIONES_CUPON), campo_id: Some("id_cupon") },
+    RutaAuditada { patron: "/api/cupones/{id}", entidad: "cupon", modulo: "Cupones", consulta: Some(FILA_CUPON), campo_id: None },
+    RutaAuditada { patron: "/api/cupones/{id}/usuarios/{id_usuario}", entidad: "cupon", modulo: "Cupones", consulta: Some(ASIGNACIONES_CUPON), campo_id: None },
+    RutaAuditada { patron: "/api/descuentos", entidad: "descuento", modulo: "Descuentos", consulta: Some(FILA_DESCUENTO), campo_id: Some("id_descuento") },
+    RutaAuditada { patron: "/api/descuentos/{id}", entidad: "descuento", modulo: "Descuentos", consulta: Some(FILA_DESCUENTO), campo_id: None },
+    // Catálogo e inventario
+    RutaAuditada { patron: "/api/productos", entidad: "producto", modulo: "Productos", consulta: Some(FILA_PRODUCTO), campo_id: Some("id_producto") },
+    RutaAuditada { patron: "/api/productos/{id}", entidad: "producto_detalle", modulo: "Productos", consulta: Some(FILA_VARIANTE), campo_id: None },
+    RutaAuditada { patron: "/api/productos-base/{id}", entidad: "producto", modulo: "Productos", consulta: Some(FILA_PRODUCTO), campo_id: None },
+    RutaAuditada {
+        patron: "/api/productos-base/{id_producto}/variantes",
+        entidad: "producto_detalle",
+        modulo: "Productos",
+        consulta: Some(FILA_VARIANTE),
+        campo_id: Some("id_producto_detalle"),
+    },
+    RutaAuditada {
+        patron: "/api/productos/{id}/especificaciones",
+        entidad: "producto_detalle",
+        modulo: "Productos",
+        consulta: Some(
+            "SELECT COALESCE(jsonb_object_agg(nombre_atributo, valor_atributo), '{}'::jsonb)
+             FROM especificacion_producto WHERE id_producto_detalle::text = $1",
+        ),
+        campo_id: None,
+    },
+    RutaAuditada {
+        patron: "/api/categorias/{id_categoria}/atributos",
+        entidad: "atributo_especificacion",
+        modulo: "Productos",
+        consulta: Some(FILA_ATRIBUTO),
+        campo_id: Some("id_atributo"),
+    },
+    RutaAuditada { patron: "/api/familias", entidad: "familia", modulo: "Catálogo", consulta: Some(FILA_FAMILIA), campo_id: Some("id_familia") },
+    RutaAuditada { patron: "/api/familias/{id}", entidad: "familia", modulo: "Catálogo", consulta: Some(FILA_FAMILIA), campo_id: None },
+    RutaAuditada { patron: "/api/categorias", entidad: "categoria", modulo: "Catálogo", consulta: Some(FILA_CATEGORIA), campo_id: Some("id_categoria") },
+    RutaAuditada { patron: "/api/categorias/{id}", entidad: "categoria", modulo: "Catálogo", consulta: Some(FILA_CATEGORIA), campo_id: None },
+    RutaAuditada {
+        patron: "/api/subcategorias",
+        entidad: "subcategoria",
+        modulo: "Catálogo",
+        consulta: Some(FILA_SUBCATEGORIA),
+        campo_id: Some("id_subcategoria"),
+    },
+    RutaAuditada { patron: "/api/subcategorias/{id}", entidad: "subcategoria", modulo: "Catálogo", consulta: Some(FILA_SUBCATEGORIA), campo_id: None },
+    RutaAuditada { patron: "/api/marcas", entidad: "marca", modulo: "Catálogo", consulta: Some(FILA_MARCA), campo_id: Some("id_marca") },
+    RutaAuditada { patron: "/api/marcas/{id}", entidad: "marca", modulo: "Catálogo", consulta: Some(FILA_MARCA), campo_id: None },
+    RutaAuditada { patron: "/api/atributos/{id}", entidad: "atributo_especificacion", modulo: "Productos", consulta: Some(FILA_ATRIBUTO), campo_id: None },
+    RutaAuditada { patron: "/api/busqueda/terminos/{id}/bloqueo", entidad: "termino_busqueda", modulo: "Catálogo", consulta: None, campo_id: None },
+    RutaAuditada { patron: "/api/inventario/entrada", entidad: "inventario", modulo: "Inventario", consulta: Some(FILA_INVENTARIO), campo_id: Some("id_producto_detalle") },
+    RutaAuditada { patron: "/api/inventario/{id}", entidad: "inventario", modulo: "Inventario", consulta: Some(FILA_INVENTARIO), campo_id: None },
+    // Ventas y reembolsos
+    RutaAuditada { patron: "/api/ventas/{id}/estado", entidad: "venta", modulo: "Ventas", consulta: Some(FILA_VENTA), campo_id: None },
+    RutaAuditada { patron: "/api/ventas/{id}/tracking", entidad: "venta", modulo: "Ventas", consulta: Some(FILA_VENTA), campo_id: None },
+    RutaAuditada { patron: "/api/ventas/{id}/notas", entidad: "venta", modulo: "Ventas", consulta: Some(FILA_VENTA), campo_id: None },
+    RutaAuditada {
+        patron: "/api/reembolsos/{id}/procesar",
+        entidad: "reembolso",
+        modulo: "Reembolsos",
+        consulta: Some("SELECT to_jsonb(t) FROM reembolso t WHERE id_reembolso::text = $1"),
+        campo_id: None,
+    },
+    // Logs
+    RutaAuditada {
+        patron: "/api/logs/limpiar",
+        entidad: "log_auditoria",
+        modulo: "Sistema",
+        consulta: Some("SELECT jsonb_build_object('total_logs', COUNT(*)) FROM log_auditoria"),
+        campo_id: None,
+    },
+];
+
+// Buscar la ruta auditada que corresponde a `ruta` y el id que va en ella, si lo hay
+fn buscar_ruta(ruta: &str) -> Option<(&'static RutaAuditada, Option<String>)> {
+    let ruta = ruta.trim_end_matches('/');
+    if RUTAS_NO_AUDITADAS.contains(&ruta) {
+        return None;
+    }
+    let segmentos: Vec<&str> = ruta.split('/').collect();
+
+    RUTAS_AUDITADAS.iter().find_map(|auditada| {
+        let patron: Vec<&str> = auditada.patron.split('/').collect();
+        if patron.len() != segmentos.len() {
+            return None;
+        }
+
+        let mut id = None;
+        for (p, s) in patron.iter().zip(&segmentos) {
+            match *p {
+                "{id}" => id = Some(s.to_string()),
+                comodin if comodin.starts_with('{') => {}
+                literal if literal == *s => {}
+                _ => return None,
+            }
+        }
+        Some((auditada, id))
+    })
+}
+
+// Buscar `campo` en un JSON: en la raíz, en `data` o un nivel más abajo (p. ej. data.api_key)
+fn buscar_campo(valor: &Value, campo: &str) -> Option<String> {
+    let texto = |v: &Value| match v {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    };
+
+    let raiz = valor.as_object()?;
+    if let Some(id) = raiz.get(campo).and_then(texto) {
+        return Some(id);
+    }
+
+    let data = raiz.get("data").and_then(Value::as_object)?;
+    data.get(campo).and_then(texto).or_else(|| {
+        data.values()
+            .filter_map(Value::as_object)
+            .find_map(|hijo| hijo.get(campo).and_then(texto))
+    })
+}
+
+async fn instantanea(pool: &PgPool, consulta: &str, id: Option<&str>) -> Option<Value> {
+    let mut query = sqlx::query_scalar::<_, Option<Value>>(consulta);
+    if let Some(id) = id {
+        query = query.bind(id);
+    }
+
+    match query.fetch_optional(pool).await {
+        Ok(fila) => fila.flatten(),
+        Err(e) => {
+            eprintln!("⚠️  Error al leer la fila auditada: {}", e);
+            None
+        }
+    }
+}
+
+fn campo_sensible(campo: &str) -> bool {
+    let campo = campo.to_lowercase();
+    CAMPOS_SENSIBLES.iter().any(|fragmento| campo.contains(fragmento))
+}
+
+// Campos que cambiaron: {"campo": {"antes": ..., "despues": ...}}
+fn diferencias(antes: Option<&Value>, despues: Option<&Value>) -> Option<Value> {
+    let vacio = Map::new();
+    let antes = antes.and_then(Value::as_object).unwrap_or(&vacio);
+    let despues = despues.and_then(Value::as_object).unwrap_or(&vacio);
+
+    let mut campos: Vec<&String> = antes.keys().chain(despues.keys()).collect();
+    campos.sort();
+    campos.dedup();
+
+    let cambios: Map<String, Value> = campos
+        .into_iter()
+        .filter(|campo| !campo_sensible(campo))
+        .filter(|campo| antes.get(*campo).unwrap_or(&Value::Null) != despues.get(*campo).unwrap_or(&Value::Null))
+        .map(|campo| {
+            (
+                campo.clone(),
+                json!({
+                    "antes": antes.get(campo).cloned().unwrap_or(Value::Null),
+                    "despues": despues.get(campo).cloned().unwrap_or(Value::Null),
+                }),
+            )
+        })
+        .collect();
+
+    (!cambios.is_empty()).then_some(Value::Object(cambios))
+}
+
+// ==================== LAYER ====================
+
+/// Auditoría del lado del servidor: registra en log_auditoria cada escritura sobre
+/// las rutas administrativas con el actor, la ruta, la entidad afectada, el diff de
+/// la fila antes y después, la IP y el navegador. No depende de que el frontend
+/// llame a POST /api/logs.
+#[derive(Clone)]
+pub struct AuditoriaLayer {
+    pool: PgPool,
+}
+
+impl AuditoriaLayer {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl<S> Layer<S> for AuditoriaLayer {
+    type Service = AuditoriaService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditoriaService {
+            inner,
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditoriaService<S> {
+    inner: S,
+    pool: PgPool,
+}
+
+impl<S> Service<Request<Body>> for AuditoriaService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let pool = self.pool.clone();
+
+        let escritura = !matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+        let auditada = escritura.then(|| buscar_ruta(request.uri().path())).flatten();
+
+        Box::pin(async move {
+            let Some((auditada, id_ruta)) = auditada else {
+                return inner.call(request).await;
+            };
+
+            let metodo = request.method().to_string();
+            let ruta = request.uri().path().to_string();
+            let headers = request.headers();
+            let peer = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip());
+            let ip = ip_cliente(headers, peer);
+            let user_agent = headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+
+            // Actor: usuario del token (el administrador real si es una suplantación) o API key
+            let claims = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .and_then(|token| AuthService::verify_token(token).ok());
+            let api_key = headers
+                .get(HEADER_API_KEY)
+                .and_then(|v| v.to_str().ok())
+                .map(|clave| clave.rsplit_once('_').map(|(prefijo, _)| prefijo).unwrap_or("?").to_string());
+            let (id_actor, email_actor, actor) = match (&claims, &api_key) {
+                (Some(claims), _) => match &claims.impersonacion {
+                    Some(imp) => (
+                        Some(imp.id_admin),
+                        Some(imp.email_admin.clone()),
+                        format!("{} (como {})", imp.email_admin, claims.email),
+                    ),
+                    None => (Some(claims.sub), Some(claims.email.clone()), format!("{} ({})", claims.email, claims.rol)),
+                },
+                (None, Some(prefijo)) => (None, None, format!("API key {}", prefijo)),
+                (None, None) => (None, None, "sin autenticar".to_string()),
+            };
+
+            // Si el id no va en la ruta, puede venir en el cuerpo (p. ej. id_cupon al asignar)
+            let (request, mut id_entidad) = match (id_ruta, auditada.campo_id) {
+                (Some(id), _) => (request, Some(id)),
+                (None, Some(campo)) => {
+                    let (parts, body) = request.into_parts();
+                    let bytes = match to_bytes(body, LIMITE_CUERPO).await {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            return Ok((
+                                StatusCode::PAYLOAD_TOO_LARGE,
+                                Json(json!({
+                                    "success": false,
+                                    "message": "El cuerpo de la solicitud es demasiado grande",
+                                })),
+                            )
+                                .into_response());
+                        }
+                    };
+                    let id = serde_json::from_slice::<Value>(&bytes)
+                        .ok()
+                        .and_then(|cuerpo| buscar_campo(&cuerpo, campo));
+                    (Request::from_parts(parts, Body::from(bytes)), id)
+                }
+                (None, None) => (request, None),
+            };
+
+            let con_id = auditada.patron.contains("{id}") || auditada.campo_id.is_some();
+            let leer = |id: Option<String>| {
+                let pool = pool.clone();
+                async move {
+                    match (auditada.consulta, con_id, id) {
+                        (Some(consulta), false, _) => instantanea(&pool, consulta, None).await,
+                        (Some(consulta), true, Some(id)) => instantanea(&pool, consulta, Some(&id)).await,
+                        _ => None,
+                    }
+                }
+            };
+
+            let antes = leer(id_entidad.clone()).await;
+
+            let mut response = inner.call(request).await?;
+            let status = response.status();
+
+            // Creaciones: el id de la nueva fila viene en la respuesta
+            if id_entidad.is_none() && status.is_success() {
+                let acotada = response.body().size_hint().upper().is_some_and(|n| n <= LIMITE_RESPUESTA);
+                if let (Some(campo), true) = (auditada.campo_id, acotada) {
+                    let (parts, body) = response.into_parts();
+                    let bytes = to_bytes(body, LIMITE_RESPUESTA as usize).await.unwrap_or_default();
+                    id_entidad = serde_json::from_slice::<Value>(&bytes)
+                        .ok()
+                        .and_then(|cuerpo| buscar_campo(&cuerpo, campo));
+                    response = Response::from_parts(parts, Body::from(bytes));
+                }
+            }
+
+            let despues = leer(id_entidad.clone()).await;
+            let cambios = diferencias(antes.as_ref(), despues.as_ref());
+
+            let resumen = match cambios.as_ref().and_then(Value::as_object) {
+                Some(campos) => format!(
+                    "{} campo(s) modificados: {}",
+                    campos.len(),
+                    campos.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+                None => "sin cambios en la fila".to_string(),
+            };
+            let entidad = match &id_entidad {
+                Some(id) => format!("{} #{}", auditada.entidad, id),
+                None => auditada.entidad.to_string(),
+            };
+            let detalles = format!(
+                "{} {} por {} → {}; {}",
+                metodo,
+                entidad,
+                actor,
+                status.as_u16(),
+                resumen
+            );
+
+            let mut log = LogService::nuevo(
+                if status.is_success() { NivelLog::Info } else { NivelLog::Warning },
+                &format!("{} {}", metodo, auditada.patron),
+                auditada.modulo,
+                Some(detalles),
+                email_actor,
+                Some(ip),
+            );
+            log.user_agent = user_agent;
+
+            LogService::registrar_solicitud(
+                &pool,
+                id_actor,
+                log,
+                SolicitudAuditada {
+                    metodo_http: metodo,
+                    ruta,
+                    entidad: auditada.entidad.to_string(),
+                    id_entidad,
+                    cambios,
+                    codigo_respuesta: status.as_u16() as i16,
+                },
+            )
+            .await;
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,217 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, OriginalUri},
+    http::{header, HeaderName, HeaderValue, Method, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::services::{ApiKeyService, AuthService, LogService};
+
+use super::rate_limit::{consumir, ip_cliente};
+
+/// Header con el que las integraciones envían su API key
+pub const HEADER_API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+const MODULO_LOG: &str = "Integraciones";
+
+const ROLES_ADMIN: &[&str] = &["administrador", "super_admin"];
+
+fn rechazar(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "success": false,
+            "message": message,
+        })),
+    )
+        .into_response()
+}
+
+// ==================== LAYER ====================
+
+/// Autentica las rutas de un recurso (inventario, ventas) para dos tipos de cliente:
+/// - Integraciones con `X-Api-Key`: la clave debe estar vigente, usarse desde una IP
+///   permitida, tener el scope `<recurso>:leer` (GET) o `<recurso>:escribir` (resto)
+///   y no superar su límite de solicitudes por minuto.
+/// - Panel de administración con `Authorization: Bearer`: el token debe ser de un administrador.
+#[derive(Clone)]
+pub struct AutenticacionLayer {
+    pool: PgPool,
+    recurso: &'static str,
+}
+
+impl AutenticacionLayer {
+    pub fn new(pool: PgPool, recurso: &'static str) -> Self {
+        Self { pool, recurso }
+    }
+}
+
+impl<S> Layer<S> for AutenticacionLayer {
+    type Service = AutenticacionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AutenticacionService {
+            inner,
+            pool: self.pool.clone(),
+            recurso: self.recurso,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AutenticacionService<S> {
+    inner: S,
+    pool: PgPool,
+    recurso: &'static str,
+}
+
+impl<S> Service<Request<Body>> for AutenticacionService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let pool = self.pool.clone();
+        let recurso = self.recurso;
+
+        let clave = request
+            .headers()
+            .get(HEADER_API_KEY)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let bearer = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|v| v.to_string());
+
+        Box::pin(async move {
+            let Some(clave) = clave else {
+                return match bearer {
+                    Some(token) => match AuthService::verify_token(&token) {
+                        Ok(claims) if ROLES_ADMIN.contains(&claims.rol.as_str()) => inner.call(request).await,
+                        Ok(_) => Ok(rechazar(StatusCode::FORBIDDEN, "No tienes permisos para acceder a este recurso")),
+                        Err(_) => Ok(rechazar(StatusCode::UNAUTHORIZED, "Token inválido o expirado")),
+                    },
+                    None => Ok(rechazar(StatusCode::UNAUTHORIZED, "Se requiere una API key o un token de administrador")),
+                };
+            };
+
+            let metodo = request.method().clone();
+            let ruta = request
+                .extensions()
+                .get::<OriginalUri>()
+                .map(|OriginalUri(uri)| uri.path().to_string())
+                .unwrap_or_else(|| request.uri().path().to_string());
+            let peer = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip());
+            let ip = ip_cliente(request.headers(), peer);
+
+            let api_key = match ApiKeyService::autenticar(&pool, &clave, &ip).await {
+                Ok(api_key) => api_key,
+                Err(motivo) => {
+                    LogService::registrar(
+                        &pool,
+                        None,
+                        LogService::nuevo(
+                            NivelLog::Warning,
+                            "API key rechazada",
+                            MODULO_LOG,
+                            Some(format!("{}: {} {}", motivo, metodo, ruta)),
+                            None,
+                            Some(ip),
+                        ),
+                    )
+                    .await;
+                    return Ok(rechazar(StatusCode::UNAUTHORIZED, "API key inválida, expirada o no permitida"));
+                }
+            };
+
+            let escritura = !matches!(metodo, Method::GET | Method::HEAD | Method::OPTIONS);
+            let scope = format!("{}:{}", recurso, if escritura { "escribir" } else { "leer" });
+            if !api_key.scopes.contains(&scope) {
+                return Ok(rechazar(
+                    StatusCode::FORBIDDEN,
+                    &format!("La API key no tiene el scope {}", scope),
+                ));
+            }
+
+            if let Err(rechazo) = consumir(
+                format!("api_key:{}", api_key.id_api_key),
+                api_key.limite_por_minuto as u32,
+                60,
+            ) {
+                let mut response = rechazar(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    &format!(
+                        "Límite de {} solicitudes por minuto excedido. Intenta nuevamente en {} segundos",
+                        api_key.limite_por_minuto, rechazo.retry_after
+                    ),
+                );
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(rechazo.retry_after));
+                return Ok(response);
+            }
+
+            {
+                let pool = pool.clone();
+                let ip = ip.clone();
+                tokio::spawn(async move {
+                    ApiKeyService::registrar_uso(&pool, api_key.id_api_key, &ip).await;
+                });
+            }
+
+            let response = inner.call(request).await?;
+
+            // Las lecturas quedan en ultimo_uso/total_usos; las escrituras se auditan
+            if escritura {
+                LogService::registrar(
+                    &pool,
+                    None,
+                    LogService::nuevo(
+                        NivelLog::Info,
+                        "Solicitud con API key",
+                        MODULO_LOG,
+                        Some(format!(
+                            "API key '{}' ({}): {} {} → {}",
+                            api_key.nombre,
+                            api_key.prefijo,
+                            metodo,
+                            ruta,
+                            response.status().as_u16()
+                        )),
+                        None,
+                        Some(ip),
+                    ),
+                )
+                .await;
+            }
+
+            Ok(response)
+        })
+    }
+}
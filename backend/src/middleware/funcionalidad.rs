@@ -0,0 +1,114 @@
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, HeaderMap, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::services::feature_flag_service::{FeatureFlagService, Funcionalidad, SujetoFuncionalidad};
+use crate::services::AuthService;
+
+use super::rate_limit::ip_cliente;
+
+/// Sujeto con el que se evalúan las funcionalidades: usuario del token (si es válido) e IP real
+pub fn sujeto_funcionalidad(headers: &HeaderMap, peer: Option<IpAddr>) -> SujetoFuncionalidad {
+    let claims = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| AuthService::verify_token(token).ok());
+
+    SujetoFuncionalidad {
+        id_usuario: claims.as_ref().map(|claims| claims.sub),
+        rol: claims.map(|claims| claims.rol),
+        ip: ip_cliente(headers, peer),
+    }
+}
+
+/// Respuesta 403 de una funcionalidad deshabilitada
+pub fn respuesta_deshabilitada(funcionalidad: Funcionalidad, sujeto: &SujetoFuncionalidad) -> Option<Response> {
+    FeatureFlagService::verificar(funcionalidad, sujeto).err().map(|error| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "success": false,
+                "message": error.message,
+                "funcionalidad": error.funcionalidad,
+            })),
+        )
+            .into_response()
+    })
+}
+
+// ==================== LAYER ====================
+
+/// Rechaza las solicitudes a una ruta cuya funcionalidad está apagada
+/// (o fuera del despliegue para el usuario) antes de llegar al handler
+#[derive(Clone)]
+pub struct FuncionalidadLayer {
+    funcionalidad: Funcionalidad,
+}
+
+impl FuncionalidadLayer {
+    pub fn new(funcionalidad: Funcionalidad) -> Self {
+        Self { funcionalidad }
+    }
+}
+
+impl<S> Layer<S> for FuncionalidadLayer {
+    type Service = FuncionalidadService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FuncionalidadService {
+            inner,
+            funcionalidad: self.funcionalidad,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FuncionalidadService<S> {
+    inner: S,
+    funcionalidad: Funcionalidad,
+}
+
+impl<S> Service<Request<Body>> for FuncionalidadService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let sujeto = sujeto_funcionalidad(request.headers(), peer);
+        let rechazo = respuesta_deshabilitada(self.funcionalidad, &sujeto);
+
+        Box::pin(async move {
+            match rechazo {
+                Some(response) => Ok(response),
+                None => inner.call(request).await,
+            }
+        })
+    }
+}
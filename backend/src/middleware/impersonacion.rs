@@ -0,0 +1,190 @@
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, HeaderName, HeaderValue, Method, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::services::auth_service::{Claims, Impersonacion, ACCION_NO_PERMITIDA_SUPLANTACION};
+use crate::services::{AuthService, LogService};
+
+use super::rate_limit::ip_cliente;
+
+/// Header que marca las respuestas servidas a un token de suplantación
+pub const HEADER_SUPLANTACION: HeaderName = HeaderName::from_static("x-suplantacion");
+
+const MODULO_LOG: &str = "Suplantación";
+
+/// Rutas vedadas con cualquier método: datos personales, 2FA y administración
+const RUTAS_BLOQUEADAS: &[&str] = &["/api/auth/mis-datos", "/api/auth/2fa", "/api/admin"];
+
+/// Rutas que no se pueden modificar ni con un token de escritura: pagos y credenciales
+const RUTAS_SIN_ESCRITURA: &[&str] = &[
+    "/api/checkout",
+    "/api/metodos-pago-cliente",
+    "/api/auth/cambiar-password",
+    "/api/auth/restablecer-password",
+    "/api/auth/perfil",
+];
+
+/// Rutas de escritura permitidas incluso en solo lectura
+const RUTAS_PERMITIDAS: &[&str] = &["/api/auth/logout"];
+
+fn coincide(ruta: &str, prefijos: &[&str]) -> bool {
+    prefijos.iter().any(|prefijo| {
+        ruta == *prefijo || ruta.strip_prefix(prefijo).is_some_and(|resto| resto.starts_with('/'))
+    })
+}
+
+// Motivo por el que se rechaza la solicitud, si corresponde
+fn motivo_bloqueo(metodo: &Method, ruta: &str, impersonacion: &Impersonacion) -> Option<&'static str> {
+    let lectura = matches!(*metodo, Method::GET | Method::HEAD | Method::OPTIONS);
+
+    if coincide(ruta, RUTAS_BLOQUEADAS) {
+        return Some(ACCION_NO_PERMITIDA_SUPLANTACION);
+    }
+
+    if lectura || coincide(ruta, RUTAS_PERMITIDAS) {
+        return None;
+    }
+
+    if coincide(ruta, RUTAS_SIN_ESCRITURA) {
+        return Some(ACCION_NO_PERMITIDA_SUPLANTACION);
+    }
+
+    if impersonacion.solo_lectura {
+        return Some("La suplantación es de solo lectura");
+    }
+
+    None
+}
+
+// ==================== LAYER ====================
+
+/// Controla los tokens de suplantación emitidos por un super_admin: bloquea las
+/// escrituras no permitidas y registra en log_auditoria cada solicitud con la
+/// identidad del administrador y la del cliente. Los demás tokens pasan sin cambios.
+#[derive(Clone)]
+pub struct ImpersonacionLayer {
+    pool: PgPool,
+}
+
+impl ImpersonacionLayer {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl<S> Layer<S> for ImpersonacionLayer {
+    type Service = ImpersonacionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ImpersonacionService {
+            inner,
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ImpersonacionService<S> {
+    inner: S,
+    pool: PgPool,
+}
+
+impl<S> Service<Request<Body>> for ImpersonacionService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let pool = self.pool.clone();
+
+        let claims = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .and_then(|token| AuthService::verify_token(token).ok())
+            .filter(|claims| claims.impersonacion.is_some());
+
+        Box::pin(async move {
+            let Some(Claims { sub, email, impersonacion: Some(impersonacion), .. }) = claims else {
+                return inner.call(request).await;
+            };
+
+            let metodo = request.method().clone();
+            let ruta = request.uri().path().to_string();
+            let peer = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip());
+            let ip = ip_cliente(request.headers(), peer);
+            let identidades = format!(
+                "{} (#{}) como {} (#{})",
+                impersonacion.email_admin, impersonacion.id_admin, email, sub
+            );
+
+            let (mut response, nivel, accion, estado) =
+                match motivo_bloqueo(&metodo, &ruta, &impersonacion) {
+                    Some(motivo) => {
+                        let response = (
+                            StatusCode::FORBIDDEN,
+                            Json(json!({
+                                "success": false,
+                                "message": motivo,
+                            })),
+                        )
+                            .into_response();
+                        (response, NivelLog::Warning, "Acción bloqueada durante suplantación", "bloqueada".to_string())
+                    }
+                    None => {
+                        let response = inner.call(request).await?;
+                        let estado = response.status().as_u16().to_string();
+                        (response, NivelLog::Info, "Solicitud durante suplantación", estado)
+                    }
+                };
+
+            LogService::registrar(
+                &pool,
+                Some(impersonacion.id_admin),
+                LogService::nuevo(
+                    nivel,
+                    accion,
+                    MODULO_LOG,
+                    Some(format!("{}: {} {} → {}", identidades, metodo, ruta, estado)),
+                    Some(impersonacion.email_admin.clone()),
+                    Some(ip),
+                ),
+            )
+            .await;
+
+            response.headers_mut().insert(
+                HEADER_SUPLANTACION,
+                HeaderValue::from_static(if impersonacion.solo_lectura { "solo-lectura" } else { "escritura" }),
+            );
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+use crate::services::mantenimiento_service::{EstadoMantenimiento, MantenimientoService, ModoMantenimiento};
+use crate::services::AuthService;
+
+use super::autenticacion::HEADER_API_KEY;
+
+const ROLES_ADMIN: &[&str] = &["administrador", "super_admin"];
+
+/// Rutas que en solo lectura no aceptan escrituras: todo lo que modifica el carrito
+/// o crea pedidos. `*` coincide con un segmento cualquiera (un id).
+const RUTAS_SOLO_LECTURA: &[&str] = &["/api/carrito", "/api/checkout", "/api/armados/*/carrito"];
+
+/// Rutas de integraciones: con API key pasan siempre, la clave se valida en la propia ruta
+const RUTAS_INTEGRACIONES: &[&str] = &["/api/inventario", "/api/ventas"];
+
+/// Rutas que siguen respondiendo con la tienda cerrada: el estado y lo necesario
+/// para que un administrador inicie sesión
+const RUTAS_SIEMPRE_ABIERTAS: &[&str] = &[
+    "/api/config/estado",
+    "/api/auth/login",
+    "/api/auth/2fa/verificar",
+    "/api/auth/logout",
+    "/api/auth/jwks",
+];
+
+// La ruta empieza por alguno de los prefijos, comparando segmento a segmento
+fn coincide(ruta: &str, prefijos: &[&str]) -> bool {
+    prefijos.iter().any(|prefijo| {
+        let mut segmentos = ruta.split('/');
+        prefijo
+            .split('/')
+            .all(|patron| segmentos.next().is_some_and(|segmento| patron == "*" || segmento == patron))
+    })
+}
+
+// La solicitud queda bloqueada por el modo vigente
+fn bloqueada(modo: ModoMantenimiento, metodo: &Method, ruta: &str) -> bool {
+    match modo {
+        ModoMantenimiento::Off => false,
+        ModoMantenimiento::ReadOnly => {
+            !matches!(*metodo, Method::GET | Method::HEAD | Method::OPTIONS) && coincide(ruta, RUTAS_SOLO_LECTURA)
+        }
+        ModoMantenimiento::Closed => !coincide(ruta, RUTAS_SIEMPRE_ABIERTAS),
+    }
+}
+
+fn respuesta_mantenimiento(estado: &EstadoMantenimiento) -> Response {
+    let mut response = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "success": false,
+            "message": estado.mensaje,
+            "mantenimiento": estado,
+        })),
+    )
+        .into_response();
+
+    if let Some(segundos) = estado.segundos_restantes() {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(segundos));
+    }
+
+    response
+}
+
+// ==================== LAYER ====================
+
+/// Modo mantenimiento (`maintenance_mode`, con ventana opcional): en solo lectura
+/// rechaza con 503 las escrituras de carrito y checkout; cerrado, todas las rutas
+/// salvo las de estado e inicio de sesión. Los tokens de administrador (no los de
+/// suplantación) y las integraciones con API key pasan siempre.
+#[derive(Clone, Default)]
+pub struct MantenimientoLayer;
+
+impl MantenimientoLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for MantenimientoLayer {
+    type Service = Mantenimiento<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Mantenimiento { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct Mantenimiento<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for Mantenimiento<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let estado = MantenimientoService::estado();
+        let rechazo = bloqueada(estado.modo, request.method(), request.uri().path()) && !exento(&request);
+
+        Box::pin(async move {
+            if rechazo {
+                return Ok(respuesta_mantenimiento(&estado));
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+// Administradores con su propio token e integraciones con API key
+fn exento(request: &Request<Body>) -> bool {
+    if request.headers().contains_key(HEADER_API_KEY) && coincide(request.uri().path(), RUTAS_INTEGRACIONES) {
+        return true;
+    }
+
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| AuthService::verify_token(token).ok())
+        .is_some_and(|claims| claims.impersonacion.is_none() && ROLES_ADMIN.contains(&claims.rol.as_str()))
+}
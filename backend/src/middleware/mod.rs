@@ -0,0 +1,14 @@
+// Middlewares (tower layers) compartidos por las rutas
+pub mod auditoria;
+pub mod autenticacion;
+pub mod funcionalidad;
+pub mod impersonacion;
+pub mod mantenimiento;
+pub mod rate_limit;
+
+pub use auditoria::AuditoriaLayer;
+pub use autenticacion::AutenticacionLayer;
+pub use funcionalidad::FuncionalidadLayer;
+pub use impersonacion::ImpersonacionLayer;
+pub use mantenimiento::MantenimientoLayer;
+pub use rate_limit::RateLimitLayer;
@@ -0,0 +1,354 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, OriginalUri},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::services::{AuthService, ConfigService, LogService};
+
+/// Cada cuántas solicitudes se eliminan las cubetas inactivas
+const LIMPIEZA_CADA: u64 = 1_000;
+
+// Cubetas por "grupo:tipo:clave"
+static CUBETAS: LazyLock<Mutex<HashMap<String, Cubeta>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static CONTADOR: AtomicU64 = AtomicU64::new(0);
+
+// ==================== CONFIGURACIÓN ====================
+
+/// Cuota de un grupo de rutas (clave `rate_limit_<grupo>`, tipo json).
+/// `por_ip` y `por_usuario` son la capacidad de la cubeta, que se recarga por
+/// completo en `periodo_segundos`. Un valor 0 desactiva ese límite.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Cuota {
+    #[serde(default)]
+    pub por_ip: u32,
+    #[serde(default)]
+    pub por_usuario: u32,
+    pub periodo_segundos: u64,
+}
+
+impl Cuota {
+    // Valores por defecto si el grupo no está configurado
+    fn por_defecto(grupo: &str) -> Self {
+        match grupo {
+            "login" => Self { por_ip: 10, por_usuario: 0, periodo_segundos: 60 },
+            "registro" => Self { por_ip: 5, por_usuario: 0, periodo_segundos: 3600 },
+            "valoraciones" => Self { por_ip: 20, por_usuario: 5, periodo_segundos: 3600 },
+            "checkout" => Self { por_ip: 20, por_usuario: 10, periodo_segundos: 600 },
+            _ => Self { por_ip: 60, por_usuario: 60, periodo_segundos: 60 },
+        }
+    }
+}
+
+// Cuota configurada del grupo, o la de por defecto si falta o no es válida
+fn cuota(grupo: &str) -> Cuota {
+    ConfigService::json::<Cuota>(&format!("rate_limit_{}", grupo))
+        .filter(|cuota| cuota.periodo_segundos > 0)
+        .unwrap_or_else(|| Cuota::por_defecto(grupo))
+}
+
+fn proxies_confiables() -> Vec<RedIp> {
+    ConfigService::texto("trusted_proxies")
+        .map(|valor| valor.split(',').filter_map(RedIp::parse).collect())
+        .unwrap_or_default()
+}
+
+// ==================== IP DEL CLIENTE ====================
+
+/// IP o red en notación CIDR ("10.0.0.0/8")
+#[derive(Debug, Clone, Copy)]
+pub struct RedIp {
+    red: IpAddr,
+    prefijo: u8,
+}
+
+impl RedIp {
+    pub fn parse(valor: &str) -> Option<Self> {
+        let valor = valor.trim();
+        let (ip, prefijo) = match valor.split_once('/') {
+            Some((ip, prefijo)) => (ip.parse::<IpAddr>().ok()?, prefijo.parse::<u8>().ok()?),
+            None => {
+                let ip = valor.parse::<IpAddr>().ok()?;
+                (ip, if ip.is_ipv4() { 32 } else { 128 })
+            }
+        };
+        Some(Self { red: ip, prefijo })
+    }
+
+    pub fn contiene(&self, ip: &IpAddr) -> bool {
+        match (self.red, ip) {
+            (IpAddr::V4(red), IpAddr::V4(ip)) => {
+                let prefijo = self.prefijo.min(32) as u32;
+                let mascara = u32::MAX.checked_shl(32 - prefijo).unwrap_or(0);
+                (u32::from(red) & mascara) == (u32::from(*ip) & mascara)
+            }
+            (IpAddr::V6(red), IpAddr::V6(ip)) => {
+                let prefijo = self.prefijo.min(128) as u32;
+                let mascara = u128::MAX.checked_shl(128 - prefijo).unwrap_or(0);
+                (u128::from(red) & mascara) == (u128::from(*ip) & mascara)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Resolver la IP real del cliente. `X-Forwarded-For` solo se acepta si la conexión
+/// viene de un proxy confiable; se recorre de derecha a izquierda saltando proxies
+/// confiables, para que el cliente no pueda falsificar su IP anteponiendo valores.
+fn resolver_ip_cliente(headers: &HeaderMap, peer: Option<IpAddr>, proxies: &[RedIp]) -> String {
+    let Some(peer) = peer else {
+        return "desconocida".to_string();
+    };
+
+    let es_confiable = |ip: &IpAddr| proxies.iter().any(|red| red.contiene(ip));
+
+    if !es_confiable(&peer) {
+        return peer.to_string();
+    }
+
+    let reenviadas: Vec<IpAddr> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .collect();
+
+    if let Some(cliente) = reenviadas.iter().rev().find(|ip| !es_confiable(ip)) {
+        return cliente.to_string();
+    }
+
+    headers
+        .get("X-Real-IP")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<IpAddr>().ok())
+        .unwrap_or(peer)
+        .to_string()
+}
+
+fn ip_de_solicitud(request: &Request<Body>, proxies: &[RedIp]) -> String {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    resolver_ip_cliente(request.headers(), peer, proxies)
+}
+
+/// IP real del cliente de una solicitud, con la misma lista de proxies confiables
+/// que usa el límite de solicitudes (para otros middlewares)
+pub fn ip_cliente(headers: &HeaderMap, peer: Option<IpAddr>) -> String {
+    resolver_ip_cliente(headers, peer, &proxies_confiables())
+}
+
+// ==================== TOKEN BUCKET ====================
+
+#[derive(Debug)]
+struct Cubeta {
+    tokens: f64,
+    actualizada: Instant,
+    periodo: Duration,
+    ultimo_log: Option<Instant>,
+}
+
+/// Resultado de consumir un token cuando la cubeta está vacía
+pub struct Rechazo {
+    pub retry_after: u64,
+    pub registrar_log: bool,
+}
+
+/// Consumir un token de la cubeta `clave`; también lo usan otros middlewares con su propio prefijo
+pub fn consumir(clave: String, capacidad: u32, periodo_segundos: u64) -> Result<(), Rechazo> {
+    let ahora = Instant::now();
+    let capacidad = capacidad as f64;
+    let periodo = Duration::from_secs(periodo_segundos);
+    let recarga_por_segundo = capacidad / periodo_segundos as f64;
+
+    let Ok(mut cubetas) = CUBETAS.lock() else {
+        return Ok(());
+    };
+
+    if CONTADOR.fetch_add(1, Ordering::Relaxed).is_multiple_of(LIMPIEZA_CADA) {
+        // Una cubeta sin uso durante su periodo ya está llena: no aporta estado
+        cubetas.retain(|_, cubeta| cubeta.actualizada.elapsed() < cubeta.periodo);
+    }
+
+    let cubeta = cubetas.entry(clave).or_insert(Cubeta {
+        tokens: capacidad,
+        actualizada: ahora,
+        periodo,
+        ultimo_log: None,
+    });
+
+    let transcurrido = ahora.duration_since(cubeta.actualizada).as_secs_f64();
+    cubeta.tokens = (cubeta.tokens + transcurrido * recarga_por_segundo).min(capacidad);
+    cubeta.actualizada = ahora;
+    cubeta.periodo = periodo;
+
+    if cubeta.tokens >= 1.0 {
+        cubeta.tokens -= 1.0;
+        return Ok(());
+    }
+
+    // Un solo registro de auditoría por cubeta y periodo, para no inundar el log
+    let registrar_log = cubeta.ultimo_log.is_none_or(|ultimo| ultimo.elapsed() >= periodo);
+    if registrar_log {
+        cubeta.ultimo_log = Some(ahora);
+    }
+
+    Err(Rechazo {
+        retry_after: ((1.0 - cubeta.tokens) / recarga_por_segundo).ceil().max(1.0) as u64,
+        registrar_log,
+    })
+}
+
+// ==================== LAYER ====================
+
+/// Límite de solicitudes por IP y por usuario autenticado para un grupo de rutas.
+/// La cuota se lee de `configuracion_sistema` (`rate_limit_<grupo>`).
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    pool: PgPool,
+    grupo: &'static str,
+}
+
+impl RateLimitLayer {
+    pub fn new(pool: PgPool, grupo: &'static str) -> Self {
+        Self { pool, grupo }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            pool: self.pool.clone(),
+            grupo: self.grupo,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    pool: PgPool,
+    grupo: &'static str,
+}
+
+impl<S> Service<Request<Body>> for RateLimit<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Usar el servicio que ya está listo y dejar un clon en su lugar
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let pool = self.pool.clone();
+        let grupo = self.grupo;
+
+        Box::pin(async move {
+            let cuota = cuota(grupo);
+
+            let ip = ip_de_solicitud(&request, &proxies_confiables());
+
+            let usuario = request
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .and_then(|token| AuthService::verify_token(token).ok());
+
+            let mut rechazo = None;
+            if cuota.por_ip > 0 {
+                if let Err(r) = consumir(format!("{}:ip:{}", grupo, ip), cuota.por_ip, cuota.periodo_segundos) {
+                    rechazo = Some(("IP", cuota.por_ip, r));
+                }
+            }
+            if let (None, Some(claims), true) = (&rechazo, &usuario, cuota.por_usuario > 0) {
+                if let Err(r) = consumir(
+                    format!("{}:usuario:{}", grupo, claims.sub),
+                    cuota.por_usuario,
+                    cuota.periodo_segundos,
+                ) {
+                    rechazo = Some(("usuario", cuota.por_usuario, r));
+                }
+            }
+
+            let Some((tipo, limite, rechazo)) = rechazo else {
+                return inner.call(request).await;
+            };
+
+            if rechazo.registrar_log {
+                LogService::registrar(
+                    &pool,
+                    usuario.as_ref().map(|claims| claims.sub),
+                    LogService::nuevo(
+                        NivelLog::Warning,
+                        "Límite de solicitudes excedido",
+                        "Seguridad",
+                        Some(format!(
+                            "Límite por {} del grupo '{}' ({} solicitudes cada {} s) excedido en {} {}",
+                            tipo,
+                            grupo,
+                            limite,
+                            cuota.periodo_segundos,
+                            request.method(),
+                            request
+                                .extensions()
+                                .get::<OriginalUri>()
+                                .map(|OriginalUri(uri)| uri.path())
+                                .unwrap_or(request.uri().path())
+                        )),
+                        usuario.map(|claims| claims.email),
+                        Some(ip),
+                    ),
+                )
+                .await;
+            }
+
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({
+                    "success": false,
+                    "message": format!(
+                        "Demasiadas solicitudes. Intenta nuevamente en {} segundos",
+                        rechazo.retry_after
+                    ),
+                })),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(rechazo.retry_after));
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+/// Scopes que se pueden conceder a una API key: `<recurso>:leer` para GET y
+/// `<recurso>:escribir` para el resto de métodos
+pub const SCOPES_API_KEY: &[&str] = &[
+    "inventario:leer",
+    "inventario:escribir",
+    "ventas:leer",
+    "ventas:escribir",
+];
+
+/// Clave de API para integraciones servidor a servidor.
+/// En BD solo se persiste el hash SHA-256 (clave_hash), que nunca se carga en el modelo.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiKey {
+    pub id_api_key: i32,
+    pub nombre: String,
+    pub prefijo: String,
+    pub scopes: Vec<String>,
+    pub ips_permitidas: Option<Vec<String>>,
+    pub limite_por_minuto: i32,
+    pub fecha_expiracion: Option<NaiveDateTime>,
+    pub activo: bool,
+    pub ultimo_uso: Option<NaiveDateTime>,
+    pub ultima_ip: Option<String>,
+    pub total_usos: i64,
+    pub creado_por: Option<i32>,
+    pub fecha_creacion: Option<NaiveDateTime>,
+    pub fecha_revocacion: Option<NaiveDateTime>,
+}
+
+// ==================== REQUEST DTOs ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CrearApiKeyRequest {
+    pub nombre: String,
+    pub scopes: Vec<String>,
+    pub ips_permitidas: Option<Vec<String>>,
+    pub limite_por_minuto: Option<i32>,
+    pub fecha_expiracion: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActualizarApiKeyRequest {
+    pub nombre: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    /// Lista vacía = cualquier IP
+    pub ips_permitidas: Option<Vec<String>>,
+    pub limite_por_minuto: Option<i32>,
+    pub fecha_expiracion: Option<NaiveDateTime>,
+    /// true para quitar la fecha de expiración
+    pub sin_expiracion: Option<bool>,
+}
+
+// ==================== RESPONSE DTOs ====================
+
+/// La clave completa solo se muestra una vez, al crearla
+#[derive(Debug, Serialize)]
+pub struct ApiKeyCreadaResponse {
+    pub clave: String,
+    pub api_key: ApiKey,
+}
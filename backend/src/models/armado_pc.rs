@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Estados del resultado de una regla de compatibilidad
+pub const REGLA_COMPATIBLE: &str = "compatible";
+pub const REGLA_INCOMPATIBLE: &str = "incompatible";
+pub const REGLA_SIN_DATOS: &str = "sin_datos";
+
+/// Ranura de un armado ("cpu", "placa_madre", ...) y la categoría que admite
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct RanuraArmado {
+    pub id_ranura: i32,
+    pub codigo: String,
+    pub nombre: String,
+    pub id_categoria: i32,
+    pub categoria: String,
+    pub obligatoria: bool,
+    pub cantidad_maxima: i32,
+    pub orden: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ReglaCompatibilidad {
+    pub codigo: String,
+    pub descripcion: String,
+    pub ranura_origen: Option<String>,
+    pub atributos_origen: Vec<String>,
+    pub ranura_destino: String,
+    pub atributo_destino: String,
+    pub operador: String,
+    pub margen_porcentaje: f64,
+    pub severidad: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ArmadoPc {
+    pub id_armado: i32,
+    pub id_usuario: i32,
+    pub nombre: String,
+    pub fecha_creacion: Option<NaiveDateTime>,
+    pub fecha_actualizacion: Option<NaiveDateTime>,
+}
+
+// ==================== DTOs DE REQUEST ====================
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComponenteRequest {
+    /// Código de la ranura
+    pub ranura: String,
+    pub id_producto_detalle: i32,
+    pub cantidad: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CrearArmadoRequest {
+    pub nombre: String,
+    pub componentes: Option<Vec<ComponenteRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AsignarComponenteRequest {
+    pub id_producto_detalle: i32,
+    pub cantidad: Option<i32>,
+}
+
+/// Armado sin guardar, para validar o pedir sugerencias sin iniciar sesión
+#[derive(Debug, Deserialize)]
+pub struct ValidarArmadoRequest {
+    pub componentes: Vec<ComponenteRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SugerenciasArmadoRequest {
+    pub componentes: Vec<ComponenteRequest>,
+    pub ranura: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SugerenciasArmadoQuery {
+    pub ranura: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// ==================== DTOs DE RESPONSE ====================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponenteArmado {
+    pub ranura: String,
+    pub nombre_ranura: String,
+    pub id_producto_detalle: i32,
+    pub nombre: String,
+    pub sku: String,
+    pub imagen_principal: Option<String>,
+    pub precio_venta: f64,
+    pub cantidad: i32,
+    pub stock_disponible: i32,
+    /// Activo y con stock para la cantidad pedida
+    pub disponible: bool,
+    pub especificaciones: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultadoRegla {
+    pub regla: String,
+    pub descripcion: String,
+    pub severidad: String,
+    /// compatible, incompatible o sin_datos
+    pub estado: String,
+    pub mensaje: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidacionArmado {
+    /// Ninguna regla de severidad "error" quedó incompatible
+    pub compatible: bool,
+    /// Todas las ranuras obligatorias tienen producto
+    pub completo: bool,
+    pub ranuras_faltantes: Vec<String>,
+    /// Reglas aplicables: las que tienen producto en sus ranuras
+    pub resultados: Vec<ResultadoRegla>,
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArmadoValidado {
+    pub componentes: Vec<ComponenteArmado>,
+    pub validacion: ValidacionArmado,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArmadoDetalle {
+    pub armado: ArmadoPc,
+    pub componentes: Vec<ComponenteArmado>,
+    pub validacion: ValidacionArmado,
+}
+
+/// Producto compatible con el resto del armado para una ranura
+#[derive(Debug, Serialize)]
+pub struct SugerenciaComponente {
+    pub componente: ComponenteArmado,
+    /// Advertencias y reglas que no se pudieron verificar con este producto
+    pub observaciones: Vec<ResultadoRegla>,
+}
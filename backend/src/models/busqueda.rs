@@ -0,0 +1,124 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+pub struct SugerenciasQuery {
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SugerenciaProducto {
+    pub id_producto_detalle: i32,
+    pub nombre: String,
+    pub slug: Option<String>,
+    pub imagen_principal: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SugerenciaMarca {
+    pub id_marca: i32,
+    pub nombre: String,
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SugerenciaCategoria {
+    pub id_categoria: i32,
+    pub nombre: String,
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SugerenciaTermino {
+    pub termino: String,
+    pub total_busquedas: i32,
+}
+
+/// Respuesta de GET /api/busqueda/sugerencias
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Sugerencias {
+    pub productos: Vec<SugerenciaProducto>,
+    pub marcas: Vec<SugerenciaMarca>,
+    pub categorias: Vec<SugerenciaCategoria>,
+    pub terminos: Vec<SugerenciaTermino>,
+}
+
+/// Término registrado en `termino_busqueda`
+#[derive(Debug, Serialize, FromRow)]
+pub struct TerminoBusqueda {
+    pub termino: String,
+    pub total_busquedas: i32,
+    pub total_buscadores: i32,
+    pub busquedas_sin_resultados: i32,
+    pub ultimos_resultados: i32,
+    pub bloqueado: bool,
+    pub primera_busqueda: Option<NaiveDateTime>,
+    pub ultima_busqueda: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BloquearTerminoRequest {
+    pub bloqueado: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TerminosBusquedaQuery {
+    /// Solo los que en la última búsqueda no devolvieron nada
+    pub sin_resultados: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+// ==================== FACETAS ====================
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FacetaMarca {
+    pub id_marca: i32,
+    pub nombre: String,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FacetaCategoria {
+    pub id_categoria: i32,
+    pub nombre: String,
+    pub total: i64,
+}
+
+/// Tramo de precio: desde (incluido) hasta (excluido); sin `hasta` es "o más"
+#[derive(Debug, Serialize)]
+pub struct FacetaPrecio {
+    pub desde: f64,
+    pub hasta: Option<f64>,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct FacetaStock {
+    pub en_stock: i64,
+    pub sin_stock: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValorFaceta {
+    pub valor: String,
+    pub total: i64,
+    pub seleccionado: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FacetaEspecificacion {
+    pub atributo: String,
+    pub valores: Vec<ValorFaceta>,
+}
+
+/// Conteos por faceta del listado de productos. Cada faceta se cuenta con todos los
+/// filtros aplicados menos el suyo, para que se pueda cambiar o sumar una opción.
+#[derive(Debug, Serialize)]
+pub struct Facetas {
+    pub marcas: Vec<FacetaMarca>,
+    pub categorias: Vec<FacetaCategoria>,
+    pub precios: Vec<FacetaPrecio>,
+    pub stock: FacetaStock,
+    pub especificaciones: Vec<FacetaEspecificacion>,
+}
@@ -24,3 +24,29 @@ pub struct CategoriaResponse {
     pub familia_nombre: Option<String>,
     pub total_productos: Option<i64>,
 }
+
+// ==================== ADMINISTRACIÓN ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CrearCategoriaRequest {
+    pub nombre: String,
+    pub descripcion: Option<String>,
+    pub icono: Option<String>,
+    pub id_familia: i32,
+    /// Se genera desde el nombre si no se envía
+    pub slug: Option<String>,
+    /// Al final de la familia si no se envía
+    pub orden: Option<i32>,
+}
+
+/// Solo se cambian los campos enviados; un texto vacío borra el campo opcional
+#[derive(Debug, Deserialize)]
+pub struct ActualizarCategoriaRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub icono: Option<String>,
+    pub id_familia: Option<i32>,
+    pub slug: Option<String>,
+    pub orden: Option<i32>,
+    pub estado: Option<String>,
+}
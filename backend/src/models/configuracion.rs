@@ -21,12 +21,61 @@ pub struct ConfiguracionSistema {
 pub struct ActualizarConfigRequest {
     pub clave: String,
     pub valor: String,
+    /// Motivo del cambio, queda en el historial
+    pub motivo: Option<String>,
 }
 
 /// Request para actualizar múltiples configuraciones
 #[derive(Debug, Deserialize)]
 pub struct ActualizarConfigBatchRequest {
     pub configuraciones: Vec<ActualizarConfigRequest>,
+    /// Motivo para las claves que no indican uno propio
+    pub motivo: Option<String>,
+}
+
+/// Versión de una clave en historial_configuracion
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct HistorialConfiguracion {
+    pub id_historial: i32,
+    pub id_lote: i32,
+    pub clave: String,
+    pub valor_anterior: Option<String>,
+    pub valor_nuevo: Option<String>,
+    pub motivo: Option<String>,
+    pub id_usuario: Option<i32>,
+    pub email_usuario: Option<String>,
+    pub revierte_historial: Option<i32>,
+    pub revierte_lote: Option<i32>,
+    pub fecha_cambio: NaiveDateTime,
+    /// Para claves json: {"campo": {"antes": ..., "despues": ...}} de primer nivel
+    #[sqlx(skip)]
+    pub diferencias: Option<serde_json::Value>,
+}
+
+/// Filtros de GET /api/config/historial
+#[derive(Debug, Deserialize)]
+pub struct HistorialConfigQuery {
+    pub clave: Option<String>,
+    pub id_lote: Option<i32>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Request para devolver una clave a una versión anterior
+#[derive(Debug, Deserialize)]
+pub struct RevertirConfigRequest {
+    /// Versión (id_historial) cuyo valor se restaura
+    pub id_historial: i32,
+    pub motivo: Option<String>,
+}
+
+/// Request para deshacer un lote de cambios
+#[derive(Debug, Deserialize)]
+pub struct RevertirLoteConfigRequest {
+    pub motivo: Option<String>,
+    /// Deshacer aunque alguna clave haya vuelto a cambiar después del lote
+    #[serde(default)]
+    pub forzar: bool,
 }
 
 /// Response de configuración como mapa clave-valor
@@ -43,27 +92,56 @@ pub struct ConfigValue {
     pub categoria: String,
 }
 
-impl ConfiguracionSistema {
-    /// Convierte el valor según su tipo
-    pub fn get_typed_value(&self) -> serde_json::Value {
-        match self.tipo.as_str() {
-            "number" => {
-                if let Ok(n) = self.valor.parse::<f64>() {
-                    serde_json::json!(n)
-                } else {
-                    serde_json::json!(self.valor)
+/// Tipos admitidos en la columna `tipo` de configuracion_sistema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipoConfig {
+    String,
+    Number,
+    Boolean,
+    Json,
+}
+
+impl TipoConfig {
+    /// Tipos desconocidos se tratan como texto
+    pub fn parse(tipo: &str) -> Self {
+        match tipo {
+            "number" => TipoConfig::Number,
+            "boolean" => TipoConfig::Boolean,
+            "json" => TipoConfig::Json,
+            _ => TipoConfig::String,
+        }
+    }
+
+    /// Validar un valor contra el tipo. Devuelve el valor normalizado que se guarda
+    /// en la BD y su representación tipada.
+    pub fn convertir(&self, valor: &str) -> Result<(String, serde_json::Value), String> {
+        match self {
+            TipoConfig::String => Ok((valor.to_string(), serde_json::json!(valor))),
+            TipoConfig::Number => {
+                let valor = valor.trim();
+                match valor.parse::<f64>() {
+                    Ok(n) if n.is_finite() => Ok((valor.to_string(), serde_json::json!(n))),
+                    _ => Err(format!("'{}' no es un número", valor)),
                 }
             }
-            "boolean" => {
-                let b = self.valor.to_lowercase() == "true" || self.valor == "1";
-                serde_json::json!(b)
-            }
-            "json" => {
-                serde_json::from_str(&self.valor).unwrap_or(serde_json::json!(self.valor))
-            }
-            _ => serde_json::json!(self.valor)
+            TipoConfig::Boolean => match valor.trim().to_lowercase().as_str() {
+                "true" | "1" => Ok(("true".to_string(), serde_json::json!(true))),
+                "false" | "0" => Ok(("false".to_string(), serde_json::json!(false))),
+                _ => Err(format!("'{}' no es un booleano (use true o false)", valor)),
+            },
+            TipoConfig::Json => serde_json::from_str::<serde_json::Value>(valor)
+                .map(|json| (valor.to_string(), json))
+                .map_err(|e| format!("JSON inválido: {}", e)),
         }
     }
 }
 
-
+impl ConfiguracionSistema {
+    /// Convierte el valor según su tipo (si no es válido se devuelve como texto)
+    pub fn get_typed_value(&self) -> serde_json::Value {
+        TipoConfig::parse(&self.tipo)
+            .convertir(&self.valor)
+            .map(|(_, valor)| valor)
+            .unwrap_or_else(|_| serde_json::json!(self.valor))
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+/// Solicitud de eliminación de cuenta (derecho de cancelación, Ley 29733).
+/// La cuenta se anonimiza cuando vence `fecha_programada`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SolicitudEliminacionCuenta {
+    pub id_solicitud: i32,
+    pub id_usuario: i32,
+    pub estado: String,  // pendiente, cancelada, ejecutada
+    pub motivo: Option<String>,
+    pub fecha_solicitud: Option<NaiveDateTime>,
+    pub fecha_programada: NaiveDateTime,
+    pub fecha_cancelacion: Option<NaiveDateTime>,
+    pub fecha_ejecucion: Option<NaiveDateTime>,
+}
+
+// ==================== REQUEST DTOs ====================
+
+#[derive(Debug, Deserialize)]
+pub struct ExportarDatosQuery {
+    /// json (por defecto) o zip
+    pub formato: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SolicitarEliminacionRequest {
+    pub password: String,
+    pub motivo: Option<String>,
+}
+
+// ==================== RESPONSE DTOs ====================
+
+#[derive(Debug, Serialize)]
+pub struct EstadoEliminacionResponse {
+    pub eliminacion_pendiente: bool,
+    pub solicitud: Option<SolicitudEliminacionCuenta>,
+    pub dias_gracia: i64,
+}
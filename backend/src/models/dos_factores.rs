@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+use crate::models::LoginResponse;
+
+/// Segundo factor TOTP de un usuario.
+/// `activo` es false mientras el enrolamiento no se confirma con un código válido.
+#[derive(Debug, Clone, FromRow)]
+pub struct AutenticacionDosFactores {
+    pub secreto: String,
+    pub activo: bool,
+    /// Calculado en la consulta: bloqueado_hasta > CURRENT_TIMESTAMP
+    pub bloqueado: bool,
+    pub fecha_activacion: Option<NaiveDateTime>,
+}
+
+// ==================== REQUEST DTOs ====================
+
+#[derive(Debug, Deserialize)]
+pub struct ActivarDosFactoresRequest {
+    pub codigo: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerificarDosFactoresRequest {
+    pub token_desafio: String,
+    /// Código TOTP de 6 dígitos o un código de recuperación
+    pub codigo: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DesactivarDosFactoresRequest {
+    pub password: String,
+    pub codigo: String,
+}
+
+// ==================== RESPONSE DTOs ====================
+
+/// Resultado del login: sesión directa o desafío de segundo factor
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResultado {
+    Sesion(LoginResponse),
+    Desafio(DesafioDosFactoresResponse),
+}
+
+#[derive(Debug, Serialize)]
+pub struct DesafioDosFactoresResponse {
+    pub requiere_2fa: bool,
+    /// true si la cuenta debe configurar 2FA antes de obtener una sesión
+    pub requiere_enrolamiento: bool,
+    pub token_desafio: String,
+    pub expira_en_segundos: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigurarDosFactoresResponse {
+    pub secreto: String,
+    pub otpauth_uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivarDosFactoresResponse {
+    /// Se muestran una sola vez; en BD solo queda su hash
+    pub codigos_recuperacion: Vec<String>,
+    /// Sesión emitida cuando la activación completa un login con enrolamiento obligatorio
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sesion: Option<LoginResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EstadoDosFactoresResponse {
+    pub activo: bool,
+    pub obligatorio: bool,
+    pub codigos_recuperacion_restantes: i64,
+    pub fecha_activacion: Option<NaiveDateTime>,
+}
@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
@@ -5,8 +6,132 @@ use sqlx::FromRow;
 pub struct EspecificacionProducto {
     pub id_especificacion: i32,
     pub id_producto_detalle: i32,
+    pub id_atributo: Option<i32>,
     pub nombre_atributo: String,
     pub valor_atributo: String,
     pub unidad_medida: Option<String>,
     pub orden: Option<i32>,
 }
+
+/// Tipos de dato de un atributo de especificación
+pub const TIPOS_DATO_ATRIBUTO: &[&str] = &["texto", "numero", "booleano", "opcion"];
+
+/// Atributo del esquema de especificaciones de una categoría
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct AtributoEspecificacion {
+    pub id_atributo: i32,
+    pub id_categoria: i32,
+    pub nombre: String,
+    pub etiqueta: String,
+    pub unidad_medida: Option<String>,
+    pub tipo_dato: String,
+    pub valores_permitidos: Option<Vec<String>>,
+    pub obligatorio: bool,
+    pub filtrable: bool,
+    pub comparable: bool,
+    pub orden: Option<i32>,
+    pub fecha_creacion: Option<NaiveDateTime>,
+    pub fecha_actualizacion: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CrearAtributoRequest {
+    pub nombre: String,
+    pub etiqueta: Option<String>,
+    pub unidad_medida: Option<String>,
+    pub tipo_dato: Option<String>,
+    pub valores_permitidos: Option<Vec<String>>,
+    pub obligatorio: Option<bool>,
+    pub filtrable: Option<bool>,
+    pub comparable: Option<bool>,
+    pub orden: Option<i32>,
+}
+
+/// Solo se cambian los campos enviados. El nombre no se cambia: es la clave con la
+/// que se guardan los valores de los productos.
+#[derive(Debug, Deserialize)]
+pub struct ActualizarAtributoRequest {
+    pub etiqueta: Option<String>,
+    pub unidad_medida: Option<String>,
+    pub tipo_dato: Option<String>,
+    pub valores_permitidos: Option<Vec<String>>,
+    pub obligatorio: Option<bool>,
+    pub filtrable: Option<bool>,
+    pub comparable: Option<bool>,
+    pub orden: Option<i32>,
+}
+
+/// Valor de un atributo para una variante (`producto_detalle`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValorEspecificacionRequest {
+    pub atributo: String,
+    pub valor: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActualizarEspecificacionesRequest {
+    pub especificaciones: Vec<ValorEspecificacionRequest>,
+}
+
+/// Valor de especificación de una variante con los datos de su atributo
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EspecificacionResponse {
+    pub id_atributo: Option<i32>,
+    pub atributo: String,
+    pub etiqueta: String,
+    pub valor: String,
+    pub unidad_medida: Option<String>,
+    pub tipo_dato: String,
+    pub filtrable: bool,
+    pub comparable: bool,
+    pub orden: Option<i32>,
+}
+
+// ==================== COMPARACIÓN ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CompararQuery {
+    /// Ids de `producto_detalle` separados por comas
+    pub ids: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ProductoComparado {
+    pub id_producto_detalle: i32,
+    pub nombre: String,
+    pub slug: Option<String>,
+    pub marca: String,
+    pub id_categoria: i32,
+    pub categoria: String,
+    pub imagen_principal: Option<String>,
+    pub precio_venta: f64,
+    pub precio_base: Option<f64>,
+    pub stock_disponible: i32,
+    pub valoracion_promedio: Option<f64>,
+    pub total_valoraciones: Option<i32>,
+    pub garantia_meses: Option<i32>,
+}
+
+/// Fila de la comparación: un valor por producto, en el orden de `productos`
+#[derive(Debug, Serialize)]
+pub struct FilaComparacion {
+    pub atributo: String,
+    pub etiqueta: String,
+    pub unidad_medida: Option<String>,
+    pub valores: Vec<Option<String>>,
+    /// Los productos no tienen todos el mismo valor
+    pub diferente: bool,
+    /// Todos los productos tienen el atributo
+    pub comun: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparacion {
+    pub productos: Vec<ProductoComparado>,
+    /// Precio, stock, valoración y garantía
+    pub generales: Vec<FilaComparacion>,
+    /// Especificaciones comparables: primero las comunes a todos los productos
+    pub especificaciones: Vec<FilaComparacion>,
+    pub misma_categoria: bool,
+    pub advertencia: Option<String>,
+}
@@ -21,3 +21,27 @@ pub struct FamiliaResponse {
     pub slug: Option<String>,
     pub total_productos: Option<i64>,
 }
+
+// ==================== ADMINISTRACIÓN ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CrearFamiliaRequest {
+    pub nombre: String,
+    pub descripcion: Option<String>,
+    pub icono: Option<String>,
+    /// Se genera desde el nombre si no se envía
+    pub slug: Option<String>,
+    /// Al final de la lista si no se envía
+    pub orden: Option<i32>,
+}
+
+/// Solo se cambian los campos enviados; un texto vacío borra el campo opcional
+#[derive(Debug, Deserialize)]
+pub struct ActualizarFamiliaRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub icono: Option<String>,
+    pub slug: Option<String>,
+    pub orden: Option<i32>,
+    pub estado: Option<String>,
+}
@@ -37,7 +37,43 @@ pub struct LogAuditoria {
     pub email_usuario: Option<String>,
     pub ip_cliente: Option<String>,
     pub user_agent: Option<String>,
+    pub origen: String,
+    pub metodo_http: Option<String>,
+    pub ruta: Option<String>,
+    pub entidad: Option<String>,
+    pub id_entidad: Option<String>,
+    pub cambios: Option<serde_json::Value>,
+    pub codigo_respuesta: Option<i16>,
     pub fecha_creacion: NaiveDateTime,
+    pub hash: String,
+}
+
+/// Datos de la solicitud HTTP auditada por el middleware de auditoría
+#[derive(Debug, Clone)]
+pub struct SolicitudAuditada {
+    pub metodo_http: String,
+    pub ruta: String,
+    pub entidad: String,
+    pub id_entidad: Option<String>,
+    /// {"campo": {"antes": ..., "despues": ...}}
+    pub cambios: Option<serde_json::Value>,
+    pub codigo_respuesta: i16,
+}
+
+/// Archivo JSONL comprimido con entradas purgadas por la política de retención
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ArchivoLogAuditoria {
+    pub id_archivo: i32,
+    pub nombre_archivo: String,
+    pub desde_id: i32,
+    pub hasta_id: i32,
+    pub total_entradas: i32,
+    pub hash_final: String,
+    pub sha256_archivo: String,
+    pub fecha_desde: Option<NaiveDateTime>,
+    pub fecha_hasta: Option<NaiveDateTime>,
+    pub creado_por: Option<i32>,
+    pub fecha_creacion: Option<NaiveDateTime>,
 }
 
 /// Request para crear un nuevo log
@@ -59,10 +95,29 @@ pub struct FiltrarLogsQuery {
     pub modulo: Option<String>,
     pub fecha_inicio: Option<String>,
     pub fecha_fin: Option<String>,
+    /// Búsqueda de texto completo en acción y detalles (sintaxis web: "frase", -excluir, OR)
+    pub q: Option<String>,
+    pub id_usuario: Option<i32>,
+    /// Coincidencia parcial, sin distinguir mayúsculas
+    pub email: Option<String>,
+    /// IP exacta o prefijo (p. ej. "192.168.")
+    pub ip: Option<String>,
+    pub origen: Option<String>,
+    pub entidad: Option<String>,
+    pub id_entidad: Option<String>,
+    /// id_log de la última entrada de la página anterior (paginación por cursor)
+    pub cursor: Option<i32>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
 }
 
+/// Formato de exportación; los filtros se leen con `FiltrarLogsQuery` de la misma query
+#[derive(Debug, Deserialize)]
+pub struct ExportarLogsQuery {
+    /// csv | jsonl
+    pub formato: Option<String>,
+}
+
 /// Response para listar logs
 #[derive(Debug, Serialize)]
 pub struct LogResponse {
@@ -74,6 +129,14 @@ pub struct LogResponse {
     pub ip: String,
     pub details: String,
     pub module: String,
+    /// "servidor" o "cliente" (enviado por el frontend, no confiable)
+    pub origin: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<serde_json::Value>,
 }
 
 impl From<LogAuditoria> for LogResponse {
@@ -87,8 +150,46 @@ impl From<LogAuditoria> for LogResponse {
             ip: log.ip_cliente.unwrap_or_else(|| "N/A".to_string()),
             details: log.detalles.unwrap_or_default(),
             module: log.modulo,
+            origin: log.origen,
+            entity: log.entidad,
+            entity_id: log.id_entidad,
+            changes: log.cambios,
         }
     }
 }
 
 
+
+/// Entrada de la cadena de hashes que no supera la verificación
+#[derive(Debug, Serialize)]
+pub struct ProblemaCadena {
+    pub id_log: i32,
+    /// contenido_modificado | cadena_rota | ancla_invalida
+    pub tipo: String,
+    pub detalle: String,
+}
+
+/// Resultado de verificar la cadena de hashes de log_auditoria
+#[derive(Debug, Serialize)]
+pub struct VerificacionCadenaResponse {
+    pub valida: bool,
+    pub total_entradas: i64,
+    /// Entradas con datos personales eliminados: solo se verifica su enlace, no su contenido
+    pub redactadas: i64,
+    pub primer_id: Option<i32>,
+    pub ultimo_id: Option<i32>,
+    /// Hash de la última entrada, para anclarlo fuera del sistema y detectar truncados
+    pub ultimo_hash: Option<String>,
+    /// hash_final del último archivo: la primera entrada en BD debe enlazar con él
+    pub ancla: Option<String>,
+    pub problemas: Vec<ProblemaCadena>,
+}
+
+/// Resultado de aplicar la política de retención
+#[derive(Debug, Serialize)]
+pub struct RetencionLogsResponse {
+    pub dias_retencion: i64,
+    pub entradas_archivadas: i64,
+    /// Un archivo por lote, en orden de la cadena
+    pub archivos: Vec<ArchivoLogAuditoria>,
+}
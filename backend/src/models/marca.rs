@@ -21,3 +21,28 @@ pub struct MarcaResponse {
     pub slug: Option<String>,
     pub total_productos: Option<i64>,
 }
+
+// ==================== ADMINISTRACIÓN ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CrearMarcaRequest {
+    pub nombre: String,
+    pub descripcion: Option<String>,
+    pub logo: Option<String>,
+    /// Se genera desde el nombre si no se envía
+    pub slug: Option<String>,
+    pub pais_origen: Option<String>,
+    pub sitio_web: Option<String>,
+}
+
+/// Solo se cambian los campos enviados; un texto vacío borra el campo opcional
+#[derive(Debug, Deserialize)]
+pub struct ActualizarMarcaRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub logo: Option<String>,
+    pub slug: Option<String>,
+    pub pais_origen: Option<String>,
+    pub sitio_web: Option<String>,
+    pub estado: Option<String>,
+}
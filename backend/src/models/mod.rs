@@ -9,6 +9,10 @@ pub mod valoracion;
 
 // Modelos - Usuario y Carrito (H3nr7 - mantener tu implementación)
 pub mod usuario;
+pub mod token_recuperacion;
+pub mod dos_factores;
+pub mod datos_personales;
+pub mod api_key;
 pub mod carrito;
 
 // Modelos - Direcciones, Ventas, Pagos (main - usar implementación del compañero)
@@ -38,13 +42,28 @@ pub mod notificacion;
 pub mod lista_deseos;
 pub mod log_auditoria;
 pub mod configuracion;
+pub mod busqueda;
+pub mod armado_pc;
 
 // Re-exportaciones para uso interno
 pub use subcategoria::Subcategoria;
 pub use valoracion::Valoracion;
 
 // Usuario y Carrito - TU implementación (con DTOs)
-pub use usuario::{Usuario, UsuarioResponse, LoginRequest, LoginResponse, RegisterRequest, RegisterResponse};
+pub use usuario::{
+    Usuario, UsuarioResponse, LoginRequest, LoginResponse, RegisterRequest, RegisterResponse,
+    ImpersonarUsuarioRequest, ImpersonacionResponse,
+};
+pub use token_recuperacion::{TokenRecuperacion, RecuperarPasswordRequest, RestablecerPasswordRequest};
+pub use dos_factores::{
+    AutenticacionDosFactores, ActivarDosFactoresRequest, VerificarDosFactoresRequest, DesactivarDosFactoresRequest,
+    LoginResultado, DesafioDosFactoresResponse, ConfigurarDosFactoresResponse, ActivarDosFactoresResponse,
+    EstadoDosFactoresResponse,
+};
+pub use datos_personales::{
+    SolicitudEliminacionCuenta, ExportarDatosQuery, SolicitarEliminacionRequest, EstadoEliminacionResponse,
+};
+pub use api_key::{ApiKey, CrearApiKeyRequest, ActualizarApiKeyRequest, ApiKeyCreadaResponse};
 pub use carrito::{Carrito, CarritoDetalle, CarritoResponse, CarritoItemResponse, AgregarAlCarritoRequest, ActualizarCantidadRequest};
 
 // Direcciones, Ventas, Pagos - Implementación del compañero + DTOs agregados
@@ -72,5 +91,5 @@ pub use envio::Envio;
 pub use imagen_valoracion::ImagenValoracion;
 pub use notificacion::Notificacion;
 pub use lista_deseos::ListaDeseos;
-pub use log_auditoria::{LogAuditoria, CrearLogRequest, FiltrarLogsQuery, LogResponse};
+pub use log_auditoria::{LogAuditoria, CrearLogRequest, FiltrarLogsQuery, LogResponse, SolicitudAuditada};
 pub use configuracion::{ConfiguracionSistema, ActualizarConfigRequest, ActualizarConfigBatchRequest};
@@ -34,3 +34,31 @@ pub struct ProductoResponse {
     pub precio_hasta: Option<f64>,
     pub total_variantes: i64,
 }
+
+// ==================== DTOs DE ADMINISTRACIÓN ====================
+
+/// Campos a cambiar del producto base; los omitidos se conservan y un texto vacío
+/// borra el valor
+#[derive(Debug, Deserialize)]
+pub struct ActualizarProductoRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub slug: Option<String>,
+    /// Al cambiar de categoría se revalidan las especificaciones de todas las variantes
+    pub id_categoria: Option<i32>,
+    /// 0 = sin subcategoría
+    pub id_subcategoria: Option<i32>,
+    pub especificaciones_base: Option<serde_json::Value>,
+    pub imagen_referencia: Option<String>,
+    pub meta_title: Option<String>,
+    pub meta_description: Option<String>,
+    pub keywords: Option<String>,
+    pub estado: Option<String>,
+}
+
+/// Producto base con todas sus variantes, activas o no
+#[derive(Debug, Serialize)]
+pub struct ProductoAdmin {
+    pub producto: Producto,
+    pub variantes: Vec<crate::models::producto_detalle::ProductoDetalle>,
+}
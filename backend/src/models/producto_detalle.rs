@@ -81,3 +81,63 @@ pub struct ProductoListItem {
     pub total_valoraciones: i32,
     pub categoria: String,
 }
+
+// ==================== DTOs DE ADMINISTRACIÓN ====================
+
+/// Nueva variante de un producto existente
+#[derive(Debug, Deserialize)]
+pub struct CrearVarianteRequest {
+    pub nombre: String,
+    pub descripcion: Option<String>,
+    pub modelo: Option<String>,
+    pub sku: String,
+    /// Se genera desde el nombre si no se envía
+    pub slug: Option<String>,
+    pub codigo_barras: Option<String>,
+    pub id_marca: i32,
+    pub precio_base: rust_decimal::Decimal,
+    pub precio_venta: rust_decimal::Decimal,
+    pub costo: Option<rust_decimal::Decimal>,
+    pub imagen_principal: Option<String>,
+    pub imagenes: Option<serde_json::Value>,
+    pub peso: Option<rust_decimal::Decimal>,
+    pub dimensiones: Option<String>,
+    pub garantia_meses: Option<i32>,
+    pub es_destacado: Option<bool>,
+    pub es_nuevo: Option<bool>,
+    pub es_oferta: Option<bool>,
+    pub cantidad_inicial: Option<i32>,
+    pub cantidad_minima: Option<i32>,
+    pub ubicacion_fisica: Option<String>,
+    /// Valores de especificación, validados contra el esquema de la categoría
+    pub especificaciones: Option<Vec<crate::models::especificacion_producto::ValorEspecificacionRequest>>,
+}
+
+/// Campos a cambiar de la variante; los omitidos se conservan y un texto vacío
+/// borra el valor
+#[derive(Debug, Deserialize)]
+pub struct ActualizarVarianteRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub modelo: Option<String>,
+    pub sku: Option<String>,
+    pub slug: Option<String>,
+    pub codigo_barras: Option<String>,
+    pub id_marca: Option<i32>,
+    pub precio_base: Option<rust_decimal::Decimal>,
+    pub precio_venta: Option<rust_decimal::Decimal>,
+    pub costo: Option<rust_decimal::Decimal>,
+    pub descuento_adicional_porcentaje: Option<rust_decimal::Decimal>,
+    pub descuento_adicional_activo: Option<bool>,
+    pub imagen_principal: Option<String>,
+    pub imagenes: Option<serde_json::Value>,
+    pub peso: Option<rust_decimal::Decimal>,
+    pub dimensiones: Option<String>,
+    pub garantia_meses: Option<i32>,
+    pub es_destacado: Option<bool>,
+    pub es_nuevo: Option<bool>,
+    pub es_oferta: Option<bool>,
+    pub estado: Option<String>,
+    /// Reemplaza todas las especificaciones de la variante
+    pub especificaciones: Option<Vec<crate::models::especificacion_producto::ValorEspecificacionRequest>>,
+}
@@ -16,3 +16,27 @@ pub struct Subcategoria {
     #[serde(skip)]
     pub fecha_actualizacion: Option<sqlx::types::time::PrimitiveDateTime>,
 }
+
+// ==================== ADMINISTRACIÓN ====================
+
+#[derive(Debug, Deserialize)]
+pub struct CrearSubcategoriaRequest {
+    pub nombre: String,
+    pub descripcion: Option<String>,
+    pub id_categoria: i32,
+    /// Se genera desde el nombre si no se envía
+    pub slug: Option<String>,
+    /// Al final de la categoría si no se envía
+    pub orden: Option<i32>,
+}
+
+/// Solo se cambian los campos enviados; un texto vacío borra el campo opcional
+#[derive(Debug, Deserialize)]
+pub struct ActualizarSubcategoriaRequest {
+    pub nombre: Option<String>,
+    pub descripcion: Option<String>,
+    pub id_categoria: Option<i32>,
+    pub slug: Option<String>,
+    pub orden: Option<i32>,
+    pub estado: Option<String>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+/// Token de recuperación de contraseña.
+/// En BD solo se persiste el hash SHA-256 (token_hash), que nunca se carga en el modelo.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TokenRecuperacion {
+    pub id_token: i32,
+    pub id_usuario: i32,
+    pub fecha_expiracion: NaiveDateTime,
+    pub usado: Option<bool>,
+    pub fecha_uso: Option<NaiveDateTime>,
+    pub ip_solicitud: Option<String>,
+    pub fecha_creacion: Option<NaiveDateTime>,
+}
+
+// ==================== REQUEST DTOs ====================
+
+#[derive(Debug, Deserialize)]
+pub struct RecuperarPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestablecerPasswordRequest {
+    pub token: String,
+    pub password_nuevo: String,
+}
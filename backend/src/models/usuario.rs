@@ -64,6 +64,15 @@ pub struct LoginRequest {
     pub remember_me: Option<bool>,
 }
 
+/// Solicitud de un super_admin para ver la cuenta de un cliente
+#[derive(Debug, Deserialize)]
+pub struct ImpersonarUsuarioRequest {
+    pub motivo: String,
+    pub minutos: Option<i64>,
+    /// Por defecto el token es de solo lectura
+    pub permitir_escritura: Option<bool>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
     pub nombre: String,
@@ -87,3 +96,12 @@ pub struct RegisterResponse {
     pub message: String,
     pub usuario: UsuarioResponse,
 }
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonacionResponse {
+    pub token: String,
+    pub expira_en: String,
+    pub expira_en_segundos: i64,
+    pub solo_lectura: bool,
+    pub usuario: UsuarioResponse,
+}
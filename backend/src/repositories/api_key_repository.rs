@@ -0,0 +1,178 @@
+use sqlx::PgPool;
+
+use crate::models::{ActualizarApiKeyRequest, ApiKey, CrearApiKeyRequest};
+
+pub struct ApiKeyRepository;
+
+impl ApiKeyRepository {
+    pub async fn listar(pool: &PgPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+        let keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT
+                id_api_key, nombre, prefijo, scopes, ips_permitidas, limite_por_minuto,
+                fecha_expiracion as "fecha_expiracion: chrono::NaiveDateTime",
+                activo as "activo!",
+                ultimo_uso as "ultimo_uso: chrono::NaiveDateTime",
+                ultima_ip,
+                total_usos as "total_usos!",
+                creado_por,
+                fecha_creacion as "fecha_creacion: chrono::NaiveDateTime",
+                fecha_revocacion as "fecha_revocacion: chrono::NaiveDateTime"
+            FROM api_key
+            ORDER BY activo DESC, fecha_creacion DESC
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    // Buscar una clave por el hash de la clave completa
+    pub async fn find_by_hash(pool: &PgPool, clave_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT
+                id_api_key, nombre, prefijo, scopes, ips_permitidas, limite_por_minuto,
+                fecha_expiracion as "fecha_expiracion: chrono::NaiveDateTime",
+                activo as "activo!",
+                ultimo_uso as "ultimo_uso: chrono::NaiveDateTime",
+                ultima_ip,
+                total_usos as "total_usos!",
+                creado_por,
+                fecha_creacion as "fecha_creacion: chrono::NaiveDateTime",
+                fecha_revocacion as "fecha_revocacion: chrono::NaiveDateTime"
+            FROM api_key
+            WHERE clave_hash = $1
+            "#,
+            clave_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    // La solicitud debe llegar ya validada y normalizada por el servicio
+    pub async fn crear(
+        pool: &PgPool,
+        request: &CrearApiKeyRequest,
+        prefijo: &str,
+        clave_hash: &str,
+        creado_por: i32,
+    ) -> Result<ApiKey, sqlx::Error> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_key (
+                nombre, prefijo, clave_hash, scopes, ips_permitidas,
+                limite_por_minuto, fecha_expiracion, creado_por
+            )
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, 60), $7, $8)
+            RETURNING
+                id_api_key, nombre, prefijo, scopes, ips_permitidas, limite_por_minuto,
+                fecha_expiracion as "fecha_expiracion: chrono::NaiveDateTime",
+                activo as "activo!",
+                ultimo_uso as "ultimo_uso: chrono::NaiveDateTime",
+                ultima_ip,
+                total_usos as "total_usos!",
+                creado_por,
+                fecha_creacion as "fecha_creacion: chrono::NaiveDateTime",
+                fecha_revocacion as "fecha_revocacion: chrono::NaiveDateTime"
+            "#,
+            request.nombre,
+            prefijo,
+            clave_hash,
+            &request.scopes,
+            request.ips_permitidas.as_deref(),
+            request.limite_por_minuto,
+            request.fecha_expiracion as _,
+            creado_por
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    // Actualizar los campos enviados de una clave activa.
+    // Una lista de IPs vacía quita la restricción; `sin_expiracion` quita la fecha de expiración.
+    pub async fn actualizar(
+        pool: &PgPool,
+        id_api_key: i32,
+        request: &ActualizarApiKeyRequest,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        let key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_key
+            SET nombre = COALESCE($2, nombre),
+                scopes = COALESCE($3, scopes),
+                ips_permitidas = CASE
+                    WHEN $4::text[] IS NULL THEN ips_permitidas
+                    WHEN cardinality($4::text[]) = 0 THEN NULL
+                    ELSE $4::text[]
+                END,
+                limite_por_minuto = COALESCE($5, limite_por_minuto),
+                fecha_expiracion = CASE WHEN $7 THEN NULL ELSE COALESCE($6, fecha_expiracion) END
+            WHERE id_api_key = $1 AND activo = TRUE
+            RETURNING
+                id_api_key, nombre, prefijo, scopes, ips_permitidas, limite_por_minuto,
+                fecha_expiracion as "fecha_expiracion: chrono::NaiveDateTime",
+                activo as "activo!",
+                ultimo_uso as "ultimo_uso: chrono::NaiveDateTime",
+                ultima_ip,
+                total_usos as "total_usos!",
+                creado_por,
+                fecha_creacion as "fecha_creacion: chrono::NaiveDateTime",
+                fecha_revocacion as "fecha_revocacion: chrono::NaiveDateTime"
+            "#,
+            id_api_key,
+            request.nombre,
+            request.scopes.as_deref(),
+            request.ips_permitidas.as_deref(),
+            request.limite_por_minuto,
+            request.fecha_expiracion as _,
+            request.sin_expiracion.unwrap_or(false)
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    // Revocar una clave. Devuelve su nombre, o None si no existía o ya estaba revocada.
+    pub async fn revocar(pool: &PgPool, id_api_key: i32) -> Result<Option<String>, sqlx::Error> {
+        let nombre = sqlx::query_scalar!(
+            r#"
+            UPDATE api_key
+            SET activo = FALSE, fecha_revocacion = CURRENT_TIMESTAMP
+            WHERE id_api_key = $1 AND activo = TRUE
+            RETURNING nombre
+            "#,
+            id_api_key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(nombre)
+    }
+
+    pub async fn registrar_uso(pool: &PgPool, id_api_key: i32, ip: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE api_key
+            SET ultimo_uso = CURRENT_TIMESTAMP, ultima_ip = $2, total_usos = total_usos + 1
+            WHERE id_api_key = $1
+            "#,
+            id_api_key,
+            ip
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
+use chrono::NaiveDateTime;
 use sqlx::PgPool;
-use crate::models::Usuario;
+use crate::models::{TokenRecuperacion, Usuario};
 
 pub struct AuthRepository;
 
@@ -79,6 +80,30 @@ impl AuthRepository {
         Ok(usuario)
     }
 
+    // Reemplazar el hash de la contraseña sin cambiarla (migración de esquema de hash).
+    // Solo aplica si el hash no cambió entretanto, para no pisar un cambio de contraseña concurrente.
+    pub async fn actualizar_hash_password(
+        pool: &PgPool,
+        user_id: i32,
+        hash_anterior: &str,
+        hash_nuevo: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE usuario
+            SET contrasena = $3
+            WHERE id_usuario = $1 AND contrasena = $2
+            "#,
+            user_id,
+            hash_anterior,
+            hash_nuevo
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     // Actualizar última conexión
     pub async fn update_last_login(pool: &PgPool, user_id: i32) -> Result<(), sqlx::Error> {
         sqlx::query!(
@@ -139,4 +164,222 @@ impl AuthRepository {
 
         Ok(result.exists)
     }
+
+    // ==================== RECUPERACIÓN DE CONTRASEÑA ====================
+
+    // Registrar un nuevo token de recuperación, invalidando los anteriores del usuario
+    pub async fn crear_token_recuperacion(
+        pool: &PgPool,
+        user_id: i32,
+        token_hash: &str,
+        ttl_minutos: i32,
+        ip_solicitud: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE token_recuperacion
+            SET usado = TRUE
+            WHERE id_usuario = $1 AND usado = FALSE
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO token_recuperacion (id_usuario, token_hash, fecha_expiracion, ip_solicitud)
+            VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(mins => $3), $4)
+            "#,
+            user_id,
+            token_hash,
+            ttl_minutos,
+            ip_solicitud
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    // Contar tokens de recuperación emitidos para el usuario en la última hora
+    pub async fn contar_tokens_recientes(pool: &PgPool, user_id: i32) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM token_recuperacion
+            WHERE id_usuario = $1
+              AND fecha_creacion >= CURRENT_TIMESTAMP - INTERVAL '1 hour'
+            "#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(result.total)
+    }
+
+    // Contar solicitudes de recuperación hechas desde una IP en la última hora.
+    // Solo cuentan los logs del servidor: los del cliente (POST /api/logs) se pueden falsificar.
+    pub async fn contar_solicitudes_recuperacion_ip(
+        pool: &PgPool,
+        accion: &str,
+        ip: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM log_auditoria
+            WHERE accion = $1
+              AND origen = 'servidor'
+              AND ip_cliente = $2
+              AND fecha_creacion >= CURRENT_TIMESTAMP - INTERVAL '1 hour'
+            "#,
+            accion,
+            ip
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(result.total)
+    }
+
+    // Buscar un token de recuperación no usado y no expirado
+    pub async fn find_token_recuperacion_vigente(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<TokenRecuperacion>, sqlx::Error> {
+        let token = sqlx::query_as!(
+            TokenRecuperacion,
+            r#"
+            SELECT
+                id_token,
+                id_usuario,
+                fecha_expiracion as "fecha_expiracion!: NaiveDateTime",
+                usado,
+                fecha_uso as "fecha_uso: NaiveDateTime",
+                ip_solicitud,
+                fecha_creacion as "fecha_creacion: NaiveDateTime"
+            FROM token_recuperacion
+            WHERE token_hash = $1
+              AND usado = FALSE
+              AND fecha_expiracion > CURRENT_TIMESTAMP
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    // Consumir un token vigente y actualizar la contraseña en una sola transacción.
+    // Devuelve None si el token no existe, ya fue usado o expiró.
+    pub async fn restablecer_password(
+        pool: &PgPool,
+        token_hash: &str,
+        password_hash: &str,
+        sesiones_revocadas_desde: NaiveDateTime,
+    ) -> Result<Option<TokenRecuperacion>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let token = sqlx::query_as!(
+            TokenRecuperacion,
+            r#"
+            SELECT
+                id_token,
+                id_usuario,
+                fecha_expiracion as "fecha_expiracion!: NaiveDateTime",
+                usado,
+                fecha_uso as "fecha_uso: NaiveDateTime",
+                ip_solicitud,
+                fecha_creacion as "fecha_creacion: NaiveDateTime"
+            FROM token_recuperacion
+            WHERE token_hash = $1
+              AND usado = FALSE
+              AND fecha_expiracion > CURRENT_TIMESTAMP
+            FOR UPDATE
+            "#,
+            token_hash
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(token) = token else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE usuario
+            SET contrasena = $1,
+                sesiones_revocadas_desde = $2,
+                fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_usuario = $3
+            "#,
+            password_hash,
+            sesiones_revocadas_desde as _,
+            token.id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE token_recuperacion
+            SET usado = TRUE,
+                fecha_uso = CASE WHEN id_token = $2 THEN CURRENT_TIMESTAMP ELSE fecha_uso END
+            WHERE id_usuario = $1 AND usado = FALSE
+            "#,
+            token.id_usuario,
+            token.id_token
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(token))
+    }
+
+    // ==================== REVOCACIÓN DE SESIONES ====================
+
+    // Obtener la fecha (UTC) desde la cual las sesiones del usuario son válidas
+    pub async fn get_sesiones_revocadas_desde(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            SELECT sesiones_revocadas_desde as "sesiones_revocadas_desde: NaiveDateTime"
+            FROM usuario
+            WHERE id_usuario = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(result.and_then(|r| r.sesiones_revocadas_desde))
+    }
+
+    // Listar todos los usuarios con sesiones revocadas
+    pub async fn get_sesiones_revocadas(pool: &PgPool) -> Result<Vec<(i32, NaiveDateTime)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id_usuario, sesiones_revocadas_desde as "sesiones_revocadas_desde!: NaiveDateTime"
+            FROM usuario
+            WHERE sesiones_revocadas_desde IS NOT NULL
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| (r.id_usuario, r.sesiones_revocadas_desde))
+            .collect())
+    }
 }
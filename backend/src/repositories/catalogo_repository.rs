@@ -1,6 +1,7 @@
 use crate::models::*;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 
 pub struct CatalogoRepository;
 
@@ -171,10 +172,7 @@ impl CatalogoRepository {
         pool: &PgPool,
         filters: &ProductoFilters,
     ) -> Result<Vec<producto_detalle::ProductoListItem>, sqlx::Error> {
-        let limit = filters.limit.unwrap_or(20).min(100) as i64;
-        let offset = filters.offset.unwrap_or(0) as i64;
-
-        let mut query = String::from(
+        let mut query = QueryBuilder::<Postgres>::new(
             r#"
             SELECT 
                 pd.id_producto_detalle,
@@ -196,165 +194,198 @@ impl CatalogoRepository {
                 CAST(p.valoracion_promedio AS FLOAT8) as valoracion_promedio,
                 p.total_valoraciones,
                 c.nombre as categoria
-            FROM producto_detalle pd
-            INNER JOIN producto p ON pd.id_producto = p.id_producto
-            INNER JOIN marca m ON pd.id_marca = m.id_marca
-            INNER JOIN categoria c ON p.id_categoria = c.id_categoria
-            LEFT JOIN inventario i ON pd.id_producto_detalle = i.id_producto_detalle
-            WHERE pd.estado = 'activo' AND p.estado = 'activo'
             "#,
         );
+        query.push(FROM_PRODUCTOS);
+        filters.push_where(&mut query, AlcanceFiltro::Catalogo);
+        filters.push_orden_y_pagina(&mut query);
 
-        // Aplicar filtros dinámicamente
-        let mut conditions = Vec::new();
-        
-        if let Some(ref search) = filters.search {
-            conditions.push(format!(
-                "(pd.nombre ILIKE '%{}%' OR pd.descripcion ILIKE '%{}%' OR pd.modelo ILIKE '%{}%')",
-                search, search, search
-            ));
-        }
-
-        if let Some(categoria_id) = filters.id_categoria {
-            conditions.push(format!("p.id_categoria = {}", categoria_id));
-        }
-
-        if let Some(subcategoria_id) = filters.id_subcategoria {
-            conditions.push(format!("p.id_subcategoria = {}", subcategoria_id));
-        }
-
-        if let Some(marca_id) = filters.id_marca {
-            conditions.push(format!("pd.id_marca = {}", marca_id));
-        }
+        let productos = query
+            .build_query_as::<producto_detalle::ProductoListItem>()
+            .fetch_all(pool)
+            .await?;
 
-        if let Some(familia_id) = filters.id_familia {
-            conditions.push(format!("c.id_familia = {}", familia_id));
-        }
+        Ok(productos)
+    }
 
-        if let Some(precio_min) = filters.precio_min {
-            conditions.push(format!("pd.precio_venta >= {}", precio_min));
-        }
+    // ==================== CONTEO DE PRODUCTOS ====================
+    pub async fn count_productos(
+        pool: &PgPool,
+        filters: &ProductoFilters,
+    ) -> Result<i64, sqlx::Error> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT COUNT(DISTINCT pd.id_producto_detalle)");
+        query.push(FROM_PRODUCTOS);
+        filters.push_where(&mut query, AlcanceFiltro::Catalogo);
 
-        if let Some(precio_max) = filters.precio_max {
-            conditions.push(format!("pd.precio_venta <= {}", precio_max));
-        }
+        let count: (i64,) = query.build_query_as().fetch_one(pool).await?;
 
-        if let Some(true) = filters.destacados {
-            conditions.push("pd.es_destacado = true".to_string());
-        }
+        Ok(count.0)
+    }
 
-        if let Some(true) = filters.nuevos {
-            conditions.push("pd.es_nuevo = true".to_string());
-        }
+    // ==================== FACETAS ====================
 
-        if let Some(true) = filters.ofertas {
-            conditions.push("pd.es_oferta = true".to_string());
-        }
+    /// Conteos por marca, categoría, tramo de precio, stock y valores de los atributos
+    /// de especificación indicados. `tramos` son los límites de precio en orden ascendente.
+    pub async fn get_facetas(
+        pool: &PgPool,
+        filters: &ProductoFilters,
+        atributos: &[String],
+        tramos: &[f64],
+    ) -> Result<busqueda::Facetas, sqlx::Error> {
+        let (marcas, categorias, precios, stock) = tokio::try_join!(
+            Self::faceta_marcas(pool, filters),
+            Self::faceta_categorias(pool, filters),
+            Self::faceta_precios(pool, filters, tramos),
+            Self::faceta_stock(pool, filters),
+        )?;
 
-        if let Some(true) = filters.en_stock {
-            conditions.push("i.cantidad_disponible > 0".to_string());
-        }
+        // Los atributos sin selección comparten una consulta; cada uno con selección
+        // se cuenta sin su propio filtro
+        let (con_seleccion, sin_seleccion): (Vec<String>, Vec<String>) = atributos
+            .iter()
+            .cloned()
+            .partition(|atributo| filters.especificaciones.contains_key(atributo));
 
-        if !conditions.is_empty() {
-            query.push_str(" AND ");
-            query.push_str(&conditions.join(" AND "));
+        let mut valores = Self::valores_especificacion(pool, filters, &sin_seleccion).await?;
+        for atributo in con_seleccion {
+            let mut sin_propio = filters.clone();
+            sin_propio.especificaciones.remove(&atributo);
+            valores.extend(Self::valores_especificacion(pool, &sin_propio, std::slice::from_ref(&atributo)).await?);
         }
 
-        // Ordenamiento
-        let order_by = match filters.order_by.as_deref() {
-            Some("precio_asc") => "pd.precio_venta ASC",
-            Some("precio_desc") => "pd.precio_venta DESC",
-            Some("nombre_asc") => "pd.nombre ASC",
-            Some("nombre_desc") => "pd.nombre DESC",
-            Some("valoracion") => "p.valoracion_promedio DESC NULLS LAST",
-            Some("mas_vendidos") => "pd.total_vendidos DESC",
-            Some("nuevos") => "pd.fecha_creacion DESC",
-            _ => "pd.id_producto_detalle DESC", // Por defecto: más recientes
-        };
+        let especificaciones = atributos
+            .iter()
+            .filter_map(|atributo| {
+                let seleccionados = filters.especificaciones.get(atributo);
+                let valores: Vec<busqueda::ValorFaceta> = valores
+                    .iter()
+                    .filter(|(a, _, _)| a == atributo)
+                    .map(|(_, valor, total)| busqueda::ValorFaceta {
+                        valor: valor.clone(),
+                        total: *total,
+                        seleccionado: seleccionados.is_some_and(|s| s.contains(valor)),
+                    })
+                    .collect();
+                (!valores.is_empty()).then(|| busqueda::FacetaEspecificacion {
+                    atributo: atributo.clone(),
+                    valores,
+                })
+            })
+            .collect();
 
-        query.push_str(&format!(" ORDER BY {} LIMIT {} OFFSET {}", order_by, limit, offset));
+        Ok(busqueda::Facetas {
+            marcas,
+            categorias,
+            precios,
+            stock,
+            especificaciones,
+        })
+    }
 
-        let productos = sqlx::query_as::<_, producto_detalle::ProductoListItem>(&query)
-            .fetch_all(pool)
-            .await?;
+    async fn faceta_marcas(pool: &PgPool, filters: &ProductoFilters) -> Result<Vec<busqueda::FacetaMarca>, sqlx::Error> {
+        let filtros = ProductoFilters { id_marca: None, ..filters.clone() };
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT m.id_marca, m.nombre, COUNT(DISTINCT pd.id_producto_detalle) AS total",
+        );
+        query.push(FROM_PRODUCTOS);
+        filtros.push_where(&mut query, AlcanceFiltro::Catalogo);
+        query.push(" GROUP BY m.id_marca, m.nombre ORDER BY total DESC, m.nombre");
 
-        Ok(productos)
+        query.build_query_as().fetch_all(pool).await
     }
 
-    // ==================== CONTEO DE PRODUCTOS ====================
-    pub async fn count_productos(
+    async fn faceta_categorias(
         pool: &PgPool,
         filters: &ProductoFilters,
-    ) -> Result<i64, sqlx::Error> {
-        let mut query = String::from(
-            r#"
-            SELECT COUNT(DISTINCT pd.id_producto_detalle)
-            FROM producto_detalle pd
-            INNER JOIN producto p ON pd.id_producto = p.id_producto
-            INNER JOIN marca m ON pd.id_marca = m.id_marca
-            INNER JOIN categoria c ON p.id_categoria = c.id_categoria
-            LEFT JOIN inventario i ON pd.id_producto_detalle = i.id_producto_detalle
-            WHERE pd.estado = 'activo' AND p.estado = 'activo'
-            "#,
+    ) -> Result<Vec<busqueda::FacetaCategoria>, sqlx::Error> {
+        let filtros = ProductoFilters { id_categoria: None, ..filters.clone() };
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT c.id_categoria, c.nombre, COUNT(DISTINCT pd.id_producto_detalle) AS total",
         );
+        query.push(FROM_PRODUCTOS);
+        filtros.push_where(&mut query, AlcanceFiltro::Catalogo);
+        query.push(" GROUP BY c.id_categoria, c.nombre ORDER BY total DESC, c.nombre");
 
-        let mut conditions = Vec::new();
-
-        if let Some(ref search) = filters.search {
-            conditions.push(format!(
-                "(pd.nombre ILIKE '%{}%' OR pd.descripcion ILIKE '%{}%' OR pd.modelo ILIKE '%{}%')",
-                search, search, search
-            ));
-        }
-
-        if let Some(categoria_id) = filters.id_categoria {
-            conditions.push(format!("p.id_categoria = {}", categoria_id));
-        }
-
-        if let Some(subcategoria_id) = filters.id_subcategoria {
-            conditions.push(format!("p.id_subcategoria = {}", subcategoria_id));
-        }
-
-        if let Some(marca_id) = filters.id_marca {
-            conditions.push(format!("pd.id_marca = {}", marca_id));
-        }
+        query.build_query_as().fetch_all(pool).await
+    }
 
-        if let Some(familia_id) = filters.id_familia {
-            conditions.push(format!("c.id_familia = {}", familia_id));
+    // Tramos vacíos no se devuelven
+    async fn faceta_precios(
+        pool: &PgPool,
+        filters: &ProductoFilters,
+        tramos: &[f64],
+    ) -> Result<Vec<busqueda::FacetaPrecio>, sqlx::Error> {
+        if tramos.is_empty() {
+            return Ok(Vec::new());
         }
 
-        if let Some(precio_min) = filters.precio_min {
-            conditions.push(format!("pd.precio_venta >= {}", precio_min));
-        }
+        let filtros = ProductoFilters {
+            precio_min: None,
+            precio_max: None,
+            ..filters.clone()
+        };
+        let mut query = QueryBuilder::<Postgres>::new("SELECT width_bucket(CAST(pd.precio_venta AS FLOAT8), ");
+        query
+            .push_bind(tramos.to_vec())
+            .push("::FLOAT8[]) AS tramo, COUNT(DISTINCT pd.id_producto_detalle) AS total");
+        query.push(FROM_PRODUCTOS);
+        filtros.push_where(&mut query, AlcanceFiltro::Catalogo);
+        query.push(" GROUP BY tramo ORDER BY tramo");
 
-        if let Some(precio_max) = filters.precio_max {
-            conditions.push(format!("pd.precio_venta <= {}", precio_max));
-        }
+        let filas: Vec<(i32, i64)> = query.build_query_as().fetch_all(pool).await?;
 
-        if let Some(true) = filters.destacados {
-            conditions.push("pd.es_destacado = true".to_string());
-        }
+        Ok(filas
+            .into_iter()
+            .map(|(tramo, total)| Self::tramo_precio(tramos, tramo, total))
+            .collect())
+    }
 
-        if let Some(true) = filters.nuevos {
-            conditions.push("pd.es_nuevo = true".to_string());
+    // width_bucket: 0 por debajo del primer límite, n por encima del último
+    fn tramo_precio(tramos: &[f64], tramo: i32, total: i64) -> busqueda::FacetaPrecio {
+        let tramo = (tramo.max(0) as usize).min(tramos.len());
+        busqueda::FacetaPrecio {
+            desde: if tramo == 0 { 0.0 } else { tramos[tramo - 1] },
+            hasta: tramos.get(tramo).copied(),
+            total,
         }
+    }
 
-        if let Some(true) = filters.ofertas {
-            conditions.push("pd.es_oferta = true".to_string());
-        }
+    async fn faceta_stock(pool: &PgPool, filters: &ProductoFilters) -> Result<busqueda::FacetaStock, sqlx::Error> {
+        let filtros = ProductoFilters { en_stock: None, ..filters.clone() };
+        let mut query = QueryBuilder::<Postgres>::new(
+            r#"
+            SELECT
+                COUNT(DISTINCT pd.id_producto_detalle) FILTER (WHERE i.cantidad_disponible > 0) AS en_stock,
+                COUNT(DISTINCT pd.id_producto_detalle) FILTER (WHERE COALESCE(i.cantidad_disponible, 0) <= 0) AS sin_stock
+            "#,
+        );
+        query.push(FROM_PRODUCTOS);
+        filtros.push_where(&mut query, AlcanceFiltro::Catalogo);
 
-        if let Some(true) = filters.en_stock {
-            conditions.push("i.cantidad_disponible > 0".to_string());
-        }
+        query.build_query_as().fetch_one(pool).await
+    }
 
-        if !conditions.is_empty() {
-            query.push_str(" AND ");
-            query.push_str(&conditions.join(" AND "));
+    // (atributo, valor, total) de los atributos indicados
+    async fn valores_especificacion(
+        pool: &PgPool,
+        filters: &ProductoFilters,
+        atributos: &[String],
+    ) -> Result<Vec<(String, String, i64)>, sqlx::Error> {
+        if atributos.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let count: (i64,) = sqlx::query_as(&query).fetch_one(pool).await?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT v.atributo, v.valor, COUNT(DISTINCT pd.id_producto_detalle) AS total",
+        );
+        query.push(FROM_PRODUCTOS).push(" CROSS JOIN LATERAL ").push(ESPECIFICACIONES_PRODUCTO);
+        filters.push_where(&mut query, AlcanceFiltro::Catalogo);
+        query
+            .push(" AND v.atributo = ANY(")
+            .push_bind(atributos.to_vec())
+            .push(") GROUP BY v.atributo, v.valor ORDER BY total DESC, v.valor");
 
-        Ok(count.0)
+        query.build_query_as().fetch_all(pool).await
     }
 
     // ==================== PRODUCTO DETALLE (INDIVIDUAL) ====================
@@ -648,6 +679,56 @@ pub async fn crear_valoracion(
 }
 
 // ==================== FILTROS ====================
+
+/// Tablas de productos con los alias que usan los filtros (pd, p, m, c, i)
+pub const FROM_PRODUCTOS: &str = r#"
+            FROM producto_detalle pd
+            INNER JOIN producto p ON pd.id_producto = p.id_producto
+            INNER JOIN marca m ON pd.id_marca = m.id_marca
+            INNER JOIN categoria c ON p.id_categoria = c.id_categoria
+            LEFT JOIN inventario i ON pd.id_producto_detalle = i.id_producto_detalle
+            "#;
+
+/// Valores de especificación de cada producto (alias `v`: atributo, valor). Los de la
+/// variante (`especificacion_producto`) reemplazan a los mismos de `especificaciones_base`.
+const ESPECIFICACIONES_PRODUCTO: &str = r#"
+            (
+                SELECT e.nombre_atributo AS atributo, e.valor_atributo AS valor
+                FROM especificacion_producto e
+                WHERE e.id_producto_detalle = pd.id_producto_detalle
+                UNION
+                SELECT b.key, b.value #>> '{}'
+                FROM jsonb_each(CASE WHEN jsonb_typeof(p.especificaciones_base) = 'object'
+                                     THEN p.especificaciones_base ELSE '{}'::jsonb END) b
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM especificacion_producto e
+                    WHERE e.id_producto_detalle = pd.id_producto_detalle AND e.nombre_atributo = b.key
+                )
+            ) v
+            "#;
+
+/// Similitud mínima (0-1) de la búsqueda por trigramas; "vengance" ~ "vengeance" da 0.58.
+/// Se fija como `pg_trgm.word_similarity_threshold` en cada conexión del pool, para que
+/// el operador `<%` aplique este umbral y pueda usar el índice de trigramas.
+pub const UMBRAL_TRIGRAMAS: f32 = 0.5;
+
+/// A quién se dirige la consulta: la tienda solo ve productos activos y usa la búsqueda
+/// de texto completo con relevancia; la administración ve todos y busca por subcadena
+/// en nombre, SKU y modelo (para localizar un producto exacto)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlcanceFiltro {
+    Catalogo,
+    Administracion,
+}
+
+/// Texto buscado, normalizado para las dos búsquedas del catálogo
+struct Busqueda {
+    /// Para trigramas (respaldo ante errores de tipeo)
+    texto: String,
+    /// Consulta de prefijos para to_tsquery ("portat:* & gamer:*"); solo letras y números
+    prefijos: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ProductoFilters {
     pub search: Option<String>,
@@ -661,6 +742,8 @@ pub struct ProductoFilters {
     pub nuevos: Option<bool>,
     pub ofertas: Option<bool>,
     pub en_stock: Option<bool>,
+    /// Valores elegidos por atributo de especificación; basta con que coincida uno
+    pub especificaciones: BTreeMap<String, Vec<String>>,
     pub order_by: Option<String>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
@@ -680,9 +763,293 @@ impl Default for ProductoFilters {
             nuevos: None,
             ofertas: None,
             en_stock: None,
+            especificaciones: BTreeMap::new(),
             order_by: None,
             limit: Some(20),
             offset: Some(0),
         }
     }
 }
+
+impl ProductoFilters {
+    /// Agregar el WHERE de los filtros. Todos los valores van como parámetros enlazados;
+    /// la consulta debe usar los alias de `FROM_PRODUCTOS`.
+    pub fn push_where(&self, query: &mut QueryBuilder<'_, Postgres>, alcance: AlcanceFiltro) {
+        match alcance {
+            AlcanceFiltro::Catalogo => query.push(" WHERE pd.estado = 'activo' AND p.estado = 'activo'"),
+            AlcanceFiltro::Administracion => query.push(" WHERE TRUE"),
+        };
+
+        match (alcance, self.busqueda()) {
+            (_, None) => {}
+            // Texto completo (español, sin tildes, por prefijos); solo si no hay ninguna
+            // coincidencia en el catálogo se recurre a trigramas (errores de tipeo)
+            (AlcanceFiltro::Catalogo, Some(busqueda)) => {
+                let busqueda_con_prefijos = !busqueda.prefijos.is_empty();
+                query.push(" AND (");
+                if busqueda_con_prefijos {
+                    query
+                        .push("pd.documento_busqueda @@ to_tsquery('es_unaccent', ")
+                        .push_bind(busqueda.prefijos.clone())
+                        .push(") OR (NOT EXISTS (SELECT 1 FROM producto_detalle b WHERE b.estado = 'activo'")
+                        .push(" AND b.documento_busqueda @@ to_tsquery('es_unaccent', ")
+                        .push_bind(busqueda.prefijos)
+                        .push(")) AND ");
+                }
+                query
+                    .push("lower(unaccent(")
+                    .push_bind(busqueda.texto)
+                    .push(")) <% pd.texto_busqueda")
+                    .push(if busqueda_con_prefijos { "))" } else { ")" });
+            }
+            (AlcanceFiltro::Administracion, Some(busqueda)) => {
+                let patron = format!("%{}%", escapar_like(&busqueda.texto));
+                query.push(" AND (");
+                for (i, campo) in ["pd.nombre", "pd.sku", "pd.modelo"].iter().enumerate() {
+                    if i > 0 {
+                        query.push(" OR ");
+                    }
+                    query.push(*campo).push(" ILIKE ").push_bind(patron.clone());
+                }
+                query.push(")");
+            }
+        }
+
+        if let Some(categoria_id) = self.id_categoria {
+            query.push(" AND p.id_categoria = ").push_bind(categoria_id);
+        }
+
+        if let Some(subcategoria_id) = self.id_subcategoria {
+            query.push(" AND p.id_subcategoria = ").push_bind(subcategoria_id);
+        }
+
+        if let Some(marca_id) = self.id_marca {
+            query.push(" AND pd.id_marca = ").push_bind(marca_id);
+        }
+
+        if let Some(familia_id) = self.id_familia {
+            query.push(" AND c.id_familia = ").push_bind(familia_id);
+        }
+
+        if let Some(precio_min) = self.precio_min {
+            query.push(" AND pd.precio_venta >= ").push_bind(precio_min).push("::NUMERIC");
+        }
+
+        if let Some(precio_max) = self.precio_max {
+            query.push(" AND pd.precio_venta <= ").push_bind(precio_max).push("::NUMERIC");
+        }
+
+        if let Some(true) = self.destacados {
+            query.push(" AND pd.es_destacado = true");
+        }
+
+        if let Some(true) = self.nuevos {
+            query.push(" AND pd.es_nuevo = true");
+        }
+
+        if let Some(true) = self.ofertas {
+            query.push(" AND pd.es_oferta = true");
+        }
+
+        if let Some(true) = self.en_stock {
+            query.push(" AND i.cantidad_disponible > 0");
+        }
+
+        for (atributo, valores) in &self.especificaciones {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM ")
+                .push(ESPECIFICACIONES_PRODUCTO)
+                .push(" WHERE v.atributo = ")
+                .push_bind(atributo.clone())
+                .push(" AND v.valor = ANY(")
+                .push_bind(valores.clone())
+                .push("))");
+        }
+    }
+
+    /// Agregar ORDER BY (solo columnas conocidas), LIMIT y OFFSET. Con texto buscado y
+    /// sin orden explícito (o con `relevancia`) se ordena por relevancia.
+    pub fn push_orden_y_pagina(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        let por_relevancia = matches!(self.order_by.as_deref(), None | Some("relevancia"));
+        match self.busqueda().filter(|_| por_relevancia) {
+            Some(busqueda) => {
+                query
+                    .push(" ORDER BY ts_rank(pd.documento_busqueda, to_tsquery('es_unaccent', ")
+                    .push_bind(busqueda.prefijos)
+                    .push(")) DESC, word_similarity(lower(unaccent(")
+                    .push_bind(busqueda.texto)
+                    .push(")), pd.texto_busqueda) DESC, pd.id_producto_detalle DESC");
+            }
+            None => {
+                query.push(" ORDER BY ").push(self.orden_columnas());
+            }
+        }
+
+        let limit = self.limit.unwrap_or(20).clamp(1, 100) as i64;
+        let offset = self.offset.unwrap_or(0).max(0) as i64;
+
+        query
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+    }
+
+    fn orden_columnas(&self) -> &'static str {
+        match self.order_by.as_deref() {
+            Some("precio_asc") => "pd.precio_venta ASC",
+            Some("precio_desc") => "pd.precio_venta DESC",
+            Some("nombre_asc") => "pd.nombre ASC",
+            Some("nombre_desc") => "pd.nombre DESC",
+            Some("valoracion") => "p.valoracion_promedio DESC NULLS LAST",
+            Some("mas_vendidos") => "pd.total_vendidos DESC",
+            Some("nuevos") => "pd.fecha_creacion DESC",
+            _ => "pd.id_producto_detalle DESC", // Por defecto: más recientes
+        }
+    }
+
+    fn busqueda(&self) -> Option<Busqueda> {
+        let texto = self.search.as_deref().map(str::trim).filter(|s| !s.is_empty())?;
+        let prefijos = texto
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|palabra| !palabra.is_empty())
+            .map(|palabra| format!("{}:*", palabra.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(" & ");
+
+        Some(Busqueda {
+            texto: texto.to_lowercase(),
+            prefijos,
+        })
+    }
+}
+
+/// Escapar los comodines de LIKE para que el texto buscado se compare literalmente
+pub fn escapar_like(texto: &str) -> String {
+    texto.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(filters: &ProductoFilters, alcance: AlcanceFiltro) -> String {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT pd.id_producto_detalle");
+        query.push(FROM_PRODUCTOS);
+        filters.push_where(&mut query, alcance);
+        filters.push_orden_y_pagina(&mut query);
+        query.sql().to_string()
+    }
+
+    #[test]
+    fn los_valores_van_como_parametros() {
+        let filters = ProductoFilters {
+            search: Some("rtx'; DROP TABLE producto; --".to_string()),
+            id_marca: Some(3),
+            precio_min: Some(100.0),
+            ..Default::default()
+        };
+
+        for alcance in [AlcanceFiltro::Catalogo, AlcanceFiltro::Administracion] {
+            let sql = sql(&filters, alcance);
+            assert!(!sql.contains("DROP"), "{}", sql);
+            assert!(sql.contains("pd.id_marca = $"), "{}", sql);
+            assert!(sql.contains("pd.precio_venta >= $"), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn el_catalogo_solo_ve_productos_activos() {
+        let filters = ProductoFilters::default();
+        assert!(sql(&filters, AlcanceFiltro::Catalogo).contains("pd.estado = 'activo' AND p.estado = 'activo'"));
+        assert!(!sql(&filters, AlcanceFiltro::Administracion).contains("'activo'"));
+    }
+
+    #[test]
+    fn busqueda_por_prefijos_normalizados() {
+        let filters = ProductoFilters {
+            search: Some("  Portátil-GAMER 16gb ".to_string()),
+            ..Default::default()
+        };
+        let busqueda = filters.busqueda().expect("hay texto buscado");
+        assert_eq!(busqueda.prefijos, "portátil:* & gamer:* & 16gb:*");
+        assert_eq!(busqueda.texto, "portátil-gamer 16gb");
+
+        let vacia = ProductoFilters {
+            search: Some("   ".to_string()),
+            ..Default::default()
+        };
+        assert!(vacia.busqueda().is_none());
+    }
+
+    #[test]
+    fn orden_solo_acepta_columnas_conocidas() {
+        let filters = ProductoFilters {
+            order_by: Some("precio_asc".to_string()),
+            ..Default::default()
+        };
+        assert!(sql(&filters, AlcanceFiltro::Catalogo).contains("ORDER BY pd.precio_venta ASC"));
+
+        let filters = ProductoFilters {
+            order_by: Some("pd.costo; DELETE FROM producto".to_string()),
+            ..Default::default()
+        };
+        let sql = sql(&filters, AlcanceFiltro::Catalogo);
+        assert!(sql.contains("ORDER BY pd.id_producto_detalle DESC"));
+        assert!(!sql.contains("DELETE"));
+    }
+
+    #[test]
+    fn con_texto_buscado_se_ordena_por_relevancia() {
+        let filters = ProductoFilters {
+            search: Some("ryzen".to_string()),
+            ..Default::default()
+        };
+        assert!(sql(&filters, AlcanceFiltro::Catalogo).contains("ORDER BY ts_rank("));
+
+        let filters = ProductoFilters {
+            order_by: Some("nombre_asc".to_string()),
+            ..filters
+        };
+        assert!(sql(&filters, AlcanceFiltro::Catalogo).contains("ORDER BY pd.nombre ASC"));
+    }
+
+    #[test]
+    fn especificaciones_combinan_atributos_con_and_y_valores_con_or() {
+        let filters = ProductoFilters {
+            especificaciones: BTreeMap::from([
+                ("memoria".to_string(), vec!["16GB".to_string(), "32GB".to_string()]),
+                ("socket".to_string(), vec!["AM5' OR '1'='1".to_string()]),
+            ]),
+            ..Default::default()
+        };
+        let sql = sql(&filters, AlcanceFiltro::Catalogo);
+
+        assert_eq!(sql.matches(" AND EXISTS (SELECT 1 FROM ").count(), 2, "{}", sql);
+        assert_eq!(sql.matches("v.valor = ANY($").count(), 2, "{}", sql);
+        assert!(!sql.contains("AM5") && !sql.contains("16GB"), "{}", sql);
+    }
+
+    #[test]
+    fn tramos_de_precio_cubren_los_extremos() {
+        let tramos = [100.0, 500.0, 1000.0];
+        let rango = |tramo| {
+            let faceta = CatalogoRepository::tramo_precio(&tramos, tramo, 7);
+            assert_eq!(faceta.total, 7);
+            (faceta.desde, faceta.hasta)
+        };
+
+        assert_eq!(rango(0), (0.0, Some(100.0)));
+        assert_eq!(rango(1), (100.0, Some(500.0)));
+        assert_eq!(rango(2), (500.0, Some(1000.0)));
+        assert_eq!(rango(3), (1000.0, None));
+        assert_eq!(rango(-1), (0.0, Some(100.0)));
+        assert_eq!(rango(9), (1000.0, None));
+    }
+
+    #[test]
+    fn escapar_like_trata_comodines_como_texto() {
+        assert_eq!(escapar_like("100%_ok\\"), "100\\%\\_ok\\\\");
+        assert_eq!(escapar_like("rtx 4070"), "rtx 4070");
+    }
+}
@@ -0,0 +1,486 @@
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::models::SolicitudEliminacionCuenta;
+
+pub struct DatosPersonalesRepository;
+
+impl DatosPersonalesRepository {
+    // Reunir en un solo documento todos los datos vinculados al usuario.
+    // Se omiten secretos (hash de contraseña, tokens de pago, secreto TOTP) y notas internas.
+    pub async fn exportar(pool: &PgPool, id_usuario: i32) -> Result<Value, sqlx::Error> {
+        let datos = sqlx::query_scalar!(
+            r#"
+            SELECT json_build_object(
+                'perfil', (
+                    SELECT row_to_json(u) FROM (
+                        SELECT id_usuario, nombre, apellido, email, telefono, dni, rol,
+                               email_verificado, activo, fecha_registro, ultima_conexion, fecha_actualizacion
+                        FROM usuario WHERE id_usuario = $1
+                    ) u
+                ),
+                'direcciones', COALESCE((
+                    SELECT json_agg(d ORDER BY d.id_direccion)
+                    FROM direccion d WHERE d.id_usuario = $1
+                ), '[]'::json),
+                'metodos_pago', COALESCE((
+                    SELECT json_agg(m ORDER BY m.id_metodo_pago_cliente) FROM (
+                        SELECT id_metodo_pago_cliente, id_metodo_pago, tipo, ultimos_4_digitos, marca,
+                               fecha_expiracion, nombre_titular, es_predeterminado, activo,
+                               fecha_creacion, fecha_actualizacion
+                        FROM metodo_pago_cliente WHERE id_usuario = $1
+                    ) m
+                ), '[]'::json),
+                'pedidos', COALESCE((
+                    SELECT json_agg(p ORDER BY p.fecha_pedido) FROM (
+                        SELECT v.id_venta, v.numero_pedido, v.subtotal, v.descuento_total, v.costo_envio,
+                               v.total, v.moneda, v.estado, v.estado_pago, v.direccion_envio, v.ciudad,
+                               v.departamento, v.codigo_postal, v.telefono_contacto, v.metodo_envio,
+                               v.numero_tracking, v.fecha_pedido, v.fecha_pago, v.fecha_confirmacion,
+                               v.fecha_envio, v.fecha_entrega_estimada, v.fecha_entrega, v.fecha_cancelacion,
+                               v.notas_cliente, v.ip_cliente, v.user_agent,
+                               COALESCE((
+                                   SELECT json_agg(dv ORDER BY dv.id_detalle_venta) FROM (
+                                       SELECT d.id_detalle_venta, d.id_producto_detalle, pd.nombre AS producto,
+                                              pd.sku, d.cantidad, d.precio_unitario, d.descuento_unitario,
+                                              d.precio_final, d.subtotal
+                                       FROM detalle_venta d
+                                       INNER JOIN producto_detalle pd ON pd.id_producto_detalle = d.id_producto_detalle
+                                       WHERE d.id_venta = v.id_venta
+                                   ) dv
+                               ), '[]'::json) AS detalles,
+                               COALESCE((
+                                   SELECT json_agg(pg ORDER BY pg.id_pago) FROM (
+                                       SELECT id_pago, id_metodo_pago, numero_transaccion, estado, monto, moneda,
+                                              proveedor_pago, ultimos_4_digitos, marca_tarjeta, ip_cliente,
+                                              user_agent, fecha_pago, fecha_creacion
+                                       FROM pago WHERE id_venta = v.id_venta
+                                   ) pg
+                               ), '[]'::json) AS pagos,
+                               COALESCE((
+                                   SELECT json_agg(e ORDER BY e.id_envio) FROM (
+                                       SELECT id_envio, empresa_envio, metodo_envio, numero_tracking, estado,
+                                              direccion_completa, ciudad, departamento, codigo_postal,
+                                              telefono_contacto, fecha_estimada, fecha_envio, fecha_entrega,
+                                              historial_tracking
+                                       FROM envio WHERE id_venta = v.id_venta
+                                   ) e
+                               ), '[]'::json) AS envios,
+                               COALESCE((
+                                   SELECT json_agg(r ORDER BY r.id_reembolso) FROM (
+                                       SELECT id_reembolso, tipo_reembolso, monto_reembolsado, motivo, estado,
+                                              fecha_solicitado, fecha_aprobado, fecha_completado
+                                       FROM reembolso WHERE id_venta = v.id_venta
+                                   ) r
+                               ), '[]'::json) AS reembolsos
+                        FROM venta v WHERE v.id_usuario = $1
+                    ) p
+                ), '[]'::json),
+                'valoraciones', COALESCE((
+                    SELECT json_agg(va ORDER BY va.id_valoracion) FROM (
+                        SELECT v.id_valoracion, v.id_producto, v.id_producto_detalle, v.id_venta, v.calificacion,
+                               v.titulo, v.comentario, v.compra_verificada, v.aprobado,
+                               v.fecha_creacion, v.fecha_actualizacion,
+                               COALESCE((
+                                   SELECT json_agg(i.url_imagen ORDER BY i.orden)
+                                   FROM imagen_valoracion i WHERE i.id_valoracion = v.id_valoracion
+                               ), '[]'::json) AS imagenes
+                        FROM valoracion v WHERE v.id_usuario = $1
+                    ) va
+                ), '[]'::json),
+                'lista_deseos', COALESCE((
+                    SELECT json_agg(l ORDER BY l.fecha_agregado) FROM (
+                        SELECT ld.id_producto_detalle, pd.nombre AS producto, ld.fecha_agregado
+                        FROM lista_deseos ld
+                        INNER JOIN producto_detalle pd ON pd.id_producto_detalle = ld.id_producto_detalle
+                        WHERE ld.id_usuario = $1
+                    ) l
+                ), '[]'::json),
+                'carritos', COALESCE((
+                    SELECT json_agg(c ORDER BY c.id_carrito) FROM (
+                        SELECT ca.id_carrito, ca.estado, ca.fecha_creacion, ca.fecha_actualizacion,
+                               COALESCE((
+                                   SELECT json_agg(it ORDER BY it.fecha_agregado) FROM (
+                                       SELECT cd.id_producto_detalle, pd.nombre AS producto, cd.cantidad,
+                                              cd.precio_unitario, cd.fecha_agregado
+                                       FROM carrito_detalle cd
+                                       INNER JOIN producto_detalle pd ON pd.id_producto_detalle = cd.id_producto_detalle
+                                       WHERE cd.id_carrito = ca.id_carrito
+                                   ) it
+                               ), '[]'::json) AS items
+                        FROM carrito ca WHERE ca.id_usuario = $1
+                    ) c
+                ), '[]'::json),
+                'armados', COALESCE((
+                    SELECT json_agg(a ORDER BY a.id_armado) FROM (
+                        SELECT ap.id_armado, ap.nombre, ap.fecha_creacion, ap.fecha_actualizacion,
+                               COALESCE((
+                                   SELECT json_agg(co ORDER BY co.fecha_agregado) FROM (
+                                       SELECT r.codigo AS ranura, ad.id_producto_detalle, pd.nombre AS producto,
+                                              ad.cantidad, ad.fecha_agregado
+                                       FROM armado_pc_detalle ad
+                                       INNER JOIN ranura_armado r ON r.id_ranura = ad.id_ranura
+                                       INNER JOIN producto_detalle pd ON pd.id_producto_detalle = ad.id_producto_detalle
+                                       WHERE ad.id_armado = ap.id_armado
+                                   ) co
+                               ), '[]'::json) AS componentes
+                        FROM armado_pc ap WHERE ap.id_usuario = $1
+                    ) a
+                ), '[]'::json),
+                'cupones_asignados', COALESCE((
+                    SELECT json_agg(ac ORDER BY ac.fecha_asignacion) FROM (
+                        SELECT cu.codigo, cu.nombre, a.usado, a.fecha_asignacion, a.fecha_uso
+                        FROM asignacion_cupon a
+                        INNER JOIN cupon cu ON cu.id_cupon = a.id_cupon
+                        WHERE a.id_usuario = $1
+                    ) ac
+                ), '[]'::json),
+                'cupones_usados', COALESCE((
+                    SELECT json_agg(uc ORDER BY uc.fecha_uso) FROM (
+                        SELECT cu.codigo, u.id_venta, u.descuento_aplicado, u.fecha_uso
+                        FROM uso_cupon u
+                        INNER JOIN cupon cu ON cu.id_cupon = u.id_cupon
+                        WHERE u.id_usuario = $1
+                    ) uc
+                ), '[]'::json),
+                'notificaciones', COALESCE((
+                    SELECT json_agg(n ORDER BY n.fecha_creacion) FROM (
+                        SELECT tipo, titulo, mensaje, url, leida, fecha_creacion, fecha_leida
+                        FROM notificacion WHERE id_usuario = $1
+                    ) n
+                ), '[]'::json),
+                'seguridad', json_build_object(
+                    'dos_factores', (
+                        SELECT json_build_object('activo', activo, 'fecha_activacion', fecha_activacion)
+                        FROM autenticacion_dos_factores WHERE id_usuario = $1
+                    ),
+                    'recuperaciones_password', COALESCE((
+                        SELECT json_agg(t ORDER BY t.fecha_creacion) FROM (
+                            SELECT fecha_creacion, fecha_expiracion, usado, fecha_uso, ip_solicitud
+                            FROM token_recuperacion WHERE id_usuario = $1
+                        ) t
+                    ), '[]'::json),
+                    'solicitudes_eliminacion', COALESCE((
+                        SELECT json_agg(s ORDER BY s.fecha_solicitud) FROM (
+                            SELECT estado, motivo, fecha_solicitud, fecha_programada, fecha_cancelacion, ip_solicitud
+                            FROM solicitud_eliminacion_cuenta WHERE id_usuario = $1
+                        ) s
+                    ), '[]'::json)
+                ),
+                'actividad', COALESCE((
+                    SELECT json_agg(la ORDER BY la.fecha_creacion DESC) FROM (
+                        SELECT fecha_creacion, nivel, modulo, accion, detalles, ip_cliente, user_agent
+                        FROM log_auditoria WHERE id_usuario = $1
+                    ) la
+                ), '[]'::json)
+            ) as "datos!"
+            "#,
+            id_usuario
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(datos)
+    }
+
+    // ==================== SOLICITUDES DE ELIMINACIÓN ====================
+
+    pub async fn find_solicitud_pendiente(
+        pool: &PgPool,
+        id_usuario: i32,
+    ) -> Result<Option<SolicitudEliminacionCuenta>, sqlx::Error> {
+        let solicitud = sqlx::query_as!(
+            SolicitudEliminacionCuenta,
+            r#"
+            SELECT
+                id_solicitud,
+                id_usuario,
+                estado::text as "estado!",
+                motivo,
+                fecha_solicitud as "fecha_solicitud: NaiveDateTime",
+                fecha_programada as "fecha_programada: NaiveDateTime",
+                fecha_cancelacion as "fecha_cancelacion: NaiveDateTime",
+                fecha_ejecucion as "fecha_ejecucion: NaiveDateTime"
+            FROM solicitud_eliminacion_cuenta
+            WHERE id_usuario = $1 AND estado = 'pendiente'
+            "#,
+            id_usuario
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(solicitud)
+    }
+
+    // Registrar una solicitud pendiente. Devuelve None si ya existía una.
+    pub async fn crear_solicitud(
+        pool: &PgPool,
+        id_usuario: i32,
+        motivo: Option<&str>,
+        fecha_programada: NaiveDateTime,
+        ip_solicitud: Option<&str>,
+    ) -> Result<Option<SolicitudEliminacionCuenta>, sqlx::Error> {
+        let solicitud = sqlx::query_as!(
+            SolicitudEliminacionCuenta,
+            r#"
+            INSERT INTO solicitud_eliminacion_cuenta (id_usuario, motivo, fecha_programada, ip_solicitud)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (id_usuario) WHERE estado = 'pendiente' DO NOTHING
+            RETURNING
+                id_solicitud,
+                id_usuario,
+                estado::text as "estado!",
+                motivo,
+                fecha_solicitud as "fecha_solicitud: NaiveDateTime",
+                fecha_programada as "fecha_programada: NaiveDateTime",
+                fecha_cancelacion as "fecha_cancelacion: NaiveDateTime",
+                fecha_ejecucion as "fecha_ejecucion: NaiveDateTime"
+            "#,
+            id_usuario,
+            motivo,
+            fecha_programada as _,
+            ip_solicitud
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(solicitud)
+    }
+
+    // Cancelar la solicitud pendiente. Devuelve false si no había ninguna.
+    pub async fn cancelar_solicitud(pool: &PgPool, id_usuario: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE solicitud_eliminacion_cuenta
+            SET estado = 'cancelada', fecha_cancelacion = CURRENT_TIMESTAMP
+            WHERE id_usuario = $1 AND estado = 'pendiente'
+            "#,
+            id_usuario
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Solicitudes cuyo periodo de gracia ya venció
+    pub async fn solicitudes_vencidas(
+        pool: &PgPool,
+        ahora: NaiveDateTime,
+    ) -> Result<Vec<SolicitudEliminacionCuenta>, sqlx::Error> {
+        let solicitudes = sqlx::query_as!(
+            SolicitudEliminacionCuenta,
+            r#"
+            SELECT
+                id_solicitud,
+                id_usuario,
+                estado::text as "estado!",
+                motivo,
+                fecha_solicitud as "fecha_solicitud: NaiveDateTime",
+                fecha_programada as "fecha_programada: NaiveDateTime",
+                fecha_cancelacion as "fecha_cancelacion: NaiveDateTime",
+                fecha_ejecucion as "fecha_ejecucion: NaiveDateTime"
+            FROM solicitud_eliminacion_cuenta
+            WHERE estado = 'pendiente' AND fecha_programada <= $1
+            ORDER BY fecha_programada
+            "#,
+            ahora as _
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(solicitudes)
+    }
+
+    // Pedidos que aún no terminan su ciclo (bloquean la anonimización)
+    pub async fn contar_pedidos_en_curso(pool: &PgPool, id_usuario: i32) -> Result<i64, sqlx::Error> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM venta
+            WHERE id_usuario = $1 AND estado IN ('pendiente', 'confirmado', 'procesando', 'enviado')
+            "#,
+            id_usuario
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(total)
+    }
+
+    // Anonimizar la cuenta en una transacción: se borran los datos que no hacen falta y se
+    // eliminan los datos personales de los snapshots de pedidos, pagos, envíos y logs.
+    // Los montos, productos y estados de las ventas se conservan para la contabilidad.
+    pub async fn anonimizar(
+        pool: &PgPool,
+        id_solicitud: i32,
+        id_usuario: i32,
+        email_anonimo: &str,
+        contrasena_inutilizable: &str,
+        ahora: NaiveDateTime,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        // Bloquear la solicitud: si fue cancelada mientras tanto no se hace nada
+        let vigente = sqlx::query_scalar!(
+            r#"
+            SELECT id_solicitud FROM solicitud_eliminacion_cuenta
+            WHERE id_solicitud = $1 AND estado = 'pendiente'
+            FOR UPDATE
+            "#,
+            id_solicitud
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if vigente.is_none() {
+            return Ok(false);
+        }
+
+        let email_original = sqlx::query_scalar!(
+            "SELECT email FROM usuario WHERE id_usuario = $1 FOR UPDATE",
+            id_usuario
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM direccion WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM metodo_pago_cliente WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM valoracion WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM lista_deseos WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM notificacion WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM carrito WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM armado_pc WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM asignacion_cupon WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM token_recuperacion WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM codigo_recuperacion_2fa WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM autenticacion_dos_factores WHERE id_usuario = $1", id_usuario)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE venta
+            SET direccion_envio = NULL,
+                codigo_postal = NULL,
+                telefono_contacto = NULL,
+                notas_cliente = NULL,
+                ip_cliente = NULL,
+                user_agent = NULL,
+                fecha_actualizacion = $2
+            WHERE id_usuario = $1
+            "#,
+            id_usuario,
+            ahora as _
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE pago
+            SET ip_cliente = NULL, user_agent = NULL, token_pago = NULL
+            WHERE id_venta IN (SELECT id_venta FROM venta WHERE id_usuario = $1)
+            "#,
+            id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE envio
+            SET direccion_completa = NULL, codigo_postal = NULL, telefono_contacto = NULL
+            WHERE id_venta IN (SELECT id_venta FROM venta WHERE id_usuario = $1)
+            "#,
+            id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Los logs se conservan, pero sin ninguna columna con datos personales. El trigger
+        // solo admite esta forma de redacción, y el hash sigue verificando el resto de la entrada.
+        sqlx::query!(
+            r#"
+            UPDATE log_auditoria
+            SET redactado = TRUE,
+                email_usuario = NULL,
+                ip_cliente = NULL,
+                user_agent = NULL,
+                detalles = NULL,
+                cambios = NULL
+            WHERE id_usuario = $1 OR email_usuario = $2::text
+               OR (entidad = 'usuario' AND id_entidad = $1::int4::text)
+            "#,
+            id_usuario,
+            email_original
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE usuario
+            SET nombre = 'Usuario',
+                apellido = 'Eliminado',
+                email = $2,
+                contrasena = $3,
+                telefono = NULL,
+                dni = NULL,
+                email_verificado = FALSE,
+                token_verificacion = NULL,
+                activo = FALSE,
+                sesiones_revocadas_desde = $4,
+                fecha_actualizacion = $4
+            WHERE id_usuario = $1
+            "#,
+            id_usuario,
+            email_anonimo,
+            contrasena_inutilizable,
+            ahora as _
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE solicitud_eliminacion_cuenta
+            SET estado = CASE WHEN id_solicitud = $1 THEN 'ejecutada' ELSE estado END,
+                fecha_ejecucion = CASE WHEN id_solicitud = $1 THEN $3 ELSE fecha_ejecucion END,
+                motivo = NULL,
+                ip_solicitud = NULL
+            WHERE id_usuario = $2
+            "#,
+            id_solicitud,
+            id_usuario,
+            ahora as _
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
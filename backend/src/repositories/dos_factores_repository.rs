@@ -0,0 +1,267 @@
+use sqlx::PgPool;
+use crate::models::AutenticacionDosFactores;
+
+pub struct DosFactoresRepository;
+
+impl DosFactoresRepository {
+    // Obtener la configuración 2FA de un usuario
+    pub async fn find_by_usuario(
+        pool: &PgPool,
+        id_usuario: i32,
+    ) -> Result<Option<AutenticacionDosFactores>, sqlx::Error> {
+        let registro = sqlx::query_as!(
+            AutenticacionDosFactores,
+            r#"
+            SELECT
+                secreto,
+                activo as "activo!",
+                (bloqueado_hasta IS NOT NULL AND bloqueado_hasta > CURRENT_TIMESTAMP) as "bloqueado!",
+                fecha_activacion as "fecha_activacion: chrono::NaiveDateTime"
+            FROM autenticacion_dos_factores
+            WHERE id_usuario = $1
+            "#,
+            id_usuario
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(registro)
+    }
+
+    // Guardar un secreto pendiente de confirmar. No reemplaza un 2FA ya activo:
+    // devuelve false en ese caso.
+    pub async fn guardar_secreto_pendiente(
+        pool: &PgPool,
+        id_usuario: i32,
+        secreto: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO autenticacion_dos_factores (id_usuario, secreto, activo)
+            VALUES ($1, $2, FALSE)
+            ON CONFLICT (id_usuario) DO UPDATE
+            SET secreto = EXCLUDED.secreto,
+                ultimo_paso_usado = NULL,
+                intentos_fallidos = 0,
+                bloqueado_hasta = NULL,
+                fecha_activacion = NULL,
+                fecha_creacion = CURRENT_TIMESTAMP
+            WHERE autenticacion_dos_factores.activo = FALSE
+            "#,
+            id_usuario,
+            secreto
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Confirmar el enrolamiento y reemplazar los códigos de recuperación en una transacción
+    pub async fn activar(
+        pool: &PgPool,
+        id_usuario: i32,
+        paso: i64,
+        codigos_hash: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE autenticacion_dos_factores
+            SET activo = TRUE,
+                ultimo_paso_usado = $2,
+                intentos_fallidos = 0,
+                bloqueado_hasta = NULL,
+                fecha_activacion = CURRENT_TIMESTAMP
+            WHERE id_usuario = $1 AND activo = FALSE
+            "#,
+            id_usuario,
+            paso
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        Self::insertar_codigos(&mut tx, id_usuario, codigos_hash).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Reemplazar los códigos de recuperación vigentes
+    pub async fn reemplazar_codigos_recuperacion(
+        pool: &PgPool,
+        id_usuario: i32,
+        codigos_hash: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        Self::insertar_codigos(&mut tx, id_usuario, codigos_hash).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insertar_codigos(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id_usuario: i32,
+        codigos_hash: &[String],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM codigo_recuperacion_2fa WHERE id_usuario = $1",
+            id_usuario
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO codigo_recuperacion_2fa (id_usuario, codigo_hash)
+            SELECT $1, UNNEST($2::TEXT[])
+            "#,
+            id_usuario,
+            codigos_hash
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // Registrar el time-step de un código aceptado. Devuelve false si el paso ya
+    // se había usado (o uno posterior), es decir, si el código se está reutilizando.
+    pub async fn registrar_paso_usado(
+        pool: &PgPool,
+        id_usuario: i32,
+        paso: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE autenticacion_dos_factores
+            SET ultimo_paso_usado = $2,
+                intentos_fallidos = 0,
+                bloqueado_hasta = NULL
+            WHERE id_usuario = $1
+              AND activo = TRUE
+              AND (ultimo_paso_usado IS NULL OR ultimo_paso_usado < $2)
+            "#,
+            id_usuario,
+            paso
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Consumir un código de recuperación. Devuelve false si no existe o ya se usó.
+    pub async fn usar_codigo_recuperacion(
+        pool: &PgPool,
+        id_usuario: i32,
+        codigo_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE codigo_recuperacion_2fa
+            SET usado = TRUE, fecha_uso = CURRENT_TIMESTAMP
+            WHERE id_usuario = $1 AND codigo_hash = $2 AND usado = FALSE
+            "#,
+            id_usuario,
+            codigo_hash
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE autenticacion_dos_factores
+            SET intentos_fallidos = 0, bloqueado_hasta = NULL
+            WHERE id_usuario = $1
+            "#,
+            id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Registrar un código inválido. Al llegar a `max_intentos` se bloquea la
+    // verificación durante `bloqueo_minutos`. Devuelve true si quedó bloqueado.
+    pub async fn registrar_intento_fallido(
+        pool: &PgPool,
+        id_usuario: i32,
+        max_intentos: i32,
+        bloqueo_minutos: i32,
+    ) -> Result<bool, sqlx::Error> {
+        let bloqueado = sqlx::query_scalar!(
+            r#"
+            UPDATE autenticacion_dos_factores
+            SET intentos_fallidos = COALESCE(intentos_fallidos, 0) + 1,
+                bloqueado_hasta = CASE
+                    WHEN COALESCE(intentos_fallidos, 0) + 1 >= $2
+                    THEN CURRENT_TIMESTAMP + make_interval(mins => $3)
+                    ELSE bloqueado_hasta
+                END
+            WHERE id_usuario = $1
+            RETURNING (bloqueado_hasta IS NOT NULL AND bloqueado_hasta > CURRENT_TIMESTAMP) as "bloqueado!"
+            "#,
+            id_usuario,
+            max_intentos,
+            bloqueo_minutos
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(bloqueado.unwrap_or(false))
+    }
+
+    // Códigos de recuperación sin usar
+    pub async fn contar_codigos_restantes(pool: &PgPool, id_usuario: i32) -> Result<i64, sqlx::Error> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "total!"
+            FROM codigo_recuperacion_2fa
+            WHERE id_usuario = $1 AND usado = FALSE
+            "#,
+            id_usuario
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(total)
+    }
+
+    // Eliminar el 2FA y sus códigos de recuperación. Devuelve false si no existía.
+    pub async fn eliminar(pool: &PgPool, id_usuario: i32) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            "DELETE FROM codigo_recuperacion_2fa WHERE id_usuario = $1",
+            id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query!(
+            "DELETE FROM autenticacion_dos_factores WHERE id_usuario = $1",
+            id_usuario
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,5 +1,8 @@
 pub mod catalogo_repository;
 pub mod auth_repository;
+pub mod dos_factores_repository;
+pub mod datos_personales_repository;
+pub mod api_key_repository;
 pub mod carrito_repository;
 pub mod direccion_repository;
 pub mod checkout_repository;
@@ -7,6 +10,9 @@ pub mod metodo_pago_cliente_repository;
 
 pub use catalogo_repository::{CatalogoRepository, ProductoFilters};
 pub use auth_repository::AuthRepository;
+pub use dos_factores_repository::DosFactoresRepository;
+pub use datos_personales_repository::DatosPersonalesRepository;
+pub use api_key_repository::ApiKeyRepository;
 pub use carrito_repository::CarritoRepository;
 pub use direccion_repository::DireccionRepository;
 pub use checkout_repository::CheckoutRepository;
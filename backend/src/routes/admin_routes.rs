@@ -1,5 +1,5 @@
 use axum::{
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use sqlx::PgPool;
@@ -8,6 +8,13 @@ use crate::handlers::admin_handler::{
     listar_usuarios_handler,
     actualizar_usuario_admin_handler,
     crear_administrador_handler,
+    restablecer_dos_factores_handler,
+    impersonar_usuario_handler,
+    reporte_hashes_password_handler,
+    listar_api_keys_handler,
+    crear_api_key_handler,
+    actualizar_api_key_handler,
+    revocar_api_key_handler,
 };
 use crate::handlers::dashboard_handler::get_dashboard_stats;
 
@@ -18,6 +25,13 @@ pub fn admin_routes(pool: PgPool) -> Router {
         // Usuarios
         .route("/usuarios", get(listar_usuarios_handler))
         .route("/usuarios/{id}", put(actualizar_usuario_admin_handler))
+        .route("/usuarios/{id}/2fa", delete(restablecer_dos_factores_handler))
+        .route("/usuarios/{id}/impersonar", post(impersonar_usuario_handler))
         .route("/administradores", post(crear_administrador_handler))
+        // Seguridad
+        .route("/seguridad/hashes-password", get(reporte_hashes_password_handler))
+        // Integraciones
+        .route("/api-keys", get(listar_api_keys_handler).post(crear_api_key_handler))
+        .route("/api-keys/{id}", put(actualizar_api_key_handler).delete(revocar_api_key_handler))
         .with_state(pool)
 }
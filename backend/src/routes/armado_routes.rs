@@ -0,0 +1,35 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::handlers::armado_handler::{
+    get_ranuras_handler,
+    validar_armado_handler,
+    sugerencias_armado_handler,
+    get_armados_handler,
+    crear_armado_handler,
+    get_armado_handler,
+    eliminar_armado_handler,
+    asignar_componente_handler,
+    quitar_componente_handler,
+    get_sugerencias_armado_handler,
+    agregar_armado_carrito_handler,
+};
+
+pub fn armado_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/armados/ranuras", get(get_ranuras_handler))
+        .route("/armados/validar", post(validar_armado_handler))
+        .route("/armados/sugerencias", post(sugerencias_armado_handler))
+        .route("/armados", get(get_armados_handler).post(crear_armado_handler))
+        .route("/armados/{id}", get(get_armado_handler).delete(eliminar_armado_handler))
+        .route(
+            "/armados/{id}/componentes/{ranura}",
+            put(asignar_componente_handler).delete(quitar_componente_handler),
+        )
+        .route("/armados/{id}/sugerencias", get(get_sugerencias_armado_handler))
+        .route("/armados/{id}/carrito", post(agregar_armado_carrito_handler))
+        .with_state(pool)
+}
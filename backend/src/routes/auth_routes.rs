@@ -1,25 +1,59 @@
 use axum::{
+    handler::Handler,
     routing::{get, post, put},
     Router,
 };
 use sqlx::PgPool;
 
+use crate::middleware::RateLimitLayer;
+
 use crate::handlers::auth_handler::{
     register_handler,
     login_handler,
     get_current_user_handler,
     logout_handler,
+    jwks_handler,
     actualizar_perfil_handler,
     cambiar_password_handler,
+    recuperar_password_handler,
+    restablecer_password_handler,
+    verificar_dos_factores_handler,
+    estado_dos_factores_handler,
+    configurar_dos_factores_handler,
+    activar_dos_factores_handler,
+    desactivar_dos_factores_handler,
+    regenerar_codigos_recuperacion_handler,
+    exportar_mis_datos_handler,
+    estado_eliminacion_cuenta_handler,
+    solicitar_eliminacion_cuenta_handler,
+    cancelar_eliminacion_cuenta_handler,
 };
 
 pub fn auth_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/register", post(register_handler))
-        .route("/login", post(login_handler))
+        .route("/register", post(register_handler.layer(RateLimitLayer::new(pool.clone(), "registro"))))
+        .route("/login", post(login_handler.layer(RateLimitLayer::new(pool.clone(), "login"))))
         .route("/me", get(get_current_user_handler))
         .route("/logout", post(logout_handler))
+        .route("/jwks", get(jwks_handler))
         .route("/perfil", put(actualizar_perfil_handler))
         .route("/cambiar-password", put(cambiar_password_handler))
+        .route("/recuperar-password", post(recuperar_password_handler))
+        .route("/restablecer-password", post(restablecer_password_handler))
+        // Autenticación en dos pasos (administradores)
+        .route("/2fa", get(estado_dos_factores_handler))
+        .route("/2fa/verificar", post(verificar_dos_factores_handler))
+        .route("/2fa/configurar", post(configurar_dos_factores_handler))
+        .route("/2fa/activar", post(activar_dos_factores_handler))
+        .route("/2fa/desactivar", post(desactivar_dos_factores_handler))
+        .route("/2fa/codigos-recuperacion", post(regenerar_codigos_recuperacion_handler))
+        // Derechos del titular de datos personales (Ley 29733)
+        .route("/mis-datos/export", get(exportar_mis_datos_handler))
+        .route(
+            "/mis-datos/eliminacion",
+            get(estado_eliminacion_cuenta_handler)
+                .post(solicitar_eliminacion_cuenta_handler)
+                .delete(cancelar_eliminacion_cuenta_handler),
+        )
         .with_state(pool)
 }
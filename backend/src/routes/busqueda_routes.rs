@@ -0,0 +1,12 @@
+use axum::{routing::{get, put}, Router};
+use sqlx::PgPool;
+
+use crate::handlers::busqueda_handler::{bloquear_termino_handler, get_sugerencias_handler, get_terminos_handler};
+
+pub fn busqueda_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/sugerencias", get(get_sugerencias_handler))
+        .route("/terminos", get(get_terminos_handler))
+        .route("/terminos/{termino}/bloqueo", put(bloquear_termino_handler))
+        .with_state(pool)
+}
@@ -1,12 +1,15 @@
 use axum::{
-    routing::{get, post},
+    handler::Handler,
+    routing::get,
     Router,
 };
 use sqlx::PgPool;
 
 use crate::handlers::catalogo_handler::*;
+use crate::middleware::{FuncionalidadLayer, RateLimitLayer};
+use crate::services::feature_flag_service::Funcionalidad;
 
-/// Rutas públicas del catálogo (sin autenticación)
+/// Rutas públicas del catálogo (sin autenticación, salvo crear valoración)
 pub fn catalogo_routes(pool: PgPool) -> Router {
     Router::new()
         // Familias, Categorías, Subcategorías, Marcas
@@ -19,7 +22,14 @@ pub fn catalogo_routes(pool: PgPool) -> Router {
         .route("/productos", get(get_productos))
         .route("/productos/slug/{slug}", get(get_producto_by_slug))
         .route("/productos/{id}", get(get_producto_by_id))
-        .route("/productos/{id}/valoraciones", get(get_valoraciones).post(crear_valoracion))
+        .route(
+            "/productos/{id}/valoraciones",
+            get(get_valoraciones).post(
+                crear_valoracion
+                    .layer(RateLimitLayer::new(pool.clone(), "valoraciones"))
+                    .layer(FuncionalidadLayer::new(Funcionalidad::Valoraciones)),
+            ),
+        )
         
         .with_state(pool)
 }
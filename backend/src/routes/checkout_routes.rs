@@ -1,9 +1,12 @@
 use axum::{
+    handler::Handler,
     routing::{get, post},
     Router,
 };
 use sqlx::PgPool;
 
+use crate::middleware::RateLimitLayer;
+
 use crate::handlers::{
     get_metodos_pago_handler,
     calcular_total_handler,
@@ -18,7 +21,10 @@ pub fn checkout_routes(pool: PgPool) -> Router {
         .route("/metodos-pago", get(get_metodos_pago_handler))
         // Checkout
         .route("/checkout/calcular-total", get(calcular_total_handler))
-        .route("/checkout/procesar", post(procesar_checkout_handler))
+        .route(
+            "/checkout/procesar",
+            post(procesar_checkout_handler.layer(RateLimitLayer::new(pool.clone(), "checkout"))),
+        )
         // Pedidos
         .route("/pedidos", get(get_pedidos_handler))
         .route("/pedidos/{id}", get(get_pedido_handler))
@@ -1,5 +1,5 @@
 use axum::{
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use sqlx::PgPool;
@@ -10,13 +10,25 @@ use crate::handlers::config_handler::{
     update_config_handler,
     update_config_batch_handler,
     get_session_timeout_handler,
+    get_funcionalidades_handler,
+    get_estado_handler,
+    get_config_history_handler,
+    get_config_key_history_handler,
+    rollback_config_handler,
+    rollback_config_batch_handler,
 };
 
 pub fn config_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/", get(get_all_config_handler).put(update_config_batch_handler))
         .route("/session-timeout", get(get_session_timeout_handler))
+        .route("/funcionalidades", get(get_funcionalidades_handler))
+        .route("/estado", get(get_estado_handler))
+        .route("/historial", get(get_config_history_handler))
+        .route("/historial/lotes/{id_lote}/rollback", post(rollback_config_batch_handler))
         .route("/{clave}", get(get_config_handler).put(update_config_handler))
+        .route("/{clave}/historial", get(get_config_key_history_handler))
+        .route("/{clave}/rollback", post(rollback_config_handler))
         .with_state(pool)
 }
 
@@ -1,12 +1,14 @@
-use axum::{routing::{get, post, delete, put}, Router};
+use axum::{handler::Handler, routing::{get, post, delete, put}, Router};
 use sqlx::PgPool;
 
 use crate::handlers::cupon_handler;
+use crate::middleware::FuncionalidadLayer;
+use crate::services::feature_flag_service::Funcionalidad;
 
 pub fn cupon_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/cupones", get(cupon_handler::get_cupones).post(cupon_handler::create_cupon))
-        .route("/cupones/mis", get(cupon_handler::get_mis_cupones))
+        .route("/cupones/mis", get(cupon_handler::get_mis_cupones.layer(FuncionalidadLayer::new(Funcionalidad::Cupones))))
         .route("/cupones/stats", get(cupon_handler::get_cupon_stats))
         .route("/cupones/{id}", get(cupon_handler::get_cupon_detalle).put(cupon_handler::update_cupon).delete(cupon_handler::delete_cupon))
         .route("/cupones/{id}/usuarios", get(cupon_handler::get_assigned_users))
@@ -0,0 +1,27 @@
+use axum::{
+    routing::{get, put},
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::handlers::especificacion_handler::{
+    get_atributos_handler,
+    create_atributo_handler,
+    update_atributo_handler,
+    delete_atributo_handler,
+    get_especificaciones_handler,
+    update_especificaciones_handler,
+    comparar_productos_handler,
+};
+
+pub fn especificacion_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/categorias/{id}/atributos", get(get_atributos_handler).post(create_atributo_handler))
+        .route("/atributos/{id}", put(update_atributo_handler).delete(delete_atributo_handler))
+        .route("/productos/comparar", get(comparar_productos_handler))
+        .route(
+            "/productos/{id}/especificaciones",
+            get(get_especificaciones_handler).put(update_especificaciones_handler),
+        )
+        .with_state(pool)
+}
@@ -2,6 +2,7 @@ use axum::{routing::{get, post, put, delete}, Router};
 use sqlx::PgPool;
 
 use crate::handlers::inventario_handler;
+use crate::middleware::AutenticacionLayer;
 
 pub fn inventario_routes(pool: PgPool) -> Router {
     Router::new()
@@ -14,5 +15,6 @@ pub fn inventario_routes(pool: PgPool) -> Router {
         .route("/inventario/{id}", delete(inventario_handler::delete_inventario))
         .route("/inventario/reportes/general", get(inventario_handler::get_reporte_general))
         .route("/inventario/reportes/valorizacion", get(inventario_handler::get_reporte_valorizacion))
+        .route_layer(AutenticacionLayer::new(pool.clone(), "inventario"))
         .with_state(pool)
 }
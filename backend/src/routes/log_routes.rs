@@ -8,11 +8,17 @@ use crate::handlers::log_handler::{
     listar_logs_handler,
     crear_log_handler,
     limpiar_logs_handler,
+    verificar_logs_handler,
+    listar_archivos_logs_handler,
+    exportar_logs_handler,
 };
 
 pub fn log_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/", get(listar_logs_handler).post(crear_log_handler))
         .route("/limpiar", delete(limpiar_logs_handler))
+        .route("/export", get(exportar_logs_handler))
+        .route("/verificar", get(verificar_logs_handler))
+        .route("/archivos", get(listar_archivos_logs_handler))
         .with_state(pool)
 }
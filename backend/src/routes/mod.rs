@@ -1,5 +1,6 @@
 // Módulos de rutas - Catálogo
 pub mod catalogo_routes;
+pub mod busqueda_routes;
 
 // Módulos de rutas - Autenticación y Carrito (H3nr7)
 pub mod auth_routes;
@@ -7,12 +8,15 @@ pub mod carrito_routes;
 pub mod direccion_routes;
 pub mod checkout_routes;
 pub mod metodo_pago_cliente_routes;
+pub mod armado_routes;
 pub mod admin_routes;
 
 // Módulos de rutas - Administración (main)
 pub mod venta_routes;
 pub mod inventario_routes;
 pub mod producto_routes;
+pub mod taxonomia_routes;
+pub mod especificacion_routes;
 pub mod descuento_routes;
 pub mod cupon_routes;
 pub mod reembolso_routes;
@@ -21,6 +25,7 @@ pub mod config_routes;
 
 // Re-exportaciones - Catálogo
 pub use catalogo_routes::*;
+pub use busqueda_routes::busqueda_routes;
 
 // Re-exportaciones - Auth & Carrito
 pub use auth_routes::auth_routes;
@@ -28,12 +33,15 @@ pub use carrito_routes::carrito_routes;
 pub use direccion_routes::direccion_routes;
 pub use checkout_routes::checkout_routes;
 pub use metodo_pago_cliente_routes::metodo_pago_cliente_routes;
+pub use armado_routes::armado_routes;
 pub use admin_routes::admin_routes;
 
 // Re-exportaciones - Administración
 pub use venta_routes::*;
 pub use inventario_routes::*;
 pub use producto_routes::*;
+pub use taxonomia_routes::taxonomia_routes;
+pub use especificacion_routes::especificacion_routes;
 pub use descuento_routes::*;
 pub use cupon_routes::*;
 pub use reembolso_routes::*;
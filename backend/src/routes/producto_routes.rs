@@ -1,4 +1,4 @@
-use axum::{routing::{get, post}, Router};
+use axum::{routing::{get, post, put}, Router};
 use sqlx::PgPool;
 
 use crate::handlers::producto_handler;
@@ -7,5 +7,16 @@ pub fn producto_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/productos", post(producto_handler::create_producto))
         .route("/productos/check-sku", post(producto_handler::check_sku_availability))
+        .route(
+            "/productos/{id}",
+            put(producto_handler::update_producto).delete(producto_handler::delete_producto),
+        )
+        .route(
+            "/productos-base/{id}",
+            get(producto_handler::get_producto_base)
+                .put(producto_handler::update_producto_base)
+                .delete(producto_handler::delete_producto_base),
+        )
+        .route("/productos-base/{id}/variantes", post(producto_handler::create_variante))
         .with_state(pool)
 }
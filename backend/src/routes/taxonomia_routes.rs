@@ -0,0 +1,37 @@
+use axum::{
+    routing::{post, put},
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::handlers::taxonomia_handler::{
+    crear_familia_handler,
+    actualizar_familia_handler,
+    eliminar_familia_handler,
+    crear_categoria_handler,
+    actualizar_categoria_handler,
+    eliminar_categoria_handler,
+    crear_subcategoria_handler,
+    actualizar_subcategoria_handler,
+    eliminar_subcategoria_handler,
+    crear_marca_handler,
+    actualizar_marca_handler,
+    eliminar_marca_handler,
+};
+
+/// Administración de familias, categorías, subcategorías y marcas (los GET están en catalogo_routes)
+pub fn taxonomia_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/familias", post(crear_familia_handler))
+        .route("/familias/{id}", put(actualizar_familia_handler).delete(eliminar_familia_handler))
+        .route("/categorias", post(crear_categoria_handler))
+        .route("/categorias/{id}", put(actualizar_categoria_handler).delete(eliminar_categoria_handler))
+        .route("/subcategorias", post(crear_subcategoria_handler))
+        .route(
+            "/subcategorias/{id}",
+            put(actualizar_subcategoria_handler).delete(eliminar_subcategoria_handler),
+        )
+        .route("/marcas", post(crear_marca_handler))
+        .route("/marcas/{id}", put(actualizar_marca_handler).delete(eliminar_marca_handler))
+        .with_state(pool)
+}
@@ -2,6 +2,7 @@ use axum::{routing::{get, put}, Router};
 use sqlx::PgPool;
 
 use crate::handlers::venta;
+use crate::middleware::AutenticacionLayer;
 
 pub fn venta_routes(pool: PgPool) -> Router {
     Router::new()
@@ -12,5 +13,6 @@ pub fn venta_routes(pool: PgPool) -> Router {
         .route("/ventas/{id}/notas", get(venta::get_notas_admin))
         .route("/ventas/{id}/notas", put(venta::update_notas_admin))
         .route("/reportes/ventas", get(venta::get_reporte_ventas))
+        .route_layer(AutenticacionLayer::new(pool.clone(), "ventas"))
         .with_state(pool)
 }
@@ -0,0 +1,547 @@
+use rust_decimal::Decimal;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::models::log_auditoria::{LogAuditoria, NivelLog};
+use crate::services::auth_service::{ACCION_LOGIN_EXITOSO, ACCION_LOGIN_FALLIDO};
+use crate::services::log_service::COLUMNAS_LOG;
+use crate::services::{ConfigService, LogService};
+
+const INTERVALO_DETECCION: Duration = Duration::from_secs(10);
+
+/// Entradas que se evalúan por vuelta; si el lote se llena se sigue sin esperar
+const TAMANO_LOTE: i64 = 500;
+
+/// Módulo de las alertas en log_auditoria
+const MODULO_LOG: &str = "Seguridad";
+
+const TIPO_NOTIFICACION: &str = "alerta_seguridad";
+const URL_NOTIFICACION: &str = "/admin/logs";
+
+const ROLES_ADMIN: &[&str] = &["administrador", "super_admin"];
+
+// Acciones de las alertas
+const ALERTA_RAFAGA_FALLOS: &str = "Anomalía: ráfaga de logins fallidos";
+const ALERTA_CREDENTIAL_STUFFING: &str = "Anomalía: posible credential stuffing";
+const ALERTA_IP_NUEVA_ADMIN: &str = "Anomalía: administrador desde una IP nueva";
+const ALERTA_CUPON_ALTO_VALOR: &str = "Anomalía: cupón de alto valor";
+const ALERTA_ASIGNACION_MASIVA: &str = "Anomalía: asignación masiva de cupón";
+const ALERTA_ROL_ADMIN: &str = "Anomalía: rol administrativo asignado";
+
+/// Umbrales de las reglas (claves anomaly_* de configuracion_sistema)
+struct ConfigAnomalias {
+    ventana_minutos: i64,
+    fallos_por_ip: i64,
+    cuentas_por_ip: i64,
+    cupon_porcentaje_maximo: Decimal,
+    cupon_monto_maximo: Decimal,
+    asignacion_masiva: i64,
+}
+
+/// Alerta generada por una regla
+struct Alerta {
+    accion: &'static str,
+    detalles: String,
+    email_usuario: Option<String>,
+    ip_cliente: Option<String>,
+}
+
+/// Detección de anomalías de seguridad. Una tarea en segundo plano recorre las
+/// entradas nuevas de log_auditoria registradas por el servidor (logins, escrituras
+/// auditadas) y les aplica las reglas:
+/// - ráfaga de logins fallidos desde una IP,
+/// - credential stuffing: fallos contra muchas cuentas distintas desde una IP,
+/// - login de un administrador desde una IP que no había usado,
+/// - creación o edición de un cupón con un valor alto,
+/// - asignación masiva de un cupón y asignación de un rol administrativo.
+///
+/// Cada alerta queda en log_auditoria con nivel security y se notifica a los
+/// administradores. El avance se guarda en deteccion_anomalias, así que tras un
+/// reinicio se continúa donde se dejó.
+pub struct AnomaliaService;
+
+impl AnomaliaService {
+    pub fn iniciar_tarea_deteccion(pool: PgPool) {
+        tokio::spawn(async move {
+            let mut intervalo = tokio::time::interval(INTERVALO_DETECCION);
+            let mut ultimo_error: Option<String> = None;
+            loop {
+                intervalo.tick().await;
+
+                loop {
+                    match Self::evaluar_pendientes(&pool).await {
+                        Ok(evaluadas) => {
+                            ultimo_error = None;
+                            if evaluadas < TAMANO_LOTE as usize {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            // Informar una sola vez mientras el error se repita
+                            if ultimo_error.as_deref() != Some(e.as_str()) {
+                                eprintln!("⚠️  Detección de anomalías: {}", e);
+                                ultimo_error = Some(e);
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Evaluar el siguiente lote de entradas. Devuelve cuántas se evaluaron.
+    async fn evaluar_pendientes(pool: &PgPool) -> Result<usize, String> {
+        let Some(config) = Self::cargar_config() else {
+            return Ok(0);
+        };
+
+        // Con varias instancias, solo una evalúa a la vez; las demás se saltan la pasada.
+        // El lock es de la transacción, así que se libera aunque la pasada falle.
+        let mut bloqueo = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Error al iniciar la detección: {}", e))?;
+        let libre = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock(hashtext('deteccion_anomalias'))")
+            .fetch_one(&mut *bloqueo)
+            .await
+            .map_err(|e| format!("Error al bloquear la detección: {}", e))?;
+        if !libre {
+            return Ok(0);
+        }
+
+        let ultimo_id = Self::ultima_evaluada(pool).await?;
+
+        let logs = sqlx::query_as::<_, LogAuditoria>(&format!(
+            "SELECT {} FROM log_auditoria WHERE id_log > $1 ORDER BY id_log LIMIT $2",
+            COLUMNAS_LOG
+        ))
+        .bind(ultimo_id)
+        .bind(TAMANO_LOTE)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Error al leer logs pendientes: {}", e))?;
+
+        for log in &logs {
+            for alerta in Self::evaluar(pool, &config, log).await? {
+                Self::alertar(pool, alerta).await;
+            }
+
+            // Guardar el avance por entrada, aunque una notificación haya fallado: las
+            // reglas sin deduplicación volverían a alertar en cada pasada
+            sqlx::query(
+                "UPDATE deteccion_anomalias SET ultimo_id_log = $1, fecha_actualizacion = CURRENT_TIMESTAMP WHERE id = 1"
+            )
+            .bind(log.id_log)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Error al guardar el avance de la detección: {}", e))?;
+        }
+
+        let _ = bloqueo.commit().await;
+        Ok(logs.len())
+    }
+
+    // Config anomaly_*; None si la detección está desactivada
+    fn cargar_config() -> Option<ConfigAnomalias> {
+        if ConfigService::booleano("anomaly_detection_enabled") == Some(false) {
+            return None;
+        }
+
+        let entero = |clave: &str, defecto: i64| {
+            ConfigService::entero(clave).filter(|v| *v > 0).unwrap_or(defecto)
+        };
+        let decimal = |clave: &str, defecto: i64| {
+            ConfigService::texto(clave)
+                .and_then(|v| Decimal::from_str(v.trim()).ok())
+                .filter(|v| *v > Decimal::ZERO)
+                .unwrap_or(Decimal::from(defecto))
+        };
+
+        Some(ConfigAnomalias {
+            ventana_minutos: entero("anomaly_window_minutes", 15),
+            fallos_por_ip: entero("anomaly_failed_logins_per_ip", 10),
+            cuentas_por_ip: entero("anomaly_accounts_per_ip", 5),
+            cupon_porcentaje_maximo: decimal("anomaly_coupon_max_percentage", 50),
+            cupon_monto_maximo: decimal("anomaly_coupon_max_amount", 500),
+            asignacion_masiva: entero("anomaly_coupon_mass_assignment", 100),
+        })
+    }
+
+    // La primera vez se empieza desde la última entrada existente: no se evalúa el histórico
+    async fn ultima_evaluada(pool: &PgPool) -> Result<i32, String> {
+        sqlx::query(
+            "INSERT INTO deteccion_anomalias (id, ultimo_id_log)
+             SELECT 1, COALESCE(MAX(id_log), 0) FROM log_auditoria
+             ON CONFLICT (id) DO NOTHING"
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Error al iniciar la detección: {}", e))?;
+
+        sqlx::query_scalar::<_, i32>("SELECT ultimo_id_log FROM deteccion_anomalias WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Error al leer el avance de la detección: {}", e))
+    }
+
+    // ==================== REGLAS ====================
+
+    async fn evaluar(pool: &PgPool, config: &ConfigAnomalias, log: &LogAuditoria) -> Result<Vec<Alerta>, String> {
+        // Los logs enviados por el frontend (origen cliente) no son fiables
+        if log.origen != "servidor" {
+            return Ok(Vec::new());
+        }
+
+        let mut alertas = Vec::new();
+
+        if log.accion == ACCION_LOGIN_FALLIDO {
+            alertas.extend(Self::regla_rafaga_fallos(pool, config, log).await?);
+            alertas.extend(Self::regla_credential_stuffing(pool, config, log).await?);
+        }
+
+        if log.accion == ACCION_LOGIN_EXITOSO {
+            alertas.extend(Self::regla_ip_nueva_admin(pool, log).await?);
+        }
+
+        let exitosa = log.codigo_respuesta.is_some_and(|c| (200..300).contains(&c));
+        if exitosa {
+            if let Some(cambios) = log.cambios.as_ref().and_then(Value::as_object) {
+                match log.entidad.as_deref() {
+                    Some("cupon") => {
+                        alertas.extend(Self::regla_cupon_alto_valor(pool, config, log, cambios).await?);
+                        alertas.extend(Self::regla_asignacion_masiva(config, log, cambios));
+                    }
+                    Some("usuario") => alertas.extend(Self::regla_rol_admin(log, cambios)),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(alertas)
+    }
+
+    // Muchos logins fallidos desde una misma IP dentro de la ventana
+    async fn regla_rafaga_fallos(
+        pool: &PgPool,
+        config: &ConfigAnomalias,
+        log: &LogAuditoria,
+    ) -> Result<Option<Alerta>, String> {
+        let Some(ip) = &log.ip_cliente else {
+            return Ok(None);
+        };
+
+        let fallos = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM log_auditoria
+             WHERE accion = $1 AND origen = 'servidor' AND ip_cliente = $2
+               AND id_log <= $3 AND fecha_creacion >= $4 - make_interval(mins => $5::int)"
+        )
+        .bind(ACCION_LOGIN_FALLIDO)
+        .bind(ip)
+        .bind(log.id_log)
+        .bind(log.fecha_creacion)
+        .bind(config.ventana_minutos as i32)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Error al contar logins fallidos: {}", e))?;
+
+        if fallos < config.fallos_por_ip || Self::alerta_reciente(pool, config, ALERTA_RAFAGA_FALLOS, log).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(Alerta {
+            accion: ALERTA_RAFAGA_FALLOS,
+            detalles: format!(
+                "{} intentos de inicio de sesión fallidos desde {} en los últimos {} minutos",
+                fallos, ip, config.ventana_minutos
+            ),
+            email_usuario: None,
+            ip_cliente: Some(ip.clone()),
+        }))
+    }
+
+    // Fallos contra muchas cuentas distintas desde una misma IP dentro de la ventana
+    async fn regla_credential_stuffing(
+        pool: &PgPool,
+        config: &ConfigAnomalias,
+        log: &LogAuditoria,
+    ) -> Result<Option<Alerta>, String> {
+        let Some(ip) = &log.ip_cliente else {
+            return Ok(None);
+        };
+
+        let cuentas = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(DISTINCT LOWER(email_usuario)) FROM log_auditoria
+             WHERE accion = $1 AND origen = 'servidor' AND ip_cliente = $2
+               AND id_log <= $3 AND fecha_creacion >= $4 - make_interval(mins => $5::int)"
+        )
+        .bind(ACCION_LOGIN_FALLIDO)
+        .bind(ip)
+        .bind(log.id_log)
+        .bind(log.fecha_creacion)
+        .bind(config.ventana_minutos as i32)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Error al contar cuentas atacadas: {}", e))?;
+
+        if cuentas < config.cuentas_por_ip || Self::alerta_reciente(pool, config, ALERTA_CREDENTIAL_STUFFING, log).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(Alerta {
+            accion: ALERTA_CREDENTIAL_STUFFING,
+            detalles: format!(
+                "Intentos fallidos contra {} cuentas distintas desde {} en los últimos {} minutos",
+                cuentas, ip, config.ventana_minutos
+            ),
+            email_usuario: None,
+            ip_cliente: Some(ip.clone()),
+        }))
+    }
+
+    // Login de un administrador desde una IP que no aparece en sus logins anteriores.
+    // El primer login registrado de una cuenta no genera alerta: no hay con qué comparar.
+    async fn regla_ip_nueva_admin(pool: &PgPool, log: &LogAuditoria) -> Result<Option<Alerta>, String> {
+        let (Some(id_usuario), Some(ip)) = (log.id_usuario, &log.ip_cliente) else {
+            return Ok(None);
+        };
+
+        let fila = sqlx::query_as::<_, (String, bool, bool)>(
+            "SELECT u.rol::TEXT,
+                    EXISTS(SELECT 1 FROM log_auditoria l
+                           WHERE l.id_usuario = $1 AND l.accion = $2
+                             AND l.origen = 'servidor' AND l.id_log < $3),
+                    EXISTS(SELECT 1 FROM log_auditoria l
+                           WHERE l.id_usuario = $1 AND l.accion = $2
+                             AND l.origen = 'servidor' AND l.id_log < $3 AND l.ip_cliente = $4)
+             FROM usuario u WHERE u.id_usuario = $1"
+        )
+        .bind(id_usuario)
+        .bind(ACCION_LOGIN_EXITOSO)
+        .bind(log.id_log)
+        .bind(ip)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Error al revisar el historial de IPs: {}", e))?;
+
+        let Some((rol, con_historial, ip_conocida)) = fila else {
+            return Ok(None);
+        };
+
+        if !ROLES_ADMIN.contains(&rol.as_str()) || !con_historial || ip_conocida {
+            return Ok(None);
+        }
+
+        let email = log.email_usuario.clone().unwrap_or_else(|| format!("usuario #{}", id_usuario));
+        Ok(Some(Alerta {
+            accion: ALERTA_IP_NUEVA_ADMIN,
+            detalles: format!("{} ({}) inició sesión desde {}, una IP que no había usado antes", email, rol, ip),
+            email_usuario: log.email_usuario.clone(),
+            ip_cliente: Some(ip.clone()),
+        }))
+    }
+
+    // Cupón creado o editado con un descuento por encima de los umbrales
+    async fn regla_cupon_alto_valor(
+        pool: &PgPool,
+        config: &ConfigAnomalias,
+        log: &LogAuditoria,
+        cambios: &serde_json::Map<String, Value>,
+    ) -> Result<Option<Alerta>, String> {
+        let Some(valor) = cambios.get("valor").and_then(|c| Self::decimal(c.get("despues"))) else {
+            return Ok(None);
+        };
+
+        // Si solo cambió el valor, el tipo se lee del cupón
+        let tipo = match cambios.get("tipo_cupon").and_then(|c| c.get("despues")).and_then(Value::as_str) {
+            Some(tipo) => Some(tipo.to_string()),
+            None => match &log.id_entidad {
+                Some(id) => sqlx::query_scalar::<_, String>(
+                    "SELECT tipo_cupon::TEXT FROM cupon WHERE id_cupon::text = $1"
+                )
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| format!("Error al leer el cupón: {}", e))?,
+                None => None,
+            },
+        };
+
+        let (umbral, unidad) = match tipo.as_deref() {
+            Some("porcentaje") => (config.cupon_porcentaje_maximo, "%"),
+            Some("monto_fijo") => (config.cupon_monto_maximo, " de descuento fijo"),
+            _ => return Ok(None),
+        };
+
+        if valor < umbral {
+            return Ok(None);
+        }
+
+        let codigo = cambios
+            .get("codigo")
+            .and_then(|c| c.get("despues"))
+            .and_then(Value::as_str)
+            .map(|c| format!("'{}' ", c))
+            .unwrap_or_default();
+
+        Ok(Some(Alerta {
+            accion: ALERTA_CUPON_ALTO_VALOR,
+            detalles: format!(
+                "Cupón {}(#{}) {} con valor {}{} (umbral {}{}) por {}",
+                codigo,
+                log.id_entidad.as_deref().unwrap_or("?"),
+                if log.metodo_http.as_deref() == Some("POST") { "creado" } else { "modificado" },
+                valor,
+                unidad,
+                umbral,
+                unidad,
+                Self::actor(log)
+            ),
+            email_usuario: log.email_usuario.clone(),
+            ip_cliente: log.ip_cliente.clone(),
+        }))
+    }
+
+    // Un cupón asignado de una vez a muchos usuarios
+    fn regla_asignacion_masiva(
+        config: &ConfigAnomalias,
+        log: &LogAuditoria,
+        cambios: &serde_json::Map<String, Value>,
+    ) -> Option<Alerta> {
+        let cambio = cambios.get("usuarios_asignados")?;
+        let cantidad = |lado: &str| cambio.get(lado).and_then(Value::as_array).map_or(0, |a| a.len() as i64);
+        let nuevos = cantidad("despues") - cantidad("antes");
+
+        if nuevos < config.asignacion_masiva {
+            return None;
+        }
+
+        Some(Alerta {
+            accion: ALERTA_ASIGNACION_MASIVA,
+            detalles: format!(
+                "Cupón #{} asignado a {} usuarios de una vez (umbral {}) por {}",
+                log.id_entidad.as_deref().unwrap_or("?"),
+                nuevos,
+                config.asignacion_masiva,
+                Self::actor(log)
+            ),
+            email_usuario: log.email_usuario.clone(),
+            ip_cliente: log.ip_cliente.clone(),
+        })
+    }
+
+    // Cuenta creada como administrador o promovida a un rol administrativo
+    fn regla_rol_admin(log: &LogAuditoria, cambios: &serde_json::Map<String, Value>) -> Option<Alerta> {
+        let cambio = cambios.get("rol")?;
+        let nuevo = cambio.get("despues").and_then(Value::as_str)?;
+        let anterior = cambio.get("antes").and_then(Value::as_str);
+
+        if !ROLES_ADMIN.contains(&nuevo) {
+            return None;
+        }
+
+        let email = cambios
+            .get("email")
+            .and_then(|c| c.get("despues"))
+            .and_then(Value::as_str)
+            .map(|e| format!("{} ", e))
+            .unwrap_or_default();
+
+        Some(Alerta {
+            accion: ALERTA_ROL_ADMIN,
+            detalles: format!(
+                "Usuario {}(#{}) {} {} por {}",
+                email,
+                log.id_entidad.as_deref().unwrap_or("?"),
+                match anterior {
+                    Some(anterior) => format!("pasó de {} a", anterior),
+                    None => "creado como".to_string(),
+                },
+                nuevo,
+                Self::actor(log)
+            ),
+            email_usuario: log.email_usuario.clone(),
+            ip_cliente: log.ip_cliente.clone(),
+        })
+    }
+
+    // ==================== HELPERS ====================
+
+    // ¿Ya hay una alerta de esta regla para la misma IP dentro de la ventana?
+    async fn alerta_reciente(
+        pool: &PgPool,
+        config: &ConfigAnomalias,
+        accion: &str,
+        log: &LogAuditoria,
+    ) -> Result<bool, String> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM log_auditoria
+                           WHERE modulo = $1 AND accion = $2 AND ip_cliente = $3
+                             AND fecha_creacion >= $4 - make_interval(mins => $5::int))"
+        )
+        .bind(MODULO_LOG)
+        .bind(accion)
+        .bind(&log.ip_cliente)
+        .bind(log.fecha_creacion)
+        .bind(config.ventana_minutos as i32)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Error al buscar alertas recientes: {}", e))
+    }
+
+    // Registrar la alerta y notificar a los administradores activos. Los errores se
+    // reportan por consola, como los de LogService::registrar
+    async fn alertar(pool: &PgPool, alerta: Alerta) {
+        LogService::registrar(
+            pool,
+            None,
+            LogService::nuevo(
+                NivelLog::Security,
+                alerta.accion,
+                MODULO_LOG,
+                Some(alerta.detalles.clone()),
+                alerta.email_usuario,
+                alerta.ip_cliente,
+            ),
+        )
+        .await;
+
+        let titulo = alerta.accion.trim_start_matches("Anomalía: ");
+
+        let resultado = sqlx::query(
+            "INSERT INTO notificacion (id_usuario, tipo, titulo, mensaje, url)
+             SELECT id_usuario, $1, $2, $3, $4 FROM usuario
+             WHERE activo = TRUE AND rol::TEXT = ANY($5)"
+        )
+        .bind(TIPO_NOTIFICACION)
+        .bind(format!("Alerta de seguridad: {}", titulo))
+        .bind(&alerta.detalles)
+        .bind(URL_NOTIFICACION)
+        .bind(ROLES_ADMIN)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = resultado {
+            eprintln!("⚠️  Error al notificar a los administradores: {}", e);
+        }
+    }
+
+    fn actor(log: &LogAuditoria) -> String {
+        log.email_usuario
+            .clone()
+            .or_else(|| log.id_usuario.map(|id| format!("usuario #{}", id)))
+            .unwrap_or_else(|| "un actor sin identificar".to_string())
+    }
+
+    // to_jsonb(numeric) da un número JSON; se acepta también texto
+    fn decimal(valor: Option<&Value>) -> Option<Decimal> {
+        match valor? {
+            Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+            Value::String(s) => Decimal::from_str(s).ok(),
+            _ => None,
+        }
+        .map(|d| d.normalize())
+    }
+}
@@ -0,0 +1,274 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::net::IpAddr;
+
+use crate::middleware::rate_limit::RedIp;
+use crate::models::api_key::SCOPES_API_KEY;
+use crate::models::log_auditoria::NivelLog;
+use crate::models::{ActualizarApiKeyRequest, ApiKey, ApiKeyCreadaResponse, CrearApiKeyRequest};
+use crate::repositories::ApiKeyRepository;
+use crate::services::LogService;
+
+/// Prefijo de todas las claves emitidas, para reconocerlas en logs o repositorios
+const PREFIJO_CLAVE: &str = "kt_";
+
+const LIMITE_MAXIMO_POR_MINUTO: i32 = 10_000;
+
+const MODULO_LOG: &str = "Integraciones";
+
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    pub async fn listar(pool: &PgPool) -> Result<Vec<ApiKey>, String> {
+        ApiKeyRepository::listar(pool)
+            .await
+            .map_err(|e| format!("Error al listar API keys: {}", e))
+    }
+
+    // Crear una clave. La clave completa solo se devuelve en esta respuesta.
+    pub async fn crear(
+        pool: &PgPool,
+        id_admin: i32,
+        mut request: CrearApiKeyRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<ApiKeyCreadaResponse, String> {
+        request.nombre = request.nombre.trim().to_string();
+        if request.nombre.is_empty() {
+            return Err("El nombre de la API key es obligatorio".to_string());
+        }
+        request.scopes = Self::normalizar_scopes(&request.scopes)?;
+        request.ips_permitidas = Self::normalizar_ips(request.ips_permitidas.as_deref())?;
+        Self::validar_limite(request.limite_por_minuto)?;
+        Self::validar_expiracion(request.fecha_expiracion.as_ref())?;
+
+        let (clave, prefijo) = Self::generar_clave();
+
+        let api_key = ApiKeyRepository::crear(pool, &request, &prefijo, &Self::hash_clave(&clave), id_admin)
+            .await
+            .map_err(|e| format!("Error al crear la API key: {}", e))?;
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "API key creada",
+                MODULO_LOG,
+                Some(format!(
+                    "API key '{}' ({}) con scopes [{}]",
+                    api_key.nombre,
+                    api_key.prefijo,
+                    api_key.scopes.join(", ")
+                )),
+                None,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(ApiKeyCreadaResponse { clave, api_key })
+    }
+
+    pub async fn actualizar(
+        pool: &PgPool,
+        id_admin: i32,
+        id_api_key: i32,
+        mut request: ActualizarApiKeyRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<ApiKey, String> {
+        if let Some(nombre) = &request.nombre {
+            let nombre = nombre.trim().to_string();
+            if nombre.is_empty() {
+                return Err("El nombre de la API key es obligatorio".to_string());
+            }
+            request.nombre = Some(nombre);
+        }
+        if let Some(scopes) = &request.scopes {
+            request.scopes = Some(Self::normalizar_scopes(scopes)?);
+        }
+        if let Some(ips) = &request.ips_permitidas {
+            // Lista vacía: quitar la restricción
+            request.ips_permitidas = Some(Self::normalizar_ips(Some(ips))?.unwrap_or_default());
+        }
+        Self::validar_limite(request.limite_por_minuto)?;
+        Self::validar_expiracion(request.fecha_expiracion.as_ref())?;
+
+        let api_key = ApiKeyRepository::actualizar(pool, id_api_key, &request)
+            .await
+            .map_err(|e| format!("Error al actualizar la API key: {}", e))?
+            .ok_or("API key no encontrada o revocada".to_string())?;
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "API key actualizada",
+                MODULO_LOG,
+                Some(format!(
+                    "API key '{}' ({}): scopes [{}], IPs [{}], {} solicitudes/min, expira {}",
+                    api_key.nombre,
+                    api_key.prefijo,
+                    api_key.scopes.join(", "),
+                    api_key.ips_permitidas.as_deref().map(|ips| ips.join(", ")).unwrap_or_else(|| "cualquiera".to_string()),
+                    api_key.limite_por_minuto,
+                    api_key
+                        .fecha_expiracion
+                        .map(|f| f.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "nunca".to_string())
+                )),
+                None,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(api_key)
+    }
+
+    pub async fn revocar(
+        pool: &PgPool,
+        id_admin: i32,
+        id_api_key: i32,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let nombre = ApiKeyRepository::revocar(pool, id_api_key)
+            .await
+            .map_err(|e| format!("Error al revocar la API key: {}", e))?
+            .ok_or("API key no encontrada o ya revocada".to_string())?;
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "API key revocada",
+                MODULO_LOG,
+                Some(format!("API key '{}' (#{}) revocada", nombre, id_api_key)),
+                None,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    // Validar una clave recibida en X-Api-Key: vigente y usada desde una IP permitida.
+    // Los scopes y el límite de solicitudes se comprueban en el middleware.
+    pub async fn autenticar(pool: &PgPool, clave: &str, ip: &str) -> Result<ApiKey, String> {
+        let api_key = ApiKeyRepository::find_by_hash(pool, &Self::hash_clave(clave.trim()))
+            .await
+            .map_err(|e| format!("Error al verificar la API key: {}", e))?
+            .ok_or("API key inválida".to_string())?;
+
+        if !api_key.activo {
+            return Err(format!("API key {} revocada", api_key.prefijo));
+        }
+
+        if api_key
+            .fecha_expiracion
+            .is_some_and(|expira| expira <= chrono::Utc::now().naive_utc())
+        {
+            return Err(format!("API key {} expirada", api_key.prefijo));
+        }
+
+        if let Some(ips) = &api_key.ips_permitidas {
+            let permitida = ip.parse::<IpAddr>().is_ok_and(|ip| {
+                ips.iter()
+                    .filter_map(|red| RedIp::parse(red))
+                    .any(|red| red.contiene(&ip))
+            });
+
+            if !permitida {
+                return Err(format!("API key {} usada desde una IP no permitida ({})", api_key.prefijo, ip));
+            }
+        }
+
+        Ok(api_key)
+    }
+
+    pub async fn registrar_uso(pool: &PgPool, id_api_key: i32, ip: &str) {
+        if let Err(e) = ApiKeyRepository::registrar_uso(pool, id_api_key, ip).await {
+            eprintln!("⚠️  Error al registrar uso de API key {}: {}", id_api_key, e);
+        }
+    }
+
+    // ==================== HELPERS ====================
+
+    fn normalizar_scopes(scopes: &[String]) -> Result<Vec<String>, String> {
+        let mut normalizados: Vec<String> = scopes.iter().map(|s| s.trim().to_lowercase()).collect();
+        normalizados.sort();
+        normalizados.dedup();
+
+        if normalizados.is_empty() {
+            return Err("La API key debe tener al menos un scope".to_string());
+        }
+
+        if let Some(invalido) = normalizados.iter().find(|s| !SCOPES_API_KEY.contains(&s.as_str())) {
+            return Err(format!(
+                "Scope no soportado: {}. Valores válidos: {}",
+                invalido,
+                SCOPES_API_KEY.join(", ")
+            ));
+        }
+
+        Ok(normalizados)
+    }
+
+    // IPs o redes CIDR; None o lista vacía = sin restricción
+    fn normalizar_ips(ips: Option<&[String]>) -> Result<Option<Vec<String>>, String> {
+        let Some(ips) = ips else {
+            return Ok(None);
+        };
+
+        let normalizadas: Vec<String> = ips
+            .iter()
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty())
+            .collect();
+
+        if let Some(invalida) = normalizadas.iter().find(|ip| RedIp::parse(ip).is_none()) {
+            return Err(format!("IP o red inválida: {}", invalida));
+        }
+
+        Ok(Some(normalizadas).filter(|ips| !ips.is_empty()))
+    }
+
+    fn validar_limite(limite: Option<i32>) -> Result<(), String> {
+        match limite {
+            Some(limite) if !(1..=LIMITE_MAXIMO_POR_MINUTO).contains(&limite) => Err(format!(
+                "El límite por minuto debe estar entre 1 y {}",
+                LIMITE_MAXIMO_POR_MINUTO
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn validar_expiracion(fecha: Option<&chrono::NaiveDateTime>) -> Result<(), String> {
+        match fecha {
+            Some(fecha) if *fecha <= chrono::Utc::now().naive_utc() => {
+                Err("La fecha de expiración debe ser futura".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Clave "kt_<prefijo>_<secreto>": el prefijo identifica la clave sin revelarla
+    fn generar_clave() -> (String, String) {
+        let mut id = [0u8; 4];
+        let mut secreto = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut id);
+        rand::rngs::OsRng.fill_bytes(&mut secreto);
+
+        let prefijo = format!("{}{}", PREFIJO_CLAVE, hex::encode(id));
+        let clave = format!("{}_{}", prefijo, hex::encode(secreto));
+        (clave, prefijo)
+    }
+
+    // La clave tiene 256 bits aleatorios: basta un SHA-256 (no hace falta un hash lento)
+    fn hash_clave(clave: &str) -> String {
+        hex::encode(Sha256::digest(clave.as_bytes()))
+    }
+}
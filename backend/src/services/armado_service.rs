@@ -0,0 +1,869 @@
+use rust_decimal::Decimal;
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::armado_pc::{
+    ArmadoDetalle, ArmadoPc, ArmadoValidado, AsignarComponenteRequest, ComponenteArmado, ComponenteRequest,
+    CrearArmadoRequest, RanuraArmado, ReglaCompatibilidad, ResultadoRegla, SugerenciaComponente, ValidacionArmado,
+    REGLA_COMPATIBLE, REGLA_INCOMPATIBLE, REGLA_SIN_DATOS,
+};
+use crate::models::CarritoResponse;
+use crate::repositories::catalogo_repository::FROM_PRODUCTOS;
+use crate::repositories::CarritoRepository;
+use crate::services::CarritoService;
+
+const SUGERENCIAS_POR_DEFECTO: i64 = 10;
+const MAXIMO_SUGERENCIAS: i64 = 50;
+const MAXIMO_NOMBRE: usize = 150;
+
+const COLUMNAS_ARMADO: &str = "id_armado, id_usuario, nombre, fecha_creacion, fecha_actualizacion";
+
+#[derive(Debug)]
+pub enum ArmadoError {
+    NoEncontrado(String),
+    Invalida(String),
+    /// Armado incompatible o con productos sin stock
+    Conflicto(String),
+    BaseDatos(String),
+}
+
+impl std::fmt::Display for ArmadoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArmadoError::NoEncontrado(m)
+            | ArmadoError::Invalida(m)
+            | ArmadoError::Conflicto(m)
+            | ArmadoError::BaseDatos(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ArmadoError {
+    fn from(e: sqlx::Error) -> Self {
+        ArmadoError::BaseDatos(format!("Error en la base de datos: {}", e))
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct ProductoArmado {
+    id_producto_detalle: i32,
+    id_categoria: i32,
+    nombre: String,
+    sku: String,
+    imagen_principal: Option<String>,
+    precio_venta: f64,
+    estado: String,
+    stock_disponible: i32,
+    #[sqlx(skip)]
+    especificaciones: BTreeMap<String, String>,
+}
+
+pub struct ArmadoService;
+
+impl ArmadoService {
+    // ==================== RANURAS Y REGLAS ====================
+
+    pub async fn ranuras(pool: &PgPool) -> Result<Vec<RanuraArmado>, sqlx::Error> {
+        sqlx::query_as::<_, RanuraArmado>(
+            r#"
+            SELECT r.id_ranura, r.codigo, r.nombre, r.id_categoria, c.nombre AS categoria,
+                   r.obligatoria, r.cantidad_maxima, r.orden
+            FROM ranura_armado r
+            INNER JOIN categoria c ON r.id_categoria = c.id_categoria
+            ORDER BY r.orden, r.codigo
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn reglas(pool: &PgPool) -> Result<Vec<ReglaCompatibilidad>, sqlx::Error> {
+        sqlx::query_as::<_, ReglaCompatibilidad>(
+            r#"
+            SELECT codigo, descripcion, ranura_origen, atributos_origen, ranura_destino, atributo_destino,
+                   operador, CAST(margen_porcentaje AS FLOAT8) AS margen_porcentaje, severidad
+            FROM regla_compatibilidad
+            WHERE activo = TRUE
+            ORDER BY id_regla
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    // ==================== VALIDACIÓN Y SUGERENCIAS ====================
+
+    /// Cargar los productos de cada ranura y evaluar las reglas de compatibilidad
+    pub async fn validar(pool: &PgPool, componentes: &[ComponenteRequest]) -> Result<ArmadoValidado, ArmadoError> {
+        let ranuras = Self::ranuras(pool).await?;
+        let componentes = Self::cargar_componentes(pool, &ranuras, componentes).await?;
+        let reglas = Self::reglas(pool).await?;
+        let validacion = Self::validacion(&ranuras, &reglas, &componentes);
+
+        Ok(ArmadoValidado { componentes, validacion })
+    }
+
+    /// Productos activos con stock de la ranura que no rompen ninguna regla de severidad
+    /// "error" con el resto del armado. El producto que ya ocupa la ranura se reemplaza.
+    pub async fn sugerencias(
+        pool: &PgPool,
+        componentes: &[ComponenteRequest],
+        ranura: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<SugerenciaComponente>, ArmadoError> {
+        let ranuras = Self::ranuras(pool).await?;
+        let objetivo = ranuras
+            .iter()
+            .find(|r| r.codigo == ranura)
+            .ok_or_else(|| ArmadoError::Invalida(format!("Ranura desconocida: '{}'", ranura)))?;
+        let limit = limit.unwrap_or(SUGERENCIAS_POR_DEFECTO).clamp(1, MAXIMO_SUGERENCIAS) as usize;
+
+        let cantidad = componentes
+            .iter()
+            .find(|c| c.ranura == objetivo.codigo)
+            .and_then(|c| c.cantidad)
+            .unwrap_or(1)
+            .clamp(1, objetivo.cantidad_maxima);
+        let resto: Vec<ComponenteRequest> = componentes
+            .iter()
+            .filter(|c| c.ranura != objetivo.codigo)
+            .cloned()
+            .collect();
+        let base = Self::cargar_componentes(pool, &ranuras, &resto).await?;
+        let reglas = Self::reglas(pool).await?;
+
+        let candidatos: Vec<i32> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT pd.id_producto_detalle
+            {}
+            WHERE p.id_categoria = $1 AND pd.estado = 'activo' AND p.estado = 'activo'
+              AND COALESCE(i.cantidad_disponible, 0) >= $2
+            ORDER BY pd.es_destacado DESC, pd.precio_venta, pd.id_producto_detalle
+            "#,
+            FROM_PRODUCTOS
+        ))
+        .bind(objetivo.id_categoria)
+        .bind(cantidad)
+        .fetch_all(pool)
+        .await?;
+        let productos = Self::productos(pool, &candidatos).await?;
+
+        let mut sugerencias = Vec::new();
+        for id in candidatos {
+            let Some(producto) = productos.get(&id) else {
+                continue;
+            };
+            let candidato = Self::componente(objetivo, producto, cantidad);
+
+            // Solo cuentan las reglas en las que interviene el candidato: el resto del
+            // armado puede tener sus propios problemas
+            let aplicables: Vec<ReglaCompatibilidad> = reglas
+                .iter()
+                .filter(|r| match r.ranura_origen.as_deref() {
+                    _ if r.ranura_destino == objetivo.codigo => true,
+                    Some(origen) => origen == objetivo.codigo,
+                    None => r.atributos_origen.iter().any(|a| candidato.especificaciones.contains_key(a)),
+                })
+                .cloned()
+                .collect();
+
+            let mut armado = base.clone();
+            armado.push(candidato.clone());
+            let resultados = Self::evaluar(&aplicables, &armado);
+            if resultados.iter().any(Self::bloquea) {
+                continue;
+            }
+
+            sugerencias.push(SugerenciaComponente {
+                componente: candidato,
+                observaciones: resultados.into_iter().filter(|r| r.estado != REGLA_COMPATIBLE).collect(),
+            });
+            if sugerencias.len() == limit {
+                break;
+            }
+        }
+
+        Ok(sugerencias)
+    }
+
+    // ==================== ARMADOS GUARDADOS ====================
+
+    pub async fn listar(pool: &PgPool, id_usuario: i32) -> Result<Vec<ArmadoPc>, sqlx::Error> {
+        sqlx::query_as::<_, ArmadoPc>(&format!(
+            "SELECT {} FROM armado_pc WHERE id_usuario = $1 ORDER BY fecha_actualizacion DESC",
+            COLUMNAS_ARMADO
+        ))
+        .bind(id_usuario)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn obtener(pool: &PgPool, id_usuario: i32, id_armado: i32) -> Result<ArmadoDetalle, ArmadoError> {
+        let armado = Self::armado(pool, id_usuario, id_armado).await?;
+        let guardados = Self::componentes_guardados(pool, id_armado).await?;
+        let validado = Self::validar(pool, &guardados).await?;
+
+        Ok(ArmadoDetalle {
+            armado,
+            componentes: validado.componentes,
+            validacion: validado.validacion,
+        })
+    }
+
+    /// Sugerencias para una ranura de un armado guardado
+    pub async fn sugerencias_armado(
+        pool: &PgPool,
+        id_usuario: i32,
+        id_armado: i32,
+        ranura: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<SugerenciaComponente>, ArmadoError> {
+        Self::armado(pool, id_usuario, id_armado).await?;
+        let guardados = Self::componentes_guardados(pool, id_armado).await?;
+
+        Self::sugerencias(pool, &guardados, ranura, limit).await
+    }
+
+    pub async fn crear(
+        pool: &PgPool,
+        id_usuario: i32,
+        request: CrearArmadoRequest,
+    ) -> Result<ArmadoDetalle, ArmadoError> {
+        let nombre = request.nombre.trim();
+        if nombre.is_empty() || nombre.chars().count() > MAXIMO_NOMBRE {
+            return Err(ArmadoError::Invalida(format!(
+                "El nombre del armado es obligatorio (máximo {} caracteres)",
+                MAXIMO_NOMBRE
+            )));
+        }
+
+        // Valida ranuras, categorías y cantidades antes de guardar
+        let componentes = request.componentes.unwrap_or_default();
+        let ranuras = Self::ranuras(pool).await?;
+        Self::cargar_componentes(pool, &ranuras, &componentes).await?;
+
+        let mut tx = pool.begin().await?;
+
+        let id_armado: i32 =
+            sqlx::query_scalar("INSERT INTO armado_pc (id_usuario, nombre) VALUES ($1, $2) RETURNING id_armado")
+                .bind(id_usuario)
+                .bind(nombre)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        for componente in &componentes {
+            sqlx::query(
+                r#"
+                INSERT INTO armado_pc_detalle (id_armado, id_ranura, id_producto_detalle, cantidad)
+                SELECT $1, id_ranura, $3, $4 FROM ranura_armado WHERE codigo = $2
+                "#,
+            )
+            .bind(id_armado)
+            .bind(&componente.ranura)
+            .bind(componente.id_producto_detalle)
+            .bind(componente.cantidad.unwrap_or(1))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Self::obtener(pool, id_usuario, id_armado).await
+    }
+
+    /// Poner (o reemplazar) el producto de una ranura
+    pub async fn asignar_componente(
+        pool: &PgPool,
+        id_usuario: i32,
+        id_armado: i32,
+        ranura: &str,
+        request: AsignarComponenteRequest,
+    ) -> Result<ArmadoDetalle, ArmadoError> {
+        Self::armado(pool, id_usuario, id_armado).await?;
+
+        let componente = ComponenteRequest {
+            ranura: ranura.to_string(),
+            id_producto_detalle: request.id_producto_detalle,
+            cantidad: request.cantidad,
+        };
+        let ranuras = Self::ranuras(pool).await?;
+        Self::cargar_componentes(pool, &ranuras, std::slice::from_ref(&componente)).await?;
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO armado_pc_detalle (id_armado, id_ranura, id_producto_detalle, cantidad)
+            SELECT $1, id_ranura, $3, $4 FROM ranura_armado WHERE codigo = $2
+            ON CONFLICT (id_armado, id_ranura) DO UPDATE
+            SET id_producto_detalle = EXCLUDED.id_producto_detalle,
+                cantidad = EXCLUDED.cantidad,
+                fecha_agregado = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(id_armado)
+        .bind(ranura)
+        .bind(componente.id_producto_detalle)
+        .bind(componente.cantidad.unwrap_or(1))
+        .execute(&mut *tx)
+        .await?;
+
+        Self::tocar(&mut tx, id_armado).await?;
+        tx.commit().await?;
+
+        Self::obtener(pool, id_usuario, id_armado).await
+    }
+
+    pub async fn quitar_componente(
+        pool: &PgPool,
+        id_usuario: i32,
+        id_armado: i32,
+        ranura: &str,
+    ) -> Result<ArmadoDetalle, ArmadoError> {
+        Self::armado(pool, id_usuario, id_armado).await?;
+
+        let mut tx = pool.begin().await?;
+
+        let eliminados = sqlx::query(
+            r#"
+            DELETE FROM armado_pc_detalle d
+            USING ranura_armado r
+            WHERE d.id_ranura = r.id_ranura AND d.id_armado = $1 AND r.codigo = $2
+            "#,
+        )
+        .bind(id_armado)
+        .bind(ranura)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if eliminados == 0 {
+            return Err(ArmadoError::NoEncontrado(format!(
+                "El armado no tiene producto en la ranura '{}'",
+                ranura
+            )));
+        }
+
+        Self::tocar(&mut tx, id_armado).await?;
+        tx.commit().await?;
+
+        Self::obtener(pool, id_usuario, id_armado).await
+    }
+
+    pub async fn eliminar(pool: &PgPool, id_usuario: i32, id_armado: i32) -> Result<(), ArmadoError> {
+        let eliminados = sqlx::query("DELETE FROM armado_pc WHERE id_armado = $1 AND id_usuario = $2")
+            .bind(id_armado)
+            .bind(id_usuario)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        if eliminados == 0 {
+            return Err(ArmadoError::NoEncontrado("Armado no encontrado".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Agregar todos los componentes al carrito. Se rechaza si alguna regla de severidad
+    /// "error" falla o si algún producto no está disponible; un armado incompleto sí se
+    /// puede agregar (el cliente puede tener ya alguna pieza).
+    pub async fn agregar_al_carrito(
+        pool: &PgPool,
+        id_usuario: i32,
+        id_armado: i32,
+    ) -> Result<CarritoResponse, ArmadoError> {
+        let detalle = Self::obtener(pool, id_usuario, id_armado).await?;
+
+        if detalle.componentes.is_empty() {
+            return Err(ArmadoError::Invalida("El armado no tiene componentes".to_string()));
+        }
+
+        if !detalle.validacion.compatible {
+            let problemas: Vec<String> = detalle
+                .validacion
+                .resultados
+                .iter()
+                .filter(|r| Self::bloquea(r))
+                .filter_map(|r| r.mensaje.clone())
+                .collect();
+            return Err(ArmadoError::Conflicto(format!(
+                "El armado tiene incompatibilidades: {}",
+                problemas.join("; ")
+            )));
+        }
+
+        let no_disponibles: Vec<&str> = detalle
+            .componentes
+            .iter()
+            .filter(|c| !c.disponible)
+            .map(|c| c.nombre.as_str())
+            .collect();
+        if !no_disponibles.is_empty() {
+            return Err(ArmadoError::Conflicto(format!(
+                "Productos sin stock o no disponibles: {}",
+                no_disponibles.join(", ")
+            )));
+        }
+
+        let carrito = CarritoRepository::get_or_create_carrito_usuario(pool, id_usuario).await?;
+
+        // Todos los componentes o ninguno: el stock puede cambiar desde la validación
+        let mut tx = pool.begin().await?;
+        for componente in &detalle.componentes {
+            let producto = sqlx::query_as::<_, (Decimal, Option<String>, i32)>(
+                "SELECT pd.precio_venta, pd.estado::TEXT, COALESCE(i.cantidad_disponible, 0)
+                 FROM producto_detalle pd
+                 LEFT JOIN inventario i ON i.id_producto_detalle = pd.id_producto_detalle
+                 WHERE pd.id_producto_detalle = $1
+                 FOR SHARE OF pd"
+            )
+            .bind(componente.id_producto_detalle)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let precio = match producto {
+                Some((precio, estado, stock)) if estado.as_deref() == Some("activo") && stock >= componente.cantidad => {
+                    precio
+                }
+                _ => {
+                    return Err(ArmadoError::Conflicto(format!(
+                        "{}: sin stock suficiente o no disponible",
+                        componente.nombre
+                    )))
+                }
+            };
+
+            sqlx::query(
+                "INSERT INTO carrito_detalle (id_carrito, id_producto_detalle, cantidad, precio_unitario)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (id_carrito, id_producto_detalle)
+                 DO UPDATE SET cantidad = carrito_detalle.cantidad + EXCLUDED.cantidad,
+                               fecha_actualizacion = CURRENT_TIMESTAMP"
+            )
+            .bind(carrito.id_carrito)
+            .bind(componente.id_producto_detalle)
+            .bind(componente.cantidad)
+            .bind(precio)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE carrito SET fecha_actualizacion = CURRENT_TIMESTAMP WHERE id_carrito = $1")
+            .bind(carrito.id_carrito)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        CarritoService::get_carrito(pool, id_usuario)
+            .await
+            .map_err(ArmadoError::BaseDatos)
+    }
+
+    // ==================== HELPERS ====================
+
+    /// Armado del usuario; los de otros usuarios se tratan como inexistentes
+    async fn armado(pool: &PgPool, id_usuario: i32, id_armado: i32) -> Result<ArmadoPc, ArmadoError> {
+        sqlx::query_as::<_, ArmadoPc>(&format!(
+            "SELECT {} FROM armado_pc WHERE id_armado = $1 AND id_usuario = $2",
+            COLUMNAS_ARMADO
+        ))
+        .bind(id_armado)
+        .bind(id_usuario)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ArmadoError::NoEncontrado("Armado no encontrado".to_string()))
+    }
+
+    async fn componentes_guardados(pool: &PgPool, id_armado: i32) -> Result<Vec<ComponenteRequest>, sqlx::Error> {
+        let filas: Vec<(String, i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT r.codigo, d.id_producto_detalle, d.cantidad
+            FROM armado_pc_detalle d
+            INNER JOIN ranura_armado r ON d.id_ranura = r.id_ranura
+            WHERE d.id_armado = $1
+            "#,
+        )
+        .bind(id_armado)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(filas
+            .into_iter()
+            .map(|(ranura, id_producto_detalle, cantidad)| ComponenteRequest {
+                ranura,
+                id_producto_detalle,
+                cantidad: Some(cantidad),
+            })
+            .collect())
+    }
+
+    async fn tocar(tx: &mut sqlx::PgConnection, id_armado: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE armado_pc SET fecha_actualizacion = CURRENT_TIMESTAMP WHERE id_armado = $1")
+            .bind(id_armado)
+            .execute(tx)
+            .await?;
+        Ok(())
+    }
+
+    /// Validar ranuras, cantidades y categorías y cargar productos con sus especificaciones,
+    /// en el orden de las ranuras
+    async fn cargar_componentes(
+        pool: &PgPool,
+        ranuras: &[RanuraArmado],
+        solicitados: &[ComponenteRequest],
+    ) -> Result<Vec<ComponenteArmado>, ArmadoError> {
+        let mut vistas = HashSet::new();
+        for componente in solicitados {
+            let ranura = ranuras
+                .iter()
+                .find(|r| r.codigo == componente.ranura)
+                .ok_or_else(|| ArmadoError::Invalida(format!("Ranura desconocida: '{}'", componente.ranura)))?;
+            if !vistas.insert(ranura.codigo.as_str()) {
+                return Err(ArmadoError::Invalida(format!(
+                    "La ranura '{}' admite un solo producto",
+                    ranura.codigo
+                )));
+            }
+            let cantidad = componente.cantidad.unwrap_or(1);
+            if cantidad < 1 || cantidad > ranura.cantidad_maxima {
+                return Err(ArmadoError::Invalida(format!(
+                    "La cantidad de {} debe estar entre 1 y {}",
+                    ranura.nombre, ranura.cantidad_maxima
+                )));
+            }
+        }
+
+        let ids: Vec<i32> = solicitados.iter().map(|c| c.id_producto_detalle).collect();
+        let productos = Self::productos(pool, &ids).await?;
+
+        let mut componentes = Vec::with_capacity(solicitados.len());
+        for ranura in ranuras {
+            let Some(solicitado) = solicitados.iter().find(|c| c.ranura == ranura.codigo) else {
+                continue;
+            };
+            let producto = productos.get(&solicitado.id_producto_detalle).ok_or_else(|| {
+                ArmadoError::NoEncontrado(format!("Producto no encontrado: {}", solicitado.id_producto_detalle))
+            })?;
+            if producto.id_categoria != ranura.id_categoria {
+                return Err(ArmadoError::Invalida(format!(
+                    "{} no corresponde a la ranura {} ({})",
+                    producto.nombre, ranura.nombre, ranura.categoria
+                )));
+            }
+            componentes.push(Self::componente(ranura, producto, solicitado.cantidad.unwrap_or(1)));
+        }
+
+        Ok(componentes)
+    }
+
+    async fn productos(pool: &PgPool, ids: &[i32]) -> Result<HashMap<i32, ProductoArmado>, sqlx::Error> {
+        let mut productos: HashMap<i32, ProductoArmado> = sqlx::query_as::<_, ProductoArmado>(&format!(
+            r#"
+            SELECT pd.id_producto_detalle, p.id_categoria, pd.nombre, pd.sku, pd.imagen_principal,
+                   CAST(pd.precio_venta AS FLOAT8) AS precio_venta,
+                   CASE WHEN pd.estado = 'activo' AND p.estado = 'activo' THEN 'activo' ELSE 'inactivo' END AS estado,
+                   COALESCE(i.cantidad_disponible, 0) AS stock_disponible
+            {}
+            WHERE pd.id_producto_detalle = ANY($1)
+            "#,
+            FROM_PRODUCTOS
+        ))
+        .bind(ids)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|p| (p.id_producto_detalle, p))
+        .collect();
+
+        let valores: Vec<(i32, String, String)> = sqlx::query_as(
+            r#"
+            SELECT id_producto_detalle, nombre_atributo, valor_atributo
+            FROM especificacion_producto
+            WHERE id_producto_detalle = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .fetch_all(pool)
+        .await?;
+
+        for (id, atributo, valor) in valores {
+            if let Some(producto) = productos.get_mut(&id) {
+                producto.especificaciones.insert(atributo, valor);
+            }
+        }
+
+        Ok(productos)
+    }
+
+    fn componente(ranura: &RanuraArmado, producto: &ProductoArmado, cantidad: i32) -> ComponenteArmado {
+        ComponenteArmado {
+            ranura: ranura.codigo.clone(),
+            nombre_ranura: ranura.nombre.clone(),
+            id_producto_detalle: producto.id_producto_detalle,
+            nombre: producto.nombre.clone(),
+            sku: producto.sku.clone(),
+            imagen_principal: producto.imagen_principal.clone(),
+            precio_venta: producto.precio_venta,
+            cantidad,
+            stock_disponible: producto.stock_disponible,
+            disponible: producto.estado == "activo" && producto.stock_disponible >= cantidad,
+            especificaciones: producto.especificaciones.clone(),
+        }
+    }
+
+    // ==================== MOTOR DE REGLAS ====================
+
+    fn validacion(
+        ranuras: &[RanuraArmado],
+        reglas: &[ReglaCompatibilidad],
+        componentes: &[ComponenteArmado],
+    ) -> ValidacionArmado {
+        let resultados = Self::evaluar(reglas, componentes);
+        let ranuras_faltantes = ranuras
+            .iter()
+            .filter(|r| r.obligatoria && !componentes.iter().any(|c| c.ranura == r.codigo))
+            .map(|r| r.codigo.clone())
+            .collect::<Vec<_>>();
+        let total = componentes.iter().map(|c| c.precio_venta * c.cantidad as f64).sum::<f64>();
+
+        ValidacionArmado {
+            compatible: !resultados.iter().any(Self::bloquea),
+            completo: ranuras_faltantes.is_empty(),
+            ranuras_faltantes,
+            resultados,
+            total: (total * 100.0).round() / 100.0,
+        }
+    }
+
+    /// Evaluar las reglas que tienen producto en sus ranuras; las demás no aplican todavía
+    fn evaluar(reglas: &[ReglaCompatibilidad], componentes: &[ComponenteArmado]) -> Vec<ResultadoRegla> {
+        reglas
+            .iter()
+            .filter_map(|regla| Self::evaluar_regla(regla, componentes))
+            .collect()
+    }
+
+    fn evaluar_regla(regla: &ReglaCompatibilidad, componentes: &[ComponenteArmado]) -> Option<ResultadoRegla> {
+        let destino = componentes.iter().find(|c| c.ranura == regla.ranura_destino)?;
+        let valor_destino = destino.especificaciones.get(&regla.atributo_destino);
+        let factor = 1.0 + regla.margen_porcentaje / 100.0;
+
+        let resultado = |estado: &str, mensaje: Option<String>| ResultadoRegla {
+            regla: regla.codigo.clone(),
+            descripcion: regla.descripcion.clone(),
+            severidad: regla.severidad.clone(),
+            estado: estado.to_string(),
+            mensaje,
+        };
+
+        if regla.operador == "suma_menor_igual" {
+            let aportes: Vec<f64> = componentes
+                .iter()
+                .filter(|c| regla.ranura_origen.as_deref().is_none_or(|origen| origen == c.ranura))
+                .flat_map(|c| {
+                    regla
+                        .atributos_origen
+                        .iter()
+                        .filter_map(|a| c.especificaciones.get(a).and_then(|v| Self::numero(v)))
+                        .map(move |v| v * c.cantidad as f64)
+                })
+                .collect();
+            if aportes.is_empty() {
+                return None;
+            }
+
+            let requerido = aportes.iter().sum::<f64>() * factor;
+            return Some(match valor_destino.and_then(|v| Self::numero(v)) {
+                None => resultado(
+                    REGLA_SIN_DATOS,
+                    Some(format!("{} no tiene '{}' cargado", destino.nombre, regla.atributo_destino)),
+                ),
+                Some(disponible) if requerido <= disponible => resultado(REGLA_COMPATIBLE, None),
+                Some(disponible) => resultado(
+                    REGLA_INCOMPATIBLE,
+                    Some(format!(
+                        "{} ofrece {} de {} y el armado necesita al menos {:.0} ({}% de margen incluido)",
+                        destino.nombre, disponible, regla.atributo_destino, requerido, regla.margen_porcentaje
+                    )),
+                ),
+            });
+        }
+
+        let origen = componentes
+            .iter()
+            .find(|c| regla.ranura_origen.as_deref() == Some(c.ranura.as_str()))?;
+        let valor_origen = regla
+            .atributos_origen
+            .iter()
+            .find_map(|a| origen.especificaciones.get(a).map(|v| (a, v)));
+
+        let (Some((atributo_origen, valor_origen)), Some(valor_destino)) = (valor_origen, valor_destino) else {
+            return Some(resultado(
+                REGLA_SIN_DATOS,
+                Some(format!(
+                    "No hay datos para verificar {} con {}",
+                    origen.nombre, destino.nombre
+                )),
+            ));
+        };
+
+        let cumple = match regla.operador.as_str() {
+            "igual" => Some(Self::normalizar(valor_origen) == Self::normalizar(valor_destino)),
+            "incluido" => Some(
+                valor_destino
+                    .split(',')
+                    .any(|v| Self::normalizar(v) == Self::normalizar(valor_origen)),
+            ),
+            "menor_igual" => match (Self::numero(valor_origen), Self::numero(valor_destino)) {
+                (Some(o), Some(d)) => Some(o * factor <= d),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        Some(match cumple {
+            Some(true) => resultado(REGLA_COMPATIBLE, None),
+            Some(false) => resultado(
+                REGLA_INCOMPATIBLE,
+                Some(format!(
+                    "{} ({}: {}) no es compatible con {} ({}: {})",
+                    origen.nombre, atributo_origen, valor_origen, destino.nombre, regla.atributo_destino, valor_destino
+                )),
+            ),
+            None => resultado(
+                REGLA_SIN_DATOS,
+                Some(format!(
+                    "No se pudo comparar {} con {}",
+                    origen.nombre, destino.nombre
+                )),
+            ),
+        })
+    }
+
+    /// Incompatibilidad que impide dar el armado por compatible
+    fn bloquea(resultado: &ResultadoRegla) -> bool {
+        resultado.estado == REGLA_INCOMPATIBLE && resultado.severidad == "error"
+    }
+
+    fn normalizar(valor: &str) -> String {
+        valor.trim().to_lowercase()
+    }
+
+    fn numero(valor: &str) -> Option<f64> {
+        valor.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regla(operador: &str, origen: Option<&str>, atributos_origen: &[&str], destino: &str, atributo: &str) -> ReglaCompatibilidad {
+        ReglaCompatibilidad {
+            codigo: "regla".to_string(),
+            descripcion: String::new(),
+            ranura_origen: origen.map(str::to_string),
+            atributos_origen: atributos_origen.iter().map(|a| a.to_string()).collect(),
+            ranura_destino: destino.to_string(),
+            atributo_destino: atributo.to_string(),
+            operador: operador.to_string(),
+            margen_porcentaje: 0.0,
+            severidad: "error".to_string(),
+        }
+    }
+
+    fn componente(ranura: &str, cantidad: i32, especificaciones: &[(&str, &str)]) -> ComponenteArmado {
+        ComponenteArmado {
+            ranura: ranura.to_string(),
+            nombre_ranura: ranura.to_string(),
+            id_producto_detalle: 1,
+            nombre: ranura.to_uppercase(),
+            sku: String::new(),
+            imagen_principal: None,
+            precio_venta: 0.0,
+            cantidad,
+            stock_disponible: cantidad,
+            disponible: true,
+            especificaciones: especificaciones.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    fn estado(regla: &ReglaCompatibilidad, componentes: &[ComponenteArmado]) -> Option<String> {
+        ArmadoService::evaluar_regla(regla, componentes).map(|r| r.estado)
+    }
+
+    #[test]
+    fn igual_compara_sin_mayusculas_ni_espacios() {
+        let socket = regla("igual", Some("cpu"), &["socket"], "placa", "socket");
+        let placa = componente("placa", 1, &[("socket", "AM5")]);
+
+        let cpu = componente("cpu", 1, &[("socket", " am5 ")]);
+        assert_eq!(estado(&socket, &[cpu, placa.clone()]).as_deref(), Some(REGLA_COMPATIBLE));
+
+        let cpu = componente("cpu", 1, &[("socket", "LGA1700")]);
+        assert_eq!(estado(&socket, &[cpu, placa]).as_deref(), Some(REGLA_INCOMPATIBLE));
+    }
+
+    #[test]
+    fn incluido_busca_en_la_lista_del_destino() {
+        let memoria = regla("incluido", Some("ram"), &["tipo"], "placa", "memorias");
+        let placa = componente("placa", 1, &[("memorias", "DDR4, DDR5")]);
+
+        let ram = componente("ram", 2, &[("tipo", "ddr5")]);
+        assert_eq!(estado(&memoria, &[ram, placa.clone()]).as_deref(), Some(REGLA_COMPATIBLE));
+
+        let ram = componente("ram", 2, &[("tipo", "DDR3")]);
+        assert_eq!(estado(&memoria, &[ram, placa]).as_deref(), Some(REGLA_INCOMPATIBLE));
+    }
+
+    #[test]
+    fn menor_igual_aplica_el_margen() {
+        let mut largo = regla("menor_igual", Some("gpu"), &["largo_mm"], "gabinete", "gpu_max_mm");
+        largo.margen_porcentaje = 10.0;
+        let gpu = componente("gpu", 1, &[("largo_mm", "300")]);
+
+        let gabinete = componente("gabinete", 1, &[("gpu_max_mm", "330")]);
+        assert_eq!(estado(&largo, &[gpu.clone(), gabinete]).as_deref(), Some(REGLA_COMPATIBLE));
+
+        let gabinete = componente("gabinete", 1, &[("gpu_max_mm", "320")]);
+        assert_eq!(estado(&largo, &[gpu.clone(), gabinete]).as_deref(), Some(REGLA_INCOMPATIBLE));
+
+        let gabinete = componente("gabinete", 1, &[("gpu_max_mm", "grande")]);
+        assert_eq!(estado(&largo, &[gpu, gabinete]).as_deref(), Some(REGLA_SIN_DATOS));
+    }
+
+    #[test]
+    fn suma_menor_igual_multiplica_por_cantidad() {
+        let potencia = regla("suma_menor_igual", None, &["consumo_w"], "fuente", "potencia_w");
+        let cpu = componente("cpu", 1, &[("consumo_w", "120")]);
+        let gpu = componente("gpu", 2, &[("consumo_w", "200")]);
+
+        let fuente = componente("fuente", 1, &[("potencia_w", "520")]);
+        let componentes = [cpu.clone(), gpu.clone(), fuente];
+        assert_eq!(estado(&potencia, &componentes).as_deref(), Some(REGLA_COMPATIBLE));
+
+        let fuente = componente("fuente", 1, &[("potencia_w", "500")]);
+        let componentes = [cpu.clone(), gpu.clone(), fuente];
+        assert_eq!(estado(&potencia, &componentes).as_deref(), Some(REGLA_INCOMPATIBLE));
+
+        let fuente = componente("fuente", 1, &[]);
+        assert_eq!(estado(&potencia, &[cpu, gpu, fuente]).as_deref(), Some(REGLA_SIN_DATOS));
+    }
+
+    #[test]
+    fn regla_sin_sus_ranuras_no_aplica() {
+        let socket = regla("igual", Some("cpu"), &["socket"], "placa", "socket");
+        let cpu = componente("cpu", 1, &[("socket", "AM5")]);
+        let placa = componente("placa", 1, &[("socket", "AM5")]);
+        assert_eq!(estado(&socket, std::slice::from_ref(&cpu)), None);
+        assert_eq!(estado(&socket, std::slice::from_ref(&placa)), None);
+
+        let cpu_sin_dato = componente("cpu", 1, &[]);
+        assert_eq!(estado(&socket, &[cpu_sin_dato, placa]).as_deref(), Some(REGLA_SIN_DATOS));
+
+        let potencia = regla("suma_menor_igual", None, &["consumo_w"], "fuente", "potencia_w");
+        let fuente = componente("fuente", 1, &[("potencia_w", "500")]);
+        assert_eq!(estado(&potencia, &[fuente]), None);
+    }
+}
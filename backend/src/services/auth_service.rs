@@ -1,11 +1,45 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
-use std::env;
-
-use crate::models::{LoginRequest, LoginResponse, RegisterRequest, RegisterResponse, Usuario, UsuarioResponse};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::models::{
+    LoginRequest, LoginResponse, LoginResultado, RecuperarPasswordRequest, RegisterRequest,
+    RegisterResponse, RestablecerPasswordRequest, Usuario, UsuarioResponse, ImpersonarUsuarioRequest,
+    ImpersonacionResponse,
+};
 use crate::repositories::AuthRepository;
+use crate::services::{ConfigService, DosFactoresService, EmailService, LogService};
+use crate::utils::jwt;
+use crate::utils::password::{self, ParametrosHash};
+use crate::utils::validacion::{validar_email, ErroresCampo, PoliticaPassword};
+
+/// Acción registrada en log_auditoria por cada solicitud de recuperación.
+/// También se usa para limitar las solicitudes por IP.
+pub const ACCION_SOLICITUD_RECUPERACION: &str = "Solicitud de recuperación de contraseña";
+
+/// Máximo de solicitudes de recuperación por IP en una hora
+const MAX_SOLICITUDES_RECUPERACION_IP: i64 = 10;
+
+/// Vigencia por defecto y máxima (si no está configurada) de un token de suplantación
+const IMPERSONACION_MINUTOS: i64 = 15;
+const IMPERSONACION_MAX_MINUTOS: i64 = 30;
+
+/// Acciones de inicio de sesión registradas en log_auditoria.
+/// Las revisa la detección de anomalías.
+pub const ACCION_LOGIN_EXITOSO: &str = "Login exitoso";
+pub const ACCION_LOGIN_FALLIDO: &str = "Intento de login fallido";
+
+pub const ACCION_NO_PERMITIDA_SUPLANTACION: &str = "Esta acción no está permitida durante una suplantación de cuenta";
+
+// Cache en memoria de usuario -> timestamp (UTC, segundos) desde el cual sus tokens son válidos.
+// Se carga al iniciar y se actualiza al revocar sesiones, para que verify_token no consulte la BD.
+static SESIONES_REVOCADAS: LazyLock<RwLock<HashMap<i32, i64>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
 
 // ==================== JWT CLAIMS ====================
 
@@ -16,6 +50,60 @@ pub struct Claims {
     pub rol: String,
     pub exp: usize,       // expiration time
     pub iat: usize,       // issued at
+    /// Presente solo en tokens de suplantación emitidos por un super_admin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonacion: Option<Impersonacion>,
+}
+
+/// Datos del administrador que usa un token de suplantación ("ver como cliente").
+/// En estos tokens `sub`, `email` y `rol` son los del cliente suplantado.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Impersonacion {
+    pub id_admin: i32,
+    pub email_admin: String,
+    pub solo_lectura: bool,
+}
+
+/// Claims del token de desafío 2FA. No incluye email ni rol, por lo que
+/// no puede decodificarse como `Claims` ni usarse como token de sesión.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DesafioClaims {
+    pub sub: i32,
+    pub proposito: String,
+    pub remember_me: bool,
+    pub exp: usize,
+    pub iat: usize,
+}
+
+// ==================== ERRORES ====================
+
+/// Error de las operaciones de autenticación que validan datos de entrada
+#[derive(Debug)]
+pub enum AuthError {
+    /// Datos inválidos, con mensajes por campo
+    Validacion(ErroresCampo),
+    Mensaje(String),
+}
+
+impl From<String> for AuthError {
+    fn from(mensaje: String) -> Self {
+        AuthError::Mensaje(mensaje)
+    }
+}
+
+impl From<ErroresCampo> for AuthError {
+    fn from(errores: ErroresCampo) -> Self {
+        AuthError::Validacion(errores)
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Validacion(errores) => write!(f, "{}", errores),
+            AuthError::Mensaje(mensaje) => write!(f, "{}", mensaje),
+        }
+    }
 }
 
 // ==================== SERVICE ====================
@@ -27,16 +115,18 @@ impl AuthService {
     pub async fn register(
         pool: &PgPool,
         request: RegisterRequest,
-    ) -> Result<RegisterResponse, String> {
-        // Validar email
-        if !Self::is_valid_email(&request.email) {
-            return Err("Email inválido".to_string());
-        }
-
-        // Validar contraseña
-        if request.password.len() < 6 {
-            return Err("La contraseña debe tener al menos 6 caracteres".to_string());
+    ) -> Result<RegisterResponse, AuthError> {
+        // Validar email y contraseña según la política configurada
+        let mut errores = ErroresCampo::new();
+        if let Err(e) = validar_email(&request.email) {
+            errores.agregar("email", e);
         }
+        let politica = PoliticaPassword::cargar();
+        errores.agregar_todos(
+            "password",
+            politica.validar(&request.password, &[&request.nombre, &request.apellido, &request.email]),
+        );
+        errores.into_result()?;
 
         // Verificar si el email ya existe
         let email_exists = AuthRepository::email_exists(pool, &request.email)
@@ -44,12 +134,11 @@ impl AuthService {
             .map_err(|e| format!("Error al verificar email: {}", e))?;
 
         if email_exists {
-            return Err("El email ya está registrado".to_string());
+            return Err("El email ya está registrado".to_string().into());
         }
 
         // Hash de la contraseña
-        let password_hash = hash(&request.password, DEFAULT_COST)
-            .map_err(|e| format!("Error al encriptar contraseña: {}", e))?;
+        let password_hash = password::hash_password(&request.password, &ParametrosHash::cargar())?;
 
         // Crear usuario
         let usuario = AuthRepository::create_user(
@@ -70,38 +159,77 @@ impl AuthService {
         })
     }
 
-    // Login de usuario
+    // Login de usuario. Para administradores con 2FA devuelve un desafío en lugar de la sesión.
     pub async fn login(
         pool: &PgPool,
         request: LoginRequest,
-    ) -> Result<LoginResponse, String> {
+        ip_cliente: Option<String>,
+    ) -> Result<LoginResultado, String> {
         // Buscar usuario por email
-        let usuario = AuthRepository::find_by_email(pool, &request.email)
+        let Some(usuario) = AuthRepository::find_by_email(pool, &request.email)
             .await
             .map_err(|e| format!("Error al buscar usuario: {}", e))?
-            .ok_or("Credenciales inválidas".to_string())?;
+        else {
+            Self::registrar_login_fallido(pool, None, &request.email, "Usuario no encontrado", ip_cliente).await;
+            return Err("Credenciales inválidas".to_string());
+        };
 
         // Verificar que el usuario esté activo
         if !usuario.activo {
+            Self::registrar_login_fallido(pool, Some(&usuario), &request.email, "Cuenta desactivada", ip_cliente).await;
             return Err("La cuenta está desactivada".to_string());
         }
 
         // Verificar contraseña
-        let password_match = verify(&request.password, &usuario.contrasena)
-            .map_err(|e| format!("Error al verificar contraseña: {}", e))?;
+        let password_match = password::verificar_password(&request.password, &usuario.contrasena)?;
 
         if !password_match {
+            Self::registrar_login_fallido(pool, Some(&usuario), &request.email, "Contraseña incorrecta", ip_cliente).await;
             return Err("Credenciales inválidas".to_string());
         }
 
+        // Migrar hashes bcrypt (o con parámetros desactualizados) a Argon2id
+        Self::rehash_si_necesario(pool, &usuario, &request.password).await;
+
+        let remember_me = request.remember_me.unwrap_or(false);
+
+        // Segundo factor para administradores
+        if let Some(desafio) = DosFactoresService::desafio_login(pool, &usuario, remember_me).await? {
+            return Ok(LoginResultado::Desafio(desafio));
+        }
+
+        Ok(LoginResultado::Sesion(Self::emitir_sesion(pool, usuario, remember_me, ip_cliente).await?))
+    }
+
+    // Emitir la sesión de un usuario ya autenticado
+    pub async fn emitir_sesion(
+        pool: &PgPool,
+        usuario: Usuario,
+        remember_me: bool,
+        ip_cliente: Option<String>,
+    ) -> Result<LoginResponse, String> {
         // Actualizar última conexión
         let _ = AuthRepository::update_last_login(pool, usuario.id_usuario).await;
 
         // Obtener session_timeout de la configuración del sistema
-        let session_timeout = Self::get_session_timeout(pool).await;
+        let session_timeout = Self::get_session_timeout();
 
         // Generar token JWT con el timeout configurado
-        let token = Self::generate_token(&usuario, request.remember_me.unwrap_or(false), session_timeout)?;
+        let token = Self::generate_token(&usuario, remember_me, session_timeout)?;
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Success,
+                ACCION_LOGIN_EXITOSO,
+                "Autenticación",
+                Some(format!("Usuario {} ({}) inició sesión", usuario.email, usuario.rol)),
+                Some(usuario.email.clone()),
+                ip_cliente,
+            ),
+        )
+        .await;
 
         Ok(LoginResponse {
             token,
@@ -109,24 +237,60 @@ impl AuthService {
         })
     }
 
-    // Obtener session_timeout desde la BD
-    async fn get_session_timeout(pool: &PgPool) -> i64 {
-        match sqlx::query_scalar::<_, String>(
-            "SELECT valor FROM configuracion_sistema WHERE clave = 'session_timeout'"
+    async fn registrar_login_fallido(
+        pool: &PgPool,
+        usuario: Option<&Usuario>,
+        email: &str,
+        motivo: &str,
+        ip_cliente: Option<String>,
+    ) {
+        let email = email.trim().to_lowercase();
+        LogService::registrar(
+            pool,
+            usuario.map(|u| u.id_usuario),
+            LogService::nuevo(
+                NivelLog::Warning,
+                ACCION_LOGIN_FALLIDO,
+                "Seguridad",
+                Some(format!("Intento fallido de login para {}: {}", email, motivo)),
+                Some(email),
+                ip_cliente,
+            ),
         )
-        .fetch_optional(pool)
-        .await
-        {
-            Ok(Some(valor)) => valor.parse().unwrap_or(24),
-            _ => 24, // Default 24 horas
+        .await;
+    }
+
+    // Regenerar el hash con Argon2id y los parámetros actuales. Un fallo no impide el login.
+    async fn rehash_si_necesario(pool: &PgPool, usuario: &Usuario, password_plano: &str) {
+        let parametros = ParametrosHash::cargar();
+        if !password::necesita_rehash(&usuario.contrasena, &parametros) {
+            return;
+        }
+
+        let resultado = match password::hash_password(password_plano, &parametros) {
+            Ok(hash_nuevo) => AuthRepository::actualizar_hash_password(
+                pool,
+                usuario.id_usuario,
+                &usuario.contrasena,
+                &hash_nuevo,
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = resultado {
+            eprintln!("⚠️  Error al migrar hash de contraseña del usuario {}: {}", usuario.id_usuario, e);
         }
     }
 
+    // Config session_timeout (horas)
+    pub fn get_session_timeout() -> i64 {
+        ConfigService::entero("session_timeout").unwrap_or(24) // Default 24 horas
+    }
+
     // Generar token JWT
     fn generate_token(usuario: &Usuario, remember_me: bool, session_timeout_hours: i64) -> Result<String, String> {
-        let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "default_secret_change_in_production".to_string());
-
         // Duración del token: session_timeout horas (o 30 días si remember_me)
         let expiration = if remember_me {
             chrono::Utc::now()
@@ -146,29 +310,157 @@ impl AuthService {
             rol: usuario.rol.clone(),
             exp: expiration,
             iat: chrono::Utc::now().timestamp() as usize,
+            impersonacion: None,
         };
 
-        encode(
-            &Header::new(Algorithm::HS256),
-            &claims,
-            &EncodingKey::from_secret(jwt_secret.as_bytes()),
-        )
-        .map_err(|e| format!("Error al generar token: {}", e))
+        jwt::firmar(&claims)
     }
 
     // Verificar token JWT
     pub fn verify_token(token: &str) -> Result<Claims, String> {
-        let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "default_secret_change_in_production".to_string());
+        let claims = jwt::verificar::<Claims>(token)?;
 
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(jwt_secret.as_bytes()),
-            &Validation::new(Algorithm::HS256),
+        if Self::sesion_revocada(claims.sub, claims.iat as i64) {
+            return Err("La sesión fue revocada. Inicia sesión nuevamente".to_string());
+        }
+
+        // Revocar las sesiones del administrador también invalida sus suplantaciones
+        if let Some(impersonacion) = &claims.impersonacion {
+            if Self::sesion_revocada(impersonacion.id_admin, claims.iat as i64) {
+                return Err("La sesión fue revocada. Inicia sesión nuevamente".to_string());
+            }
+        }
+
+        Ok(claims)
+    }
+
+    // Verificar un token de sesión rechazando los de suplantación.
+    // Para acciones que solo puede hacer el propio titular de la cuenta.
+    pub fn verify_token_titular(token: &str) -> Result<Claims, String> {
+        let claims = Self::verify_token(token)?;
+
+        if claims.impersonacion.is_some() {
+            return Err(ACCION_NO_PERMITIDA_SUPLANTACION.to_string());
+        }
+
+        Ok(claims)
+    }
+
+    // ==================== SUPLANTACIÓN ====================
+
+    // Emitir un token de corta duración para ver la cuenta de un cliente como él la ve.
+    // Es de solo lectura salvo que se pida lo contrario, y nunca permite pagar ni cambiar credenciales.
+    pub async fn emitir_token_impersonacion(
+        pool: &PgPool,
+        id_admin: i32,
+        id_usuario: i32,
+        request: ImpersonarUsuarioRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<ImpersonacionResponse, String> {
+        let motivo = request.motivo.trim().to_string();
+        if motivo.is_empty() {
+            return Err("Indica el motivo de la suplantación".to_string());
+        }
+
+        let admin = AuthRepository::find_by_id(pool, id_admin)
+            .await
+            .map_err(|e| format!("Error al buscar administrador: {}", e))?
+            .ok_or("Administrador no encontrado".to_string())?;
+
+        let usuario = AuthRepository::find_by_id(pool, id_usuario)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            .ok_or("Usuario no encontrado".to_string())?;
+
+        if usuario.rol != "cliente" {
+            return Err("Solo se pueden suplantar cuentas de clientes".to_string());
+        }
+
+        if !usuario.activo {
+            return Err("La cuenta del cliente está desactivada".to_string());
+        }
+
+        let maximo = Self::get_config_number("impersonation_max_minutes", IMPERSONACION_MAX_MINUTOS).max(1);
+        let minutos = request.minutos.unwrap_or(IMPERSONACION_MINUTOS).clamp(1, maximo);
+        let solo_lectura = !request.permitir_escritura.unwrap_or(false);
+
+        let ahora = chrono::Utc::now();
+        let expira = ahora + chrono::Duration::minutes(minutos);
+        let claims = Claims {
+            sub: usuario.id_usuario,
+            email: usuario.email.clone(),
+            rol: usuario.rol.clone(),
+            exp: expira.timestamp() as usize,
+            iat: ahora.timestamp() as usize,
+            impersonacion: Some(Impersonacion {
+                id_admin: admin.id_usuario,
+                email_admin: admin.email.clone(),
+                solo_lectura,
+            }),
+        };
+        let token = jwt::firmar(&claims)?;
+
+        LogService::registrar(
+            pool,
+            Some(admin.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Suplantación iniciada",
+                "Suplantación",
+                Some(format!(
+                    "{} (#{}) suplanta a {} (#{}) durante {} min, {}. Motivo: {}",
+                    admin.email,
+                    admin.id_usuario,
+                    usuario.email,
+                    usuario.id_usuario,
+                    minutos,
+                    if solo_lectura { "solo lectura" } else { "con escritura" },
+                    motivo
+                )),
+                Some(admin.email),
+                ip_cliente,
+            ),
         )
-        .map_err(|e| format!("Token inválido: {}", e))?;
+        .await;
+
+        Ok(ImpersonacionResponse {
+            token,
+            expira_en: expira.to_rfc3339(),
+            expira_en_segundos: minutos * 60,
+            solo_lectura,
+            usuario: UsuarioResponse::from(usuario),
+        })
+    }
+
+    // Generar un token de desafío 2FA de corta duración
+    pub fn generar_token_desafio(
+        id_usuario: i32,
+        proposito: &str,
+        remember_me: bool,
+        minutos: i64,
+    ) -> Result<String, String> {
+        let ahora = chrono::Utc::now();
+        let claims = DesafioClaims {
+            sub: id_usuario,
+            proposito: proposito.to_string(),
+            remember_me,
+            exp: (ahora + chrono::Duration::minutes(minutos)).timestamp() as usize,
+            iat: ahora.timestamp() as usize,
+        };
 
-        Ok(token_data.claims)
+        jwt::firmar(&claims)
+    }
+
+    // Verificar un token de desafío 2FA con el propósito esperado
+    pub fn verificar_token_desafio(token: &str, proposito: &str) -> Result<DesafioClaims, String> {
+        let claims = jwt::verificar::<DesafioClaims>(token)
+            .map_err(|_| "El desafío de verificación es inválido o expiró. Inicia sesión nuevamente".to_string())?;
+
+        if claims.proposito != proposito {
+            return Err("El desafío de verificación es inválido o expiró. Inicia sesión nuevamente".to_string());
+        }
+
+        Ok(claims)
     }
 
     // Obtener usuario actual por token
@@ -187,12 +479,20 @@ impl AuthService {
             return Err("La cuenta está desactivada".to_string());
         }
 
-        Ok(UsuarioResponse::from(usuario))
-    }
+        // Confirmar contra la BD por si la revocación se hizo en otra instancia
+        let revocadas_desde = AuthRepository::get_sesiones_revocadas_desde(pool, claims.sub)
+            .await
+            .map_err(|e| format!("Error al verificar sesión: {}", e))?;
+
+        if let Some(desde) = revocadas_desde {
+            let desde = desde.and_utc().timestamp();
+            Self::marcar_sesiones_revocadas(claims.sub, desde);
+            if (claims.iat as i64) < desde {
+                return Err("La sesión fue revocada. Inicia sesión nuevamente".to_string());
+            }
+        }
 
-    // Validar email
-    fn is_valid_email(email: &str) -> bool {
-        email.contains('@') && email.contains('.') && email.len() > 5
+        Ok(UsuarioResponse::from(usuario))
     }
 
     // Actualizar perfil de usuario
@@ -201,7 +501,7 @@ impl AuthService {
         token: &str,
         request: crate::handlers::auth_handler::ActualizarPerfilRequest,
     ) -> Result<UsuarioResponse, String> {
-        let claims = Self::verify_token(token)?;
+        let claims = Self::verify_token_titular(token)?;
 
         // Actualizar solo los campos proporcionados
         let mut query = String::from("UPDATE usuario SET ");
@@ -292,13 +592,8 @@ impl AuthService {
         pool: &PgPool,
         token: &str,
         request: crate::handlers::auth_handler::CambiarPasswordRequest,
-    ) -> Result<(), String> {
-        let claims = Self::verify_token(token)?;
-
-        // Validar nueva contraseña
-        if request.password_nuevo.len() < 6 {
-            return Err("La nueva contraseña debe tener al menos 6 caracteres".to_string());
-        }
+    ) -> Result<(), AuthError> {
+        let claims = Self::verify_token_titular(token)?;
 
         // Obtener usuario actual
         let usuario = AuthRepository::find_by_id(pool, claims.sub)
@@ -306,17 +601,24 @@ impl AuthService {
             .map_err(|e| format!("Error al buscar usuario: {}", e))?
             .ok_or("Usuario no encontrado".to_string())?;
 
+        // Validar nueva contraseña según la política configurada
+        let mut errores = ErroresCampo::new();
+        let politica = PoliticaPassword::cargar();
+        errores.agregar_todos(
+            "password_nuevo",
+            politica.validar(&request.password_nuevo, &[&usuario.nombre, &usuario.apellido, &usuario.email]),
+        );
+        errores.into_result()?;
+
         // Verificar contraseña actual
-        let password_match = verify(&request.password_actual, &usuario.contrasena)
-            .map_err(|e| format!("Error al verificar contraseña: {}", e))?;
+        let password_match = password::verificar_password(&request.password_actual, &usuario.contrasena)?;
 
         if !password_match {
-            return Err("La contraseña actual es incorrecta".to_string());
+            return Err("La contraseña actual es incorrecta".to_string().into());
         }
 
         // Hash de la nueva contraseña
-        let password_hash = hash(&request.password_nuevo, DEFAULT_COST)
-            .map_err(|e| format!("Error al encriptar contraseña: {}", e))?;
+        let password_hash = password::hash_password(&request.password_nuevo, &ParametrosHash::cargar())?;
 
         // Actualizar contraseña
         sqlx::query("UPDATE usuario SET contrasena = $1 WHERE id_usuario = $2")
@@ -328,4 +630,207 @@ impl AuthService {
 
         Ok(())
     }
+
+    // ==================== RECUPERACIÓN DE CONTRASEÑA ====================
+
+    // Verificar si una IP superó el límite de solicitudes de recuperación
+    pub async fn recuperacion_ip_bloqueada(pool: &PgPool, ip: &str) -> bool {
+        AuthRepository::contar_solicitudes_recuperacion_ip(pool, ACCION_SOLICITUD_RECUPERACION, ip)
+            .await
+            .map(|total| total >= MAX_SOLICITUDES_RECUPERACION_IP)
+            .unwrap_or(false)
+    }
+
+    // Solicitar un enlace de recuperación. No revela si el email existe:
+    // para cuentas inexistentes, inactivas o sobre el límite simplemente no se envía nada.
+    pub async fn solicitar_recuperacion(
+        pool: &PgPool,
+        request: RecuperarPasswordRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let email = request.email.trim().to_string();
+
+        LogService::registrar(
+            pool,
+            None,
+            LogService::nuevo(
+                NivelLog::Info,
+                ACCION_SOLICITUD_RECUPERACION,
+                "Autenticación",
+                Some(format!("Solicitud de recuperación de contraseña para {}", email)),
+                Some(email.clone()),
+                ip_cliente.clone(),
+            ),
+        )
+        .await;
+
+        let usuario = match AuthRepository::find_by_email(pool, &email)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+        {
+            Some(usuario) if usuario.activo => usuario,
+            _ => return Ok(()),
+        };
+
+        let max_solicitudes = Self::get_config_number("password_reset_max_requests", 3);
+        let recientes = AuthRepository::contar_tokens_recientes(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al verificar solicitudes: {}", e))?;
+
+        if recientes >= max_solicitudes {
+            return Ok(());
+        }
+
+        let ttl_minutos = Self::get_config_number("password_reset_ttl_minutes", 30);
+        let (token, token_hash) = Self::generar_token_recuperacion();
+
+        AuthRepository::crear_token_recuperacion(
+            pool,
+            usuario.id_usuario,
+            &token_hash,
+            ttl_minutos as i32,
+            ip_cliente.as_deref(),
+        )
+        .await
+        .map_err(|e| format!("Error al crear token de recuperación: {}", e))?;
+
+        let site_url = ConfigService::texto("site_url")
+            .unwrap_or_else(|| "http://localhost:5173".to_string());
+
+        let enlace = format!(
+            "{}/restablecer-password?token={}",
+            site_url.trim_end_matches('/'),
+            token
+        );
+        let cuerpo = format!(
+            "Hola {},\n\n\
+             Recibimos una solicitud para restablecer la contraseña de tu cuenta.\n\
+             Usa el siguiente enlace (válido por {} minutos y de un solo uso):\n\n{}\n\n\
+             Si no solicitaste este cambio, ignora este mensaje: tu contraseña no se modificará.\n",
+            usuario.nombre, ttl_minutos, enlace
+        );
+
+        // El envío se hace en segundo plano para no alterar el tiempo de respuesta
+        tokio::spawn(async move {
+            if let Err(e) =
+                EmailService::enviar(&usuario.email, "Restablece tu contraseña", &cuerpo).await
+            {
+                eprintln!("⚠️  Error al enviar email de recuperación: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    // Restablecer la contraseña con un token de recuperación y revocar las sesiones existentes
+    pub async fn restablecer_password(
+        pool: &PgPool,
+        request: RestablecerPasswordRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<(), AuthError> {
+        const TOKEN_INVALIDO: &str = "El enlace de recuperación es inválido o ha expirado";
+        let token_hash = Self::hash_token(request.token.trim());
+
+        let token = AuthRepository::find_token_recuperacion_vigente(pool, &token_hash)
+            .await
+            .map_err(|e| format!("Error al verificar token: {}", e))?
+            .ok_or(TOKEN_INVALIDO.to_string())?;
+
+        let usuario = AuthRepository::find_by_id(pool, token.id_usuario)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            .ok_or(TOKEN_INVALIDO.to_string())?;
+
+        // Validar nueva contraseña según la política configurada
+        let mut errores = ErroresCampo::new();
+        let politica = PoliticaPassword::cargar();
+        errores.agregar_todos(
+            "password_nuevo",
+            politica.validar(&request.password_nuevo, &[&usuario.nombre, &usuario.apellido, &usuario.email]),
+        );
+        errores.into_result()?;
+
+        let password_hash = password::hash_password(&request.password_nuevo, &ParametrosHash::cargar())?;
+
+        // El token se vuelve a verificar (con bloqueo) al consumirlo
+        let ahora = chrono::Utc::now();
+        let token = AuthRepository::restablecer_password(
+            pool,
+            &token_hash,
+            &password_hash,
+            ahora.naive_utc(),
+        )
+        .await
+        .map_err(|e| format!("Error al restablecer contraseña: {}", e))?
+        .ok_or(TOKEN_INVALIDO.to_string())?;
+
+        Self::marcar_sesiones_revocadas(token.id_usuario, ahora.timestamp());
+
+        let email = Some(usuario.email);
+
+        LogService::registrar(
+            pool,
+            Some(token.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Contraseña restablecida",
+                "Autenticación",
+                Some("Contraseña restablecida mediante enlace de recuperación; sesiones anteriores revocadas".to_string()),
+                email,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    // Generar un token aleatorio de 256 bits y su hash para persistir
+    fn generar_token_recuperacion() -> (String, String) {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let token_hash = Self::hash_token(&token);
+        (token, token_hash)
+    }
+
+    fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    // Leer un valor numérico de configuracion_sistema
+    fn get_config_number(clave: &str, default: i64) -> i64 {
+        ConfigService::entero(clave).unwrap_or(default)
+    }
+
+    // ==================== REVOCACIÓN DE SESIONES ====================
+
+    // Cargar las revocaciones persistidas (llamar al iniciar el servidor)
+    pub async fn cargar_sesiones_revocadas(pool: &PgPool) -> Result<usize, String> {
+        let revocadas = AuthRepository::get_sesiones_revocadas(pool)
+            .await
+            .map_err(|e| format!("Error al cargar sesiones revocadas: {}", e))?;
+
+        let total = revocadas.len();
+        for (id_usuario, desde) in revocadas {
+            Self::marcar_sesiones_revocadas(id_usuario, desde.and_utc().timestamp());
+        }
+
+        Ok(total)
+    }
+
+    pub fn marcar_sesiones_revocadas(id_usuario: i32, desde: i64) {
+        if let Ok(mut revocadas) = SESIONES_REVOCADAS.write() {
+            let actual = revocadas.entry(id_usuario).or_insert(desde);
+            *actual = (*actual).max(desde);
+        }
+    }
+
+    fn sesion_revocada(id_usuario: i32, iat: i64) -> bool {
+        SESIONES_REVOCADAS
+            .read()
+            .ok()
+            .and_then(|revocadas| revocadas.get(&id_usuario).copied())
+            .is_some_and(|desde| iat < desde)
+    }
 }
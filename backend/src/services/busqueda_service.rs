@@ -0,0 +1,295 @@
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::models::busqueda::{
+    SugerenciaCategoria, SugerenciaMarca, SugerenciaProducto, SugerenciaTermino, Sugerencias, TerminoBusqueda,
+    TerminosBusquedaQuery,
+};
+use crate::repositories::catalogo_repository::escapar_like;
+
+/// Largo mínimo del texto para sugerir
+const MINIMO_CARACTERES: usize = 2;
+
+/// Largo máximo de un término guardado (columna `termino`)
+const MAXIMO_CARACTERES: usize = 100;
+
+/// Tiempo máximo para armar las sugerencias; si se pasa se responde vacío
+const PRESUPUESTO: Duration = Duration::from_millis(300);
+
+/// Vigencia de las sugerencias cacheadas por texto
+const VIGENCIA_CACHE: Duration = Duration::from_secs(60);
+
+/// Entradas a partir de las cuales se vacía la caché
+const MAXIMO_CACHE: usize = 5_000;
+
+/// Clientes distintos que deben buscar un término para sugerirlo a otros
+const MINIMO_BUSCADORES_POPULAR: i32 = 10;
+
+// Sal de las huellas de buscador_termino; al no guardarse, la huella no lleva a la IP
+static SAL_HUELLA: LazyLock<[u8; 32]> = LazyLock::new(rand::random);
+
+static CACHE: LazyLock<Mutex<HashMap<String, (Instant, Sugerencias)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub struct BusquedaService;
+
+impl BusquedaService {
+    /// Productos, marcas, categorías y búsquedas populares que empiezan con el texto
+    pub async fn sugerencias(pool: &PgPool, texto: &str) -> Result<Sugerencias, sqlx::Error> {
+        let texto = Self::normalizar(texto);
+        if texto.chars().count() < MINIMO_CARACTERES {
+            return Ok(Sugerencias::default());
+        }
+
+        if let Some(sugerencias) = Self::cacheadas(&texto) {
+            return Ok(sugerencias);
+        }
+
+        let patron = escapar_like(&texto);
+        let consultas = async {
+            tokio::try_join!(
+                Self::productos(pool, &patron),
+                Self::marcas(pool, &patron),
+                Self::categorias(pool, &patron),
+                Self::terminos_populares(pool, &patron),
+            )
+        };
+
+        // Fuera de presupuesto no se cachea: la próxima tecla lo vuelve a intentar
+        let Ok(resultado) = tokio::time::timeout(PRESUPUESTO, consultas).await else {
+            return Ok(Sugerencias::default());
+        };
+        let (productos, marcas, categorias, terminos) = resultado?;
+
+        let sugerencias = Sugerencias {
+            productos,
+            marcas,
+            categorias,
+            terminos,
+        };
+        Self::cachear(texto, sugerencias.clone());
+        Ok(sugerencias)
+    }
+
+    /// Registra una búsqueda del catálogo en segundo plano. Solo se guarda el término
+    /// normalizado, sus contadores y una huella anónima del cliente para contar
+    /// buscadores distintos; los que parecen datos personales (correos, documentos o
+    /// teléfonos) no se guardan.
+    pub fn registrar(pool: &PgPool, texto: &str, resultados: i64, ip_cliente: &str) {
+        let termino: String = Self::normalizar(texto).chars().take(MAXIMO_CARACTERES).collect();
+        if termino.chars().count() < MINIMO_CARACTERES || Self::parece_dato_personal(&termino) {
+            return;
+        }
+
+        let pool = pool.clone();
+        let resultados = resultados.min(i32::MAX as i64) as i32;
+        let huella = Self::huella(ip_cliente);
+        tokio::spawn(async move {
+            if let Err(e) = Self::guardar_busqueda(&pool, &termino, resultados, &huella).await {
+                eprintln!("⚠️  No se pudo registrar el término de búsqueda: {}", e);
+            }
+        });
+    }
+
+    async fn guardar_busqueda(pool: &PgPool, termino: &str, resultados: i32, huella: &str) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let termino: String = sqlx::query_scalar(
+            r#"
+            INSERT INTO termino_busqueda (termino, total_busquedas, busquedas_sin_resultados, ultimos_resultados)
+            VALUES (LEFT(unaccent($1), 100), 1, CASE WHEN $2 = 0 THEN 1 ELSE 0 END, $2)
+            ON CONFLICT (termino) DO UPDATE SET
+                total_busquedas = termino_busqueda.total_busquedas + 1,
+                busquedas_sin_resultados = termino_busqueda.busquedas_sin_resultados + EXCLUDED.busquedas_sin_resultados,
+                ultimos_resultados = EXCLUDED.ultimos_resultados,
+                ultima_busqueda = CURRENT_TIMESTAMP
+            RETURNING termino
+            "#,
+        )
+        .bind(termino)
+        .bind(resultados)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Solo la primera búsqueda de cada cliente suma un buscador
+        let nuevo = sqlx::query("INSERT INTO buscador_termino (termino, huella) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(&termino)
+            .bind(huella)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        if nuevo {
+            sqlx::query("UPDATE termino_busqueda SET total_buscadores = total_buscadores + 1 WHERE termino = $1")
+                .bind(&termino)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Excluir un término de las sugerencias (o volver a permitirlo)
+    pub async fn bloquear_termino(pool: &PgPool, termino: &str, bloqueado: bool) -> Result<Option<TerminoBusqueda>, sqlx::Error> {
+        let termino = sqlx::query_as::<_, TerminoBusqueda>(
+            r#"
+            UPDATE termino_busqueda SET bloqueado = $2
+            WHERE termino = LEFT(unaccent($1), 100)
+            RETURNING termino, total_busquedas, total_buscadores, busquedas_sin_resultados, ultimos_resultados,
+                      bloqueado, primera_busqueda, ultima_busqueda
+            "#,
+        )
+        .bind(Self::normalizar(termino))
+        .bind(bloqueado)
+        .fetch_optional(pool)
+        .await?;
+
+        // Las sugerencias cacheadas podrían seguir mostrándolo
+        if let Ok(mut cache) = CACHE.lock() {
+            cache.clear();
+        }
+
+        Ok(termino)
+    }
+
+    /// Términos más buscados, o solo los que no dieron resultados
+    pub async fn terminos(pool: &PgPool, query: &TerminosBusquedaQuery) -> Result<Vec<TerminoBusqueda>, sqlx::Error> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+        sqlx::query_as::<_, TerminoBusqueda>(
+            r#"
+            SELECT termino, total_busquedas, total_buscadores, busquedas_sin_resultados, ultimos_resultados,
+                   bloqueado, primera_busqueda, ultima_busqueda
+            FROM termino_busqueda
+            WHERE NOT $1 OR ultimos_resultados = 0
+            ORDER BY total_busquedas DESC, ultima_busqueda DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(query.sin_resultados.unwrap_or(false))
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Minúsculas y espacios simples; las tildes se quitan en la base con unaccent
+    fn normalizar(texto: &str) -> String {
+        texto.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    }
+
+    fn huella(ip_cliente: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(*SAL_HUELLA);
+        hasher.update(ip_cliente.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    fn parece_dato_personal(termino: &str) -> bool {
+        let mut digitos_seguidos = 0;
+        for c in termino.chars() {
+            digitos_seguidos = if c.is_ascii_digit() { digitos_seguidos + 1 } else { 0 };
+            if digitos_seguidos >= 7 {
+                return true;
+            }
+        }
+        termino.contains('@')
+    }
+
+    fn cacheadas(texto: &str) -> Option<Sugerencias> {
+        let cache = CACHE.lock().ok()?;
+        cache
+            .get(texto)
+            .filter(|(guardadas, _)| guardadas.elapsed() < VIGENCIA_CACHE)
+            .map(|(_, sugerencias)| sugerencias.clone())
+    }
+
+    fn cachear(texto: String, sugerencias: Sugerencias) {
+        if let Ok(mut cache) = CACHE.lock() {
+            if cache.len() >= MAXIMO_CACHE {
+                cache.retain(|_, (guardadas, _)| guardadas.elapsed() < VIGENCIA_CACHE);
+                if cache.len() >= MAXIMO_CACHE {
+                    cache.clear();
+                }
+            }
+            cache.insert(texto, (Instant::now(), sugerencias));
+        }
+    }
+
+    // ==================== CONSULTAS ====================
+
+    // Productos con alguna palabra que empieza con el texto. El LIKE sobre la columna usa
+    // el índice de trigramas de texto_busqueda (desde 3 caracteres); el segundo, sobre una
+    // expresión, no usa índice y solo descarta coincidencias a mitad de palabra.
+    async fn productos(pool: &PgPool, patron: &str) -> Result<Vec<SugerenciaProducto>, sqlx::Error> {
+        sqlx::query_as::<_, SugerenciaProducto>(
+            r#"
+            SELECT pd.id_producto_detalle, pd.nombre, pd.slug, pd.imagen_principal
+            FROM producto_detalle pd
+            INNER JOIN producto p ON pd.id_producto = p.id_producto
+            WHERE pd.estado = 'activo' AND p.estado = 'activo'
+              AND pd.texto_busqueda LIKE '%' || unaccent($1) || '%'
+              AND ' ' || pd.texto_busqueda LIKE '% ' || unaccent($1) || '%'
+            ORDER BY pd.total_vendidos DESC NULLS LAST, pd.nombre
+            LIMIT 5
+            "#,
+        )
+        .bind(patron)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Marcas y categorías son tablas pequeñas: basta con recorrerlas
+    async fn marcas(pool: &PgPool, patron: &str) -> Result<Vec<SugerenciaMarca>, sqlx::Error> {
+        sqlx::query_as::<_, SugerenciaMarca>(
+            r#"
+            SELECT id_marca, nombre, slug
+            FROM marca
+            WHERE estado = 'activo' AND ' ' || lower(unaccent(nombre)) LIKE '% ' || unaccent($1) || '%'
+            ORDER BY nombre
+            LIMIT 3
+            "#,
+        )
+        .bind(patron)
+        .fetch_all(pool)
+        .await
+    }
+
+    async fn categorias(pool: &PgPool, patron: &str) -> Result<Vec<SugerenciaCategoria>, sqlx::Error> {
+        sqlx::query_as::<_, SugerenciaCategoria>(
+            r#"
+            SELECT id_categoria, nombre, slug
+            FROM categoria
+            WHERE estado = 'activo' AND ' ' || lower(unaccent(nombre)) LIKE '% ' || unaccent($1) || '%'
+            ORDER BY orden, nombre
+            LIMIT 3
+            "#,
+        )
+        .bind(patron)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Búsquedas de otros clientes que empiezan igual, que sí encontraron productos y
+    // que hicieron suficientes clientes distintos (uno solo no puede imponer un término)
+    async fn terminos_populares(pool: &PgPool, patron: &str) -> Result<Vec<SugerenciaTermino>, sqlx::Error> {
+        sqlx::query_as::<_, SugerenciaTermino>(
+            r#"
+            SELECT termino, total_busquedas
+            FROM termino_busqueda
+            WHERE termino LIKE unaccent($1) || '%'
+              AND ultimos_resultados > 0
+              AND NOT bloqueado
+              AND total_buscadores >= $2
+            ORDER BY total_busquedas DESC, termino
+            LIMIT 5
+            "#,
+        )
+        .bind(patron)
+        .bind(MINIMO_BUSCADORES_POPULAR)
+        .fetch_all(pool)
+        .await
+    }
+}
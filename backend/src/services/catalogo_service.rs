@@ -1,7 +1,14 @@
 use crate::repositories::{CatalogoRepository, ProductoFilters};
-use crate::models::{familia, categoria, marca, producto_detalle, Subcategoria, Valoracion, valoracion::CrearValoracionRequest};
+use crate::models::{busqueda, familia, categoria, marca, producto_detalle, Subcategoria, Valoracion, valoracion::CrearValoracionRequest};
+use crate::services::{BusquedaService, ConfigService, EspecificacionService};
 use sqlx::PgPool;
 
+/// Atributos de especificación con faceta si `facet_spec_attributes` no está configurada
+const ATRIBUTOS_FACETA: &[&str] = &["socket", "nucleos", "memoria", "tipo", "interfaz"];
+
+/// Límites de los tramos de precio si `facet_price_ranges` no está configurada
+const TRAMOS_PRECIO: &[f64] = &[500.0, 1000.0, 2000.0, 5000.0];
+
 pub struct CatalogoService;
 
 impl CatalogoService {
@@ -30,12 +37,41 @@ impl CatalogoService {
     pub async fn get_productos(
         pool: &PgPool,
         filters: ProductoFilters,
+        ip_cliente: &str,
     ) -> Result<(Vec<producto_detalle::ProductoListItem>, i64), sqlx::Error> {
         let productos = CatalogoRepository::get_productos_list(pool, &filters).await?;
         let total = CatalogoRepository::count_productos(pool, &filters).await?;
+
+        // Solo la primera página cuenta como búsqueda; las siguientes son paginación
+        if let Some(search) = filters.search.as_deref().filter(|_| filters.offset.unwrap_or(0) == 0) {
+            BusquedaService::registrar(pool, search, total, ip_cliente);
+        }
+
         Ok((productos, total))
     }
 
+    /// Facetas del listado con los mismos filtros. Con una categoría elegida, las de
+    /// especificación son los atributos filtrables de su esquema.
+    pub async fn get_facetas(pool: &PgPool, filters: &ProductoFilters) -> Result<busqueda::Facetas, sqlx::Error> {
+        let filtrables = match filters.id_categoria {
+            Some(id_categoria) => EspecificacionService::atributos_filtrables(pool, id_categoria).await?,
+            None => Vec::new(),
+        };
+        let atributos = if !filtrables.is_empty() {
+            filtrables
+        } else {
+            ConfigService::json::<Vec<String>>("facet_spec_attributes")
+                .unwrap_or_else(|| ATRIBUTOS_FACETA.iter().map(|a| a.to_string()).collect())
+        };
+
+        let mut tramos = ConfigService::json::<Vec<f64>>("facet_price_ranges").unwrap_or_else(|| TRAMOS_PRECIO.to_vec());
+        tramos.retain(|limite| limite.is_finite() && *limite > 0.0);
+        tramos.sort_by(f64::total_cmp);
+        tramos.dedup();
+
+        CatalogoRepository::get_facetas(pool, filters, &atributos, &tramos).await
+    }
+
     pub async fn get_producto_by_id(
         pool: &PgPool,
         id: i32,
@@ -0,0 +1,621 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use crate::models::configuracion::{
+    ActualizarConfigRequest, ConfiguracionSistema, HistorialConfigQuery, HistorialConfiguracion, TipoConfig,
+};
+use crate::models::log_auditoria::NivelLog;
+use crate::services::{LogService, SiemService};
+
+/// Canal de LISTEN/NOTIFY; lo dispara el trigger de configuracion_sistema con la clave modificada
+const CANAL_CONFIG: &str = "configuracion_sistema";
+
+const MODULO_LOG: &str = "Configuración";
+
+/// Espera antes de reintentar la escucha si se pierde la conexión
+const REINTENTO_ESCUCHA: Duration = Duration::from_secs(5);
+
+/// Fila de configuración con su valor ya convertido según `tipo`
+#[derive(Debug, Clone)]
+struct EntradaConfig {
+    fila: ConfiguracionSistema,
+    valor: Value,
+}
+
+impl EntradaConfig {
+    fn new(fila: ConfiguracionSistema) -> Self {
+        let valor = fila.get_typed_value();
+        Self { fila, valor }
+    }
+}
+
+/// Origen de un cambio que es un rollback (columnas revierte_* del historial)
+#[derive(Debug, Clone, Copy, Default)]
+struct Reversion {
+    historial: Option<i32>,
+    lote: Option<i32>,
+    /// Deshacer el lote aunque alguna clave haya cambiado después
+    forzar: bool,
+}
+
+/// Claves que solo puede cambiar un super_admin: deciden a dónde salen los logs y cuánto se conservan
+const PREFIJOS_SUPER_ADMIN: &[&str] = &["siem_", "audit_archive_dir", "audit_retention_days"];
+
+// Cache de toda la tabla, por clave
+static CACHE: LazyLock<RwLock<HashMap<String, EntradaConfig>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug)]
+pub enum ConfigError {
+    NoEncontrada(String),
+    Invalida(String),
+    /// El rollback de un lote pisaría cambios posteriores
+    Conflicto(String),
+    /// El rol del usuario no puede cambiar la clave
+    Prohibida(String),
+    BaseDatos(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::NoEncontrada(m)
+            | ConfigError::Invalida(m)
+            | ConfigError::Conflicto(m)
+            | ConfigError::Prohibida(m)
+            | ConfigError::BaseDatos(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+/// Configuración del sistema en memoria. Se carga completa al iniciar y se mantiene
+/// al día con LISTEN/NOTIFY: cada cambio en configuracion_sistema, venga de esta
+/// instancia, de otra o de SQL manual, notifica la clave y se recarga. Las lecturas
+/// no tocan la BD; si una clave no está (o su valor no es del tipo pedido) el
+/// llamador usa su valor por defecto.
+pub struct ConfigService;
+
+impl ConfigService {
+    /// Cargar la configuración y empezar a escuchar cambios (llamar al iniciar el servidor)
+    pub async fn iniciar(pool: PgPool) -> Result<usize, String> {
+        let total = Self::recargar(&pool).await?;
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = Self::escuchar(&pool).await {
+                    eprintln!("⚠️  Escucha de cambios de configuración: {}", e);
+                }
+                tokio::time::sleep(REINTENTO_ESCUCHA).await;
+            }
+        });
+
+        Ok(total)
+    }
+
+    // ==================== LECTURA ====================
+
+    pub fn obtener(clave: &str) -> Option<ConfiguracionSistema> {
+        Self::leer(clave, |entrada| Some(entrada.fila.clone()))
+    }
+
+    /// Todas las filas, ordenadas por categoría y clave
+    pub fn listar() -> Vec<ConfiguracionSistema> {
+        let mut filas: Vec<ConfiguracionSistema> = CACHE
+            .read()
+            .map(|cache| cache.values().map(|entrada| entrada.fila.clone()).collect())
+            .unwrap_or_default();
+        filas.sort_by(|a, b| (&a.categoria, &a.clave).cmp(&(&b.categoria, &b.clave)));
+        filas
+    }
+
+    pub fn texto(clave: &str) -> Option<String> {
+        Self::leer(clave, |entrada| Some(entrada.fila.valor.clone()))
+    }
+
+    pub fn numero(clave: &str) -> Option<f64> {
+        Self::leer(clave, |entrada| entrada.valor.as_f64())
+    }
+
+    /// Valor numérico entero; los decimales se descartan
+    pub fn entero(clave: &str) -> Option<i64> {
+        Self::numero(clave).map(|n| n as i64)
+    }
+
+    pub fn booleano(clave: &str) -> Option<bool> {
+        Self::leer(clave, |entrada| entrada.valor.as_bool())
+    }
+
+    /// Valor de tipo json deserializado
+    pub fn json<T: DeserializeOwned>(clave: &str) -> Option<T> {
+        Self::leer(clave, |entrada| serde_json::from_value(entrada.valor.clone()).ok())
+    }
+
+    /// Valores en texto de las claves que empiezan por `prefijo`
+    pub fn con_prefijo(prefijo: &str) -> HashMap<String, String> {
+        CACHE
+            .read()
+            .map(|cache| {
+                cache
+                    .iter()
+                    .filter(|(clave, _)| clave.starts_with(prefijo))
+                    .map(|(clave, entrada)| (clave.clone(), entrada.fila.valor.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn leer<T>(clave: &str, f: impl FnOnce(&EntradaConfig) -> Option<T>) -> Option<T> {
+        CACHE.read().ok()?.get(clave).and_then(f)
+    }
+
+    // ==================== ESCRITURA ====================
+
+    /// Actualizar una clave validando el valor contra su tipo
+    pub async fn actualizar(
+        pool: &PgPool,
+        id_usuario: i32,
+        rol: &str,
+        clave: &str,
+        valor: &str,
+        motivo: Option<String>,
+    ) -> Result<ConfiguracionSistema, ConfigError> {
+        let cambio = ActualizarConfigRequest { clave: clave.to_string(), valor: valor.to_string(), motivo };
+        let (_, mut filas) = Self::actualizar_lote(pool, id_usuario, rol, &[cambio], None).await?;
+
+        filas.pop().ok_or_else(|| ConfigError::NoEncontrada(format!("Configuración '{}' no encontrada", clave)))
+    }
+
+    /// Actualizar varias claves en una transacción: si alguna no existe o su valor
+    /// no es válido no se aplica ninguna. Devuelve el lote del historial.
+    pub async fn actualizar_lote(
+        pool: &PgPool,
+        id_usuario: i32,
+        rol: &str,
+        cambios: &[ActualizarConfigRequest],
+        motivo: Option<&str>,
+    ) -> Result<(i32, Vec<ConfiguracionSistema>), ConfigError> {
+        Self::aplicar(pool, id_usuario, rol, cambios, motivo, Reversion::default()).await
+    }
+
+    async fn aplicar(
+        pool: &PgPool,
+        id_usuario: i32,
+        rol: &str,
+        cambios: &[ActualizarConfigRequest],
+        motivo: Option<&str>,
+        reversion: Reversion,
+    ) -> Result<(i32, Vec<ConfiguracionSistema>), ConfigError> {
+        if rol != "super_admin" {
+            let reservadas: Vec<&str> = cambios
+                .iter()
+                .map(|c| c.clave.as_str())
+                .filter(|clave| PREFIJOS_SUPER_ADMIN.iter().any(|prefijo| clave.starts_with(prefijo)))
+                .collect();
+            if !reservadas.is_empty() {
+                return Err(ConfigError::Prohibida(format!(
+                    "Solo un super_admin puede cambiar: {}",
+                    reservadas.join(", ")
+                )));
+            }
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| ConfigError::BaseDatos(format!("Error al iniciar transacción: {}", e)))?;
+
+        // Bloquear las filas: el tipo no puede cambiar entre la validación y la escritura
+        let claves: Vec<String> = cambios.iter().map(|c| c.clave.clone()).collect();
+        let actuales: HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+            "SELECT clave, tipo FROM configuracion_sistema WHERE clave = ANY($1) FOR UPDATE"
+        )
+        .bind(&claves)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| ConfigError::BaseDatos(format!("Error al leer configuración: {}", e)))?
+        .into_iter()
+        .collect();
+
+        // Con las filas bloqueadas ya no pueden cambiar entre esta comprobación y el UPDATE
+        if let Some(id_lote) = reversion.lote.filter(|_| !reversion.forzar) {
+            let modificadas: Vec<String> = sqlx::query_scalar(
+                "SELECT DISTINCT p.clave
+                 FROM historial_configuracion p
+                 WHERE p.clave = ANY($2)
+                   AND p.id_historial > (SELECT MAX(h.id_historial) FROM historial_configuracion h
+                                         WHERE h.id_lote = $1 AND h.clave = p.clave)
+                 ORDER BY p.clave"
+            )
+            .bind(id_lote)
+            .bind(&claves)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| ConfigError::BaseDatos(format!("Error al leer el lote: {}", e)))?;
+
+            if !modificadas.is_empty() {
+                return Err(ConfigError::Conflicto(format!(
+                    "Estas claves cambiaron después del lote #{}: {}. Usa forzar para deshacerlo igualmente",
+                    id_lote,
+                    modificadas.join(", ")
+                )));
+            }
+        }
+
+        let mut normalizados = Vec::with_capacity(cambios.len());
+        let mut no_encontradas = Vec::new();
+        let mut invalidas = Vec::new();
+        for cambio in cambios {
+            match actuales.get(&cambio.clave) {
+                None => no_encontradas.push(format!("'{}'", cambio.clave)),
+                Some(tipo) => match TipoConfig::parse(tipo)
+                    .convertir(&cambio.valor)
+                    .and_then(|(valor, _)| Self::validar_formato(&cambio.clave, valor))
+                {
+                    Ok(valor) => {
+                        let motivo = cambio.motivo.as_deref().or(motivo).map(str::trim).unwrap_or_default();
+                        normalizados.push((cambio.clave.as_str(), valor, motivo))
+                    }
+                    Err(e) => invalidas.push(format!("{} ({}): {}", cambio.clave, tipo, e)),
+                },
+            }
+        }
+
+        if !no_encontradas.is_empty() {
+            return Err(ConfigError::NoEncontrada(format!(
+                "Configuración no encontrada: {}",
+                no_encontradas.join(", ")
+            )));
+        }
+        if !invalidas.is_empty() {
+            return Err(ConfigError::Invalida(format!("Valores inválidos: {}", invalidas.join("; "))));
+        }
+
+        // Autor, lote y origen del cambio para el trigger que escribe el historial
+        let id_lote = sqlx::query_scalar::<_, i32>(
+            "SELECT set_config('kronos.config_lote', nextval('historial_configuracion_lote_seq')::TEXT, true)::INTEGER
+             FROM (SELECT set_config('kronos.config_usuario', $1, true),
+                          set_config('kronos.config_revierte_historial', $2, true),
+                          set_config('kronos.config_revierte_lote', $3, true)) variables"
+        )
+        .bind(id_usuario.to_string())
+        .bind(reversion.historial.map(|id| id.to_string()).unwrap_or_default())
+        .bind(reversion.lote.map(|id| id.to_string()).unwrap_or_default())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ConfigError::BaseDatos(format!("Error al preparar el historial: {}", e)))?;
+
+        let mut filas = Vec::with_capacity(normalizados.len());
+        for (clave, valor, motivo) in normalizados {
+            sqlx::query("SELECT set_config('kronos.config_motivo', $1, true)")
+                .bind(motivo)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ConfigError::BaseDatos(format!("Error al preparar el historial: {}", e)))?;
+
+            let fila = sqlx::query_as::<_, ConfiguracionSistema>(
+                "UPDATE configuracion_sistema
+                 SET valor = $1, fecha_actualizacion = CURRENT_TIMESTAMP, actualizado_por = $2
+                 WHERE clave = $3
+                 RETURNING *"
+            )
+            .bind(&valor)
+            .bind(id_usuario)
+            .bind(clave)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| ConfigError::BaseDatos(format!("Error al actualizar '{}': {}", clave, e)))?;
+            filas.push(fila);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| ConfigError::BaseDatos(format!("Error al confirmar transacción: {}", e)))?;
+
+        // El NOTIFY llega también a esta instancia, pero se actualiza ya para
+        // que la siguiente lectura vea el cambio
+        if let Ok(mut cache) = CACHE.write() {
+            for fila in &filas {
+                cache.insert(fila.clave.clone(), EntradaConfig::new(fila.clone()));
+            }
+        }
+
+        Ok((id_lote, filas))
+    }
+
+    // Formato de claves concretas, además de su tipo
+    fn validar_formato(clave: &str, valor: String) -> Result<String, String> {
+        match clave {
+            "maintenance_mode" if !["off", "read_only", "closed"].contains(&valor.as_str()) => {
+                Err("Debe ser off, read_only o closed".to_string())
+            }
+            "maintenance_start" | "maintenance_end"
+                if !valor.trim().is_empty() && chrono::DateTime::parse_from_rfc3339(valor.trim()).is_err() =>
+            {
+                Err("Debe ser una fecha RFC 3339 (2026-01-31T22:00:00-05:00) o vacío".to_string())
+            }
+            "audit_archive_dir" => LogService::validar_directorio_archivo(&valor).map(|_| valor),
+            "audit_retention_days" => LogService::validar_dias_retencion(&valor).map(|_| valor),
+            "siem_destination" => SiemService::validar_destino(&valor).map(|_| valor),
+            "siem_format" if !["syslog", "jsonl"].contains(&valor.as_str()) => {
+                Err("Debe ser syslog o jsonl".to_string())
+            }
+            _ => Ok(valor),
+        }
+    }
+
+    // ==================== HISTORIAL Y ROLLBACK ====================
+
+    /// Versiones registradas, de la más reciente a la más antigua
+    pub async fn historial(
+        pool: &PgPool,
+        filtros: &HistorialConfigQuery,
+    ) -> Result<Vec<HistorialConfiguracion>, ConfigError> {
+        let mut versiones = sqlx::query_as::<_, HistorialConfiguracion>(
+            "SELECT h.id_historial, h.id_lote, h.clave, h.valor_anterior, h.valor_nuevo, h.motivo,
+                    h.id_usuario, u.email as email_usuario, h.revierte_historial, h.revierte_lote, h.fecha_cambio
+             FROM historial_configuracion h
+             LEFT JOIN usuario u ON u.id_usuario = h.id_usuario
+             WHERE ($1::TEXT IS NULL OR h.clave = $1)
+               AND ($2::INTEGER IS NULL OR h.id_lote = $2)
+             ORDER BY h.id_historial DESC
+             LIMIT $3 OFFSET $4"
+        )
+        .bind(&filtros.clave)
+        .bind(filtros.id_lote)
+        .bind(filtros.limit.unwrap_or(50).clamp(1, 500))
+        .bind(filtros.offset.unwrap_or(0).max(0))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ConfigError::BaseDatos(format!("Error al obtener el historial: {}", e)))?;
+
+        for version in &mut versiones {
+            let es_json = Self::obtener(&version.clave).is_some_and(|c| TipoConfig::parse(&c.tipo) == TipoConfig::Json);
+            if es_json {
+                version.diferencias = Self::diferencias_json(version.valor_anterior.as_deref(), version.valor_nuevo.as_deref());
+            }
+        }
+
+        Ok(versiones)
+    }
+
+    /// Devolver una clave al valor que tenía en una versión del historial
+    pub async fn revertir_clave(
+        pool: &PgPool,
+        id_admin: i32,
+        rol: &str,
+        clave: &str,
+        id_historial: i32,
+        motivo: Option<String>,
+        ip_cliente: Option<String>,
+    ) -> Result<ConfiguracionSistema, ConfigError> {
+        let version = Self::version(pool, id_historial)
+            .await?
+            .filter(|v| v.clave == clave)
+            .ok_or_else(|| {
+                ConfigError::NoEncontrada(format!("La versión #{} no existe para '{}'", id_historial, clave))
+            })?;
+
+        let valor = version
+            .valor_nuevo
+            .ok_or_else(|| ConfigError::Invalida(format!("La versión #{} es la baja de la clave", id_historial)))?;
+
+        if Self::texto(clave).as_deref() == Some(valor.as_str()) {
+            return Err(ConfigError::Invalida(format!("'{}' ya tiene el valor de la versión #{}", clave, id_historial)));
+        }
+
+        let motivo = Self::motivo_rollback(&format!("versión #{}", id_historial), motivo.as_deref());
+        let cambio = ActualizarConfigRequest { clave: clave.to_string(), valor, motivo: None };
+        let reversion = Reversion { historial: Some(id_historial), lote: None, forzar: false };
+        let (id_lote, mut filas) = Self::aplicar(pool, id_admin, rol, &[cambio], Some(&motivo), reversion).await?;
+        let fila = filas
+            .pop()
+            .ok_or_else(|| ConfigError::NoEncontrada(format!("Configuración '{}' no encontrada", clave)))?;
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Configuración revertida",
+                MODULO_LOG,
+                Some(format!(
+                    "'{}' devuelta a la versión #{} (lote #{}). {}",
+                    clave, id_historial, id_lote, motivo
+                )),
+                None,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(fila)
+    }
+
+    /// Deshacer un lote: cada clave vuelve al valor que tenía antes del lote.
+    /// Si alguna cambió después, se rechaza salvo que se fuerce.
+    pub async fn revertir_lote(
+        pool: &PgPool,
+        id_admin: i32,
+        rol: &str,
+        id_lote: i32,
+        motivo: Option<String>,
+        forzar: bool,
+        ip_cliente: Option<String>,
+    ) -> Result<(i32, Vec<ConfiguracionSistema>), ConfigError> {
+        // Por clave, el valor previo al primer cambio del lote. Si alguna cambió después
+        // se comprueba en `aplicar`, con las filas ya bloqueadas.
+        let claves = sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT h.clave, (ARRAY_AGG(h.valor_anterior ORDER BY h.id_historial))[1]
+             FROM historial_configuracion h
+             WHERE h.id_lote = $1
+             GROUP BY h.clave
+             ORDER BY h.clave"
+        )
+        .bind(id_lote)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ConfigError::BaseDatos(format!("Error al leer el lote: {}", e)))?;
+
+        if claves.is_empty() {
+            return Err(ConfigError::NoEncontrada(format!("El lote #{} no existe", id_lote)));
+        }
+
+        let altas: Vec<&str> = claves.iter().filter(|(_, anterior)| anterior.is_none()).map(|(c, _)| c.as_str()).collect();
+        if !altas.is_empty() {
+            return Err(ConfigError::Invalida(format!(
+                "El lote #{} dio de alta claves sin valor anterior: {}",
+                id_lote,
+                altas.join(", ")
+            )));
+        }
+
+        let cambios: Vec<ActualizarConfigRequest> = claves
+            .into_iter()
+            .filter_map(|(clave, anterior)| anterior.map(|valor| ActualizarConfigRequest { clave, valor, motivo: None }))
+            .collect();
+
+        let motivo = Self::motivo_rollback(&format!("lote #{}", id_lote), motivo.as_deref());
+        let reversion = Reversion { historial: None, lote: Some(id_lote), forzar };
+        let (id_lote_nuevo, filas) = Self::aplicar(pool, id_admin, rol, &cambios, Some(&motivo), reversion).await?;
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Lote de configuración revertido",
+                MODULO_LOG,
+                Some(format!(
+                    "Lote #{} deshecho en el lote #{} ({} claves: {}){}. {}",
+                    id_lote,
+                    id_lote_nuevo,
+                    filas.len(),
+                    filas.iter().map(|f| f.clave.as_str()).collect::<Vec<_>>().join(", "),
+                    if forzar { ", forzado" } else { "" },
+                    motivo
+                )),
+                None,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok((id_lote_nuevo, filas))
+    }
+
+    async fn version(pool: &PgPool, id_historial: i32) -> Result<Option<HistorialConfiguracion>, ConfigError> {
+        sqlx::query_as::<_, HistorialConfiguracion>(
+            "SELECT h.id_historial, h.id_lote, h.clave, h.valor_anterior, h.valor_nuevo, h.motivo,
+                    h.id_usuario, NULL::TEXT as email_usuario, h.revierte_historial, h.revierte_lote, h.fecha_cambio
+             FROM historial_configuracion h WHERE h.id_historial = $1"
+        )
+        .bind(id_historial)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ConfigError::BaseDatos(format!("Error al leer la versión: {}", e)))
+    }
+
+    fn motivo_rollback(destino: &str, motivo: Option<&str>) -> String {
+        match motivo.map(str::trim).filter(|m| !m.is_empty()) {
+            Some(motivo) => format!("Rollback a {}: {}", destino, motivo),
+            None => format!("Rollback a {}", destino),
+        }
+    }
+
+    // Campos de primer nivel que cambiaron entre dos valores json
+    fn diferencias_json(antes: Option<&str>, despues: Option<&str>) -> Option<Value> {
+        let leer = |valor: Option<&str>| {
+            valor
+                .and_then(|v| serde_json::from_str::<Value>(v).ok())
+                .and_then(|v| v.as_object().cloned())
+                .unwrap_or_default()
+        };
+        let antes = leer(antes);
+        let despues = leer(despues);
+
+        let mut campos: Vec<&String> = antes.keys().chain(despues.keys()).collect();
+        campos.sort();
+        campos.dedup();
+
+        let cambios: serde_json::Map<String, Value> = campos
+            .into_iter()
+            .filter(|campo| antes.get(*campo) != despues.get(*campo))
+            .map(|campo| {
+                (
+                    campo.clone(),
+                    serde_json::json!({
+                        "antes": antes.get(campo).cloned().unwrap_or(Value::Null),
+                        "despues": despues.get(campo).cloned().unwrap_or(Value::Null),
+                    }),
+                )
+            })
+            .collect();
+
+        (!cambios.is_empty()).then_some(Value::Object(cambios))
+    }
+
+    // ==================== SINCRONIZACIÓN ====================
+
+    async fn recargar(pool: &PgPool) -> Result<usize, String> {
+        let filas = sqlx::query_as::<_, ConfiguracionSistema>("SELECT * FROM configuracion_sistema")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Error al cargar la configuración: {}", e))?;
+
+        let total = filas.len();
+        let nueva: HashMap<String, EntradaConfig> = filas
+            .into_iter()
+            .map(|fila| (fila.clave.clone(), EntradaConfig::new(fila)))
+            .collect();
+
+        if let Ok(mut cache) = CACHE.write() {
+            *cache = nueva;
+        }
+
+        Ok(total)
+    }
+
+    async fn recargar_clave(pool: &PgPool, clave: &str) -> Result<(), String> {
+        let fila = sqlx::query_as::<_, ConfiguracionSistema>("SELECT * FROM configuracion_sistema WHERE clave = $1")
+            .bind(clave)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Error al recargar '{}': {}", clave, e))?;
+
+        if let Ok(mut cache) = CACHE.write() {
+            match fila {
+                Some(fila) => cache.insert(clave.to_string(), EntradaConfig::new(fila)),
+                None => cache.remove(clave),
+            };
+        }
+
+        Ok(())
+    }
+
+    // Escuchar NOTIFY hasta que falle la conexión. Tras conectar (o reconectar) se
+    // recarga todo, porque las notificaciones emitidas mientras tanto se pierden.
+    async fn escuchar(pool: &PgPool) -> Result<(), String> {
+        let mut listener = PgListener::connect_with(pool)
+            .await
+            .map_err(|e| format!("Error al conectar: {}", e))?;
+        listener
+            .listen(CANAL_CONFIG)
+            .await
+            .map_err(|e| format!("Error al escuchar {}: {}", CANAL_CONFIG, e))?;
+
+        Self::recargar(pool).await?;
+
+        loop {
+            match listener.try_recv().await {
+                Ok(Some(notificacion)) => Self::recargar_clave(pool, notificacion.payload()).await?,
+                // Conexión perdida: try_recv reconecta en la siguiente llamada
+                Ok(None) => Self::recargar(pool).await.map(|_| ())?,
+                Err(e) => return Err(format!("Error al recibir notificación: {}", e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use rand::RngCore;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::io::{Cursor, Write};
+use std::time::Duration;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::models::log_auditoria::NivelLog;
+use crate::models::{EstadoEliminacionResponse, SolicitarEliminacionRequest, SolicitudEliminacionCuenta, Usuario};
+use crate::repositories::{AuthRepository, DatosPersonalesRepository};
+use crate::services::{AuthService, ConfigService, DosFactoresService, EmailService, LogService};
+use crate::utils::password;
+
+/// Versión del formato de exportación
+const FORMATO_EXPORTACION: &str = "kronostech-datos-personales/1";
+
+const DIAS_GRACIA_DEFECTO: i64 = 30;
+
+/// Cada cuánto se revisan las solicitudes con el periodo de gracia vencido
+const INTERVALO_ELIMINACIONES: Duration = Duration::from_secs(60 * 60);
+
+const MODULO_LOG: &str = "Privacidad";
+
+/// Derechos ARCO del titular de datos personales (Ley N.° 29733):
+/// acceso (exportación) y cancelación (eliminación con anonimización).
+pub struct DatosPersonalesService;
+
+impl DatosPersonalesService {
+    // ==================== EXPORTACIÓN ====================
+
+    // Exportar todos los datos del usuario autenticado
+    pub async fn exportar(
+        pool: &PgPool,
+        token: &str,
+        formato: &str,
+        ip_cliente: Option<String>,
+    ) -> Result<Value, String> {
+        let claims = AuthService::verify_token_titular(token)?;
+        let usuario = Self::usuario_activo(pool, claims.sub).await?;
+
+        let mut datos = DatosPersonalesRepository::exportar(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al exportar datos personales: {}", e))?;
+
+        if let Some(secciones) = datos.as_object_mut() {
+            secciones.insert(
+                "exportacion".to_string(),
+                json!({
+                    "formato": FORMATO_EXPORTACION,
+                    "generado_en": chrono::Utc::now().to_rfc3339(),
+                    "base_legal": "Ley N.° 29733 de Protección de Datos Personales - derecho de acceso",
+                }),
+            );
+        }
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Info,
+                "Exportación de datos personales",
+                MODULO_LOG,
+                Some(format!("Datos personales exportados en formato {}", formato)),
+                Some(usuario.email),
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(datos)
+    }
+
+    // Empaquetar la exportación en un ZIP con un archivo JSON por sección
+    pub fn empaquetar_zip(datos: &Value) -> Result<Vec<u8>, String> {
+        let error = |e: &dyn std::fmt::Display| format!("Error al generar el archivo ZIP: {}", e);
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let opciones = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.start_file("LEEME.txt", opciones).map_err(|e| error(&e))?;
+        zip.write_all(
+            "Exportación de datos personales de KronosTech.\n\n\
+             Cada archivo JSON contiene una sección de los datos asociados a tu cuenta:\n\
+             perfil, direcciones, métodos de pago, pedidos (con pagos, envíos y reembolsos),\n\
+             valoraciones, lista de deseos, carritos, armados de PC, cupones, notificaciones, seguridad y actividad.\n\n\
+             Por seguridad no se incluyen contraseñas, tokens de pago ni secretos de autenticación.\n"
+                .as_bytes(),
+        )
+        .map_err(|e| error(&e))?;
+
+        if let Some(secciones) = datos.as_object() {
+            for (seccion, contenido) in secciones {
+                let json = serde_json::to_vec_pretty(contenido).map_err(|e| error(&e))?;
+                zip.start_file(format!("{}.json", seccion), opciones).map_err(|e| error(&e))?;
+                zip.write_all(&json).map_err(|e| error(&e))?;
+            }
+        }
+
+        let cursor = zip.finish().map_err(|e| error(&e))?;
+        Ok(cursor.into_inner())
+    }
+
+    // ==================== ELIMINACIÓN DE CUENTA ====================
+
+    pub async fn estado_eliminacion(pool: &PgPool, token: &str) -> Result<EstadoEliminacionResponse, String> {
+        let claims = AuthService::verify_token(token)?;
+
+        let solicitud = DatosPersonalesRepository::find_solicitud_pendiente(pool, claims.sub)
+            .await
+            .map_err(|e| format!("Error al consultar la solicitud: {}", e))?;
+
+        Ok(EstadoEliminacionResponse {
+            eliminacion_pendiente: solicitud.is_some(),
+            solicitud,
+            dias_gracia: Self::dias_gracia(),
+        })
+    }
+
+    // Programar la eliminación de la cuenta al final del periodo de gracia.
+    // Requiere la contraseña y que no haya pedidos en curso.
+    pub async fn solicitar_eliminacion(
+        pool: &PgPool,
+        token: &str,
+        request: SolicitarEliminacionRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<SolicitudEliminacionCuenta, String> {
+        let claims = AuthService::verify_token_titular(token)?;
+        let usuario = Self::usuario_activo(pool, claims.sub).await?;
+
+        if DosFactoresService::es_rol_admin(&usuario.rol) {
+            return Err("Las cuentas de administrador se dan de baja desde el panel de administración".to_string());
+        }
+
+        let password_match = password::verificar_password(&request.password, &usuario.contrasena)?;
+
+        if !password_match {
+            return Err("La contraseña es incorrecta".to_string());
+        }
+
+        let pedidos_en_curso = DatosPersonalesRepository::contar_pedidos_en_curso(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al verificar pedidos: {}", e))?;
+
+        if pedidos_en_curso > 0 {
+            return Err(format!(
+                "Tienes {} pedido(s) en curso. Podrás solicitar la eliminación cuando se completen",
+                pedidos_en_curso
+            ));
+        }
+
+        let dias_gracia = Self::dias_gracia();
+        let fecha_programada = chrono::Utc::now().naive_utc() + chrono::Duration::days(dias_gracia);
+        let motivo = request.motivo.as_deref().map(str::trim).filter(|m| !m.is_empty());
+
+        let solicitud = DatosPersonalesRepository::crear_solicitud(
+            pool,
+            usuario.id_usuario,
+            motivo,
+            fecha_programada,
+            ip_cliente.as_deref(),
+        )
+        .await
+        .map_err(|e| format!("Error al registrar la solicitud: {}", e))?
+        .ok_or("Ya existe una solicitud de eliminación pendiente".to_string())?;
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Eliminación de cuenta solicitada",
+                MODULO_LOG,
+                Some(format!(
+                    "Solicitud #{}: la cuenta se anonimizará el {} UTC",
+                    solicitud.id_solicitud,
+                    solicitud.fecha_programada.format("%Y-%m-%d %H:%M")
+                )),
+                Some(usuario.email.clone()),
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        let cuerpo = format!(
+            "Hola {},\n\n\
+             Recibimos tu solicitud para eliminar tu cuenta de KronosTech.\n\
+             Tus datos personales se eliminarán el {} (UTC).\n\n\
+             Hasta esa fecha puedes cancelar la solicitud iniciando sesión.\n\
+             Los registros de tus pedidos se conservarán sin datos personales, \
+             como exige la normativa contable.\n\n\
+             Si no solicitaste la eliminación, cambia tu contraseña y cancela la solicitud de inmediato.\n",
+            usuario.nombre,
+            solicitud.fecha_programada.format("%d/%m/%Y %H:%M")
+        );
+        Self::notificar(usuario.email, "Solicitud de eliminación de cuenta", cuerpo);
+
+        Ok(solicitud)
+    }
+
+    // Cancelar una solicitud pendiente durante el periodo de gracia
+    pub async fn cancelar_eliminacion(
+        pool: &PgPool,
+        token: &str,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let claims = AuthService::verify_token_titular(token)?;
+        let usuario = Self::usuario_activo(pool, claims.sub).await?;
+
+        let cancelada = DatosPersonalesRepository::cancelar_solicitud(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al cancelar la solicitud: {}", e))?;
+
+        if !cancelada {
+            return Err("No tienes una solicitud de eliminación pendiente".to_string());
+        }
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Eliminación de cuenta cancelada",
+                MODULO_LOG,
+                Some("El usuario canceló su solicitud de eliminación de cuenta".to_string()),
+                Some(usuario.email.clone()),
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        let cuerpo = format!(
+            "Hola {},\n\n\
+             Cancelaste la solicitud de eliminación de tu cuenta. Tu cuenta seguirá activa.\n",
+            usuario.nombre
+        );
+        Self::notificar(usuario.email, "Eliminación de cuenta cancelada", cuerpo);
+
+        Ok(())
+    }
+
+    // Anonimizar las cuentas cuyo periodo de gracia venció. Las que tienen pedidos
+    // en curso se posponen hasta la siguiente revisión.
+    pub async fn ejecutar_eliminaciones_vencidas(pool: &PgPool) -> Result<usize, String> {
+        let ahora = chrono::Utc::now();
+
+        let solicitudes = DatosPersonalesRepository::solicitudes_vencidas(pool, ahora.naive_utc())
+            .await
+            .map_err(|e| format!("Error al buscar solicitudes vencidas: {}", e))?;
+
+        let mut ejecutadas = 0;
+
+        for solicitud in solicitudes {
+            let id_usuario = solicitud.id_usuario;
+
+            let pedidos_en_curso = DatosPersonalesRepository::contar_pedidos_en_curso(pool, id_usuario)
+                .await
+                .map_err(|e| format!("Error al verificar pedidos: {}", e))?;
+
+            if pedidos_en_curso > 0 {
+                println!(
+                    "⏳ Eliminación de la cuenta {} pospuesta: {} pedido(s) en curso",
+                    id_usuario, pedidos_en_curso
+                );
+                continue;
+            }
+
+            let Some(usuario) = AuthRepository::find_by_id(pool, id_usuario)
+                .await
+                .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            else {
+                continue;
+            };
+
+            let email_anonimo = format!("eliminado-{}@anonimo.invalid", id_usuario);
+
+            let anonimizada = DatosPersonalesRepository::anonimizar(
+                pool,
+                solicitud.id_solicitud,
+                id_usuario,
+                &email_anonimo,
+                &Self::contrasena_inutilizable(),
+                ahora.naive_utc(),
+            )
+            .await
+            .map_err(|e| format!("Error al anonimizar la cuenta {}: {}", id_usuario, e))?;
+
+            if !anonimizada {
+                continue;
+            }
+
+            AuthService::marcar_sesiones_revocadas(id_usuario, ahora.timestamp());
+            ejecutadas += 1;
+
+            LogService::registrar(
+                pool,
+                Some(id_usuario),
+                LogService::nuevo(
+                    NivelLog::Security,
+                    "Cuenta anonimizada",
+                    MODULO_LOG,
+                    Some(format!(
+                        "Solicitud #{} ejecutada: datos personales eliminados; pedidos y registros contables conservados",
+                        solicitud.id_solicitud
+                    )),
+                    Some(email_anonimo),
+                    None,
+                ),
+            )
+            .await;
+
+            // Último aviso a la dirección original, que ya no queda almacenada
+            let cuerpo = format!(
+                "Hola {},\n\n\
+                 Tu cuenta de KronosTech fue eliminada y tus datos personales fueron anonimizados.\n\
+                 Gracias por haber sido parte de KronosTech.\n",
+                usuario.nombre
+            );
+            Self::notificar(usuario.email, "Tu cuenta fue eliminada", cuerpo);
+        }
+
+        Ok(ejecutadas)
+    }
+
+    // Revisar periódicamente las solicitudes vencidas en segundo plano
+    pub fn iniciar_tarea_eliminaciones(pool: PgPool) {
+        tokio::spawn(async move {
+            let mut intervalo = tokio::time::interval(INTERVALO_ELIMINACIONES);
+            loop {
+                intervalo.tick().await;
+                match Self::ejecutar_eliminaciones_vencidas(&pool).await {
+                    Ok(0) => {}
+                    Ok(total) => println!("🗑️  {} cuentas anonimizadas", total),
+                    Err(e) => eprintln!("⚠️  {}", e),
+                }
+            }
+        });
+    }
+
+    // ==================== HELPERS ====================
+
+    async fn usuario_activo(pool: &PgPool, id_usuario: i32) -> Result<Usuario, String> {
+        let usuario = AuthRepository::find_by_id(pool, id_usuario)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            .ok_or("Usuario no encontrado".to_string())?;
+
+        if !usuario.activo {
+            return Err("La cuenta está desactivada".to_string());
+        }
+
+        Ok(usuario)
+    }
+
+    // Config account_deletion_grace_days
+    fn dias_gracia() -> i64 {
+        ConfigService::entero("account_deletion_grace_days")
+            .unwrap_or(DIAS_GRACIA_DEFECTO)
+            .max(0)
+    }
+
+    // Valor que no corresponde a ningún hash válido: nadie puede volver a iniciar sesión
+    fn contrasena_inutilizable() -> String {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        format!("!eliminada!{}", hex::encode(bytes))
+    }
+
+    // El envío se hace en segundo plano para no alterar el tiempo de respuesta
+    fn notificar(destinatario: String, asunto: &'static str, cuerpo: String) {
+        tokio::spawn(async move {
+            if let Err(e) = EmailService::enviar(&destinatario, asunto, &cuerpo).await {
+                eprintln!("⚠️  Error al enviar email '{}': {}", asunto, e);
+            }
+        });
+    }
+}
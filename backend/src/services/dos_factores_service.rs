@@ -0,0 +1,487 @@
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::models::log_auditoria::NivelLog;
+use crate::models::{
+    ActivarDosFactoresRequest, ActivarDosFactoresResponse, ConfigurarDosFactoresResponse,
+    DesactivarDosFactoresRequest, DesafioDosFactoresResponse, EstadoDosFactoresResponse,
+    LoginResponse, Usuario, VerificarDosFactoresRequest,
+};
+use crate::repositories::{AuthRepository, DosFactoresRepository};
+use crate::services::{AuthService, ConfigService, LogService};
+use crate::utils::{password, totp};
+
+/// Propósito del token de desafío emitido cuando la cuenta ya tiene 2FA activo
+pub const PROPOSITO_VERIFICACION: &str = "2fa_verificacion";
+
+/// Propósito del token de desafío que solo permite configurar y activar 2FA
+pub const PROPOSITO_ENROLAMIENTO: &str = "2fa_enrolamiento";
+
+/// Vigencia del desafío de login (minutos)
+const DESAFIO_MINUTOS: i64 = 5;
+
+/// Vigencia del token de enrolamiento obligatorio (minutos)
+const ENROLAMIENTO_MINUTOS: i64 = 15;
+
+/// Códigos fallidos permitidos antes de bloquear la verificación
+const MAX_INTENTOS: i32 = 5;
+const BLOQUEO_MINUTOS: i32 = 15;
+
+const TOTAL_CODIGOS_RECUPERACION: usize = 10;
+
+// Sin caracteres ambiguos (0/o, 1/l/i)
+const ALFABETO_CODIGOS: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+const MODULO_LOG: &str = "Autenticación";
+
+pub struct DosFactoresService;
+
+impl DosFactoresService {
+    pub fn es_rol_admin(rol: &str) -> bool {
+        rol == "administrador" || rol == "super_admin"
+    }
+
+    // Config require_2fa_admin
+    pub fn obligatorio_para_admins() -> bool {
+        ConfigService::booleano("require_2fa_admin").unwrap_or(false)
+    }
+
+    // Decidir, tras validar la contraseña, si el login requiere un segundo paso.
+    // None significa que se puede emitir la sesión directamente.
+    pub async fn desafio_login(
+        pool: &PgPool,
+        usuario: &Usuario,
+        remember_me: bool,
+    ) -> Result<Option<DesafioDosFactoresResponse>, String> {
+        if !Self::es_rol_admin(&usuario.rol) {
+            return Ok(None);
+        }
+
+        let activo = DosFactoresRepository::find_by_usuario(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al verificar 2FA: {}", e))?
+            .is_some_and(|registro| registro.activo);
+
+        let (proposito, minutos) = if activo {
+            (PROPOSITO_VERIFICACION, DESAFIO_MINUTOS)
+        } else if Self::obligatorio_para_admins() {
+            (PROPOSITO_ENROLAMIENTO, ENROLAMIENTO_MINUTOS)
+        } else {
+            return Ok(None);
+        };
+
+        let token_desafio =
+            AuthService::generar_token_desafio(usuario.id_usuario, proposito, remember_me, minutos)?;
+
+        Ok(Some(DesafioDosFactoresResponse {
+            requiere_2fa: true,
+            requiere_enrolamiento: !activo,
+            token_desafio,
+            expira_en_segundos: minutos * 60,
+        }))
+    }
+
+    // POST /auth/2fa/verificar: segundo paso del login
+    pub async fn verificar_login(
+        pool: &PgPool,
+        request: VerificarDosFactoresRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<LoginResponse, String> {
+        let desafio = AuthService::verificar_token_desafio(&request.token_desafio, PROPOSITO_VERIFICACION)?;
+
+        let usuario = Self::usuario_activo(pool, desafio.sub).await?;
+
+        Self::validar_segundo_factor(pool, &usuario, &request.codigo, ip_cliente.clone()).await?;
+
+        AuthService::emitir_sesion(pool, usuario, desafio.remember_me, ip_cliente).await
+    }
+
+    // Iniciar (o reiniciar) la configuración: genera un secreto pendiente de confirmar
+    pub async fn configurar(pool: &PgPool, token: &str) -> Result<ConfigurarDosFactoresResponse, String> {
+        let (usuario, _) = Self::resolver_usuario(pool, token).await?;
+
+        let secreto = totp::generar_secreto();
+
+        let guardado = DosFactoresRepository::guardar_secreto_pendiente(pool, usuario.id_usuario, &secreto)
+            .await
+            .map_err(|e| format!("Error al guardar configuración 2FA: {}", e))?;
+
+        if !guardado {
+            return Err("La autenticación en dos pasos ya está activa".to_string());
+        }
+
+        let emisor = ConfigService::texto("site_name")
+            .unwrap_or_else(|| "KronosTech".to_string());
+
+        let otpauth_uri = totp::otpauth_uri(&emisor, &usuario.email, &secreto);
+        let qr_svg = totp::qr_svg(&otpauth_uri)?;
+
+        Ok(ConfigurarDosFactoresResponse {
+            secreto,
+            otpauth_uri,
+            qr_svg,
+        })
+    }
+
+    // Confirmar la configuración con un código de la app y generar los códigos de recuperación.
+    // Si se llamó con un token de enrolamiento obligatorio, también emite la sesión.
+    pub async fn activar(
+        pool: &PgPool,
+        token: &str,
+        request: ActivarDosFactoresRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<ActivarDosFactoresResponse, String> {
+        let (usuario, remember_me) = Self::resolver_usuario(pool, token).await?;
+
+        let registro = DosFactoresRepository::find_by_usuario(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al verificar 2FA: {}", e))?
+            .filter(|registro| !registro.activo)
+            .ok_or("No hay una configuración de 2FA pendiente de activar".to_string())?;
+
+        let paso = totp::verificar(&registro.secreto, &request.codigo, chrono::Utc::now().timestamp())
+            .ok_or("Código de verificación inválido".to_string())?;
+
+        let (codigos, hashes) = Self::generar_codigos_recuperacion();
+
+        let activado = DosFactoresRepository::activar(pool, usuario.id_usuario, paso, &hashes)
+            .await
+            .map_err(|e| format!("Error al activar 2FA: {}", e))?;
+
+        if !activado {
+            return Err("No hay una configuración de 2FA pendiente de activar".to_string());
+        }
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Autenticación en dos pasos activada",
+                MODULO_LOG,
+                Some(format!("2FA (TOTP) activado para {}", usuario.email)),
+                Some(usuario.email.clone()),
+                ip_cliente.clone(),
+            ),
+        )
+        .await;
+
+        let sesion = match remember_me {
+            Some(remember_me) => Some(AuthService::emitir_sesion(pool, usuario, remember_me, ip_cliente).await?),
+            None => None,
+        };
+
+        Ok(ActivarDosFactoresResponse {
+            codigos_recuperacion: codigos,
+            sesion,
+        })
+    }
+
+    // Desactivar 2FA propio: requiere contraseña y un código vigente
+    pub async fn desactivar(
+        pool: &PgPool,
+        token: &str,
+        request: DesactivarDosFactoresRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let claims = AuthService::verify_token(token)?;
+        let usuario = Self::usuario_activo(pool, claims.sub).await?;
+
+        if Self::es_rol_admin(&usuario.rol) && Self::obligatorio_para_admins() {
+            return Err("La autenticación en dos pasos es obligatoria para administradores".to_string());
+        }
+
+        let password_match = password::verificar_password(&request.password, &usuario.contrasena)?;
+
+        if !password_match {
+            return Err("La contraseña es incorrecta".to_string());
+        }
+
+        Self::validar_segundo_factor(pool, &usuario, &request.codigo, ip_cliente.clone()).await?;
+
+        DosFactoresRepository::eliminar(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al desactivar 2FA: {}", e))?;
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Autenticación en dos pasos desactivada",
+                MODULO_LOG,
+                Some(format!("2FA (TOTP) desactivado por el propio usuario {}", usuario.email)),
+                Some(usuario.email),
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    // Generar un nuevo juego de códigos de recuperación (invalida los anteriores)
+    pub async fn regenerar_codigos(
+        pool: &PgPool,
+        token: &str,
+        request: ActivarDosFactoresRequest,
+        ip_cliente: Option<String>,
+    ) -> Result<Vec<String>, String> {
+        let claims = AuthService::verify_token(token)?;
+        let usuario = Self::usuario_activo(pool, claims.sub).await?;
+
+        Self::validar_segundo_factor(pool, &usuario, &request.codigo, ip_cliente.clone()).await?;
+
+        let (codigos, hashes) = Self::generar_codigos_recuperacion();
+
+        DosFactoresRepository::reemplazar_codigos_recuperacion(pool, usuario.id_usuario, &hashes)
+            .await
+            .map_err(|e| format!("Error al generar códigos de recuperación: {}", e))?;
+
+        LogService::registrar(
+            pool,
+            Some(usuario.id_usuario),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Códigos de recuperación 2FA regenerados",
+                MODULO_LOG,
+                None,
+                Some(usuario.email),
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(codigos)
+    }
+
+    // Estado del 2FA del usuario autenticado
+    pub async fn estado(pool: &PgPool, token: &str) -> Result<EstadoDosFactoresResponse, String> {
+        let claims = AuthService::verify_token(token)?;
+
+        let registro = DosFactoresRepository::find_by_usuario(pool, claims.sub)
+            .await
+            .map_err(|e| format!("Error al verificar 2FA: {}", e))?
+            .filter(|registro| registro.activo);
+
+        let codigos_recuperacion_restantes = if registro.is_some() {
+            DosFactoresRepository::contar_codigos_restantes(pool, claims.sub)
+                .await
+                .map_err(|e| format!("Error al contar códigos de recuperación: {}", e))?
+        } else {
+            0
+        };
+
+        Ok(EstadoDosFactoresResponse {
+            activo: registro.is_some(),
+            obligatorio: Self::es_rol_admin(&claims.rol) && Self::obligatorio_para_admins(),
+            codigos_recuperacion_restantes,
+            fecha_activacion: registro.and_then(|registro| registro.fecha_activacion),
+        })
+    }
+
+    // Restablecer el 2FA de otro usuario (p. ej. dispositivo perdido). Solo super_admin.
+    pub async fn restablecer_por_admin(
+        pool: &PgPool,
+        id_admin: i32,
+        id_usuario: i32,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let usuario = AuthRepository::find_by_id(pool, id_usuario)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            .ok_or("Usuario no encontrado".to_string())?;
+
+        let eliminado = DosFactoresRepository::eliminar(pool, id_usuario)
+            .await
+            .map_err(|e| format!("Error al restablecer 2FA: {}", e))?;
+
+        if !eliminado {
+            return Err("El usuario no tiene la autenticación en dos pasos configurada".to_string());
+        }
+
+        let email_admin = AuthRepository::find_by_id(pool, id_admin)
+            .await
+            .ok()
+            .flatten()
+            .map(|admin| admin.email);
+
+        LogService::registrar(
+            pool,
+            Some(id_admin),
+            LogService::nuevo(
+                NivelLog::Security,
+                "Autenticación en dos pasos restablecida",
+                MODULO_LOG,
+                Some(format!(
+                    "2FA (TOTP) de {} (ID {}) eliminado por un super_admin",
+                    usuario.email, usuario.id_usuario
+                )),
+                email_admin,
+                ip_cliente,
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    // ==================== HELPERS ====================
+
+    // Aceptar un token de sesión o un token de enrolamiento obligatorio.
+    // Con este último se devuelve el remember_me del login original.
+    async fn resolver_usuario(pool: &PgPool, token: &str) -> Result<(Usuario, Option<bool>), String> {
+        let (id_usuario, remember_me) = match AuthService::verify_token(token) {
+            Ok(claims) => (claims.sub, None),
+            Err(err) => match AuthService::verificar_token_desafio(token, PROPOSITO_ENROLAMIENTO) {
+                Ok(desafio) => (desafio.sub, Some(desafio.remember_me)),
+                Err(_) => return Err(err),
+            },
+        };
+
+        let usuario = Self::usuario_activo(pool, id_usuario).await?;
+
+        if !Self::es_rol_admin(&usuario.rol) {
+            return Err("La autenticación en dos pasos solo está disponible para administradores".to_string());
+        }
+
+        Ok((usuario, remember_me))
+    }
+
+    async fn usuario_activo(pool: &PgPool, id_usuario: i32) -> Result<Usuario, String> {
+        let usuario = AuthRepository::find_by_id(pool, id_usuario)
+            .await
+            .map_err(|e| format!("Error al buscar usuario: {}", e))?
+            .ok_or("Usuario no encontrado".to_string())?;
+
+        if !usuario.activo {
+            return Err("La cuenta está desactivada".to_string());
+        }
+
+        Ok(usuario)
+    }
+
+    // Validar un código TOTP o de recuperación, con bloqueo tras varios fallos
+    async fn validar_segundo_factor(
+        pool: &PgPool,
+        usuario: &Usuario,
+        codigo: &str,
+        ip_cliente: Option<String>,
+    ) -> Result<(), String> {
+        let registro = DosFactoresRepository::find_by_usuario(pool, usuario.id_usuario)
+            .await
+            .map_err(|e| format!("Error al verificar 2FA: {}", e))?
+            .filter(|registro| registro.activo)
+            .ok_or("La autenticación en dos pasos no está activa".to_string())?;
+
+        if registro.bloqueado {
+            return Err("Demasiados códigos inválidos. Intenta nuevamente en unos minutos".to_string());
+        }
+
+        let codigo = codigo.trim();
+        let es_totp = codigo.len() == totp::DIGITOS && codigo.chars().all(|c| c.is_ascii_digit());
+
+        let valido = if es_totp {
+            match totp::verificar(&registro.secreto, codigo, chrono::Utc::now().timestamp()) {
+                // Un código ya usado cuenta como inválido
+                Some(paso) => DosFactoresRepository::registrar_paso_usado(pool, usuario.id_usuario, paso)
+                    .await
+                    .map_err(|e| format!("Error al verificar código: {}", e))?,
+                None => false,
+            }
+        } else {
+            let usado = DosFactoresRepository::usar_codigo_recuperacion(
+                pool,
+                usuario.id_usuario,
+                &Self::hash_codigo(codigo),
+            )
+            .await
+            .map_err(|e| format!("Error al verificar código: {}", e))?;
+
+            if usado {
+                let restantes = DosFactoresRepository::contar_codigos_restantes(pool, usuario.id_usuario)
+                    .await
+                    .unwrap_or(0);
+
+                LogService::registrar(
+                    pool,
+                    Some(usuario.id_usuario),
+                    LogService::nuevo(
+                        NivelLog::Security,
+                        "Código de recuperación 2FA utilizado",
+                        MODULO_LOG,
+                        Some(format!("Quedan {} códigos de recuperación", restantes)),
+                        Some(usuario.email.clone()),
+                        ip_cliente.clone(),
+                    ),
+                )
+                .await;
+            }
+
+            usado
+        };
+
+        if valido {
+            return Ok(());
+        }
+
+        let bloqueado = DosFactoresRepository::registrar_intento_fallido(
+            pool,
+            usuario.id_usuario,
+            MAX_INTENTOS,
+            BLOQUEO_MINUTOS,
+        )
+        .await
+        .map_err(|e| format!("Error al registrar intento: {}", e))?;
+
+        if bloqueado {
+            LogService::registrar(
+                pool,
+                Some(usuario.id_usuario),
+                LogService::nuevo(
+                    NivelLog::Security,
+                    "Verificación 2FA bloqueada",
+                    MODULO_LOG,
+                    Some(format!(
+                        "{} códigos inválidos; verificación bloqueada por {} minutos",
+                        MAX_INTENTOS, BLOQUEO_MINUTOS
+                    )),
+                    Some(usuario.email.clone()),
+                    ip_cliente,
+                ),
+            )
+            .await;
+        }
+
+        Err("Código de verificación inválido".to_string())
+    }
+
+    // Códigos con formato xxxxx-xxxxx y su hash para persistir
+    fn generar_codigos_recuperacion() -> (Vec<String>, Vec<String>) {
+        let mut rng = rand::rngs::OsRng;
+
+        let codigos: Vec<String> = (0..TOTAL_CODIGOS_RECUPERACION)
+            .map(|_| {
+                let caracteres: String = (0..10)
+                    .map(|_| ALFABETO_CODIGOS[rng.gen_range(0..ALFABETO_CODIGOS.len())] as char)
+                    .collect();
+                format!("{}-{}", &caracteres[..5], &caracteres[5..])
+            })
+            .collect();
+
+        let hashes = codigos.iter().map(|codigo| Self::hash_codigo(codigo)).collect();
+
+        (codigos, hashes)
+    }
+
+    // El hash ignora mayúsculas, guiones y espacios
+    fn hash_codigo(codigo: &str) -> String {
+        let normalizado: String = codigo
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        hex::encode(Sha256::digest(normalizado.as_bytes()))
+    }
+}
@@ -0,0 +1,66 @@
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::env;
+
+use crate::services::ConfigService;
+
+pub struct EmailService;
+
+impl EmailService {
+    /// Enviar un email de texto plano usando la configuración SMTP del sistema.
+    /// La contraseña SMTP se lee de la variable de entorno SMTP_PASSWORD.
+    pub async fn enviar(
+        destinatario: &str,
+        asunto: &str,
+        cuerpo: &str,
+    ) -> Result<(), String> {
+        let habilitado = ConfigService::booleano("email_enabled").unwrap_or(false);
+
+        if !habilitado {
+            println!("📧 Email deshabilitado, no se envía '{}' a {}", asunto, destinatario);
+            return Ok(());
+        }
+
+        let host = ConfigService::texto("smtp_host")
+            .ok_or("Servidor SMTP no configurado".to_string())?;
+        let port: u16 = ConfigService::entero("smtp_port")
+            .and_then(|v| u16::try_from(v).ok())
+            .unwrap_or(587);
+        let usuario = ConfigService::texto("smtp_user")
+            .ok_or("Usuario SMTP no configurado".to_string())?;
+        let nombre_sitio = ConfigService::texto("site_name")
+            .unwrap_or_else(|| "KronosTech".to_string());
+        let password = env::var("SMTP_PASSWORD").unwrap_or_default();
+
+        let from: Mailbox = format!("{} <{}>", nombre_sitio, usuario)
+            .parse()
+            .map_err(|e| format!("Remitente inválido: {}", e))?;
+        let to: Mailbox = destinatario
+            .parse()
+            .map_err(|e| format!("Destinatario inválido: {}", e))?;
+
+        let mensaje = Message::builder()
+            .from(from)
+            .to(to)
+            .subject(asunto)
+            .header(ContentType::TEXT_PLAIN)
+            .body(cuerpo.to_string())
+            .map_err(|e| format!("Error al construir email: {}", e))?;
+
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+            .map_err(|e| format!("Error al configurar SMTP: {}", e))?
+            .port(port)
+            .credentials(Credentials::new(usuario, password))
+            .build();
+
+        mailer
+            .send(mensaje)
+            .await
+            .map_err(|e| format!("Error al enviar email: {}", e))?;
+
+        Ok(())
+    }
+}
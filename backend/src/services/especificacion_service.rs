@@ -0,0 +1,774 @@
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::collections::HashSet;
+
+use crate::models::especificacion_producto::{
+    ActualizarAtributoRequest, AtributoEspecificacion, Comparacion, CrearAtributoRequest, EspecificacionResponse,
+    FilaComparacion, ProductoComparado, ValorEspecificacionRequest, TIPOS_DATO_ATRIBUTO,
+};
+use crate::repositories::catalogo_repository::FROM_PRODUCTOS;
+use crate::services::ConfigService;
+
+/// Largo máximo de un valor de texto
+const MAXIMO_TEXTO: usize = 255;
+
+/// Productos por comparación si `compare_max_products` no está configurada
+const MAXIMO_COMPARAR: i64 = 4;
+
+/// Fila en armado de la comparación: atributo, etiqueta, unidad y un valor por producto
+type FilaEnArmado = (String, String, Option<String>, Vec<Option<String>>);
+
+const COLUMNAS_ATRIBUTO: &str = "id_atributo, id_categoria, nombre, etiqueta, unidad_medida, tipo_dato, \
+     valores_permitidos, obligatorio, filtrable, comparable, orden, fecha_creacion, fecha_actualizacion";
+
+#[derive(Debug)]
+pub enum EspecificacionError {
+    NoEncontrado(String),
+    Invalida(String),
+    /// El cambio deja inválidos valores ya cargados, o el atributo está en uso
+    Conflicto(String),
+    BaseDatos(String),
+}
+
+impl std::fmt::Display for EspecificacionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EspecificacionError::NoEncontrado(m)
+            | EspecificacionError::Invalida(m)
+            | EspecificacionError::Conflicto(m)
+            | EspecificacionError::BaseDatos(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<sqlx::Error> for EspecificacionError {
+    fn from(e: sqlx::Error) -> Self {
+        EspecificacionError::BaseDatos(format!("Error en la base de datos: {}", e))
+    }
+}
+
+/// Valor de especificación validado contra el esquema de la categoría, listo para guardar
+#[derive(Debug, Clone)]
+pub struct ValorValidado {
+    pub id_atributo: i32,
+    pub nombre: String,
+    pub valor: String,
+}
+
+pub struct EspecificacionService;
+
+impl EspecificacionService {
+    // ==================== ESQUEMA ====================
+
+    pub async fn listar_atributos(
+        db: impl PgExecutor<'_>,
+        id_categoria: i32,
+    ) -> Result<Vec<AtributoEspecificacion>, sqlx::Error> {
+        sqlx::query_as::<_, AtributoEspecificacion>(&format!(
+            "SELECT {} FROM atributo_especificacion WHERE id_categoria = $1 ORDER BY orden, nombre",
+            COLUMNAS_ATRIBUTO
+        ))
+        .bind(id_categoria)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Atributos de la categoría marcados como filtrables (facetas del catálogo)
+    pub async fn atributos_filtrables(pool: &PgPool, id_categoria: i32) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT nombre FROM atributo_especificacion WHERE id_categoria = $1 AND filtrable ORDER BY orden, nombre",
+        )
+        .bind(id_categoria)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn crear_atributo(
+        pool: &PgPool,
+        id_categoria: i32,
+        request: CrearAtributoRequest,
+    ) -> Result<AtributoEspecificacion, EspecificacionError> {
+        let existe: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM categoria WHERE id_categoria = $1)")
+            .bind(id_categoria)
+            .fetch_one(pool)
+            .await?;
+        if !existe {
+            return Err(EspecificacionError::NoEncontrado("Categoría no encontrada".to_string()));
+        }
+
+        let nombre = Self::normalizar_nombre(&request.nombre)?;
+        let etiqueta = Self::etiqueta(request.etiqueta.as_deref(), &nombre)?;
+        let tipo_dato = Self::tipo_dato(request.tipo_dato.as_deref().unwrap_or("texto"))?;
+        let valores_permitidos = Self::valores_permitidos(&tipo_dato, request.valores_permitidos)?;
+
+        sqlx::query_as::<_, AtributoEspecificacion>(&format!(
+            r#"
+            INSERT INTO atributo_especificacion (
+                id_categoria, nombre, etiqueta, unidad_medida, tipo_dato, valores_permitidos,
+                obligatorio, filtrable, comparable, orden
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING {}
+            "#,
+            COLUMNAS_ATRIBUTO
+        ))
+        .bind(id_categoria)
+        .bind(&nombre)
+        .bind(etiqueta)
+        .bind(Self::texto_opcional(request.unidad_medida))
+        .bind(tipo_dato)
+        .bind(valores_permitidos)
+        .bind(request.obligatorio.unwrap_or(false))
+        .bind(request.filtrable.unwrap_or(false))
+        .bind(request.comparable.unwrap_or(true))
+        .bind(request.orden.unwrap_or(0))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => EspecificacionError::Conflicto(format!(
+                "La categoría ya tiene un atributo '{}'",
+                nombre
+            )),
+            e => e.into(),
+        })
+    }
+
+    /// Actualizar un atributo. Si cambia el tipo o las opciones, los valores ya cargados
+    /// deben seguir siendo válidos; se comprueba con el atributo bloqueado, dentro de la
+    /// misma transacción que lo actualiza.
+    pub async fn actualizar_atributo(
+        pool: &PgPool,
+        id_atributo: i32,
+        request: ActualizarAtributoRequest,
+    ) -> Result<AtributoEspecificacion, EspecificacionError> {
+        let mut tx = pool.begin().await?;
+        let actual = Self::obtener_atributo(&mut tx, id_atributo).await?;
+
+        let etiqueta = match request.etiqueta.as_deref() {
+            Some(etiqueta) => Self::etiqueta(Some(etiqueta), &actual.nombre)?,
+            None => actual.etiqueta.clone(),
+        };
+        let tipo_dato = match request.tipo_dato.as_deref() {
+            Some(tipo) => Self::tipo_dato(tipo)?,
+            None => actual.tipo_dato.clone(),
+        };
+        let valores_permitidos = Self::valores_permitidos(
+            &tipo_dato,
+            request.valores_permitidos.or_else(|| actual.valores_permitidos.clone()),
+        )?;
+        let unidad_medida = match request.unidad_medida {
+            Some(unidad) => Self::texto_opcional(Some(unidad)),
+            None => actual.unidad_medida.clone(),
+        };
+
+        let nuevo = AtributoEspecificacion {
+            etiqueta,
+            tipo_dato,
+            valores_permitidos,
+            unidad_medida,
+            obligatorio: request.obligatorio.unwrap_or(actual.obligatorio),
+            filtrable: request.filtrable.unwrap_or(actual.filtrable),
+            comparable: request.comparable.unwrap_or(actual.comparable),
+            orden: request.orden.or(actual.orden),
+            ..actual
+        };
+
+        let cargados: Vec<(i32, String)> = sqlx::query_as(
+            "SELECT id_producto_detalle, valor_atributo FROM especificacion_producto WHERE id_atributo = $1",
+        )
+        .bind(id_atributo)
+        .fetch_all(&mut *tx)
+        .await?;
+        let invalidos: Vec<i32> = cargados
+            .iter()
+            .filter(|(_, valor)| Self::normalizar_valor(&nuevo, &Value::String(valor.clone())).is_err())
+            .map(|(id, _)| *id)
+            .collect();
+        if !invalidos.is_empty() {
+            return Err(EspecificacionError::Conflicto(format!(
+                "El cambio deja inválido el valor de '{}' en {} producto(s): {:?}",
+                nuevo.nombre,
+                invalidos.len(),
+                invalidos
+            )));
+        }
+
+        // Un atributo pasa a obligatorio solo si ya lo tienen todas las variantes activas
+        // de la categoría; si no, esas variantes dejarían de poder guardarse
+        if nuevo.obligatorio && !actual.obligatorio {
+            let sin_valor: Vec<i32> = sqlx::query_scalar(
+                r#"
+                SELECT pd.id_producto_detalle
+                FROM producto_detalle pd
+                JOIN producto p ON p.id_producto = pd.id_producto
+                WHERE p.id_categoria = $1
+                  AND pd.estado = 'activo'
+                  AND NOT EXISTS (
+                      SELECT 1 FROM especificacion_producto e
+                      WHERE e.id_producto_detalle = pd.id_producto_detalle AND e.id_atributo = $2
+                  )
+                ORDER BY pd.id_producto_detalle
+                "#,
+            )
+            .bind(nuevo.id_categoria)
+            .bind(id_atributo)
+            .fetch_all(&mut *tx)
+            .await?;
+            if !sin_valor.is_empty() {
+                return Err(EspecificacionError::Conflicto(format!(
+                    "{} variante(s) activa(s) no tienen '{}'; cárgalo antes de hacerlo obligatorio: {:?}",
+                    sin_valor.len(),
+                    nuevo.nombre,
+                    sin_valor
+                )));
+            }
+        }
+
+        let atributo = sqlx::query_as::<_, AtributoEspecificacion>(&format!(
+            r#"
+            UPDATE atributo_especificacion
+            SET etiqueta = $2, unidad_medida = $3, tipo_dato = $4, valores_permitidos = $5,
+                obligatorio = $6, filtrable = $7, comparable = $8, orden = $9,
+                fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_atributo = $1
+            RETURNING {}
+            "#,
+            COLUMNAS_ATRIBUTO
+        ))
+        .bind(id_atributo)
+        .bind(&nuevo.etiqueta)
+        .bind(&nuevo.unidad_medida)
+        .bind(&nuevo.tipo_dato)
+        .bind(&nuevo.valores_permitidos)
+        .bind(nuevo.obligatorio)
+        .bind(nuevo.filtrable)
+        .bind(nuevo.comparable)
+        .bind(nuevo.orden)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Los valores guardan una copia de la unidad y el orden para el catálogo
+        sqlx::query("UPDATE especificacion_producto SET unidad_medida = $2, orden = $3 WHERE id_atributo = $1")
+            .bind(id_atributo)
+            .bind(&atributo.unidad_medida)
+            .bind(atributo.orden)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(atributo)
+    }
+
+    /// Eliminar un atributo. Si hay productos con valor cargado hace falta `forzar`,
+    /// que borra también esos valores.
+    pub async fn eliminar_atributo(pool: &PgPool, id_atributo: i32, forzar: bool) -> Result<i64, EspecificacionError> {
+        let mut tx = pool.begin().await?;
+        let atributo = Self::obtener_atributo(&mut tx, id_atributo).await?;
+        let en_uso: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM especificacion_producto WHERE id_atributo = $1")
+            .bind(id_atributo)
+            .fetch_one(&mut *tx)
+            .await?;
+        if en_uso > 0 && !forzar {
+            return Err(EspecificacionError::Conflicto(format!(
+                "El atributo '{}' tiene valores en {} producto(s); usa forzar=true para eliminarlos también",
+                atributo.nombre, en_uso
+            )));
+        }
+
+        sqlx::query("DELETE FROM especificacion_producto WHERE id_atributo = $1")
+            .bind(id_atributo)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM atributo_especificacion WHERE id_atributo = $1")
+            .bind(id_atributo)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(en_uso)
+    }
+
+    // Atributo bloqueado `FOR UPDATE`: los cambios de esquema y los guardados de
+    // valores (que lo bloquean `FOR SHARE`) se serializan
+    async fn obtener_atributo(
+        conn: &mut PgConnection,
+        id_atributo: i32,
+    ) -> Result<AtributoEspecificacion, EspecificacionError> {
+        sqlx::query_as::<_, AtributoEspecificacion>(&format!(
+            "SELECT {} FROM atributo_especificacion WHERE id_atributo = $1 FOR UPDATE",
+            COLUMNAS_ATRIBUTO
+        ))
+        .bind(id_atributo)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| EspecificacionError::NoEncontrado("Atributo no encontrado".to_string()))
+    }
+
+    // ==================== VALORES ====================
+
+    /// Especificaciones de una variante con los datos de su atributo
+    pub async fn especificaciones(
+        db: impl PgExecutor<'_>,
+        id_producto_detalle: i32,
+    ) -> Result<Vec<EspecificacionResponse>, sqlx::Error> {
+        sqlx::query_as::<_, EspecificacionResponse>(
+            r#"
+            SELECT e.id_atributo,
+                   e.nombre_atributo AS atributo,
+                   COALESCE(a.etiqueta, e.nombre_atributo) AS etiqueta,
+                   e.valor_atributo AS valor,
+                   COALESCE(a.unidad_medida, e.unidad_medida) AS unidad_medida,
+                   COALESCE(a.tipo_dato, 'texto') AS tipo_dato,
+                   COALESCE(a.filtrable, FALSE) AS filtrable,
+                   COALESCE(a.comparable, TRUE) AS comparable,
+                   COALESCE(a.orden, e.orden) AS orden
+            FROM especificacion_producto e
+            LEFT JOIN atributo_especificacion a ON e.id_atributo = a.id_atributo
+            WHERE e.id_producto_detalle = $1
+            ORDER BY COALESCE(a.orden, e.orden), e.nombre_atributo
+            "#,
+        )
+        .bind(id_producto_detalle)
+        .fetch_all(db)
+        .await
+    }
+
+    /// Reemplazar todas las especificaciones de una variante, validadas contra el
+    /// esquema de su categoría
+    pub async fn reemplazar(
+        pool: &PgPool,
+        id_producto_detalle: i32,
+        valores: &[ValorEspecificacionRequest],
+    ) -> Result<Vec<EspecificacionResponse>, EspecificacionError> {
+        let mut tx = pool.begin().await?;
+        let id_categoria: i32 = sqlx::query_scalar(
+            r#"
+            SELECT p.id_categoria
+            FROM producto_detalle pd
+            INNER JOIN producto p ON pd.id_producto = p.id_producto
+            WHERE pd.id_producto_detalle = $1
+            "#,
+        )
+        .bind(id_producto_detalle)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| EspecificacionError::NoEncontrado("Producto no encontrado".to_string()))?;
+
+        let validados = Self::validar(&mut *tx, id_categoria, valores).await?;
+        Self::guardar(&mut tx, id_producto_detalle, &validados).await?;
+        tx.commit().await?;
+
+        Ok(Self::especificaciones(pool, id_producto_detalle).await?)
+    }
+
+    /// Validar los valores de una variante contra el esquema de su categoría: atributos
+    /// conocidos, sin repetir, del tipo correcto y con todos los obligatorios. Los
+    /// valores vacíos de atributos opcionales se descartan.
+    pub async fn validar(
+        db: impl PgExecutor<'_>,
+        id_categoria: i32,
+        valores: &[ValorEspecificacionRequest],
+    ) -> Result<Vec<ValorValidado>, EspecificacionError> {
+        let esquema = Self::listar_atributos(db, id_categoria).await?;
+
+        let mut errores = Vec::new();
+        let mut vistos = HashSet::new();
+        let mut con_error = HashSet::new();
+        let mut validados = Vec::new();
+
+        for valor in valores {
+            let nombre = valor.atributo.trim().to_lowercase();
+            let Some(atributo) = esquema.iter().find(|a| a.nombre == nombre) else {
+                errores.push(format!("El atributo '{}' no existe en la categoría", valor.atributo.trim()));
+                continue;
+            };
+            if !vistos.insert(atributo.id_atributo) {
+                errores.push(format!("El atributo '{}' está repetido", atributo.nombre));
+                continue;
+            }
+            if Self::vacio(&valor.valor) {
+                continue;
+            }
+
+            match Self::normalizar_valor(atributo, &valor.valor) {
+                Ok(normalizado) => validados.push(ValorValidado {
+                    id_atributo: atributo.id_atributo,
+                    nombre: atributo.nombre.clone(),
+                    valor: normalizado,
+                }),
+                Err(mensaje) => {
+                    con_error.insert(atributo.id_atributo);
+                    errores.push(mensaje);
+                }
+            }
+        }
+
+        for atributo in esquema.iter().filter(|a| a.obligatorio && !con_error.contains(&a.id_atributo)) {
+            if !validados.iter().any(|v| v.id_atributo == atributo.id_atributo) {
+                errores.push(format!("Falta el atributo obligatorio '{}'", atributo.nombre));
+            }
+        }
+
+        if !errores.is_empty() {
+            return Err(EspecificacionError::Invalida(errores.join("; ")));
+        }
+
+        Ok(validados)
+    }
+
+    /// Guardar los valores validados de una variante (reemplaza los anteriores). El
+    /// esquema de la categoría se bloquea `FOR SHARE` y los valores se comprueban otra
+    /// vez contra él, así un cambio de atributo concurrente espera o se rechaza aquí.
+    pub async fn guardar(
+        conn: &mut PgConnection,
+        id_producto_detalle: i32,
+        valores: &[ValorValidado],
+    ) -> Result<(), EspecificacionError> {
+        let esquema = sqlx::query_as::<_, AtributoEspecificacion>(&format!(
+            r#"
+            SELECT {}
+            FROM atributo_especificacion
+            WHERE id_categoria = (
+                SELECT p.id_categoria
+                FROM producto_detalle pd
+                INNER JOIN producto p ON pd.id_producto = p.id_producto
+                WHERE pd.id_producto_detalle = $1
+            )
+            ORDER BY id_atributo
+            FOR SHARE
+            "#,
+            COLUMNAS_ATRIBUTO
+        ))
+        .bind(id_producto_detalle)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut errores = Vec::new();
+        for valor in valores {
+            match esquema.iter().find(|a| a.id_atributo == valor.id_atributo) {
+                Some(atributo) => {
+                    if Self::normalizar_valor(atributo, &Value::String(valor.valor.clone())).as_ref() != Ok(&valor.valor) {
+                        errores.push(format!("El atributo '{}' cambió; el valor ya no es válido", atributo.nombre));
+                    }
+                }
+                None => errores.push(format!("El atributo '{}' ya no existe en la categoría", valor.nombre)),
+            }
+        }
+        for atributo in esquema.iter().filter(|a| a.obligatorio) {
+            if !valores.iter().any(|v| v.id_atributo == atributo.id_atributo) {
+                errores.push(format!("Falta el atributo obligatorio '{}'", atributo.nombre));
+            }
+        }
+        if !errores.is_empty() {
+            return Err(EspecificacionError::Conflicto(errores.join("; ")));
+        }
+
+        sqlx::query("DELETE FROM especificacion_producto WHERE id_producto_detalle = $1")
+            .bind(id_producto_detalle)
+            .execute(&mut *conn)
+            .await?;
+
+        // Unidad y orden se copian del esquema bloqueado
+        for valor in valores {
+            let atributo = esquema.iter().find(|a| a.id_atributo == valor.id_atributo);
+            sqlx::query(
+                r#"
+                INSERT INTO especificacion_producto (
+                    id_producto_detalle, id_atributo, nombre_atributo, valor_atributo, unidad_medida, orden
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(id_producto_detalle)
+            .bind(valor.id_atributo)
+            .bind(&valor.nombre)
+            .bind(&valor.valor)
+            .bind(atributo.and_then(|a| a.unidad_medida.clone()))
+            .bind(atributo.and_then(|a| a.orden))
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // ==================== COMPARACIÓN ====================
+
+    /// Comparar variantes activas (`ids` separados por comas, en ese orden). Las
+    /// especificaciones comparables se alinean por atributo; entre categorías distintas
+    /// los atributos que no comparten quedan sin valor en los demás productos.
+    pub async fn comparar(pool: &PgPool, ids: &str) -> Result<Comparacion, EspecificacionError> {
+        let mut solicitados: Vec<i32> = Vec::new();
+        for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let id: i32 = id
+                .parse()
+                .map_err(|_| EspecificacionError::Invalida(format!("Id de producto inválido: '{}'", id)))?;
+            if !solicitados.contains(&id) {
+                solicitados.push(id);
+            }
+        }
+
+        let maximo = ConfigService::entero("compare_max_products")
+            .filter(|maximo| *maximo >= 2)
+            .unwrap_or(MAXIMO_COMPARAR) as usize;
+        if solicitados.len() < 2 {
+            return Err(EspecificacionError::Invalida(
+                "Indica al menos 2 productos distintos para comparar (ids=1,2)".to_string(),
+            ));
+        }
+        if solicitados.len() > maximo {
+            return Err(EspecificacionError::Invalida(format!(
+                "Se pueden comparar hasta {} productos a la vez",
+                maximo
+            )));
+        }
+
+        let encontrados = sqlx::query_as::<_, ProductoComparado>(&format!(
+            r#"
+            SELECT pd.id_producto_detalle, pd.nombre, pd.slug, m.nombre AS marca,
+                   p.id_categoria, c.nombre AS categoria, pd.imagen_principal,
+                   CAST(pd.precio_venta AS FLOAT8) AS precio_venta,
+                   CAST(pd.precio_base AS FLOAT8) AS precio_base,
+                   COALESCE(i.cantidad_disponible, 0) AS stock_disponible,
+                   CAST(p.valoracion_promedio AS FLOAT8) AS valoracion_promedio,
+                   p.total_valoraciones, pd.garantia_meses
+            {}
+            WHERE pd.id_producto_detalle = ANY($1) AND pd.estado = 'activo' AND p.estado = 'activo'
+            "#,
+            FROM_PRODUCTOS
+        ))
+        .bind(&solicitados)
+        .fetch_all(pool)
+        .await?;
+
+        let faltantes: Vec<i32> = solicitados
+            .iter()
+            .filter(|id| !encontrados.iter().any(|p| p.id_producto_detalle == **id))
+            .copied()
+            .collect();
+        if !faltantes.is_empty() {
+            return Err(EspecificacionError::NoEncontrado(format!("Productos no encontrados: {:?}", faltantes)));
+        }
+
+        let productos: Vec<ProductoComparado> = solicitados
+            .iter()
+            .filter_map(|id| encontrados.iter().find(|p| p.id_producto_detalle == *id).cloned())
+            .collect();
+
+        let valores: Vec<(i32, String, String, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT e.id_producto_detalle, e.nombre_atributo, e.valor_atributo,
+                   COALESCE(a.etiqueta, e.nombre_atributo), COALESCE(a.unidad_medida, e.unidad_medida)
+            FROM especificacion_producto e
+            LEFT JOIN atributo_especificacion a ON e.id_atributo = a.id_atributo
+            WHERE e.id_producto_detalle = ANY($1) AND COALESCE(a.comparable, TRUE)
+            ORDER BY COALESCE(a.orden, e.orden, 0), e.nombre_atributo
+            "#,
+        )
+        .bind(&solicitados)
+        .fetch_all(pool)
+        .await?;
+
+        // Una fila por atributo, en el orden del esquema
+        let mut filas: Vec<FilaEnArmado> = Vec::new();
+        for (id, atributo, valor, etiqueta, unidad) in valores {
+            let Some(posicion) = productos.iter().position(|p| p.id_producto_detalle == id) else {
+                continue;
+            };
+            let indice = match filas.iter().position(|(nombre, ..)| *nombre == atributo) {
+                Some(indice) => indice,
+                None => {
+                    filas.push((atributo, etiqueta, unidad, vec![None; productos.len()]));
+                    filas.len() - 1
+                }
+            };
+            filas[indice].3[posicion] = Some(valor);
+        }
+
+        let mut especificaciones: Vec<FilaComparacion> = filas
+            .into_iter()
+            .map(|(atributo, etiqueta, unidad, valores)| Self::fila(&atributo, &etiqueta, unidad.as_deref(), valores))
+            .collect();
+        especificaciones.sort_by_key(|fila| !fila.comun);
+
+        let generales = vec![
+            Self::fila(
+                "precio_venta",
+                "Precio",
+                Some("S/"),
+                productos.iter().map(|p| Some(format!("{:.2}", p.precio_venta))).collect(),
+            ),
+            Self::fila(
+                "stock_disponible",
+                "Stock",
+                None,
+                productos.iter().map(|p| Some(p.stock_disponible.to_string())).collect(),
+            ),
+            Self::fila(
+                "valoracion_promedio",
+                "Valoración",
+                None,
+                productos.iter().map(|p| p.valoracion_promedio.map(|v| format!("{:.1}", v))).collect(),
+            ),
+            Self::fila(
+                "garantia_meses",
+                "Garantía",
+                Some("meses"),
+                productos.iter().map(|p| p.garantia_meses.map(|g| g.to_string())).collect(),
+            ),
+        ];
+
+        let misma_categoria = productos.iter().all(|p| p.id_categoria == productos[0].id_categoria);
+        let advertencia = (!misma_categoria).then(|| {
+            let mut categorias: Vec<&str> = productos.iter().map(|p| p.categoria.as_str()).collect();
+            categorias.sort_unstable();
+            categorias.dedup();
+            format!(
+                "Los productos son de categorías distintas ({}): solo se alinean las especificaciones que comparten",
+                categorias.join(", ")
+            )
+        });
+
+        Ok(Comparacion {
+            productos,
+            generales,
+            especificaciones,
+            misma_categoria,
+            advertencia,
+        })
+    }
+
+    fn fila(atributo: &str, etiqueta: &str, unidad_medida: Option<&str>, valores: Vec<Option<String>>) -> FilaComparacion {
+        let comun = valores.iter().all(Option::is_some);
+        let diferente = valores.iter().any(|valor| *valor != valores[0]);
+
+        FilaComparacion {
+            atributo: atributo.to_string(),
+            etiqueta: etiqueta.to_string(),
+            unidad_medida: unidad_medida.map(str::to_string),
+            valores,
+            diferente,
+            comun,
+        }
+    }
+
+    // ==================== VALIDACIONES ====================
+
+    // Forma canónica del valor según el tipo del atributo
+    fn normalizar_valor(atributo: &AtributoEspecificacion, valor: &Value) -> Result<String, String> {
+        let invalido = |detalle: &str| format!("Valor inválido para '{}': {}", atributo.nombre, detalle);
+
+        let texto = match valor {
+            Value::String(s) => s.trim().to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => return Err(invalido("debe ser texto, número o booleano")),
+        };
+
+        match atributo.tipo_dato.as_str() {
+            "numero" => {
+                let numero: f64 = texto
+                    .replace(',', ".")
+                    .parse()
+                    .ok()
+                    .filter(|n: &f64| n.is_finite())
+                    .ok_or_else(|| invalido("se esperaba un número"))?;
+                Ok(if numero.fract() == 0.0 && numero.abs() < 1e15 {
+                    format!("{}", numero as i64)
+                } else {
+                    numero.to_string()
+                })
+            }
+            "booleano" => match texto.to_lowercase().as_str() {
+                "true" | "si" | "sí" | "1" => Ok("true".to_string()),
+                "false" | "no" | "0" => Ok("false".to_string()),
+                _ => Err(invalido("se esperaba sí o no")),
+            },
+            "opcion" => atributo
+                .valores_permitidos
+                .iter()
+                .flatten()
+                .find(|opcion| opcion.eq_ignore_ascii_case(&texto))
+                .cloned()
+                .ok_or_else(|| {
+                    invalido(&format!(
+                        "debe ser uno de: {}",
+                        atributo.valores_permitidos.clone().unwrap_or_default().join(", ")
+                    ))
+                }),
+            _ => {
+                if texto.chars().count() > MAXIMO_TEXTO {
+                    return Err(invalido(&format!("máximo {} caracteres", MAXIMO_TEXTO)));
+                }
+                Ok(texto)
+            }
+        }
+    }
+
+    fn vacio(valor: &Value) -> bool {
+        match valor {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    // Clave del atributo: minúsculas, guiones bajos en lugar de espacios, solo [a-z0-9_]
+    fn normalizar_nombre(nombre: &str) -> Result<String, EspecificacionError> {
+        let nombre = nombre.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("_");
+        let valido = !nombre.is_empty()
+            && nombre.len() <= 100
+            && nombre.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valido {
+            return Err(EspecificacionError::Invalida(
+                "El nombre del atributo solo puede tener letras sin tildes, números y guiones bajos".to_string(),
+            ));
+        }
+        Ok(nombre)
+    }
+
+    fn etiqueta(etiqueta: Option<&str>, nombre: &str) -> Result<String, EspecificacionError> {
+        let etiqueta = etiqueta.map(str::trim).filter(|e| !e.is_empty()).unwrap_or(nombre);
+        if etiqueta.chars().count() > 100 {
+            return Err(EspecificacionError::Invalida("La etiqueta admite máximo 100 caracteres".to_string()));
+        }
+        Ok(etiqueta.to_string())
+    }
+
+    fn tipo_dato(tipo: &str) -> Result<String, EspecificacionError> {
+        let tipo = tipo.trim().to_lowercase();
+        if !TIPOS_DATO_ATRIBUTO.contains(&tipo.as_str()) {
+            return Err(EspecificacionError::Invalida(format!(
+                "Tipo de dato inválido. Valores: {}",
+                TIPOS_DATO_ATRIBUTO.join(", ")
+            )));
+        }
+        Ok(tipo)
+    }
+
+    // Opciones sin repetir; solo se guardan para el tipo `opcion`, que las necesita
+    fn valores_permitidos(tipo_dato: &str, valores: Option<Vec<String>>) -> Result<Option<Vec<String>>, EspecificacionError> {
+        if tipo_dato != "opcion" {
+            return Ok(None);
+        }
+
+        let mut opciones: Vec<String> = Vec::new();
+        for valor in valores.unwrap_or_default() {
+            let valor = valor.trim();
+            if !valor.is_empty() && !opciones.iter().any(|o| o.eq_ignore_ascii_case(valor)) {
+                opciones.push(valor.to_string());
+            }
+        }
+
+        if opciones.is_empty() {
+            return Err(EspecificacionError::Invalida(
+                "Un atributo de tipo opcion necesita valores_permitidos".to_string(),
+            ));
+        }
+        Ok(Some(opciones))
+    }
+
+    fn texto_opcional(texto: Option<String>) -> Option<String> {
+        texto.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::services::ConfigService;
+
+/// Clave json con las reglas de despliegue de cada funcionalidad
+const CLAVE_REGLAS: &str = "feature_flag_rules";
+
+/// Rol con el que se evalúan las solicitudes sin sesión
+pub const ROL_INVITADO: &str = "invitado";
+
+/// Funcionalidades que se pueden encender y apagar desde `configuracion_sistema`
+/// (categoría `funcionalidades`, claves booleanas)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Funcionalidad {
+    Valoraciones,
+    ListaDeseos,
+    Cupones,
+    CheckoutInvitado,
+}
+
+impl Funcionalidad {
+    pub const TODAS: [Funcionalidad; 4] = [
+        Funcionalidad::Valoraciones,
+        Funcionalidad::ListaDeseos,
+        Funcionalidad::Cupones,
+        Funcionalidad::CheckoutInvitado,
+    ];
+
+    pub fn clave(&self) -> &'static str {
+        match self {
+            Funcionalidad::Valoraciones => "enable_reviews",
+            Funcionalidad::ListaDeseos => "enable_wishlist",
+            Funcionalidad::Cupones => "enable_coupons",
+            Funcionalidad::CheckoutInvitado => "allow_guest_checkout",
+        }
+    }
+
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            Funcionalidad::Valoraciones => "valoraciones",
+            Funcionalidad::ListaDeseos => "lista de deseos",
+            Funcionalidad::Cupones => "cupones",
+            Funcionalidad::CheckoutInvitado => "compra sin registro",
+        }
+    }
+}
+
+/// Regla de despliegue de una funcionalidad encendida. Sin regla, aplica a todos.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReglaFuncionalidad {
+    /// Porcentaje de usuarios (0-100) que la ven; el reparto es estable por usuario
+    #[serde(default = "porcentaje_completo")]
+    pub porcentaje: u8,
+    /// Roles a los que se limita (`cliente`, `administrador`, `super_admin`, `invitado`); vacío = todos
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+fn porcentaje_completo() -> u8 {
+    100
+}
+
+/// Quién hace la solicitud. Sin sesión el reparto por porcentaje usa la IP.
+#[derive(Debug, Clone)]
+pub struct SujetoFuncionalidad {
+    pub id_usuario: Option<i32>,
+    pub rol: Option<String>,
+    pub ip: String,
+}
+
+impl SujetoFuncionalidad {
+    fn rol(&self) -> &str {
+        self.rol.as_deref().unwrap_or(ROL_INVITADO)
+    }
+
+    fn identificador(&self) -> String {
+        match self.id_usuario {
+            Some(id) => format!("usuario:{}", id),
+            None => format!("ip:{}", self.ip),
+        }
+    }
+}
+
+/// Error de una funcionalidad apagada para el sujeto
+#[derive(Debug, Serialize)]
+pub struct FuncionalidadDeshabilitada {
+    pub funcionalidad: &'static str,
+    pub message: String,
+}
+
+pub struct FeatureFlagService;
+
+impl FeatureFlagService {
+    /// La funcionalidad está encendida y la regla de despliegue incluye al sujeto.
+    /// Si la clave no existe se considera encendida (comportamiento anterior).
+    pub fn activa(funcionalidad: Funcionalidad, sujeto: &SujetoFuncionalidad) -> bool {
+        if !ConfigService::booleano(funcionalidad.clave()).unwrap_or(true) {
+            return false;
+        }
+
+        let Some(regla) = Self::reglas().remove(funcionalidad.clave()) else {
+            return true;
+        };
+
+        if !regla.roles.is_empty() && !regla.roles.iter().any(|rol| rol == sujeto.rol()) {
+            return false;
+        }
+
+        regla.porcentaje >= 100 || Self::cubeta(funcionalidad.clave(), &sujeto.identificador()) < regla.porcentaje
+    }
+
+    pub fn verificar(
+        funcionalidad: Funcionalidad,
+        sujeto: &SujetoFuncionalidad,
+    ) -> Result<(), FuncionalidadDeshabilitada> {
+        if Self::activa(funcionalidad, sujeto) {
+            return Ok(());
+        }
+
+        Err(FuncionalidadDeshabilitada {
+            funcionalidad: funcionalidad.clave(),
+            message: format!("La funcionalidad de {} está deshabilitada", funcionalidad.nombre()),
+        })
+    }
+
+    /// Estado de todas las funcionalidades para el sujeto (lo que necesita la tienda)
+    pub fn evaluar(sujeto: &SujetoFuncionalidad) -> BTreeMap<&'static str, bool> {
+        Funcionalidad::TODAS
+            .iter()
+            .map(|funcionalidad| (funcionalidad.clave(), Self::activa(*funcionalidad, sujeto)))
+            .collect()
+    }
+
+    fn reglas() -> HashMap<String, ReglaFuncionalidad> {
+        ConfigService::json(CLAVE_REGLAS).unwrap_or_default()
+    }
+
+    // Cubeta 0-99 estable para cada par funcionalidad/sujeto (FNV-1a), para que
+    // subir el porcentaje no saque a nadie y cada funcionalidad reparta distinto
+    fn cubeta(clave: &str, identificador: &str) -> u8 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in clave.bytes().chain([b':']).chain(identificador.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        (hash % 100) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cubeta_es_estable_y_menor_a_cien() {
+        for id in 0..1000 {
+            let identificador = format!("usuario:{}", id);
+            let cubeta = FeatureFlagService::cubeta("feature_checkout", &identificador);
+            assert!(cubeta < 100);
+            assert_eq!(cubeta, FeatureFlagService::cubeta("feature_checkout", &identificador));
+        }
+    }
+
+    #[test]
+    fn cubeta_reparte_de_forma_pareja() {
+        let incluidos = (0..10_000)
+            .filter(|id| FeatureFlagService::cubeta("feature_checkout", &format!("usuario:{}", id)) < 30)
+            .count();
+        assert!((2_700..=3_300).contains(&incluidos), "incluidos: {}", incluidos);
+    }
+
+    #[test]
+    fn cubeta_depende_de_la_funcionalidad() {
+        let distintas = (0..100)
+            .map(|id| format!("usuario:{}", id))
+            .filter(|id| FeatureFlagService::cubeta("feature_a", id) != FeatureFlagService::cubeta("feature_b", id))
+            .count();
+        assert!(distintas > 50, "distintas: {}", distintas);
+    }
+
+    #[test]
+    fn sujeto_sin_sesion_se_identifica_por_ip_como_invitado() {
+        let sujeto = SujetoFuncionalidad {
+            id_usuario: None,
+            rol: None,
+            ip: "203.0.113.7".to_string(),
+        };
+        assert_eq!(sujeto.identificador(), "ip:203.0.113.7");
+        assert_eq!(sujeto.rol(), ROL_INVITADO);
+
+        let sujeto = SujetoFuncionalidad {
+            id_usuario: Some(42),
+            rol: Some("cliente".to_string()),
+            ..sujeto
+        };
+        assert_eq!(sujeto.identificador(), "usuario:42");
+        assert_eq!(sujeto.rol(), "cliente");
+    }
+
+    #[test]
+    fn regla_sin_campos_aplica_a_todos() {
+        let regla: ReglaFuncionalidad = serde_json::from_str("{}").unwrap();
+        assert_eq!(regla.porcentaje, 100);
+        assert!(regla.roles.is_empty());
+    }
+}
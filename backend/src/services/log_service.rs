@@ -0,0 +1,690 @@
+use axum::body::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::models::log_auditoria::{
+    ArchivoLogAuditoria, CrearLogRequest, FiltrarLogsQuery, LogAuditoria, NivelLog, ProblemaCadena,
+    RetencionLogsResponse, VerificacionCadenaResponse,
+};
+use crate::models::SolicitudAuditada;
+use crate::services::ConfigService;
+use crate::utils::rutas::ruta_confinada;
+
+const DIAS_RETENCION_DEFECTO: i64 = 365;
+/// Mínimo de audit_retention_days distinto de 0: el archivado diario no puede vaciar el log
+const DIAS_RETENCION_MINIMO: i64 = 90;
+const DIRECTORIO_ARCHIVO_DEFECTO: &str = "archivos/auditoria";
+/// Variable de entorno con el directorio dentro del que debe estar audit_archive_dir
+/// (por defecto, el directorio de trabajo del servidor)
+const VARIABLE_RAIZ_ARCHIVO: &str = "AUDIT_ARCHIVE_ROOT";
+const INTERVALO_RETENCION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Entradas por archivo y transacción al aplicar la retención
+const TAMANO_LOTE_RETENCION: i64 = 5000;
+
+/// Máximo de problemas que se devuelven al verificar la cadena
+const MAX_PROBLEMAS: i64 = 100;
+
+const NIVELES_VALIDOS: &[&str] = &["info", "warning", "error", "success", "security"];
+
+/// Columnas de log_auditoria que se leen como `LogAuditoria`
+pub const COLUMNAS_LOG: &str = "id_log, nivel::TEXT as nivel, accion, detalles, modulo,
+    id_usuario, email_usuario, ip_cliente, user_agent, origen, metodo_http, ruta,
+    entidad, id_entidad, cambios, codigo_respuesta, fecha_creacion, hash::TEXT as hash";
+
+/// Columnas de la exportación CSV, en orden
+const COLUMNAS_CSV: &[&str] = &[
+    "id_log", "fecha_creacion", "nivel", "origen", "modulo", "accion", "detalles", "id_usuario",
+    "email_usuario", "ip_cliente", "user_agent", "metodo_http", "ruta", "entidad", "id_entidad",
+    "codigo_respuesta", "cambios", "hash",
+];
+
+/// Formato de exportación de logs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatoExportacion {
+    Csv,
+    Jsonl,
+}
+
+pub struct LogService;
+
+impl LogService {
+    /// Registrar una entrada en log_auditoria desde el propio backend.
+    /// Los errores se reportan por consola: un fallo de auditoría no debe
+    /// interrumpir la operación que se está auditando.
+    pub async fn registrar(pool: &PgPool, id_usuario: Option<i32>, log: CrearLogRequest) {
+        let result = sqlx::query(
+            "INSERT INTO log_auditoria (nivel, accion, detalles, modulo, id_usuario, email_usuario, ip_cliente, user_agent)
+             VALUES ($1::nivel_log, $2, $3, $4, $5, $6, $7, $8)"
+        )
+        .bind(&log.nivel)
+        .bind(&log.accion)
+        .bind(&log.detalles)
+        .bind(&log.modulo)
+        .bind(id_usuario)
+        .bind(&log.email_usuario)
+        .bind(&log.ip_cliente)
+        .bind(&log.user_agent)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("⚠️  Error al registrar log de auditoría '{}': {}", log.accion, e);
+        }
+    }
+
+    /// Registrar una solicitud auditada por el middleware de auditoría, con la
+    /// ruta, la entidad afectada y el diff de la fila antes y después
+    pub async fn registrar_solicitud(
+        pool: &PgPool,
+        id_usuario: Option<i32>,
+        log: CrearLogRequest,
+        solicitud: SolicitudAuditada,
+    ) {
+        let result = sqlx::query(
+            "INSERT INTO log_auditoria (
+                nivel, accion, detalles, modulo, id_usuario, email_usuario, ip_cliente, user_agent,
+                metodo_http, ruta, entidad, id_entidad, cambios, codigo_respuesta
+             )
+             VALUES ($1::nivel_log, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)"
+        )
+        .bind(&log.nivel)
+        .bind(&log.accion)
+        .bind(&log.detalles)
+        .bind(&log.modulo)
+        .bind(id_usuario)
+        .bind(&log.email_usuario)
+        .bind(&log.ip_cliente)
+        .bind(&log.user_agent)
+        .bind(&solicitud.metodo_http)
+        .bind(&solicitud.ruta)
+        .bind(&solicitud.entidad)
+        .bind(&solicitud.id_entidad)
+        .bind(&solicitud.cambios)
+        .bind(solicitud.codigo_respuesta)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("⚠️  Error al registrar auditoría de '{}': {}", log.accion, e);
+        }
+    }
+
+    /// Atajo para construir el request de log con los campos habituales
+    pub fn nuevo(
+        nivel: NivelLog,
+        accion: &str,
+        modulo: &str,
+        detalles: Option<String>,
+        email_usuario: Option<String>,
+        ip_cliente: Option<String>,
+    ) -> CrearLogRequest {
+        CrearLogRequest {
+            nivel: nivel.to_string(),
+            accion: accion.to_string(),
+            detalles,
+            modulo: modulo.to_string(),
+            email_usuario,
+            ip_cliente,
+            user_agent: None,
+        }
+    }
+
+    // ==================== BÚSQUEDA Y EXPORTACIÓN ====================
+
+    /// SELECT sobre log_auditoria con los filtros de búsqueda aplicados (sin ORDER BY)
+    pub fn consulta_filtrada(filtros: &FiltrarLogsQuery) -> Result<QueryBuilder<'static, Postgres>, String> {
+        let mut qb = QueryBuilder::new(format!("SELECT {} FROM log_auditoria WHERE 1=1", COLUMNAS_LOG));
+
+        if let Some(nivel) = &filtros.nivel {
+            if !NIVELES_VALIDOS.contains(&nivel.as_str()) {
+                return Err(format!("Nivel inválido. Debe ser uno de: {}", NIVELES_VALIDOS.join(", ")));
+            }
+            qb.push(" AND nivel = ").push_bind(nivel.clone()).push("::nivel_log");
+        }
+        if let Some(origen) = &filtros.origen {
+            if origen != "servidor" && origen != "cliente" {
+                return Err("Origen inválido. Debe ser 'servidor' o 'cliente'".to_string());
+            }
+            qb.push(" AND origen = ").push_bind(origen.clone());
+        }
+        if let Some(modulo) = &filtros.modulo {
+            qb.push(" AND modulo = ").push_bind(modulo.clone());
+        }
+        if let Some(fecha_inicio) = &filtros.fecha_inicio {
+            qb.push(" AND fecha_creacion >= ").push_bind(fecha_inicio.clone()).push("::timestamp");
+        }
+        if let Some(fecha_fin) = &filtros.fecha_fin {
+            qb.push(" AND fecha_creacion <= ").push_bind(fecha_fin.clone()).push("::timestamp");
+        }
+        if let Some(q) = filtros.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            // Debe coincidir con la expresión del índice idx_log_busqueda
+            qb.push(" AND to_tsvector('spanish', accion || ' ' || COALESCE(detalles, '')) @@ websearch_to_tsquery('spanish', ")
+                .push_bind(q.to_string())
+                .push(")");
+        }
+        if let Some(id_usuario) = filtros.id_usuario {
+            qb.push(" AND id_usuario = ").push_bind(id_usuario);
+        }
+        if let Some(email) = filtros.email.as_deref().map(str::trim).filter(|e| !e.is_empty()) {
+            qb.push(" AND email_usuario ILIKE ").push_bind(format!("%{}%", Self::escapar_like(email)));
+        }
+        if let Some(ip) = filtros.ip.as_deref().map(str::trim).filter(|ip| !ip.is_empty()) {
+            qb.push(" AND ip_cliente LIKE ").push_bind(format!("{}%", Self::escapar_like(ip)));
+        }
+        if let Some(entidad) = &filtros.entidad {
+            qb.push(" AND entidad = ").push_bind(entidad.clone());
+        }
+        if let Some(id_entidad) = &filtros.id_entidad {
+            qb.push(" AND id_entidad = ").push_bind(id_entidad.clone());
+        }
+
+        Ok(qb)
+    }
+
+    /// Página de logs, de la más reciente a la más antigua. Con `cursor` se continúa
+    /// después de esa entrada; `offset` se mantiene para los clientes existentes.
+    /// Devuelve también el cursor de la página siguiente, si la hay.
+    pub async fn buscar(pool: &PgPool, filtros: &FiltrarLogsQuery) -> Result<(Vec<LogAuditoria>, Option<i32>), String> {
+        let limit = filtros.limit.unwrap_or(100).clamp(1, 500);
+
+        let mut qb = Self::consulta_filtrada(filtros)?;
+        if let Some(cursor) = filtros.cursor {
+            qb.push(" AND id_log < ").push_bind(cursor);
+        }
+        qb.push(" ORDER BY id_log DESC LIMIT ").push_bind(limit);
+        if filtros.cursor.is_none() {
+            qb.push(" OFFSET ").push_bind(filtros.offset.unwrap_or(0).max(0));
+        }
+
+        let logs = qb
+            .build_query_as::<LogAuditoria>()
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Error al listar logs: {}", e))?;
+
+        let siguiente = (logs.len() == limit as usize)
+            .then(|| logs.last().map(|log| log.id_log))
+            .flatten();
+
+        Ok((logs, siguiente))
+    }
+
+    /// Exportar los logs filtrados como flujo de bytes (CSV o JSON lines), leyendo la
+    /// BD por partes: el cuerpo se envía a medida que llegan las filas
+    pub fn exportar(
+        pool: PgPool,
+        filtros: &FiltrarLogsQuery,
+        formato: FormatoExportacion,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<Bytes, std::io::Error>>, String> {
+        let mut qb = Self::consulta_filtrada(filtros)?;
+        qb.push(" ORDER BY id_log");
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+
+        tokio::spawn(async move {
+            if formato == FormatoExportacion::Csv {
+                let encabezado = format!("{}\r\n", COLUMNAS_CSV.join(","));
+                if tx.send(Ok(Bytes::from(encabezado))).await.is_err() {
+                    return;
+                }
+            }
+
+            let mut filas = qb.build_query_as::<LogAuditoria>().fetch(&pool);
+            loop {
+                let linea = match filas.try_next().await {
+                    Ok(Some(log)) => match formato {
+                        FormatoExportacion::Csv => Self::linea_csv(&log),
+                        FormatoExportacion::Jsonl => {
+                            serde_json::to_string(&log).map(|json| json + "\n").unwrap_or_default()
+                        }
+                    },
+                    Ok(None) => break,
+                    Err(e) => {
+                        // El encabezado ya se envió: cortar el flujo para que el cliente vea el error
+                        let _ = tx.send(Err(std::io::Error::other(format!("Error al exportar logs: {}", e)))).await;
+                        break;
+                    }
+                };
+
+                // El cliente cerró la conexión
+                if tx.send(Ok(Bytes::from(linea))).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    fn linea_csv(log: &LogAuditoria) -> String {
+        let opcional = |v: &Option<String>| v.clone().unwrap_or_default();
+        let campos = [
+            log.id_log.to_string(),
+            log.fecha_creacion.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+            log.nivel.clone(),
+            log.origen.clone(),
+            log.modulo.clone(),
+            log.accion.clone(),
+            opcional(&log.detalles),
+            log.id_usuario.map(|id| id.to_string()).unwrap_or_default(),
+            opcional(&log.email_usuario),
+            opcional(&log.ip_cliente),
+            opcional(&log.user_agent),
+            opcional(&log.metodo_http),
+            opcional(&log.ruta),
+            opcional(&log.entidad),
+            opcional(&log.id_entidad),
+            log.codigo_respuesta.map(|c| c.to_string()).unwrap_or_default(),
+            log.cambios.as_ref().map(|c| c.to_string()).unwrap_or_default(),
+            log.hash.clone(),
+        ];
+
+        let mut linea = campos.iter().map(|c| Self::celda_csv(c)).collect::<Vec<_>>().join(",");
+        linea.push_str("\r\n");
+        linea
+    }
+
+    // Comillas según RFC 4180; las celdas que empiezan como fórmula se neutralizan
+    // para que una hoja de cálculo no las ejecute
+    fn celda_csv(valor: &str) -> String {
+        let valor = if valor.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+            format!("'{}", valor)
+        } else {
+            valor.to_string()
+        };
+
+        if valor.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", valor.replace('"', "\"\""))
+        } else {
+            valor
+        }
+    }
+
+    fn escapar_like(valor: &str) -> String {
+        valor.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    }
+
+    // ==================== CADENA DE HASHES ====================
+
+    /// Recalcular la cadena de log_auditoria: detecta entradas editadas (hash que no
+    /// coincide con su contenido) y huecos (hash_anterior que no enlaza con la entrada previa).
+    /// El hash cubre el digest guardado de los datos personales, así que las entradas
+    /// redactadas también se verifican; de ellas se comprueba además que la redacción
+    /// haya borrado todos los datos personales, que es lo único que el trigger permite.
+    pub async fn verificar_cadena(pool: &PgPool) -> Result<VerificacionCadenaResponse, String> {
+        let resumen = sqlx::query(
+            "SELECT COUNT(*) AS total,
+                    COUNT(*) FILTER (WHERE redactado) AS redactadas,
+                    MIN(id_log) AS primer_id,
+                    MAX(id_log) AS ultimo_id,
+                    (SELECT hash::TEXT FROM log_auditoria ORDER BY id_log DESC LIMIT 1) AS ultimo_hash
+             FROM log_auditoria"
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Error al verificar logs: {}", e))?;
+
+        let ancla = sqlx::query_scalar::<_, String>(
+            "SELECT hash_final::TEXT FROM archivo_log_auditoria ORDER BY hasta_id DESC LIMIT 1"
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Error al leer archivos de logs: {}", e))?;
+
+        let filas = sqlx::query(
+            "WITH cadena AS (
+                SELECT id_log, hash::TEXT AS hash, hash_anterior::TEXT AS hash_anterior, redactado,
+                       hash_log_auditoria(l)::TEXT AS calculado,
+                       CASE
+                           WHEN redactado THEN num_nonnulls(email_usuario, ip_cliente, user_agent, detalles, cambios) = 0
+                           ELSE hash_datos_personales_log(l) = hash_datos_personales
+                       END AS datos_personales_validos,
+                       LAG(hash::TEXT) OVER (ORDER BY id_log) AS previo,
+                       LAG(id_log) OVER (ORDER BY id_log) AS id_previo,
+                       ROW_NUMBER() OVER (ORDER BY id_log) AS n
+                FROM log_auditoria l
+             )
+             SELECT id_log, hash, hash_anterior, redactado, calculado, datos_personales_validos,
+                    previo, id_previo, n
+             FROM cadena
+             WHERE n = 1
+                OR calculado <> hash
+                OR NOT datos_personales_validos
+                OR hash_anterior IS DISTINCT FROM previo
+             ORDER BY id_log
+             LIMIT $1"
+        )
+        .bind(MAX_PROBLEMAS + 1)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Error al verificar logs: {}", e))?;
+
+        let mut problemas = Vec::new();
+        for fila in filas {
+            let id_log: i32 = fila.get("id_log");
+            let hash: String = fila.get("hash");
+            let hash_anterior: Option<String> = fila.get("hash_anterior");
+            let calculado: String = fila.get("calculado");
+            let n: i64 = fila.get("n");
+
+            if calculado != hash {
+                problemas.push(ProblemaCadena {
+                    id_log,
+                    tipo: "contenido_modificado".to_string(),
+                    detalle: format!("Hash almacenado {} no coincide con el contenido ({})", hash, calculado),
+                });
+            }
+
+            if !fila.get::<bool, _>("datos_personales_validos") {
+                problemas.push(ProblemaCadena {
+                    id_log,
+                    tipo: "datos_personales_modificados".to_string(),
+                    detalle: if fila.get::<bool, _>("redactado") {
+                        "La entrada está redactada pero conserva datos personales".to_string()
+                    } else {
+                        "Los datos personales no coinciden con su digest".to_string()
+                    },
+                });
+            }
+
+            if n == 1 {
+                if hash_anterior != ancla {
+                    problemas.push(ProblemaCadena {
+                        id_log,
+                        tipo: "ancla_invalida".to_string(),
+                        detalle: match &ancla {
+                            Some(_) => "La primera entrada no enlaza con el último archivo de logs".to_string(),
+                            None => "La primera entrada enlaza con entradas que no existen ni están archivadas".to_string(),
+                        },
+                    });
+                }
+            } else if hash_anterior != fila.get::<Option<String>, _>("previo") {
+                problemas.push(ProblemaCadena {
+                    id_log,
+                    tipo: "cadena_rota".to_string(),
+                    detalle: format!(
+                        "hash_anterior no coincide con la entrada previa #{}: faltan o se alteraron entradas",
+                        fila.get::<Option<i32>, _>("id_previo").unwrap_or_default()
+                    ),
+                });
+            }
+        }
+        problemas.truncate(MAX_PROBLEMAS as usize);
+
+        Ok(VerificacionCadenaResponse {
+            valida: problemas.is_empty(),
+            total_entradas: resumen.get("total"),
+            redactadas: resumen.get("redactadas"),
+            primer_id: resumen.get("primer_id"),
+            ultimo_id: resumen.get("ultimo_id"),
+            ultimo_hash: resumen.get("ultimo_hash"),
+            ancla,
+            problemas,
+        })
+    }
+
+    // ==================== RETENCIÓN ====================
+
+    pub async fn listar_archivos(pool: &PgPool) -> Result<Vec<ArchivoLogAuditoria>, String> {
+        sqlx::query_as::<_, ArchivoLogAuditoria>(
+            "SELECT id_archivo, nombre_archivo, desde_id, hasta_id, total_entradas,
+                    hash_final::TEXT AS hash_final, sha256_archivo::TEXT AS sha256_archivo,
+                    fecha_desde, fecha_hasta, creado_por, fecha_creacion
+             FROM archivo_log_auditoria
+             ORDER BY hasta_id DESC"
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Error al listar archivos de logs: {}", e))
+    }
+
+    /// Aplicar la política de retención (audit_retention_days): las entradas más antiguas
+    /// se escriben en archivos JSONL comprimidos en audit_archive_dir y luego se purgan.
+    /// Solo se archiva el tramo inicial de la cadena (hasta la primera entrada vigente),
+    /// y el hash de su última entrada queda como ancla de la cadena que sigue en BD.
+    /// Se archiva por lotes de `TAMANO_LOTE_RETENCION`, cada uno en su transacción, para
+    /// no retener en memoria ni bloquear las inserciones de log mientras dura el proceso.
+    pub async fn aplicar_retencion(
+        pool: &PgPool,
+        id_admin: Option<i32>,
+        email_admin: Option<String>,
+    ) -> Result<RetencionLogsResponse, String> {
+        let dias_retencion = Self::dias_retencion();
+        let mut respuesta = RetencionLogsResponse {
+            dias_retencion,
+            entradas_archivadas: 0,
+            archivos: Vec::new(),
+        };
+        if dias_retencion == 0 {
+            return Ok(respuesta);
+        }
+
+        let directorio = Self::directorio_archivo()?;
+        let corte = chrono::Utc::now().naive_utc() - chrono::Duration::days(dias_retencion);
+
+        while let Some(archivo) = Self::archivar_lote(pool, &directorio, corte, id_admin).await? {
+            Self::registrar(
+                pool,
+                id_admin,
+                Self::nuevo(
+                    NivelLog::Security,
+                    "Logs archivados",
+                    "Sistema",
+                    Some(format!(
+                        "{} entradas (#{} a #{}) con más de {} días archivadas en {} (sha256 {})",
+                        archivo.total_entradas,
+                        archivo.desde_id,
+                        archivo.hasta_id,
+                        dias_retencion,
+                        archivo.nombre_archivo,
+                        archivo.sha256_archivo
+                    )),
+                    email_admin.clone(),
+                    None,
+                ),
+            )
+            .await;
+
+            respuesta.entradas_archivadas += archivo.total_entradas as i64;
+            let ultimo_lote = (archivo.total_entradas as i64) < TAMANO_LOTE_RETENCION;
+            respuesta.archivos.push(archivo);
+            if ultimo_lote {
+                break;
+            }
+        }
+
+        Ok(respuesta)
+    }
+
+    // Archivar y purgar las primeras entradas vencidas (hasta TAMANO_LOTE_RETENCION)
+    // en una transacción. None si no quedan entradas vencidas al inicio de la cadena.
+    async fn archivar_lote(
+        pool: &PgPool,
+        directorio: &Path,
+        corte: chrono::NaiveDateTime,
+        id_admin: Option<i32>,
+    ) -> Result<Option<ArchivoLogAuditoria>, String> {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Error al iniciar la retención de logs: {}", e))?;
+
+        // Mismo lock que el trigger de inserción: la cadena no avanza mientras se archiva,
+        // y el permiso de purga solo vale dentro de esta transacción
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('log_auditoria')), set_config('kronos.purga_auditoria', 'on', TRUE)")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error al bloquear log_auditoria: {}", e))?;
+
+        // Tramo inicial de entradas vencidas: se detiene en la primera entrada vigente
+        let filas = sqlx::query(
+            "WITH limite AS (
+                SELECT MIN(id_log) AS primer_vigente
+                FROM log_auditoria
+                WHERE fecha_creacion IS NULL OR fecha_creacion >= $1
+             )
+             SELECT id_log, hash::TEXT AS hash, fecha_creacion, to_jsonb(l) AS entrada
+             FROM log_auditoria l, limite
+             WHERE primer_vigente IS NULL OR id_log < primer_vigente
+             ORDER BY id_log
+             LIMIT $2"
+        )
+        .bind(corte)
+        .bind(TAMANO_LOTE_RETENCION)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| format!("Error al leer logs vencidos: {}", e))?;
+
+        let (Some(primera), Some(ultima)) = (filas.first(), filas.last()) else {
+            return Ok(None);
+        };
+        let desde_id: i32 = primera.get("id_log");
+        let hasta_id: i32 = ultima.get("id_log");
+        let hash_final: String = ultima.get("hash");
+        let fechas = filas
+            .iter()
+            .filter_map(|fila| fila.get::<Option<chrono::NaiveDateTime>, _>("fecha_creacion"));
+        let fecha_desde = fechas.clone().min();
+        let fecha_hasta = fechas.max();
+
+        // Una entrada JSON por línea, comprimido con gzip
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        for fila in &filas {
+            let entrada: serde_json::Value = fila.get("entrada");
+            serde_json::to_writer(&mut gz, &entrada).map_err(|e| format!("Error al comprimir logs: {}", e))?;
+            gz.write_all(b"\n").map_err(|e| format!("Error al comprimir logs: {}", e))?;
+        }
+        let contenido = gz.finish().map_err(|e| format!("Error al comprimir logs: {}", e))?;
+        let sha256_archivo = hex::encode(Sha256::digest(&contenido));
+
+        let nombre_archivo = format!(
+            "log_auditoria_{:08}_{:08}_{}.jsonl.gz",
+            desde_id,
+            hasta_id,
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        );
+        let ruta = directorio.join(&nombre_archivo);
+
+        tokio::fs::create_dir_all(directorio)
+            .await
+            .map_err(|e| format!("No se pudo crear el directorio {}: {}", directorio.display(), e))?;
+        Self::escribir_archivo(&ruta, &contenido)
+            .await
+            .map_err(|e| format!("No se pudo escribir {}: {}", ruta.display(), e))?;
+
+        // Registrar el archivo y purgar; si algo falla, el archivo escrito se descarta
+        let resultado = async {
+            let archivo = sqlx::query_as::<_, ArchivoLogAuditoria>(
+                "INSERT INTO archivo_log_auditoria (
+                    nombre_archivo, desde_id, hasta_id, total_entradas, hash_final,
+                    sha256_archivo, fecha_desde, fecha_hasta, creado_por
+                 )
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 RETURNING id_archivo, nombre_archivo, desde_id, hasta_id, total_entradas,
+                           hash_final::TEXT AS hash_final, sha256_archivo::TEXT AS sha256_archivo,
+                           fecha_desde, fecha_hasta, creado_por, fecha_creacion"
+            )
+            .bind(&nombre_archivo)
+            .bind(desde_id)
+            .bind(hasta_id)
+            .bind(filas.len() as i32)
+            .bind(&hash_final)
+            .bind(&sha256_archivo)
+            .bind(fecha_desde)
+            .bind(fecha_hasta)
+            .bind(id_admin)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query("DELETE FROM log_auditoria WHERE id_log <= $1")
+                .bind(hasta_id)
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(archivo)
+        }
+        .await;
+
+        match resultado {
+            Ok(archivo) => Ok(Some(archivo)),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&ruta).await;
+                Err(format!("Error al purgar logs archivados: {}", e))
+            }
+        }
+    }
+
+    /// Tarea en segundo plano que aplica la política de retención una vez al día
+    pub fn iniciar_tarea_retencion(pool: PgPool) {
+        tokio::spawn(async move {
+            let mut intervalo = tokio::time::interval(INTERVALO_RETENCION);
+            loop {
+                intervalo.tick().await;
+                match Self::aplicar_retencion(&pool, None, None).await {
+                    Ok(RetencionLogsResponse { entradas_archivadas: 0, .. }) => {}
+                    Ok(resultado) => println!("🗄️  {} logs de auditoría archivados", resultado.entradas_archivadas),
+                    Err(e) => eprintln!("⚠️  {}", e),
+                }
+            }
+        });
+    }
+
+    /// Validar un valor de audit_archive_dir antes de guardarlo
+    pub fn validar_directorio_archivo(valor: &str) -> Result<(), String> {
+        ruta_confinada(&Self::raiz_archivo(), valor).map(|_| ())
+    }
+
+    /// Validar un valor de audit_retention_days antes de guardarlo: 0 o al menos el mínimo
+    pub fn validar_dias_retencion(valor: &str) -> Result<(), String> {
+        match valor.trim().parse::<i64>() {
+            Ok(0) => Ok(()),
+            Ok(dias) if dias >= DIAS_RETENCION_MINIMO => Ok(()),
+            _ => Err(format!(
+                "Debe ser 0 (sin límite) o al menos {} días",
+                DIAS_RETENCION_MINIMO
+            )),
+        }
+    }
+
+    // Config audit_retention_days (0 = sin límite). Se eleva al mínimo al usar:
+    // el valor pudo guardarse antes de esta regla o cambiar por SQL
+    fn dias_retencion() -> i64 {
+        match ConfigService::entero("audit_retention_days").unwrap_or(DIAS_RETENCION_DEFECTO) {
+            dias if dias <= 0 => 0,
+            dias => dias.max(DIAS_RETENCION_MINIMO),
+        }
+    }
+
+    // Config audit_archive_dir, dentro de AUDIT_ARCHIVE_ROOT. Se revalida al usar:
+    // el valor pudo guardarse antes de esta regla o cambiar por SQL
+    fn directorio_archivo() -> Result<PathBuf, String> {
+        let directorio = ConfigService::texto("audit_archive_dir")
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DIRECTORIO_ARCHIVO_DEFECTO.to_string());
+
+        ruta_confinada(&Self::raiz_archivo(), &directorio).map_err(|e| format!("audit_archive_dir inválido: {}", e))
+    }
+
+    fn raiz_archivo() -> PathBuf {
+        std::env::var(VARIABLE_RAIZ_ARCHIVO)
+            .ok()
+            .filter(|d| !d.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    // Escribir y sincronizar a disco antes de purgar las entradas de la BD
+    async fn escribir_archivo(ruta: &Path, contenido: &[u8]) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut archivo = tokio::fs::File::create_new(ruta).await?;
+        archivo.write_all(contenido).await?;
+        archivo.sync_all().await
+    }
+}
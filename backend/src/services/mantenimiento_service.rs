@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::services::ConfigService;
+
+const MENSAJE_POR_DEFECTO: &str = "La tienda está en mantenimiento. Vuelve a intentarlo en unos minutos.";
+
+/// Modo de mantenimiento configurado en `maintenance_mode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModoMantenimiento {
+    /// Tienda abierta
+    Off,
+    /// Se puede navegar, pero no modificar el carrito ni comprar
+    ReadOnly,
+    /// Solo responden las rutas de estado y autenticación
+    Closed,
+}
+
+impl ModoMantenimiento {
+    fn parse(valor: &str) -> Self {
+        match valor.trim() {
+            "read_only" => ModoMantenimiento::ReadOnly,
+            "closed" => ModoMantenimiento::Closed,
+            _ => ModoMantenimiento::Off,
+        }
+    }
+}
+
+/// Estado de mantenimiento que se publica en GET /api/config/estado
+#[derive(Debug, Clone, Serialize)]
+pub struct EstadoMantenimiento {
+    /// Modo vigente ahora mismo (fuera de la ventana programada es `off`)
+    pub modo: ModoMantenimiento,
+    /// Modo configurado, vigente o programado
+    pub modo_configurado: ModoMantenimiento,
+    pub mensaje: Option<String>,
+    pub inicio: Option<DateTime<Utc>>,
+    pub fin: Option<DateTime<Utc>>,
+    /// Hay un mantenimiento configurado que todavía no empieza
+    pub programado: bool,
+}
+
+impl EstadoMantenimiento {
+    /// Segundos hasta el fin de la ventana, para `Retry-After`
+    pub fn segundos_restantes(&self) -> Option<u64> {
+        self.fin.map(|fin| (fin - Utc::now()).num_seconds().max(1) as u64)
+    }
+}
+
+pub struct MantenimientoService;
+
+impl MantenimientoService {
+    /// Estado actual según la configuración. La ventana va de `maintenance_start`
+    /// a `maintenance_end` (RFC 3339); si una fecha falta, la ventana queda abierta por ese lado.
+    pub fn estado() -> EstadoMantenimiento {
+        let modo_configurado = ConfigService::texto("maintenance_mode")
+            .map(|valor| ModoMantenimiento::parse(&valor))
+            .unwrap_or(ModoMantenimiento::Off);
+        let inicio = Self::fecha("maintenance_start");
+        let fin = Self::fecha("maintenance_end");
+        let ahora = Utc::now();
+
+        let en_ventana = inicio.is_none_or(|inicio| ahora >= inicio) && fin.is_none_or(|fin| ahora < fin);
+        let modo = if en_ventana { modo_configurado } else { ModoMantenimiento::Off };
+        let programado = modo_configurado != ModoMantenimiento::Off && inicio.is_some_and(|inicio| ahora < inicio);
+
+        let mensaje = (modo_configurado != ModoMantenimiento::Off).then(|| {
+            ConfigService::texto("maintenance_message")
+                .filter(|mensaje| !mensaje.trim().is_empty())
+                .unwrap_or_else(|| MENSAJE_POR_DEFECTO.to_string())
+        });
+
+        EstadoMantenimiento {
+            modo,
+            modo_configurado,
+            mensaje,
+            inicio,
+            fin,
+            programado,
+        }
+    }
+
+    fn fecha(clave: &str) -> Option<DateTime<Utc>> {
+        ConfigService::texto(clave)
+            .filter(|valor| !valor.trim().is_empty())
+            .and_then(|valor| DateTime::parse_from_rfc3339(valor.trim()).ok())
+            .map(|fecha| fecha.with_timezone(&Utc))
+    }
+}
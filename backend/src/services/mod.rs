@@ -1,13 +1,43 @@
 pub mod catalogo_service;
+pub mod config_service;
 pub mod auth_service;
+pub mod dos_factores_service;
+pub mod datos_personales_service;
+pub mod api_key_service;
 pub mod carrito_service;
 pub mod direccion_service;
 pub mod checkout_service;
 pub mod metodo_pago_cliente_service;
+pub mod email_service;
+pub mod log_service;
+pub mod siem_service;
+pub mod anomalia_service;
+pub mod feature_flag_service;
+pub mod mantenimiento_service;
+pub mod busqueda_service;
+pub mod especificacion_service;
+pub mod armado_service;
+pub mod taxonomia_service;
+pub mod producto_service;
 
 pub use catalogo_service::CatalogoService;
+pub use config_service::ConfigService;
 pub use auth_service::AuthService;
+pub use dos_factores_service::DosFactoresService;
+pub use datos_personales_service::DatosPersonalesService;
+pub use api_key_service::ApiKeyService;
 pub use carrito_service::CarritoService;
 pub use direccion_service::DireccionService;
 pub use checkout_service::CheckoutService;
 pub use metodo_pago_cliente_service::MetodoPagoClienteService;
+pub use email_service::EmailService;
+pub use log_service::LogService;
+pub use siem_service::SiemService;
+pub use anomalia_service::AnomaliaService;
+pub use feature_flag_service::FeatureFlagService;
+pub use mantenimiento_service::MantenimientoService;
+pub use busqueda_service::BusquedaService;
+pub use especificacion_service::EspecificacionService;
+pub use armado_service::ArmadoService;
+pub use taxonomia_service::TaxonomiaService;
+pub use producto_service::ProductoService;
@@ -0,0 +1,681 @@
+use rust_decimal::Decimal;
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::especificacion_producto::ValorEspecificacionRequest;
+use crate::models::producto::{ActualizarProductoRequest, Producto, ProductoAdmin};
+use crate::models::producto_detalle::{ActualizarVarianteRequest, CrearVarianteRequest, ProductoDetalle};
+use crate::services::especificacion_service::{EspecificacionError, ValorValidado};
+use crate::services::EspecificacionService;
+use crate::utils::slug::{self, SlugRechazado};
+
+const COLUMNAS_PRODUCTO: &str = r#"
+    id_producto, nombre, descripcion, slug, id_categoria, id_subcategoria, especificaciones_base,
+    imagen_referencia, valoracion_promedio, COALESCE(total_valoraciones, 0) AS total_valoraciones,
+    meta_title, meta_description, keywords, estado::TEXT AS estado
+"#;
+
+const COLUMNAS_VARIANTE: &str = r#"
+    id_producto_detalle, nombre, descripcion, modelo, sku, slug, codigo_barras, id_producto, id_marca,
+    precio_base, precio_venta, costo, descuento_adicional_porcentaje,
+    COALESCE(descuento_adicional_activo, FALSE) AS descuento_adicional_activo,
+    imagen_principal, imagenes, peso, dimensiones, COALESCE(garantia_meses, 0) AS garantia_meses,
+    COALESCE(total_vendidos, 0) AS total_vendidos, COALESCE(vistas, 0) AS vistas,
+    COALESCE(es_destacado, FALSE) AS es_destacado, COALESCE(es_nuevo, FALSE) AS es_nuevo,
+    COALESCE(es_oferta, FALSE) AS es_oferta, estado::TEXT AS estado
+"#;
+
+/// Valores de `estado_general`
+const ESTADOS: &[&str] = &["activo", "inactivo"];
+
+/// Largo máximo de nombres de producto y variante (VARCHAR(200))
+const MAXIMO_NOMBRE: usize = 200;
+
+/// Largo máximo del SKU (VARCHAR(100))
+const MAXIMO_SKU: usize = 100;
+
+#[derive(Debug)]
+pub enum ProductoError {
+    NoEncontrado(String),
+    Invalida(String),
+    /// SKU o slug repetido, especificaciones incompatibles con la nueva categoría
+    Conflicto(String),
+    BaseDatos(String),
+}
+
+impl std::fmt::Display for ProductoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProductoError::NoEncontrado(m)
+            | ProductoError::Invalida(m)
+            | ProductoError::Conflicto(m)
+            | ProductoError::BaseDatos(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ProductoError {
+    fn from(e: sqlx::Error) -> Self {
+        ProductoError::BaseDatos(format!("Error en la base de datos: {}", e))
+    }
+}
+
+impl From<EspecificacionError> for ProductoError {
+    fn from(e: EspecificacionError) -> Self {
+        match e {
+            EspecificacionError::NoEncontrado(m) => ProductoError::NoEncontrado(m),
+            EspecificacionError::Invalida(m) => ProductoError::Invalida(m),
+            EspecificacionError::Conflicto(m) => ProductoError::Conflicto(m),
+            EspecificacionError::BaseDatos(m) => ProductoError::BaseDatos(m),
+        }
+    }
+}
+
+/// Administración de productos base y sus variantes (`producto_detalle`). "Eliminar" es
+/// desactivar: las variantes siguen referenciadas por ventas, carritos y valoraciones.
+pub struct ProductoService;
+
+impl ProductoService {
+    // ==================== PRODUCTO BASE ====================
+
+    /// Producto base con todas sus variantes, incluidas las inactivas
+    pub async fn obtener_producto(pool: &PgPool, id_producto: i32) -> Result<ProductoAdmin, ProductoError> {
+        let producto = Self::producto(pool, id_producto).await?;
+        let variantes = sqlx::query_as::<_, ProductoDetalle>(&format!(
+            "SELECT {} FROM producto_detalle WHERE id_producto = $1 ORDER BY id_producto_detalle",
+            COLUMNAS_VARIANTE
+        ))
+        .bind(id_producto)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ProductoAdmin { producto, variantes })
+    }
+
+    pub async fn actualizar_producto(
+        pool: &PgPool,
+        id_producto: i32,
+        request: ActualizarProductoRequest,
+    ) -> Result<ProductoAdmin, ProductoError> {
+        let mut tx = pool.begin().await?;
+        let actual = Self::producto_bloqueado(&mut tx, id_producto, "FOR UPDATE").await?;
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+        let id_categoria = request.id_categoria.unwrap_or(actual.id_categoria);
+        let cambia_categoria = id_categoria != actual.id_categoria;
+
+        // Una subcategoría de otra categoría no se conserva al mover el producto
+        let id_subcategoria = match request.id_subcategoria {
+            Some(0) => None,
+            Some(id_subcategoria) => {
+                Self::subcategoria_de(&mut tx, id_subcategoria, id_categoria)
+                    .await?
+                    .then_some(id_subcategoria)
+                    .ok_or_else(|| {
+                        ProductoError::Invalida(format!(
+                            "La subcategoría {} no pertenece a la categoría {}",
+                            id_subcategoria, id_categoria
+                        ))
+                    })?;
+                Some(id_subcategoria)
+            }
+            None => match actual.id_subcategoria {
+                Some(id_subcategoria) if cambia_categoria => {
+                    Self::subcategoria_de(&mut tx, id_subcategoria, id_categoria)
+                        .await?
+                        .then_some(id_subcategoria)
+                }
+                otra => otra,
+            },
+        };
+
+        // Las especificaciones de cada variante deben valer en la nueva categoría
+        let revalidadas = if cambia_categoria {
+            Self::revalidar_especificaciones(&mut tx, id_producto, id_categoria).await?
+        } else {
+            Vec::new()
+        };
+
+        if cambia_categoria || (estado == "activo" && actual.estado != "activo") {
+            Self::categoria_activa(&mut tx, id_categoria).await?;
+        }
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("producto", "id_producto"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_producto),
+            error_slug,
+        )
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE producto
+            SET nombre = $2, descripcion = $3, slug = $4, id_categoria = $5, id_subcategoria = $6,
+                especificaciones_base = $7, imagen_referencia = $8, meta_title = $9,
+                meta_description = $10, keywords = $11, estado = $12::estado_general,
+                fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_producto = $1
+            "#,
+        )
+        .bind(id_producto)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(&slug)
+        .bind(id_categoria)
+        .bind(id_subcategoria)
+        .bind(request.especificaciones_base.or(actual.especificaciones_base))
+        .bind(Self::texto(request.imagen_referencia, actual.imagen_referencia))
+        .bind(Self::texto(request.meta_title, actual.meta_title))
+        .bind(Self::texto(request.meta_description, actual.meta_description))
+        .bind(Self::texto(request.keywords, actual.keywords))
+        .bind(&estado)
+        .execute(&mut *tx)
+        .await
+        .map_err(Self::unico)?;
+
+        for (id_producto_detalle, validados) in &revalidadas {
+            EspecificacionService::guardar(&mut tx, *id_producto_detalle, validados).await?;
+        }
+        // Un producto inactivo no deja variantes a la venta; al reactivarlo las
+        // variantes se reactivan una por una
+        if estado == "inactivo" {
+            Self::desactivar_variantes(&mut tx, id_producto).await?;
+        }
+
+        tx.commit().await?;
+
+        Self::obtener_producto(pool, id_producto).await
+    }
+
+    /// Desactivar el producto base y todas sus variantes
+    pub async fn eliminar_producto(pool: &PgPool, id_producto: i32) -> Result<(), ProductoError> {
+        Self::producto(pool, id_producto).await?;
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE producto SET estado = 'inactivo', fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_producto = $1
+            "#,
+        )
+        .bind(id_producto)
+        .execute(&mut *tx)
+        .await?;
+        Self::desactivar_variantes(&mut tx, id_producto).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Especificaciones actuales de cada variante validadas contra el esquema de otra
+    /// categoría; todos los errores juntos, indicando el SKU
+    async fn revalidar_especificaciones(
+        conn: &mut PgConnection,
+        id_producto: i32,
+        id_categoria: i32,
+    ) -> Result<Vec<(i32, Vec<ValorValidado>)>, ProductoError> {
+        let variantes: Vec<(i32, String)> =
+            sqlx::query_as("SELECT id_producto_detalle, sku FROM producto_detalle WHERE id_producto = $1")
+                .bind(id_producto)
+                .fetch_all(&mut *conn)
+                .await?;
+
+        let mut revalidadas = Vec::new();
+        let mut errores = Vec::new();
+        for (id_producto_detalle, sku) in variantes {
+            let valores: Vec<ValorEspecificacionRequest> =
+                EspecificacionService::especificaciones(&mut *conn, id_producto_detalle)
+                    .await?
+                    .into_iter()
+                    .map(|e| ValorEspecificacionRequest {
+                        atributo: e.atributo,
+                        valor: Value::String(e.valor),
+                    })
+                    .collect();
+
+            match EspecificacionService::validar(&mut *conn, id_categoria, &valores).await {
+                Ok(validados) => revalidadas.push((id_producto_detalle, validados)),
+                Err(EspecificacionError::Invalida(mensaje)) => errores.push(format!("{}: {}", sku, mensaje)),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if !errores.is_empty() {
+            return Err(ProductoError::Conflicto(format!(
+                "Las especificaciones no son válidas en la nueva categoría. {}",
+                errores.join(". ")
+            )));
+        }
+
+        Ok(revalidadas)
+    }
+
+    async fn desactivar_variantes(conn: &mut PgConnection, id_producto: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE producto_detalle SET estado = 'inactivo', fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_producto = $1 AND estado = 'activo'
+            "#,
+        )
+        .bind(id_producto)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    // ==================== VARIANTES ====================
+
+    pub async fn crear_variante(
+        pool: &PgPool,
+        id_producto: i32,
+        request: CrearVarianteRequest,
+    ) -> Result<ProductoDetalle, ProductoError> {
+        let mut tx = pool.begin().await?;
+        let producto = Self::producto_bloqueado(&mut tx, id_producto, "FOR SHARE").await?;
+        let nombre = Self::nombre(&request.nombre)?;
+        let sku = Self::sku(&request.sku)?;
+        Self::precios(request.precio_base, request.precio_venta, request.costo, None)?;
+        if request.cantidad_inicial.is_some_and(|c| c < 0) || request.cantidad_minima.is_some_and(|c| c < 0) {
+            return Err(ProductoError::Invalida("Las cantidades no pueden ser negativas".to_string()));
+        }
+        Self::sku_libre(&mut tx, &sku, None).await?;
+
+        let especificaciones = EspecificacionService::validar(
+            &mut *tx,
+            producto.id_categoria,
+            request.especificaciones.as_deref().unwrap_or_default(),
+        )
+        .await?;
+
+        Self::marca_activa(&mut tx, request.id_marca).await?;
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("producto_detalle", "id_producto_detalle"),
+            request.slug.as_deref(),
+            None,
+            &nombre,
+            None,
+            error_slug,
+        )
+        .await?;
+
+        let variante = sqlx::query_as::<_, ProductoDetalle>(&format!(
+            r#"
+            INSERT INTO producto_detalle (
+                id_producto, id_marca, nombre, descripcion, modelo, sku, slug, codigo_barras,
+                precio_base, precio_venta, costo, imagen_principal, imagenes, peso, dimensiones,
+                garantia_meses, es_destacado, es_nuevo, es_oferta
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            RETURNING {}
+            "#,
+            COLUMNAS_VARIANTE
+        ))
+        .bind(id_producto)
+        .bind(request.id_marca)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, None))
+        .bind(Self::texto(request.modelo, None))
+        .bind(&sku)
+        .bind(&slug)
+        .bind(Self::texto(request.codigo_barras, None))
+        .bind(request.precio_base)
+        .bind(request.precio_venta)
+        .bind(request.costo)
+        .bind(Self::texto(request.imagen_principal, None))
+        .bind(request.imagenes)
+        .bind(request.peso)
+        .bind(Self::texto(request.dimensiones, None))
+        .bind(request.garantia_meses.unwrap_or(12))
+        .bind(request.es_destacado.unwrap_or(false))
+        .bind(request.es_nuevo.unwrap_or(false))
+        .bind(request.es_oferta.unwrap_or(false))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Self::unico)?;
+
+        if let Some(cantidad) = request.cantidad_inicial {
+            sqlx::query(
+                r#"
+                INSERT INTO inventario (id_producto_detalle, cantidad_disponible, cantidad_minima, ubicacion_fisica)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(variante.id_producto_detalle)
+            .bind(cantidad)
+            .bind(request.cantidad_minima.unwrap_or(5))
+            .bind(Self::texto(request.ubicacion_fisica, None))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        EspecificacionService::guardar(&mut tx, variante.id_producto_detalle, &especificaciones).await?;
+
+        tx.commit().await?;
+        Ok(variante)
+    }
+
+    pub async fn actualizar_variante(
+        pool: &PgPool,
+        id_producto_detalle: i32,
+        request: ActualizarVarianteRequest,
+    ) -> Result<ProductoDetalle, ProductoError> {
+        // Primero el producto y después la variante, en el mismo orden que
+        // `actualizar_producto` (que desactiva variantes con el producto bloqueado)
+        let mut tx = pool.begin().await?;
+        let id_producto: i32 =
+            sqlx::query_scalar("SELECT id_producto FROM producto_detalle WHERE id_producto_detalle = $1")
+                .bind(id_producto_detalle)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| ProductoError::NoEncontrado("Producto no encontrado".to_string()))?;
+        let producto = Self::producto_bloqueado(&mut tx, id_producto, "FOR SHARE").await?;
+        let actual = Self::variante_bloqueada(&mut tx, id_producto_detalle).await?;
+
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let sku = match request.sku {
+            Some(sku) => Self::sku(&sku)?,
+            None => actual.sku.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+        let id_marca = request.id_marca.unwrap_or(actual.id_marca);
+        let precio_base = request.precio_base.unwrap_or(actual.precio_base);
+        let precio_venta = request.precio_venta.unwrap_or(actual.precio_venta);
+        let costo = request.costo.or(actual.costo);
+        let descuento = request.descuento_adicional_porcentaje.or(actual.descuento_adicional_porcentaje);
+        Self::precios(precio_base, precio_venta, costo, descuento)?;
+
+        if sku != actual.sku {
+            Self::sku_libre(&mut tx, &sku, Some(id_producto_detalle)).await?;
+        }
+        if estado == "activo" && producto.estado != "activo" {
+            return Err(ProductoError::Conflicto(
+                "No se puede activar la variante de un producto inactivo; activa primero el producto".to_string(),
+            ));
+        }
+
+        let especificaciones = match &request.especificaciones {
+            Some(valores) => Some(EspecificacionService::validar(&mut *tx, producto.id_categoria, valores).await?),
+            None => None,
+        };
+
+        if id_marca != actual.id_marca || (estado == "activo" && actual.estado != "activo") {
+            Self::marca_activa(&mut tx, id_marca).await?;
+        }
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("producto_detalle", "id_producto_detalle"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_producto_detalle),
+            error_slug,
+        )
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE producto_detalle
+            SET nombre = $2, descripcion = $3, modelo = $4, sku = $5, slug = $6, codigo_barras = $7,
+                id_marca = $8, precio_base = $9, precio_venta = $10, costo = $11,
+                descuento_adicional_porcentaje = $12, descuento_adicional_activo = $13,
+                imagen_principal = $14, imagenes = $15, peso = $16, dimensiones = $17,
+                garantia_meses = $18, es_destacado = $19, es_nuevo = $20, es_oferta = $21,
+                estado = $22::estado_general, fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_producto_detalle = $1
+            "#,
+        )
+        .bind(id_producto_detalle)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(Self::texto(request.modelo, actual.modelo))
+        .bind(&sku)
+        .bind(&slug)
+        .bind(Self::texto(request.codigo_barras, actual.codigo_barras))
+        .bind(id_marca)
+        .bind(precio_base)
+        .bind(precio_venta)
+        .bind(costo)
+        .bind(descuento)
+        .bind(request.descuento_adicional_activo.unwrap_or(actual.descuento_adicional_activo))
+        .bind(Self::texto(request.imagen_principal, actual.imagen_principal))
+        .bind(request.imagenes.or(actual.imagenes))
+        .bind(request.peso.or(actual.peso))
+        .bind(Self::texto(request.dimensiones, actual.dimensiones))
+        .bind(request.garantia_meses.unwrap_or(actual.garantia_meses))
+        .bind(request.es_destacado.unwrap_or(actual.es_destacado))
+        .bind(request.es_nuevo.unwrap_or(actual.es_nuevo))
+        .bind(request.es_oferta.unwrap_or(actual.es_oferta))
+        .bind(&estado)
+        .execute(&mut *tx)
+        .await
+        .map_err(Self::unico)?;
+
+        if let Some(especificaciones) = especificaciones {
+            EspecificacionService::guardar(&mut tx, id_producto_detalle, &especificaciones).await?;
+        }
+
+        tx.commit().await?;
+
+        Self::variante(pool, id_producto_detalle).await
+    }
+
+    pub async fn eliminar_variante(pool: &PgPool, id_producto_detalle: i32) -> Result<(), ProductoError> {
+        Self::variante(pool, id_producto_detalle).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE producto_detalle SET estado = 'inactivo', fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_producto_detalle = $1
+            "#,
+        )
+        .bind(id_producto_detalle)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // ==================== HELPERS ====================
+
+    async fn producto(pool: &PgPool, id_producto: i32) -> Result<Producto, ProductoError> {
+        sqlx::query_as::<_, Producto>(&format!("SELECT {} FROM producto WHERE id_producto = $1", COLUMNAS_PRODUCTO))
+            .bind(id_producto)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ProductoError::NoEncontrado("Producto no encontrado".to_string()))
+    }
+
+    async fn variante(pool: &PgPool, id_producto_detalle: i32) -> Result<ProductoDetalle, ProductoError> {
+        sqlx::query_as::<_, ProductoDetalle>(&format!(
+            "SELECT {} FROM producto_detalle WHERE id_producto_detalle = $1",
+            COLUMNAS_VARIANTE
+        ))
+        .bind(id_producto_detalle)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ProductoError::NoEncontrado("Producto no encontrado".to_string()))
+    }
+
+    // Fila leída dentro de la transacción que la modifica: `FOR UPDATE` para cambiar el
+    // producto, `FOR SHARE` para que no cambie mientras se modifica una de sus variantes
+    async fn producto_bloqueado(
+        conn: &mut PgConnection,
+        id_producto: i32,
+        bloqueo: &str,
+    ) -> Result<Producto, ProductoError> {
+        sqlx::query_as::<_, Producto>(&format!(
+            "SELECT {} FROM producto WHERE id_producto = $1 {}",
+            COLUMNAS_PRODUCTO, bloqueo
+        ))
+        .bind(id_producto)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| ProductoError::NoEncontrado("Producto no encontrado".to_string()))
+    }
+
+    async fn variante_bloqueada(
+        conn: &mut PgConnection,
+        id_producto_detalle: i32,
+    ) -> Result<ProductoDetalle, ProductoError> {
+        sqlx::query_as::<_, ProductoDetalle>(&format!(
+            "SELECT {} FROM producto_detalle WHERE id_producto_detalle = $1 FOR UPDATE",
+            COLUMNAS_VARIANTE
+        ))
+        .bind(id_producto_detalle)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| ProductoError::NoEncontrado("Producto no encontrado".to_string()))
+    }
+
+    // FOR SHARE: espera a una desactivación de la categoría en curso (ver TaxonomiaService)
+    async fn categoria_activa(conn: &mut PgConnection, id_categoria: i32) -> Result<(), ProductoError> {
+        let estado: Option<String> =
+            sqlx::query_scalar("SELECT estado::TEXT FROM categoria WHERE id_categoria = $1 FOR SHARE")
+                .bind(id_categoria)
+                .fetch_optional(conn)
+                .await?;
+
+        match estado.as_deref() {
+            None => Err(ProductoError::Invalida(format!("La categoría {} no existe", id_categoria))),
+            Some("activo") => Ok(()),
+            Some(_) => Err(ProductoError::Conflicto(format!("La categoría {} está inactiva", id_categoria))),
+        }
+    }
+
+    async fn marca_activa(conn: &mut PgConnection, id_marca: i32) -> Result<(), ProductoError> {
+        let estado: Option<String> = sqlx::query_scalar("SELECT estado::TEXT FROM marca WHERE id_marca = $1 FOR SHARE")
+            .bind(id_marca)
+            .fetch_optional(conn)
+            .await?;
+
+        match estado.as_deref() {
+            None => Err(ProductoError::Invalida(format!("La marca {} no existe", id_marca))),
+            Some("activo") => Ok(()),
+            Some(_) => Err(ProductoError::Conflicto(format!("La marca {} está inactiva", id_marca))),
+        }
+    }
+
+    async fn subcategoria_de(
+        conn: &mut PgConnection,
+        id_subcategoria: i32,
+        id_categoria: i32,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM subcategoria WHERE id_subcategoria = $1 AND id_categoria = $2)",
+        )
+        .bind(id_subcategoria)
+        .bind(id_categoria)
+        .fetch_one(conn)
+        .await
+    }
+
+    async fn sku_libre(conn: &mut PgConnection, sku: &str, excluir: Option<i32>) -> Result<(), ProductoError> {
+        let en_uso: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM producto_detalle
+                WHERE sku = $1 AND ($2::INTEGER IS NULL OR id_producto_detalle <> $2)
+            )
+            "#,
+        )
+        .bind(sku)
+        .bind(excluir)
+        .fetch_one(conn)
+        .await?;
+
+        if en_uso {
+            return Err(ProductoError::Conflicto(format!("El SKU '{}' ya está en uso", sku)));
+        }
+        Ok(())
+    }
+
+    fn precios(
+        precio_base: Decimal,
+        precio_venta: Decimal,
+        costo: Option<Decimal>,
+        descuento: Option<Decimal>,
+    ) -> Result<(), ProductoError> {
+        if precio_base < Decimal::ZERO || precio_venta < Decimal::ZERO || costo.is_some_and(|c| c < Decimal::ZERO) {
+            return Err(ProductoError::Invalida("Los precios no pueden ser negativos".to_string()));
+        }
+        if descuento.is_some_and(|d| d < Decimal::ZERO || d > Decimal::ONE_HUNDRED) {
+            return Err(ProductoError::Invalida("El descuento adicional debe estar entre 0 y 100".to_string()));
+        }
+        Ok(())
+    }
+
+    fn nombre(nombre: &str) -> Result<String, ProductoError> {
+        let nombre = nombre.trim();
+        if nombre.is_empty() || nombre.chars().count() > MAXIMO_NOMBRE {
+            return Err(ProductoError::Invalida(format!(
+                "El nombre es obligatorio (máximo {} caracteres)",
+                MAXIMO_NOMBRE
+            )));
+        }
+        Ok(nombre.to_string())
+    }
+
+    fn sku(sku: &str) -> Result<String, ProductoError> {
+        let sku = sku.trim();
+        if sku.is_empty() || sku.chars().count() > MAXIMO_SKU {
+            return Err(ProductoError::Invalida(format!(
+                "El SKU es obligatorio (máximo {} caracteres)",
+                MAXIMO_SKU
+            )));
+        }
+        Ok(sku.to_string())
+    }
+
+    fn estado(estado: Option<String>, actual: &str) -> Result<String, ProductoError> {
+        let estado = estado.map(|e| e.trim().to_lowercase()).unwrap_or_else(|| actual.to_string());
+        if !ESTADOS.contains(&estado.as_str()) {
+            return Err(ProductoError::Invalida(format!(
+                "Estado inválido. Valores: {}",
+                ESTADOS.join(", ")
+            )));
+        }
+        Ok(estado)
+    }
+
+    /// Texto enviado (vacío = borrar) o el valor actual
+    fn texto(nuevo: Option<String>, actual: Option<String>) -> Option<String> {
+        match nuevo {
+            Some(texto) => Some(texto.trim().to_string()).filter(|t| !t.is_empty()),
+            None => actual,
+        }
+    }
+
+    /// Violación de unicidad como conflicto (carrera entre dos altas con el mismo SKU o slug)
+    fn unico(e: sqlx::Error) -> ProductoError {
+        match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => {
+                if db.constraint().is_some_and(|c| c.contains("sku")) {
+                    ProductoError::Conflicto("El SKU ya está en uso".to_string())
+                } else {
+                    ProductoError::Conflicto("El slug ya está en uso".to_string())
+                }
+            }
+            _ => e.into(),
+        }
+    }
+}
+
+fn error_slug(rechazo: SlugRechazado) -> ProductoError {
+    match rechazo {
+        SlugRechazado::Invalido(mensaje) => ProductoError::Invalida(mensaje),
+        SlugRechazado::EnUso(mensaje) => ProductoError::Conflicto(mensaje),
+    }
+}
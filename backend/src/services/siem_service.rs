@@ -0,0 +1,413 @@
+use sqlx::PgPool;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use crate::models::log_auditoria::LogAuditoria;
+use crate::services::log_service::COLUMNAS_LOG;
+use crate::services::ConfigService;
+use crate::utils::rutas::ruta_confinada;
+
+const INTERVALO_REENVIO: Duration = Duration::from_secs(5);
+
+/// Entradas que se envían por vuelta; si el lote se llena se sigue sin esperar
+const TAMANO_LOTE: i64 = 500;
+
+/// Facility 13 de syslog: "log audit"
+const FACILITY_AUDITORIA: u8 = 13;
+
+/// SD-ID con el número de empresa reservado para documentación (RFC 5612)
+const SD_ID: &str = "kronos@32473";
+
+const APP_NAME: &str = "kronostech";
+
+/// Variable de entorno con el directorio de los destinos `file:`; sin ella no se
+/// puede reenviar a archivos
+const VARIABLE_DIRECTORIO: &str = "SIEM_FILE_DIR";
+
+static HOSTNAME: LazyLock<String> = LazyLock::new(|| {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty() && h.is_ascii() && !h.contains(' '))
+        .unwrap_or_else(|| "-".to_string())
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FormatoSiem {
+    /// RFC 5424
+    Syslog,
+    /// Una entrada JSON por línea
+    Jsonl,
+}
+
+const FORMATO_DESTINO: &str =
+    "use file:archivo (dentro de SIEM_FILE_DIR), udp:127.0.0.1:puerto, tcp:127.0.0.1:puerto o unix:/ruta";
+
+/// Destino del reenvío (config siem_destination). Solo destinos locales: un archivo
+/// dentro de SIEM_FILE_DIR, un socket de loopback (el agente del SIEM en el mismo
+/// host) o un socket unix de datagramas, como /dev/log.
+#[derive(Debug, Clone, PartialEq)]
+enum Destino {
+    Archivo(PathBuf),
+    Udp(SocketAddr),
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Destino {
+    fn parse(valor: &str, directorio: Option<&Path>) -> Result<Self, String> {
+        let invalido = |detalle: &str| format!("siem_destination inválido ({}): {}", detalle, FORMATO_DESTINO);
+
+        let (tipo, direccion) = valor
+            .trim()
+            .split_once(':')
+            .filter(|(_, direccion)| !direccion.is_empty())
+            .ok_or_else(|| invalido("falta el tipo o la dirección"))?;
+
+        match tipo {
+            "file" => {
+                let directorio = directorio
+                    .ok_or_else(|| format!("Los destinos file: requieren la variable de entorno {}", VARIABLE_DIRECTORIO))?;
+                ruta_confinada(directorio, direccion).map(Destino::Archivo)
+            }
+            "udp" => Self::loopback(direccion).map(Destino::Udp).ok_or_else(|| invalido("solo loopback")),
+            "tcp" => Self::loopback(direccion).map(Destino::Tcp).ok_or_else(|| invalido("solo loopback")),
+            "unix" if direccion.starts_with('/') => Ok(Destino::Unix(PathBuf::from(direccion))),
+            "unix" => Err(invalido("la ruta del socket debe ser absoluta")),
+            _ => Err(invalido("tipo desconocido")),
+        }
+    }
+
+    // host:puerto con una IP de loopback o "localhost"; sin resolver DNS
+    fn loopback(direccion: &str) -> Option<SocketAddr> {
+        let direccion = match direccion.strip_prefix("localhost:") {
+            Some(puerto) => SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), puerto.parse().ok()?),
+            None => direccion.parse().ok()?,
+        };
+
+        (direccion.ip().is_loopback() && direccion.port() != 0).then_some(direccion)
+    }
+}
+
+fn directorio_archivos() -> Option<PathBuf> {
+    std::env::var(VARIABLE_DIRECTORIO)
+        .ok()
+        .filter(|d| !d.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+struct ConfigSiem {
+    formato: FormatoSiem,
+    destino: Destino,
+}
+
+/// Reenvío de log_auditoria a un SIEM. Una tarea en segundo plano lee las entradas
+/// nuevas por id_log y guarda en reenvio_siem la última enviada, de modo que tras
+/// un fallo o un reinicio se reanuda sin perder entradas (entrega al menos una vez).
+pub struct SiemService;
+
+impl SiemService {
+    /// Validar un valor de siem_destination antes de guardarlo
+    pub fn validar_destino(valor: &str) -> Result<(), String> {
+        Destino::parse(valor, directorio_archivos().as_deref()).map(|_| ())
+    }
+
+    pub fn iniciar_tarea_reenvio(pool: PgPool) {
+        tokio::spawn(async move {
+            let mut intervalo = tokio::time::interval(INTERVALO_REENVIO);
+            let mut ultimo_error: Option<String> = None;
+            loop {
+                intervalo.tick().await;
+
+                // Vaciar lo pendiente en lotes; un lote incompleto significa que no hay más
+                loop {
+                    match Self::reenviar_pendientes(&pool).await {
+                        Ok(enviadas) => {
+                            if ultimo_error.take().is_some() {
+                                println!("📡 Reenvío de logs al SIEM restablecido");
+                            }
+                            if enviadas < TAMANO_LOTE as usize {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            // Informar una sola vez mientras el error se repita
+                            if ultimo_error.as_deref() != Some(e.as_str()) {
+                                eprintln!("⚠️  Reenvío de logs al SIEM: {}", e);
+                                ultimo_error = Some(e);
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Enviar el siguiente lote de entradas. Devuelve cuántas se enviaron.
+    async fn reenviar_pendientes(pool: &PgPool) -> Result<usize, String> {
+        let Some(config) = Self::cargar_config()? else {
+            return Ok(0);
+        };
+
+        // Con varias instancias, solo una reenvía a la vez; las demás se saltan la pasada.
+        // El lock es de la transacción, así que se libera aunque el envío falle.
+        let mut bloqueo = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Error al iniciar el reenvío: {}", e))?;
+        let libre = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock(hashtext('reenvio_siem'))")
+            .fetch_one(&mut *bloqueo)
+            .await
+            .map_err(|e| format!("Error al bloquear el reenvío: {}", e))?;
+        if !libre {
+            return Ok(0);
+        }
+
+        let ultimo_id = Self::ultimo_enviado(pool).await?;
+
+        let logs = sqlx::query_as::<_, LogAuditoria>(&format!(
+            "SELECT {} FROM log_auditoria WHERE id_log > $1 ORDER BY id_log LIMIT $2",
+            COLUMNAS_LOG
+        ))
+        .bind(ultimo_id)
+        .bind(TAMANO_LOTE)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Error al leer logs pendientes: {}", e))?;
+
+        let Some(ultimo) = logs.last() else {
+            return Ok(0);
+        };
+
+        let mensajes: Vec<String> = logs
+            .iter()
+            .map(|log| match config.formato {
+                FormatoSiem::Syslog => Self::mensaje_syslog(log),
+                FormatoSiem::Jsonl => serde_json::to_string(log).unwrap_or_default(),
+            })
+            .collect();
+
+        Self::enviar(&config, &mensajes).await?;
+
+        sqlx::query(
+            "UPDATE reenvio_siem SET ultimo_id_log = $1, fecha_actualizacion = CURRENT_TIMESTAMP WHERE id = 1"
+        )
+        .bind(ultimo.id_log)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Error al guardar el avance del reenvío: {}", e))?;
+
+        let _ = bloqueo.commit().await;
+        Ok(logs.len())
+    }
+
+    // Config siem_*; None si el reenvío está desactivado
+    fn cargar_config() -> Result<Option<ConfigSiem>, String> {
+        let valores = ConfigService::con_prefijo("siem_");
+
+        if valores.get("siem_forwarding_enabled").map(String::as_str) != Some("true") {
+            return Ok(None);
+        }
+
+        let formato = match valores.get("siem_format").map(|f| f.trim()) {
+            Some("jsonl") => FormatoSiem::Jsonl,
+            Some("syslog") | None => FormatoSiem::Syslog,
+            Some(otro) => return Err(format!("siem_format inválido: {} (use syslog o jsonl)", otro)),
+        };
+
+        // Se revalida al usar: el valor pudo guardarse antes de estas reglas o cambiar por SQL
+        let destino = Destino::parse(
+            valores.get("siem_destination").map(String::as_str).unwrap_or_default(),
+            directorio_archivos().as_deref(),
+        )?;
+
+        Ok(Some(ConfigSiem { formato, destino }))
+    }
+
+    // La primera vez se empieza desde la última entrada existente: no se reenvía el histórico
+    async fn ultimo_enviado(pool: &PgPool) -> Result<i32, String> {
+        sqlx::query(
+            "INSERT INTO reenvio_siem (id, ultimo_id_log)
+             SELECT 1, COALESCE(MAX(id_log), 0) FROM log_auditoria
+             ON CONFLICT (id) DO NOTHING"
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Error al iniciar el reenvío: {}", e))?;
+
+        sqlx::query_scalar::<_, i32>("SELECT ultimo_id_log FROM reenvio_siem WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Error al leer el avance del reenvío: {}", e))
+    }
+
+    async fn enviar(config: &ConfigSiem, mensajes: &[String]) -> Result<(), String> {
+        let error = |e: std::io::Error| format!("No se pudo enviar a {:?}: {}", config.destino, e);
+
+        match &config.destino {
+            Destino::Archivo(ruta) => {
+                let mut archivo = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(ruta)
+                    .await
+                    .map_err(error)?;
+                let contenido: String = mensajes.iter().map(|m| format!("{}\n", m)).collect();
+                archivo.write_all(contenido.as_bytes()).await.map_err(error)?;
+                archivo.flush().await.map_err(error)?;
+            }
+            Destino::Udp(destino) => {
+                let local = if destino.is_ipv4() { "127.0.0.1:0" } else { "[::1]:0" };
+                let socket = tokio::net::UdpSocket::bind(local).await.map_err(error)?;
+                for mensaje in mensajes {
+                    socket.send_to(mensaje.as_bytes(), destino).await.map_err(error)?;
+                }
+            }
+            Destino::Tcp(direccion) => {
+                let mut stream = tokio::net::TcpStream::connect(direccion).await.map_err(error)?;
+                // Syslog sobre TCP usa octet counting (RFC 6587); JSON lines, un salto de línea
+                let contenido: String = mensajes
+                    .iter()
+                    .map(|m| match config.formato {
+                        FormatoSiem::Syslog => format!("{} {}", m.len(), m),
+                        FormatoSiem::Jsonl => format!("{}\n", m),
+                    })
+                    .collect();
+                stream.write_all(contenido.as_bytes()).await.map_err(error)?;
+                stream.shutdown().await.map_err(error)?;
+            }
+            Destino::Unix(ruta) => {
+                let socket = tokio::net::UnixDatagram::unbound().map_err(error)?;
+                for mensaje in mensajes {
+                    socket.send_to(mensaje.as_bytes(), ruta).await.map_err(error)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // RFC 5424: <PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] BOM MSG
+    fn mensaje_syslog(log: &LogAuditoria) -> String {
+        let severidad = match log.nivel.as_str() {
+            "error" => 3,
+            "warning" | "security" => 4,
+            "success" => 5,
+            _ => 6,
+        };
+        let prioridad = FACILITY_AUDITORIA * 8 + severidad;
+
+        // Las fechas de la BD se guardan en UTC
+        let timestamp = log
+            .fecha_creacion
+            .and_utc()
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+
+        let parametros: String = [
+            ("id_log", Some(log.id_log.to_string())),
+            ("nivel", Some(log.nivel.clone())),
+            ("modulo", Some(log.modulo.clone())),
+            ("origen", Some(log.origen.clone())),
+            ("id_usuario", log.id_usuario.map(|id| id.to_string())),
+            ("email", log.email_usuario.clone()),
+            ("ip", log.ip_cliente.clone()),
+            ("metodo", log.metodo_http.clone()),
+            ("ruta", log.ruta.clone()),
+            ("entidad", log.entidad.clone()),
+            ("id_entidad", log.id_entidad.clone()),
+            ("codigo", log.codigo_respuesta.map(|c| c.to_string())),
+            ("hash", Some(log.hash.clone())),
+        ]
+        .into_iter()
+        .filter_map(|(nombre, valor)| valor.map(|v| format!(" {}=\"{}\"", nombre, Self::escapar_sd(&v))))
+        .collect();
+
+        let mensaje = match &log.detalles {
+            Some(detalles) => format!("{}: {}", log.accion, detalles),
+            None => log.accion.clone(),
+        }
+        .replace(['\r', '\n'], " ");
+
+        format!(
+            "<{}>1 {} {} {} {} {} [{}{}] \u{feff}{}",
+            prioridad,
+            timestamp,
+            *HOSTNAME,
+            APP_NAME,
+            std::process::id(),
+            log.nivel,
+            SD_ID,
+            parametros,
+            mensaje
+        )
+    }
+
+    // En PARAM-VALUE hay que escapar '"', '\' y ']'
+    fn escapar_sd(valor: &str) -> String {
+        valor
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace(']', "\\]")
+            .replace(['\r', '\n'], " ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinos_de_red_solo_aceptan_loopback() {
+        assert!(matches!(
+            Destino::parse("udp:127.0.0.1:514", None),
+            Ok(Destino::Udp(direccion)) if direccion.port() == 514
+        ));
+        assert!(matches!(Destino::parse("tcp:[::1]:6514", None), Ok(Destino::Tcp(_))));
+        assert!(matches!(
+            Destino::parse(" tcp:localhost:6514 ", None),
+            Ok(Destino::Tcp(direccion)) if direccion.ip().is_loopback()
+        ));
+
+        for valor in [
+            "udp:10.0.0.5:514",
+            "tcp:0.0.0.0:514",
+            "tcp:siem.example.com:6514",
+            "tcp:127.0.0.1:0",
+            "tcp:127.0.0.1",
+            "udp:localhost:",
+        ] {
+            assert!(Destino::parse(valor, None).is_err(), "{}", valor);
+        }
+    }
+
+    #[test]
+    fn destinos_de_archivo_quedan_en_el_directorio_configurado() {
+        let directorio = Path::new("/var/log/kronos");
+        assert!(matches!(
+            Destino::parse("file:siem.jsonl", Some(directorio)),
+            Ok(Destino::Archivo(ruta)) if ruta == directorio.join("siem.jsonl")
+        ));
+
+        assert!(Destino::parse("file:siem.jsonl", None).is_err());
+        assert!(Destino::parse("file:/etc/passwd", Some(directorio)).is_err());
+        assert!(Destino::parse("file:../../etc/passwd", Some(directorio)).is_err());
+    }
+
+    #[test]
+    fn sockets_unix_requieren_ruta_absoluta() {
+        assert!(matches!(Destino::parse("unix:/run/siem.sock", None), Ok(Destino::Unix(_))));
+        assert!(Destino::parse("unix:siem.sock", None).is_err());
+    }
+
+    #[test]
+    fn rechaza_tipos_desconocidos_o_incompletos() {
+        for valor in ["", "udp:", "siem", "http://127.0.0.1:514"] {
+            assert!(Destino::parse(valor, None).is_err(), "{:?}", valor);
+        }
+    }
+}
@@ -0,0 +1,777 @@
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgConnection, PgPool};
+
+use crate::models::categoria::{ActualizarCategoriaRequest, Categoria, CrearCategoriaRequest};
+use crate::models::familia::{ActualizarFamiliaRequest, CrearFamiliaRequest, Familia};
+use crate::models::marca::{ActualizarMarcaRequest, CrearMarcaRequest, Marca};
+use crate::models::subcategoria::{ActualizarSubcategoriaRequest, CrearSubcategoriaRequest, Subcategoria};
+use crate::utils::slug::{self, SlugRechazado};
+
+const COLUMNAS_FAMILIA: &str =
+    "id_familia, nombre, descripcion, icono, slug, COALESCE(orden, 0) AS orden, estado::TEXT AS estado";
+const COLUMNAS_CATEGORIA: &str =
+    "id_categoria, nombre, descripcion, icono, slug, id_familia, COALESCE(orden, 0) AS orden, estado::TEXT AS estado";
+const COLUMNAS_SUBCATEGORIA: &str = "id_subcategoria, nombre, descripcion, slug, id_categoria, \
+     COALESCE(orden, 0) AS orden, estado::TEXT AS estado, fecha_creacion, fecha_actualizacion";
+const COLUMNAS_MARCA: &str =
+    "id_marca, nombre, descripcion, logo, slug, pais_origen, sitio_web, estado::TEXT AS estado";
+
+/// Valores de `estado_general`
+const ESTADOS: &[&str] = &["activo", "inactivo"];
+
+/// Largo máximo de los nombres (VARCHAR(100))
+const MAXIMO_NOMBRE: usize = 100;
+
+#[derive(Debug)]
+pub enum TaxonomiaError {
+    NoEncontrado(String),
+    Invalida(String),
+    /// Nombre o slug repetido, padre inactivo, o todavía tiene productos
+    Conflicto(String),
+    BaseDatos(String),
+}
+
+impl std::fmt::Display for TaxonomiaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaxonomiaError::NoEncontrado(m)
+            | TaxonomiaError::Invalida(m)
+            | TaxonomiaError::Conflicto(m)
+            | TaxonomiaError::BaseDatos(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<sqlx::Error> for TaxonomiaError {
+    fn from(e: sqlx::Error) -> Self {
+        TaxonomiaError::BaseDatos(format!("Error en la base de datos: {}", e))
+    }
+}
+
+/// Familias, categorías, subcategorías y marcas. "Eliminar" es desactivar (`estado`):
+/// las filas siguen referenciadas por productos y ventas.
+pub struct TaxonomiaService;
+
+impl TaxonomiaService {
+    // ==================== FAMILIAS ====================
+
+    pub async fn crear_familia(pool: &PgPool, request: CrearFamiliaRequest) -> Result<Familia, TaxonomiaError> {
+        let nombre = Self::nombre(&request.nombre)?;
+
+        let mut tx = pool.begin().await?;
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("familia", "id_familia"),
+            request.slug.as_deref(),
+            None,
+            &nombre,
+            None,
+            error_slug,
+        )
+        .await?;
+        let orden = match request.orden {
+            Some(orden) => orden,
+            None => Self::orden_siguiente(&mut tx, "familia", None).await?,
+        };
+
+        let familia = sqlx::query_as::<_, Familia>(&format!(
+            "INSERT INTO familia (nombre, descripcion, icono, slug, orden) VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+            COLUMNAS_FAMILIA
+        ))
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, None))
+        .bind(Self::texto(request.icono, None))
+        .bind(&slug)
+        .bind(orden)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una familia con ese nombre"))?;
+
+        tx.commit().await?;
+        Ok(familia)
+    }
+
+    pub async fn actualizar_familia(
+        pool: &PgPool,
+        id_familia: i32,
+        request: ActualizarFamiliaRequest,
+    ) -> Result<Familia, TaxonomiaError> {
+        let mut tx = pool.begin().await?;
+        let actual: Familia =
+            Self::fila_bloqueada(&mut tx, COLUMNAS_FAMILIA, "familia", "id_familia", id_familia, "Familia").await?;
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("familia", "id_familia"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_familia),
+            error_slug,
+        )
+        .await?;
+        if estado == "inactivo" && actual.estado != "inactivo" {
+            Self::proteger_familia(&mut tx, id_familia).await?;
+        }
+
+        let familia = sqlx::query_as::<_, Familia>(&format!(
+            r#"
+            UPDATE familia
+            SET nombre = $2, descripcion = $3, icono = $4, slug = $5, orden = $6,
+                estado = $7::estado_general, fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_familia = $1
+            RETURNING {}
+            "#,
+            COLUMNAS_FAMILIA
+        ))
+        .bind(id_familia)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(Self::texto(request.icono, actual.icono))
+        .bind(&slug)
+        .bind(request.orden.unwrap_or(actual.orden))
+        .bind(&estado)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una familia con ese nombre"))?;
+
+        tx.commit().await?;
+        Ok(familia)
+    }
+
+    pub async fn eliminar_familia(pool: &PgPool, id_familia: i32) -> Result<(), TaxonomiaError> {
+        Self::fila::<Familia>(pool, COLUMNAS_FAMILIA, "familia", "id_familia", id_familia, "Familia").await?;
+
+        let mut tx = pool.begin().await?;
+        Self::proteger_familia(&mut tx, id_familia).await?;
+        Self::desactivar(&mut tx, "familia", "id_familia", id_familia).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn proteger_familia(conn: &mut PgConnection, id_familia: i32) -> Result<(), TaxonomiaError> {
+        Self::bloquear(&mut *conn, "familia", "id_familia", id_familia).await?;
+
+        let categorias: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM categoria WHERE id_familia = $1 AND estado = 'activo'")
+                .bind(id_familia)
+                .fetch_one(conn)
+                .await?;
+
+        if categorias > 0 {
+            return Err(TaxonomiaError::Conflicto(format!(
+                "La familia tiene {} categoría(s) activa(s); desactívalas o muévelas primero",
+                categorias
+            )));
+        }
+        Ok(())
+    }
+
+    // ==================== CATEGORÍAS ====================
+
+    pub async fn crear_categoria(pool: &PgPool, request: CrearCategoriaRequest) -> Result<Categoria, TaxonomiaError> {
+        let nombre = Self::nombre(&request.nombre)?;
+
+        let mut tx = pool.begin().await?;
+
+        Self::padre_activo(&mut tx, "familia", "id_familia", request.id_familia, "La familia").await?;
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("categoria", "id_categoria"),
+            request.slug.as_deref(),
+            None,
+            &nombre,
+            None,
+            error_slug,
+        )
+        .await?;
+        let orden = match request.orden {
+            Some(orden) => orden,
+            None => Self::orden_siguiente(&mut tx, "categoria", Some(("id_familia", request.id_familia))).await?,
+        };
+
+        let categoria = sqlx::query_as::<_, Categoria>(&format!(
+            r#"
+            INSERT INTO categoria (nombre, descripcion, icono, slug, id_familia, orden)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            COLUMNAS_CATEGORIA
+        ))
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, None))
+        .bind(Self::texto(request.icono, None))
+        .bind(&slug)
+        .bind(request.id_familia)
+        .bind(orden)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una categoría con ese nombre en la familia"))?;
+
+        tx.commit().await?;
+        Ok(categoria)
+    }
+
+    pub async fn actualizar_categoria(
+        pool: &PgPool,
+        id_categoria: i32,
+        request: ActualizarCategoriaRequest,
+    ) -> Result<Categoria, TaxonomiaError> {
+        let mut tx = pool.begin().await?;
+        let actual: Categoria =
+            Self::fila_bloqueada(&mut tx, COLUMNAS_CATEGORIA, "categoria", "id_categoria", id_categoria, "Categoría")
+                .await?;
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+        let id_familia = request.id_familia.unwrap_or(actual.id_familia);
+
+        if id_familia != actual.id_familia || (estado == "activo" && actual.estado != "activo") {
+            Self::padre_activo(&mut tx, "familia", "id_familia", id_familia, "La familia").await?;
+        }
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("categoria", "id_categoria"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_categoria),
+            error_slug,
+        )
+        .await?;
+        if estado == "inactivo" && actual.estado != "inactivo" {
+            Self::proteger_categoria(&mut tx, id_categoria).await?;
+        }
+
+        let categoria = sqlx::query_as::<_, Categoria>(&format!(
+            r#"
+            UPDATE categoria
+            SET nombre = $2, descripcion = $3, icono = $4, slug = $5, id_familia = $6, orden = $7,
+                estado = $8::estado_general, fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_categoria = $1
+            RETURNING {}
+            "#,
+            COLUMNAS_CATEGORIA
+        ))
+        .bind(id_categoria)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(Self::texto(request.icono, actual.icono))
+        .bind(&slug)
+        .bind(id_familia)
+        .bind(request.orden.unwrap_or(actual.orden))
+        .bind(&estado)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una categoría con ese nombre en la familia"))?;
+
+        if estado == "inactivo" {
+            Self::desactivar_subcategorias(&mut tx, id_categoria).await?;
+        }
+
+        tx.commit().await?;
+        Ok(categoria)
+    }
+
+    /// Desactivar la categoría y sus subcategorías; no se puede mientras tenga productos
+    /// activos o sea una ranura de "arma tu PC"
+    pub async fn eliminar_categoria(pool: &PgPool, id_categoria: i32) -> Result<(), TaxonomiaError> {
+        Self::fila::<Categoria>(pool, COLUMNAS_CATEGORIA, "categoria", "id_categoria", id_categoria, "Categoría")
+            .await?;
+
+        let mut tx = pool.begin().await?;
+        Self::proteger_categoria(&mut tx, id_categoria).await?;
+        Self::desactivar(&mut tx, "categoria", "id_categoria", id_categoria).await?;
+        Self::desactivar_subcategorias(&mut tx, id_categoria).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn proteger_categoria(conn: &mut PgConnection, id_categoria: i32) -> Result<(), TaxonomiaError> {
+        Self::bloquear(&mut *conn, "categoria", "id_categoria", id_categoria).await?;
+
+        let productos: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM producto WHERE id_categoria = $1 AND estado = 'activo'")
+                .bind(id_categoria)
+                .fetch_one(&mut *conn)
+                .await?;
+        if productos > 0 {
+            return Err(TaxonomiaError::Conflicto(format!(
+                "La categoría tiene {} producto(s) activo(s); desactívalos o muévelos a otra categoría primero",
+                productos
+            )));
+        }
+
+        let ranuras: Vec<String> = sqlx::query_scalar("SELECT nombre FROM ranura_armado WHERE id_categoria = $1")
+            .bind(id_categoria)
+            .fetch_all(&mut *conn)
+            .await?;
+        if !ranuras.is_empty() {
+            return Err(TaxonomiaError::Conflicto(format!(
+                "La categoría se usa en \"arma tu PC\" ({})",
+                ranuras.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn desactivar_subcategorias(conn: &mut PgConnection, id_categoria: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE subcategoria SET estado = 'inactivo', fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_categoria = $1 AND estado = 'activo'
+            "#,
+        )
+        .bind(id_categoria)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    // ==================== SUBCATEGORÍAS ====================
+
+    pub async fn crear_subcategoria(
+        pool: &PgPool,
+        request: CrearSubcategoriaRequest,
+    ) -> Result<Subcategoria, TaxonomiaError> {
+        let nombre = Self::nombre(&request.nombre)?;
+
+        let mut tx = pool.begin().await?;
+
+        Self::padre_activo(&mut tx, "categoria", "id_categoria", request.id_categoria, "La categoría").await?;
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("subcategoria", "id_subcategoria"),
+            request.slug.as_deref(),
+            None,
+            &nombre,
+            None,
+            error_slug,
+        )
+        .await?;
+        let orden = match request.orden {
+            Some(orden) => orden,
+            None => Self::orden_siguiente(&mut tx, "subcategoria", Some(("id_categoria", request.id_categoria))).await?,
+        };
+
+        let subcategoria = sqlx::query_as::<_, Subcategoria>(&format!(
+            r#"
+            INSERT INTO subcategoria (nombre, descripcion, slug, id_categoria, orden)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            COLUMNAS_SUBCATEGORIA
+        ))
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, None))
+        .bind(&slug)
+        .bind(request.id_categoria)
+        .bind(orden)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una subcategoría con ese nombre en la categoría"))?;
+
+        tx.commit().await?;
+        Ok(subcategoria)
+    }
+
+    pub async fn actualizar_subcategoria(
+        pool: &PgPool,
+        id_subcategoria: i32,
+        request: ActualizarSubcategoriaRequest,
+    ) -> Result<Subcategoria, TaxonomiaError> {
+        let mut tx = pool.begin().await?;
+        // La categoría antes que la subcategoría, en el mismo orden que
+        // `actualizar_categoria` (que desactiva subcategorías con la categoría bloqueada)
+        sqlx::query(
+            r#"
+            SELECT 1 FROM categoria
+            WHERE id_categoria = (SELECT id_categoria FROM subcategoria WHERE id_subcategoria = $1)
+            FOR SHARE
+            "#,
+        )
+        .bind(id_subcategoria)
+        .execute(&mut *tx)
+        .await?;
+        let actual: Subcategoria = Self::fila_bloqueada(
+            &mut tx,
+            COLUMNAS_SUBCATEGORIA,
+            "subcategoria",
+            "id_subcategoria",
+            id_subcategoria,
+            "Subcategoría",
+        )
+        .await?;
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+        let id_categoria = request.id_categoria.unwrap_or(actual.id_categoria);
+
+        if id_categoria != actual.id_categoria || (estado == "activo" && actual.estado != "activo") {
+            Self::padre_activo(&mut tx, "categoria", "id_categoria", id_categoria, "La categoría").await?;
+        }
+        // Los productos de la subcategoría pertenecen a su categoría: no puede cambiar de
+        // categoría ni desactivarse mientras tenga productos activos
+        if id_categoria != actual.id_categoria || (estado == "inactivo" && actual.estado != "inactivo") {
+            Self::proteger_subcategoria(&mut tx, id_subcategoria).await?;
+        }
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("subcategoria", "id_subcategoria"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_subcategoria),
+            error_slug,
+        )
+        .await?;
+
+        let subcategoria = sqlx::query_as::<_, Subcategoria>(&format!(
+            r#"
+            UPDATE subcategoria
+            SET nombre = $2, descripcion = $3, slug = $4, id_categoria = $5, orden = $6,
+                estado = $7::estado_general, fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_subcategoria = $1
+            RETURNING {}
+            "#,
+            COLUMNAS_SUBCATEGORIA
+        ))
+        .bind(id_subcategoria)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(&slug)
+        .bind(id_categoria)
+        .bind(request.orden.unwrap_or(actual.orden))
+        .bind(&estado)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una subcategoría con ese nombre en la categoría"))?;
+
+        tx.commit().await?;
+        Ok(subcategoria)
+    }
+
+    pub async fn eliminar_subcategoria(pool: &PgPool, id_subcategoria: i32) -> Result<(), TaxonomiaError> {
+        Self::fila::<Subcategoria>(
+            pool,
+            COLUMNAS_SUBCATEGORIA,
+            "subcategoria",
+            "id_subcategoria",
+            id_subcategoria,
+            "Subcategoría",
+        )
+        .await?;
+
+        let mut tx = pool.begin().await?;
+        Self::proteger_subcategoria(&mut tx, id_subcategoria).await?;
+        Self::desactivar(&mut tx, "subcategoria", "id_subcategoria", id_subcategoria).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn proteger_subcategoria(conn: &mut PgConnection, id_subcategoria: i32) -> Result<(), TaxonomiaError> {
+        Self::bloquear(&mut *conn, "subcategoria", "id_subcategoria", id_subcategoria).await?;
+
+        let productos: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM producto WHERE id_subcategoria = $1 AND estado = 'activo'")
+                .bind(id_subcategoria)
+                .fetch_one(conn)
+                .await?;
+
+        if productos > 0 {
+            return Err(TaxonomiaError::Conflicto(format!(
+                "La subcategoría tiene {} producto(s) activo(s); desactívalos o muévelos primero",
+                productos
+            )));
+        }
+        Ok(())
+    }
+
+    // ==================== MARCAS ====================
+
+    pub async fn crear_marca(pool: &PgPool, request: CrearMarcaRequest) -> Result<Marca, TaxonomiaError> {
+        let nombre = Self::nombre(&request.nombre)?;
+
+        let mut tx = pool.begin().await?;
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("marca", "id_marca"),
+            request.slug.as_deref(),
+            None,
+            &nombre,
+            None,
+            error_slug,
+        )
+        .await?;
+
+        let marca = sqlx::query_as::<_, Marca>(&format!(
+            r#"
+            INSERT INTO marca (nombre, descripcion, logo, slug, pais_origen, sitio_web)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            COLUMNAS_MARCA
+        ))
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, None))
+        .bind(Self::texto(request.logo, None))
+        .bind(&slug)
+        .bind(Self::texto(request.pais_origen, None))
+        .bind(Self::texto(request.sitio_web, None))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una marca con ese nombre"))?;
+
+        tx.commit().await?;
+        Ok(marca)
+    }
+
+    pub async fn actualizar_marca(
+        pool: &PgPool,
+        id_marca: i32,
+        request: ActualizarMarcaRequest,
+    ) -> Result<Marca, TaxonomiaError> {
+        let mut tx = pool.begin().await?;
+        let actual: Marca = Self::fila_bloqueada(&mut tx, COLUMNAS_MARCA, "marca", "id_marca", id_marca, "Marca").await?;
+        let nombre = match request.nombre {
+            Some(nombre) => Self::nombre(&nombre)?,
+            None => actual.nombre.clone(),
+        };
+        let estado = Self::estado(request.estado, &actual.estado)?;
+
+        let slug = slug::resolver_slug(
+            &mut tx,
+            ("marca", "id_marca"),
+            request.slug.as_deref(),
+            actual.slug.as_deref(),
+            &nombre,
+            Some(id_marca),
+            error_slug,
+        )
+        .await?;
+        if estado == "inactivo" && actual.estado != "inactivo" {
+            Self::proteger_marca(&mut tx, id_marca).await?;
+        }
+
+        let marca = sqlx::query_as::<_, Marca>(&format!(
+            r#"
+            UPDATE marca
+            SET nombre = $2, descripcion = $3, logo = $4, slug = $5, pais_origen = $6, sitio_web = $7,
+                estado = $8::estado_general, fecha_actualizacion = CURRENT_TIMESTAMP
+            WHERE id_marca = $1
+            RETURNING {}
+            "#,
+            COLUMNAS_MARCA
+        ))
+        .bind(id_marca)
+        .bind(&nombre)
+        .bind(Self::texto(request.descripcion, actual.descripcion))
+        .bind(Self::texto(request.logo, actual.logo))
+        .bind(&slug)
+        .bind(Self::texto(request.pais_origen, actual.pais_origen))
+        .bind(Self::texto(request.sitio_web, actual.sitio_web))
+        .bind(&estado)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Self::unico(e, "Ya existe una marca con ese nombre"))?;
+
+        tx.commit().await?;
+        Ok(marca)
+    }
+
+    pub async fn eliminar_marca(pool: &PgPool, id_marca: i32) -> Result<(), TaxonomiaError> {
+        Self::fila::<Marca>(pool, COLUMNAS_MARCA, "marca", "id_marca", id_marca, "Marca").await?;
+
+        let mut tx = pool.begin().await?;
+        Self::proteger_marca(&mut tx, id_marca).await?;
+        Self::desactivar(&mut tx, "marca", "id_marca", id_marca).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn proteger_marca(conn: &mut PgConnection, id_marca: i32) -> Result<(), TaxonomiaError> {
+        Self::bloquear(&mut *conn, "marca", "id_marca", id_marca).await?;
+
+        let variantes: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM producto_detalle WHERE id_marca = $1 AND estado = 'activo'")
+                .bind(id_marca)
+                .fetch_one(conn)
+                .await?;
+
+        if variantes > 0 {
+            return Err(TaxonomiaError::Conflicto(format!(
+                "La marca tiene {} producto(s) activo(s); desactívalos primero",
+                variantes
+            )));
+        }
+        Ok(())
+    }
+
+    // ==================== HELPERS ====================
+    // `tabla` y las columnas que reciben estos helpers son nombres fijos del código
+
+    async fn fila<T>(
+        pool: &PgPool,
+        columnas: &str,
+        tabla: &str,
+        columna_id: &str,
+        id: i32,
+        entidad: &str,
+    ) -> Result<T, TaxonomiaError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        sqlx::query_as::<_, T>(&format!("SELECT {} FROM {} WHERE {} = $1", columnas, tabla, columna_id))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| TaxonomiaError::NoEncontrado(format!("{} no encontrada", entidad)))
+    }
+
+    /// Como `fila`, dentro de la transacción que la actualiza y con la fila bloqueada
+    /// FOR UPDATE: los valores que no cambian se copian de una lectura vigente
+    async fn fila_bloqueada<T>(
+        conn: &mut PgConnection,
+        columnas: &str,
+        tabla: &str,
+        columna_id: &str,
+        id: i32,
+        entidad: &str,
+    ) -> Result<T, TaxonomiaError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        sqlx::query_as::<_, T>(&format!("SELECT {} FROM {} WHERE {} = $1 FOR UPDATE", columnas, tabla, columna_id))
+            .bind(id)
+            .fetch_optional(conn)
+            .await?
+            .ok_or_else(|| TaxonomiaError::NoEncontrado(format!("{} no encontrada", entidad)))
+    }
+
+    async fn desactivar(conn: &mut PgConnection, tabla: &str, columna_id: &str, id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET estado = 'inactivo', fecha_actualizacion = CURRENT_TIMESTAMP WHERE {} = $1",
+            tabla, columna_id
+        ))
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// El padre debe existir y estar activo
+    async fn padre_activo(
+        conn: &mut PgConnection,
+        tabla: &str,
+        columna_id: &str,
+        id: i32,
+        entidad: &str,
+    ) -> Result<(), TaxonomiaError> {
+        // FOR SHARE: espera a una desactivación del padre en curso (ver `bloquear`)
+        let estado: Option<String> =
+            sqlx::query_scalar(&format!("SELECT estado::TEXT FROM {} WHERE {} = $1 FOR SHARE", tabla, columna_id))
+                .bind(id)
+                .fetch_optional(conn)
+                .await?;
+
+        match estado.as_deref() {
+            None => Err(TaxonomiaError::Invalida(format!("{} {} no existe", entidad, id))),
+            Some("activo") => Ok(()),
+            Some(_) => Err(TaxonomiaError::Conflicto(format!("{} {} está inactiva", entidad, id))),
+        }
+    }
+
+    /// Bloquear la fila antes de comprobar si se puede desactivar: quien agrega o activa
+    /// hijos la lee con FOR SHARE (o la bloquea por su clave foránea), así que no puede
+    /// colarse un hijo entre la comprobación y la desactivación
+    async fn bloquear(conn: &mut PgConnection, tabla: &str, columna_id: &str, id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!("SELECT 1 FROM {} WHERE {} = $1 FOR UPDATE", tabla, columna_id))
+            .bind(id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Siguiente `orden` al final de la lista (dentro del padre, si lo hay)
+    async fn orden_siguiente(
+        conn: &mut PgConnection,
+        tabla: &str,
+        padre: Option<(&str, i32)>,
+    ) -> Result<i32, sqlx::Error> {
+        let (columna, id) = padre.unwrap_or(("NULL", 0));
+        sqlx::query_scalar(&format!(
+            "SELECT COALESCE(MAX(orden), 0) + 1 FROM {} WHERE $1::INTEGER = 0 OR {} = $1",
+            tabla, columna
+        ))
+        .bind(id)
+        .fetch_one(conn)
+        .await
+    }
+
+    fn nombre(nombre: &str) -> Result<String, TaxonomiaError> {
+        let nombre = nombre.trim();
+        if nombre.is_empty() || nombre.chars().count() > MAXIMO_NOMBRE {
+            return Err(TaxonomiaError::Invalida(format!(
+                "El nombre es obligatorio (máximo {} caracteres)",
+                MAXIMO_NOMBRE
+            )));
+        }
+        Ok(nombre.to_string())
+    }
+
+    fn estado(estado: Option<String>, actual: &str) -> Result<String, TaxonomiaError> {
+        let estado = estado.map(|e| e.trim().to_lowercase()).unwrap_or_else(|| actual.to_string());
+        if !ESTADOS.contains(&estado.as_str()) {
+            return Err(TaxonomiaError::Invalida(format!(
+                "Estado inválido. Valores: {}",
+                ESTADOS.join(", ")
+            )));
+        }
+        Ok(estado)
+    }
+
+    /// Texto enviado (vacío = borrar) o el valor actual
+    fn texto(nuevo: Option<String>, actual: Option<String>) -> Option<String> {
+        match nuevo {
+            Some(texto) => Some(texto.trim().to_string()).filter(|t| !t.is_empty()),
+            None => actual,
+        }
+    }
+
+    /// Violación de unicidad como conflicto: del slug (carrera entre dos altas) o del nombre
+    fn unico(e: sqlx::Error, mensaje: &str) -> TaxonomiaError {
+        match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => {
+                if db.constraint().is_some_and(|c| c.contains("slug")) {
+                    TaxonomiaError::Conflicto("El slug ya está en uso".to_string())
+                } else {
+                    TaxonomiaError::Conflicto(mensaje.to_string())
+                }
+            }
+            _ => e.into(),
+        }
+    }
+}
+
+fn error_slug(rechazo: SlugRechazado) -> TaxonomiaError {
+    match rechazo {
+        SlugRechazado::Invalido(mensaje) => TaxonomiaError::Invalida(mensaje),
+        SlugRechazado::EnUso(mensaje) => TaxonomiaError::Conflicto(mensaje),
+    }
+}
@@ -0,0 +1,313 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use ring::rsa::PublicKeyComponents;
+use ring::signature::{Ed25519KeyPair, KeyPair, RsaKeyPair};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use simple_asn1::{from_der, oid, ASN1Block};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::config::settings::JwtSettings;
+
+// Llavero cargado una sola vez al iniciar el servidor
+static LLAVERO: OnceLock<LlaveroJwt> = OnceLock::new();
+
+/// Clave de firma/verificación identificada por su `kid`
+struct ClaveJwt {
+    algoritmo: Algorithm,
+    decodificacion: DecodingKey,
+    /// JWK pública; None para secretos HMAC, que nunca se publican
+    jwk: Option<Value>,
+}
+
+struct LlaveroJwt {
+    kid_activo: String,
+    codificacion_activa: EncodingKey,
+    /// kid usado para los tokens antiguos que no traen `kid` en el header
+    kid_legado: String,
+    claves: HashMap<String, ClaveJwt>,
+}
+
+/// Cargar las claves configuradas. Falla si alguna clave es inválida.
+/// Devuelve el kid y algoritmo de la clave activa y el total de claves.
+pub fn inicializar(config: &JwtSettings) -> Result<(String, String, usize), String> {
+    let mut claves = HashMap::new();
+
+    // Secreto principal y secretos retirados (HS256)
+    agregar_clave(&mut claves, &config.secreto_kid, clave_hmac(&config.secreto))?;
+    for (kid, secreto) in &config.secretos_anteriores {
+        agregar_clave(&mut claves, kid, clave_hmac(secreto))?;
+    }
+
+    // Claves asimétricas retiradas (solo verificación)
+    for (kid, archivo) in &config.claves_anteriores {
+        agregar_clave(&mut claves, kid, clave_publica(kid, archivo)?)?;
+    }
+
+    let (kid_activo, codificacion_activa) = match config.algoritmo.to_uppercase().as_str() {
+        "HS256" => (
+            config.secreto_kid.clone(),
+            EncodingKey::from_secret(config.secreto.as_bytes()),
+        ),
+        "EDDSA" | "RS256" => {
+            let kid = config
+                .kid
+                .clone()
+                .ok_or("JWT_KID es obligatorio con firma asimétrica".to_string())?;
+            let archivo = config
+                .clave_privada_archivo
+                .as_deref()
+                .ok_or("JWT_PRIVATE_KEY_FILE es obligatorio con firma asimétrica".to_string())?;
+
+            let (clave, codificacion) = clave_asimetrica(&kid, archivo)?;
+            let esperado = if config.algoritmo.eq_ignore_ascii_case("RS256") {
+                Algorithm::RS256
+            } else {
+                Algorithm::EdDSA
+            };
+            if clave.algoritmo != esperado {
+                return Err(format!(
+                    "La clave de {} no corresponde a JWT_ALGORITHM={}",
+                    archivo, config.algoritmo
+                ));
+            }
+
+            agregar_clave(&mut claves, &kid, clave)?;
+            (kid, codificacion)
+        }
+        otro => return Err(format!("JWT_ALGORITHM no soportado: {}", otro)),
+    };
+
+    let algoritmo = format!("{:?}", claves[&kid_activo].algoritmo);
+    let total = claves.len();
+
+    LLAVERO
+        .set(LlaveroJwt {
+            kid_activo: kid_activo.clone(),
+            codificacion_activa,
+            kid_legado: config.secreto_kid.clone(),
+            claves,
+        })
+        .map_err(|_| "Las claves JWT ya fueron inicializadas".to_string())?;
+
+    Ok((kid_activo, algoritmo, total))
+}
+
+/// Firmar claims con la clave activa, incluyendo su `kid` en el header
+pub fn firmar<T: Serialize>(claims: &T) -> Result<String, String> {
+    let llavero = llavero()?;
+    let clave = &llavero.claves[&llavero.kid_activo];
+
+    let mut header = Header::new(clave.algoritmo);
+    header.kid = Some(llavero.kid_activo.clone());
+
+    encode(&header, claims, &llavero.codificacion_activa).map_err(|e| format!("Error al generar token: {}", e))
+}
+
+/// Verificar un token con la clave indicada por su `kid`.
+/// El algoritmo lo fija la clave, no el header, para evitar confusión de algoritmos.
+pub fn verificar<T: DeserializeOwned>(token: &str) -> Result<T, String> {
+    let llavero = llavero()?;
+
+    let header = decode_header(token).map_err(|e| format!("Token inválido: {}", e))?;
+    let kid = header.kid.as_deref().unwrap_or(&llavero.kid_legado);
+
+    let clave = llavero
+        .claves
+        .get(kid)
+        .ok_or("Token inválido: clave de firma desconocida".to_string())?;
+
+    decode::<T>(token, &clave.decodificacion, &Validation::new(clave.algoritmo))
+        .map(|data| data.claims)
+        .map_err(|e| format!("Token inválido: {}", e))
+}
+
+/// Conjunto JWKS con las claves públicas (los secretos HMAC no se publican)
+pub fn jwks() -> Value {
+    let claves: Vec<&Value> = LLAVERO
+        .get()
+        .map(|llavero| llavero.claves.values().filter_map(|clave| clave.jwk.as_ref()).collect())
+        .unwrap_or_default();
+
+    json!({ "keys": claves })
+}
+
+fn llavero() -> Result<&'static LlaveroJwt, String> {
+    LLAVERO.get().ok_or("Las claves JWT no están inicializadas".to_string())
+}
+
+// Un kid repetido reemplazaría en silencio a la clave anterior y sus tokens dejarían de
+// verificarse (o se verificarían con otra clave)
+fn agregar_clave(claves: &mut HashMap<String, ClaveJwt>, kid: &str, clave: ClaveJwt) -> Result<(), String> {
+    if claves.contains_key(kid) {
+        return Err(format!("El kid '{}' está repetido en la configuración de claves JWT", kid));
+    }
+    claves.insert(kid.to_string(), clave);
+    Ok(())
+}
+
+fn clave_hmac(secreto: &str) -> ClaveJwt {
+    ClaveJwt {
+        algoritmo: Algorithm::HS256,
+        decodificacion: DecodingKey::from_secret(secreto.as_bytes()),
+        jwk: None,
+    }
+}
+
+// Cargar una clave privada PEM Ed25519 (PKCS#8) o RSA (PKCS#8 o PKCS#1)
+fn clave_asimetrica(kid: &str, archivo: &str) -> Result<(ClaveJwt, EncodingKey), String> {
+    let contenido = fs::read(archivo).map_err(|e| format!("No se pudo leer la clave {}: {}", archivo, e))?;
+    let pem = pem::parse(&contenido).map_err(|e| format!("La clave {} no es un PEM válido: {}", archivo, e))?;
+
+    if pem.tag() == "PRIVATE KEY" {
+        if let Ok(par) = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pem.contents()) {
+            let x = URL_SAFE_NO_PAD.encode(par.public_key().as_ref());
+            let clave = ClaveJwt {
+                algoritmo: Algorithm::EdDSA,
+                decodificacion: DecodingKey::from_ed_components(&x)
+                    .map_err(|e| format!("Clave Ed25519 inválida en {}: {}", archivo, e))?,
+                jwk: Some(json!({
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": x,
+                    "kid": kid,
+                    "alg": "EdDSA",
+                    "use": "sig",
+                })),
+            };
+            let codificacion = EncodingKey::from_ed_der(pem.contents());
+            return Ok((clave, codificacion));
+        }
+    }
+
+    let par = match pem.tag() {
+        "PRIVATE KEY" => RsaKeyPair::from_pkcs8(pem.contents()),
+        "RSA PRIVATE KEY" => RsaKeyPair::from_der(pem.contents()),
+        otro => return Err(format!("Tipo de clave no soportado en {}: {}", archivo, otro)),
+    }
+    .map_err(|e| format!("La clave {} no es Ed25519 ni RSA válida: {}", archivo, e))?;
+
+    let componentes: PublicKeyComponents<Vec<u8>> = par.public().into();
+    let n = URL_SAFE_NO_PAD.encode(&componentes.n);
+    let e = URL_SAFE_NO_PAD.encode(&componentes.e);
+
+    let clave = ClaveJwt {
+        algoritmo: Algorithm::RS256,
+        decodificacion: DecodingKey::from_rsa_components(&n, &e)
+            .map_err(|err| format!("Clave RSA inválida en {}: {}", archivo, err))?,
+        jwk: Some(json!({
+            "kty": "RSA",
+            "n": n,
+            "e": e,
+            "kid": kid,
+            "alg": "RS256",
+            "use": "sig",
+        })),
+    };
+    let codificacion = EncodingKey::from_rsa_pem(&contenido)
+        .map_err(|err| format!("Clave RSA inválida en {}: {}", archivo, err))?;
+
+    Ok((clave, codificacion))
+}
+
+// Cargar una clave pública PEM retirada: SPKI ("PUBLIC KEY") Ed25519, EC o RSA, o PKCS#1 ("RSA PUBLIC KEY").
+// El algoritmo se deduce del OID de la clave, nunca de la configuración.
+fn clave_publica(kid: &str, archivo: &str) -> Result<ClaveJwt, String> {
+    let contenido = fs::read(archivo).map_err(|e| format!("No se pudo leer la clave {}: {}", archivo, e))?;
+    let pem = pem::parse(&contenido).map_err(|e| format!("La clave {} no es un PEM válido: {}", archivo, e))?;
+    let invalida = |detalle: &str| format!("Clave pública inválida en {}: {}", archivo, detalle);
+
+    let (algoritmo, decodificacion, jwk) = match pem.tag() {
+        "RSA PUBLIC KEY" => {
+            let (n, e) = componentes_rsa(pem.contents()).ok_or_else(|| invalida("RSA PKCS#1 mal formada"))?;
+            let decodificacion = DecodingKey::from_rsa_pem(&contenido).map_err(|e| invalida(&e.to_string()))?;
+            (Algorithm::RS256, decodificacion, json!({ "kty": "RSA", "n": n, "e": e }))
+        }
+        "PUBLIC KEY" => {
+            let (algoritmo_oid, parametros, bits) =
+                partes_spki(pem.contents()).ok_or_else(|| invalida("SPKI mal formada"))?;
+
+            if algoritmo_oid == oid!(1, 3, 101, 112) {
+                let decodificacion = DecodingKey::from_ed_pem(&contenido).map_err(|e| invalida(&e.to_string()))?;
+                let x = URL_SAFE_NO_PAD.encode(&bits);
+                (Algorithm::EdDSA, decodificacion, json!({ "kty": "OKP", "crv": "Ed25519", "x": x }))
+            } else if algoritmo_oid == oid!(1, 2, 840, 10045, 2, 1) {
+                let (algoritmo, curva, tamano) = match parametros {
+                    Some(curva) if curva == oid!(1, 2, 840, 10045, 3, 1, 7) => (Algorithm::ES256, "P-256", 32),
+                    Some(curva) if curva == oid!(1, 3, 132, 0, 34) => (Algorithm::ES384, "P-384", 48),
+                    _ => return Err(invalida("curva EC no soportada (solo P-256 y P-384)")),
+                };
+                // Punto sin comprimir: 0x04 || x || y
+                if bits.len() != 1 + 2 * tamano || bits[0] != 0x04 {
+                    return Err(invalida("punto EC no soportado"));
+                }
+                let decodificacion = DecodingKey::from_ec_pem(&contenido).map_err(|e| invalida(&e.to_string()))?;
+                let x = URL_SAFE_NO_PAD.encode(&bits[1..=tamano]);
+                let y = URL_SAFE_NO_PAD.encode(&bits[1 + tamano..]);
+                (algoritmo, decodificacion, json!({ "kty": "EC", "crv": curva, "x": x, "y": y }))
+            } else if algoritmo_oid == oid!(1, 2, 840, 113549, 1, 1, 1) {
+                let (n, e) = componentes_rsa(&bits).ok_or_else(|| invalida("RSA mal formada"))?;
+                let decodificacion = DecodingKey::from_rsa_pem(&contenido).map_err(|e| invalida(&e.to_string()))?;
+                (Algorithm::RS256, decodificacion, json!({ "kty": "RSA", "n": n, "e": e }))
+            } else {
+                return Err(invalida("algoritmo de clave no soportado"));
+            }
+        }
+        "PRIVATE KEY" | "RSA PRIVATE KEY" | "EC PRIVATE KEY" => {
+            return Err(format!(
+                "La clave {} es privada; JWT_CLAVES_ANTERIORES solo admite claves públicas",
+                archivo
+            ))
+        }
+        otro => return Err(format!("Tipo de clave no soportado en {}: {}", archivo, otro)),
+    };
+
+    let mut jwk = jwk;
+    jwk["kid"] = json!(kid);
+    jwk["alg"] = json!(format!("{:?}", algoritmo));
+    jwk["use"] = json!("sig");
+
+    Ok(ClaveJwt {
+        algoritmo,
+        decodificacion,
+        jwk: Some(jwk),
+    })
+}
+
+// SubjectPublicKeyInfo: SEQUENCE { SEQUENCE { OID, parámetros }, BIT STRING }
+fn partes_spki(der: &[u8]) -> Option<(simple_asn1::OID, Option<simple_asn1::OID>, Vec<u8>)> {
+    let bloques = from_der(der).ok()?;
+    let [ASN1Block::Sequence(_, spki)] = bloques.as_slice() else {
+        return None;
+    };
+    let [ASN1Block::Sequence(_, identificador), ASN1Block::BitString(_, _, bits)] = spki.as_slice() else {
+        return None;
+    };
+    let (algoritmo, parametros) = match identificador.as_slice() {
+        [ASN1Block::ObjectIdentifier(_, algoritmo)] => (algoritmo, None),
+        [ASN1Block::ObjectIdentifier(_, algoritmo), ASN1Block::ObjectIdentifier(_, curva)] => {
+            (algoritmo, Some(curva.clone()))
+        }
+        [ASN1Block::ObjectIdentifier(_, algoritmo), _] => (algoritmo, None),
+        _ => return None,
+    };
+    Some((algoritmo.clone(), parametros, bits.clone()))
+}
+
+// RSAPublicKey (PKCS#1): SEQUENCE { INTEGER n, INTEGER e }, devueltos en base64url
+fn componentes_rsa(der: &[u8]) -> Option<(String, String)> {
+    let bloques = from_der(der).ok()?;
+    let [ASN1Block::Sequence(_, enteros)] = bloques.as_slice() else {
+        return None;
+    };
+    let [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] = enteros.as_slice() else {
+        return None;
+    };
+    let (_, n) = n.to_bytes_be();
+    let (_, e) = e.to_bytes_be();
+    Some((URL_SAFE_NO_PAD.encode(n), URL_SAFE_NO_PAD.encode(e)))
+}
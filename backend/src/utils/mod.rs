@@ -1 +1,7 @@
 // Utilidades globales (próximamente: errors, jwt, password, etc.)
+pub mod jwt;
+pub mod password;
+pub mod rutas;
+pub mod slug;
+pub mod totp;
+pub mod validacion;
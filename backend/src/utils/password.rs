@@ -0,0 +1,106 @@
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use crate::services::ConfigService;
+
+/// Parámetros de coste de Argon2id, configurables desde configuracion_sistema (categoría seguridad).
+/// Los valores por defecto siguen la recomendación de OWASP (19 MiB, 2 iteraciones, 1 hilo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParametrosHash {
+    pub memoria_kib: u32,
+    pub iteraciones: u32,
+    pub paralelismo: u32,
+}
+
+impl Default for ParametrosHash {
+    fn default() -> Self {
+        Self {
+            memoria_kib: 19_456,
+            iteraciones: 2,
+            paralelismo: 1,
+        }
+    }
+}
+
+impl ParametrosHash {
+    /// Cargar los parámetros de la configuración; valores ausentes o fuera de rango usan el defecto
+    pub fn cargar() -> Self {
+        let defecto = Self::default();
+        let leer = |clave: &str, defecto: u32| {
+            ConfigService::entero(clave)
+                .and_then(|v| u32::try_from(v).ok())
+                .unwrap_or(defecto)
+        };
+
+        let parametros = Self {
+            memoria_kib: leer("password_hash_memory_kib", defecto.memoria_kib),
+            iteraciones: leer("password_hash_iterations", defecto.iteraciones),
+            paralelismo: leer("password_hash_parallelism", defecto.paralelismo),
+        };
+
+        if parametros.argon2().is_err() {
+            eprintln!("⚠️  Parámetros de Argon2id inválidos en la configuración, se usan los valores por defecto");
+            return Self::default();
+        }
+
+        parametros
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, String> {
+        let params = Params::new(self.memoria_kib, self.iteraciones, self.paralelismo, None)
+            .map_err(|e| format!("Parámetros de Argon2id inválidos: {}", e))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// Generar el hash Argon2id (formato PHC) de una contraseña
+pub fn hash_password(password: &str, parametros: &ParametrosHash) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    parametros
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Error al encriptar contraseña: {}", e))
+}
+
+/// Verificar una contraseña contra un hash Argon2id o bcrypt (legado)
+pub fn verificar_password(password: &str, hash: &str) -> Result<bool, String> {
+    if es_hash_legado(hash) {
+        return bcrypt::verify(password, hash).map_err(|e| format!("Error al verificar contraseña: {}", e));
+    }
+
+    let parsed = PasswordHash::new(hash).map_err(|e| format!("Hash de contraseña inválido: {}", e))?;
+
+    // Los parámetros se toman del propio hash
+    Ok(Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+/// true si el hash es bcrypt ($2a$, $2b$, $2y$)
+pub fn es_hash_legado(hash: &str) -> bool {
+    hash.starts_with("$2")
+}
+
+/// true si el hash debe regenerarse: es bcrypt, no es Argon2id o usa otros parámetros
+pub fn necesita_rehash(hash: &str, parametros: &ParametrosHash) -> bool {
+    if es_hash_legado(hash) {
+        return true;
+    }
+
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return true;
+    };
+
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != parametros.memoria_kib
+                || params.t_cost() != parametros.iteraciones
+                || params.p_cost() != parametros.paralelismo
+        }
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::{Component, Path, PathBuf};
+
+/// `relativa` dentro de `base`: solo componentes normales, sin rutas absolutas ni `..`.
+/// Para rutas que vienen de la configuración editable desde el panel.
+pub fn ruta_confinada(base: &Path, relativa: &str) -> Result<PathBuf, String> {
+    let relativa = Path::new(relativa.trim());
+    if relativa.as_os_str().is_empty() {
+        return Err("La ruta está vacía".to_string());
+    }
+    if !relativa.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(format!(
+            "La ruta debe ser relativa a {} y no puede contener '..'",
+            base.display()
+        ));
+    }
+
+    Ok(base.join(relativa))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acepta_rutas_relativas_dentro_de_la_base() {
+        let base = Path::new("/var/log/kronos");
+        assert_eq!(ruta_confinada(base, "siem.jsonl"), Ok(base.join("siem.jsonl")));
+        assert_eq!(ruta_confinada(base, " ./siem/hoy.jsonl "), Ok(base.join("./siem/hoy.jsonl")));
+    }
+
+    #[test]
+    fn rechaza_rutas_que_escapan_de_la_base() {
+        let base = Path::new("/var/log/kronos");
+        for ruta in ["", "   ", "/etc/passwd", "../secreto", "siem/../../secreto", ".."] {
+            assert!(ruta_confinada(base, ruta).is_err(), "{:?}", ruta);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use sqlx::PgConnection;
+
+/// Largo máximo de la base del slug; deja lugar al sufijo "-N" en columnas VARCHAR(100)
+const SLUG_MAX_LENGTH: usize = 90;
+
+/// Slug para URLs: minúsculas sin tildes, letras y números separados por guiones.
+/// "Tarjetas Gráficas RTX" -> "tarjetas-graficas-rtx"
+pub fn generar_slug(texto: &str) -> String {
+    let mut slug = String::with_capacity(texto.len());
+    for c in texto.trim().to_lowercase().chars() {
+        let c = match c {
+            'á' | 'à' | 'ä' | 'â' | 'ã' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' | 'õ' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ñ' => 'n',
+            'ç' => 'c',
+            c => c,
+        };
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.truncate(SLUG_MAX_LENGTH);
+    slug.trim_end_matches('-').to_string()
+}
+
+/// `slug` ya usado en `tabla` por otra fila que `excluir`. `tabla` y `columna_id`
+/// son nombres fijos del código, nunca datos del usuario.
+pub async fn slug_en_uso(
+    conn: &mut PgConnection,
+    tabla: &str,
+    columna_id: &str,
+    slug: &str,
+    excluir: Option<i32>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM {} WHERE slug = $1 AND ($2::INTEGER IS NULL OR {} <> $2))",
+        tabla, columna_id
+    ))
+    .bind(slug)
+    .bind(excluir)
+    .fetch_one(conn)
+    .await
+}
+
+/// Slug libre a partir de `texto`: agrega "-2", "-3", ... si ya está en uso
+pub async fn slug_unico(
+    conn: &mut PgConnection,
+    tabla: &str,
+    columna_id: &str,
+    texto: &str,
+    excluir: Option<i32>,
+) -> Result<String, sqlx::Error> {
+    let base = match generar_slug(texto) {
+        base if base.is_empty() => "item".to_string(),
+        base => base,
+    };
+
+    let mut slug = base.clone();
+    let mut sufijo = 2;
+    while slug_en_uso(conn, tabla, columna_id, &slug, excluir).await? {
+        slug = format!("{}-{}", base, sufijo);
+        sufijo += 1;
+    }
+
+    Ok(slug)
+}
+
+/// Motivo por el que se rechaza un slug enviado; cada servicio lo traduce a su error
+pub enum SlugRechazado {
+    /// Sin letras ni números
+    Invalido(String),
+    /// Ya lo usa otra fila
+    EnUso(String),
+}
+
+/// Slug enviado (normalizado y libre), el actual, o uno nuevo desde el nombre. Un
+/// cambio de nombre no cambia el slug para no romper enlaces existentes.
+/// `(tabla, columna_id)` siguen la regla de `slug_en_uso`.
+pub async fn resolver_slug<E: From<sqlx::Error>>(
+    conn: &mut PgConnection,
+    (tabla, columna_id): (&str, &str),
+    solicitado: Option<&str>,
+    actual: Option<&str>,
+    nombre: &str,
+    excluir: Option<i32>,
+    error: impl FnOnce(SlugRechazado) -> E,
+) -> Result<String, E> {
+    if let Some(solicitado) = solicitado {
+        let slug = generar_slug(solicitado);
+        if slug.is_empty() {
+            return Err(error(SlugRechazado::Invalido("El slug debe tener letras o números".to_string())));
+        }
+        if slug_en_uso(conn, tabla, columna_id, &slug, excluir).await? {
+            return Err(error(SlugRechazado::EnUso(format!("El slug '{}' ya está en uso", slug))));
+        }
+        return Ok(slug);
+    }
+
+    match actual {
+        Some(actual) => Ok(actual.to_string()),
+        None => Ok(slug_unico(conn, tabla, columna_id, nombre, excluir).await?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quita_tildes_y_separa_con_guiones() {
+        assert_eq!(generar_slug("Tarjetas Gráficas RTX"), "tarjetas-graficas-rtx");
+        assert_eq!(generar_slug("  ÑANDÚ & Compañía  "), "nandu-compania");
+        assert_eq!(generar_slug("Ryzen 7 7800X3D (AM5)"), "ryzen-7-7800x3d-am5");
+        assert_eq!(generar_slug("--ya-es-slug--"), "ya-es-slug");
+    }
+
+    #[test]
+    fn sin_letras_ni_numeros_queda_vacio() {
+        assert_eq!(generar_slug(""), "");
+        assert_eq!(generar_slug(" ¿?¡! — "), "");
+    }
+
+    #[test]
+    fn se_recorta_dejando_lugar_al_sufijo() {
+        let slug = generar_slug(&"palabra ".repeat(30));
+        assert!(slug.len() <= SLUG_MAX_LENGTH);
+        assert!(!slug.ends_with('-'));
+        assert!(format!("{}-99", slug).len() <= 100);
+    }
+}
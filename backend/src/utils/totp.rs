@@ -0,0 +1,130 @@
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::{render::svg, QrCode};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Duración de cada time-step en segundos (RFC 6238)
+pub const PERIODO: i64 = 30;
+
+/// Cantidad de dígitos del código
+pub const DIGITOS: usize = 6;
+
+/// Pasos de tolerancia hacia atrás y adelante por desfase de reloj
+const VENTANA: i64 = 1;
+
+const ALFABETO_BASE32: base32::Alphabet = base32::Alphabet::Rfc4648 { padding: false };
+
+/// Generar un secreto aleatorio de 160 bits codificado en base32
+pub fn generar_secreto() -> String {
+    let mut bytes = [0u8; 20];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    base32::encode(ALFABETO_BASE32, &bytes)
+}
+
+/// Time-step correspondiente a un timestamp UNIX
+pub fn paso_actual(ahora: i64) -> i64 {
+    ahora.div_euclid(PERIODO)
+}
+
+/// Calcular el código HOTP (RFC 4226) de un paso
+fn codigo_para_paso(secreto: &[u8], paso: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secreto).expect("HMAC acepta claves de cualquier longitud");
+    mac.update(&(paso as u64).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Truncamiento dinámico
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binario = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]])
+        & 0x7fff_ffff;
+
+    format!("{:0width$}", binario % 10u32.pow(DIGITOS as u32), width = DIGITOS)
+}
+
+/// Verificar un código contra el secreto. Devuelve el time-step que coincidió,
+/// para que el llamador pueda rechazar la reutilización del mismo código.
+pub fn verificar(secreto: &str, codigo: &str, ahora: i64) -> Option<i64> {
+    let codigo = codigo.trim();
+    if codigo.len() != DIGITOS || !codigo.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let secreto = base32::decode(ALFABETO_BASE32, secreto)?;
+    let paso = paso_actual(ahora);
+
+    // Se recorre toda la ventana para no filtrar por tiempo de respuesta qué paso coincidió
+    let mut coincidencia = None;
+    for candidato in (paso - VENTANA)..=(paso + VENTANA) {
+        let esperado = codigo_para_paso(&secreto, candidato);
+        let iguales = esperado
+            .bytes()
+            .zip(codigo.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+        if iguales && coincidencia.is_none() {
+            coincidencia = Some(candidato);
+        }
+    }
+
+    coincidencia
+}
+
+/// URI otpauth:// para registrar la cuenta en una app autenticadora
+pub fn otpauth_uri(emisor: &str, cuenta: &str, secreto: &str) -> String {
+    let emisor = utf8_percent_encode(emisor, NON_ALPHANUMERIC).to_string();
+    let cuenta = utf8_percent_encode(cuenta, NON_ALPHANUMERIC).to_string();
+
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        emisor, cuenta, secreto, emisor, DIGITOS, PERIODO
+    )
+}
+
+/// Código QR de la URI en formato SVG
+pub fn qr_svg(uri: &str) -> Result<String, String> {
+    let codigo = QrCode::new(uri.as_bytes()).map_err(|e| format!("Error al generar código QR: {}", e))?;
+
+    Ok(codigo
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Secreto ASCII "12345678901234567890" de los vectores del RFC 6238, en base32
+    const SECRETO_RFC: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn verificar_acepta_vectores_rfc_6238() {
+        // Últimos 6 dígitos de los códigos SHA1 del apéndice B
+        for (ahora, codigo) in [(59, "287082"), (1111111109, "081804"), (1234567890, "005924")] {
+            assert_eq!(verificar(SECRETO_RFC, codigo, ahora), Some(paso_actual(ahora)), "t={}", ahora);
+        }
+    }
+
+    #[test]
+    fn verificar_tolera_un_paso_de_desfase() {
+        let ahora = 1111111109;
+        assert_eq!(verificar(SECRETO_RFC, "081804", ahora + PERIODO), Some(paso_actual(ahora)));
+        assert_eq!(verificar(SECRETO_RFC, "081804", ahora - PERIODO), Some(paso_actual(ahora)));
+        assert_eq!(verificar(SECRETO_RFC, "081804", ahora + 2 * PERIODO), None);
+    }
+
+    #[test]
+    fn verificar_rechaza_codigos_mal_formados() {
+        for codigo in ["", "28708", "2870821", "28708a", "+87082"] {
+            assert_eq!(verificar(SECRETO_RFC, codigo, 59), None, "{:?}", codigo);
+        }
+        assert_eq!(verificar(SECRETO_RFC, " 287082 ", 59), Some(paso_actual(59)));
+        assert_eq!(verificar("no es base32!", "287082", 59), None);
+    }
+
+    #[test]
+    fn secreto_generado_es_base32_de_160_bits() {
+        let secreto = generar_secreto();
+        assert_eq!(base32::decode(ALFABETO_BASE32, &secreto).map(|b| b.len()), Some(20));
+    }
+}
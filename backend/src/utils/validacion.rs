@@ -0,0 +1,247 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::services::ConfigService;
+
+// Lista embebida de contraseñas comunes (una por línea, en minúsculas)
+static PASSWORDS_COMUNES: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    include_str!("passwords_comunes.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+});
+
+/// Longitud máxima aceptada para una contraseña
+const PASSWORD_MAX_LENGTH: usize = 128;
+
+// ==================== ERRORES POR CAMPO ====================
+
+/// Errores de validación agrupados por campo: `{"password": ["...", "..."], "email": ["..."]}`
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct ErroresCampo(BTreeMap<String, Vec<String>>);
+
+impl ErroresCampo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn agregar(&mut self, campo: &str, mensaje: impl Into<String>) {
+        self.0.entry(campo.to_string()).or_default().push(mensaje.into());
+    }
+
+    pub fn agregar_todos(&mut self, campo: &str, mensajes: Vec<String>) {
+        for mensaje in mensajes {
+            self.agregar(campo, mensaje);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Ok(()) si no hay errores
+    pub fn into_result(self) -> Result<(), ErroresCampo> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ErroresCampo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mensajes: Vec<&str> = self.0.values().flatten().map(String::as_str).collect();
+        write!(f, "{}", mensajes.join(". "))
+    }
+}
+
+// ==================== POLÍTICA DE CONTRASEÑAS ====================
+
+/// Política de contraseñas configurable desde configuracion_sistema (categoría seguridad)
+#[derive(Debug, Clone)]
+pub struct PoliticaPassword {
+    pub min_length: usize,
+    pub requiere_mayuscula: bool,
+    pub requiere_minuscula: bool,
+    pub requiere_numero: bool,
+    pub requiere_simbolo: bool,
+}
+
+impl Default for PoliticaPassword {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            requiere_mayuscula: true,
+            requiere_minuscula: true,
+            requiere_numero: true,
+            requiere_simbolo: false,
+        }
+    }
+}
+
+impl PoliticaPassword {
+    /// Cargar la política de la configuración; las claves ausentes usan el valor por defecto
+    pub fn cargar() -> Self {
+        let defecto = Self::default();
+
+        Self {
+            min_length: ConfigService::entero("password_min_length")
+                .and_then(|v| usize::try_from(v).ok())
+                .unwrap_or(defecto.min_length),
+            requiere_mayuscula: ConfigService::booleano("password_require_uppercase")
+                .unwrap_or(defecto.requiere_mayuscula),
+            requiere_minuscula: ConfigService::booleano("password_require_lowercase")
+                .unwrap_or(defecto.requiere_minuscula),
+            requiere_numero: ConfigService::booleano("password_require_number")
+                .unwrap_or(defecto.requiere_numero),
+            requiere_simbolo: ConfigService::booleano("password_require_symbol")
+                .unwrap_or(defecto.requiere_simbolo),
+        }
+    }
+
+    /// Validar una contraseña. `datos_personales` son nombre, apellido, email, etc.
+    /// que la contraseña no debe contener. Devuelve la lista de incumplimientos.
+    pub fn validar(&self, password: &str, datos_personales: &[&str]) -> Vec<String> {
+        let mut errores = Vec::new();
+        let longitud = password.chars().count();
+
+        if longitud < self.min_length {
+            errores.push(format!(
+                "La contraseña debe tener al menos {} caracteres",
+                self.min_length
+            ));
+        }
+        if longitud > PASSWORD_MAX_LENGTH {
+            errores.push(format!(
+                "La contraseña no puede superar los {} caracteres",
+                PASSWORD_MAX_LENGTH
+            ));
+        }
+        if self.requiere_mayuscula && !password.chars().any(char::is_uppercase) {
+            errores.push("La contraseña debe incluir al menos una letra mayúscula".to_string());
+        }
+        if self.requiere_minuscula && !password.chars().any(char::is_lowercase) {
+            errores.push("La contraseña debe incluir al menos una letra minúscula".to_string());
+        }
+        if self.requiere_numero && !password.chars().any(|c| c.is_ascii_digit()) {
+            errores.push("La contraseña debe incluir al menos un número".to_string());
+        }
+        if self.requiere_simbolo && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            errores.push("La contraseña debe incluir al menos un símbolo".to_string());
+        }
+
+        let password_lower = password.to_lowercase();
+
+        if Self::es_comun(&password_lower) {
+            errores.push("La contraseña es demasiado común, elige otra".to_string());
+        }
+
+        let contiene_dato_personal = datos_personales
+            .iter()
+            .flat_map(|dato| {
+                // Del email se revisa tanto la dirección completa como la parte local
+                let dato = dato.trim().to_lowercase();
+                let local = dato.split('@').next().unwrap_or_default().to_string();
+                [dato, local]
+            })
+            .filter(|dato| dato.chars().count() >= 3)
+            .any(|dato| password_lower.contains(&dato));
+
+        if contiene_dato_personal {
+            errores.push("La contraseña no debe contener tu nombre ni tu email".to_string());
+        }
+
+        errores
+    }
+
+    // Coincidencia exacta o con sufijo numérico/símbolos ("password2024!")
+    fn es_comun(password_lower: &str) -> bool {
+        if PASSWORDS_COMUNES.contains(password_lower) {
+            return true;
+        }
+        let base = password_lower.trim_end_matches(|c: char| !c.is_alphabetic());
+        base.chars().count() >= 4 && PASSWORDS_COMUNES.contains(base)
+    }
+}
+
+// ==================== EMAIL (RFC 5322) ====================
+
+/// addr-spec de RFC 5322 sin formas obsoletas ni comentarios: dot-atom o quoted-string
+/// en la parte local; dot-atom (etiquetas de hasta 63 caracteres) o domain-literal en
+/// el dominio. Es la misma expresión del CHECK `email_valido` de la tabla usuario
+/// (ddl.sql): si cambia una, debe cambiar la otra.
+pub const PATRON_EMAIL: &str = r#"^(?:[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]+)*|"(?:[ !#-\[\]-~\t]|\\[\t -~])*")@(?:[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]{1,63}(?:\.[A-Za-z0-9!#$%&'*+/=?^_`{|}~-]{1,63})*|\[[!-Z^-~]+\])$"#;
+
+static REGEX_EMAIL: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATRON_EMAIL).expect("PATRON_EMAIL inválido"));
+
+/// Validar un email con `PATRON_EMAIL` y los límites de longitud de RFC 5321
+/// (254 en total, 64 en la parte local).
+pub fn validar_email(email: &str) -> Result<(), String> {
+    let local = email.rsplit_once('@').map_or(email, |(local, _)| local);
+
+    if email.len() <= 254 && local.len() <= 64 && REGEX_EMAIL.is_match(email) {
+        Ok(())
+    } else {
+        Err("El email no tiene un formato válido".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Los mismos casos se comprobaron contra el CHECK email_valido de la base
+    #[test]
+    fn email_acepta_formas_rfc_5322() {
+        for email in [
+            "a@b.pe",
+            "user.name+tag@example.com",
+            "o'brien@x.ie",
+            "\"john doe\"@example.com",
+            "\"a\\\"b\"@x.io",
+            "x@[192.168.0.1]",
+            "a@b",
+        ] {
+            assert!(validar_email(email).is_ok(), "{}", email);
+        }
+    }
+
+    #[test]
+    fn email_rechaza_formas_invalidas() {
+        for email in [
+            "",
+            "@b.pe",
+            "a@",
+            ".a@b.pe",
+            "a.@b.pe",
+            "a..b@b.pe",
+            "a@b..pe",
+            "a b@c.pe",
+            "ñ@b.pe",
+            "\"a\"b\"@x.io",
+            "x@[a[b]",
+        ] {
+            assert!(validar_email(email).is_err(), "{}", email);
+        }
+    }
+
+    #[test]
+    fn email_respeta_limites_de_longitud() {
+        let local = "a".repeat(64);
+        assert!(validar_email(&format!("{}@b.pe", local)).is_ok());
+        assert!(validar_email(&format!("a{}@b.pe", local)).is_err());
+
+        let etiqueta = "b".repeat(63);
+        assert!(validar_email(&format!("a@{}.pe", etiqueta)).is_ok());
+        assert!(validar_email(&format!("a@b{}.pe", etiqueta)).is_err());
+
+        let dominio = vec!["c".repeat(60); 5].join(".");
+        assert!(validar_email(&format!("a@{}", dominio)).is_err());
+    }
+}
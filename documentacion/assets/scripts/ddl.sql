@@ -8,6 +8,12 @@
 CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
 CREATE EXTENSION IF NOT EXISTS "pgcrypto";
 CREATE EXTENSION IF NOT EXISTS "pg_trgm";  -- Para búsqueda difusa
+CREATE EXTENSION IF NOT EXISTS "unaccent";  -- Búsqueda sin tildes ("portatil" = "portátil")
+
+-- Español sin tildes: unaccent antes del stemmer
+CREATE TEXT SEARCH CONFIGURATION es_unaccent (COPY = spanish);
+ALTER TEXT SEARCH CONFIGURATION es_unaccent
+    ALTER MAPPING FOR hword, hword_part, word WITH unaccent, spanish_stem;
 
 -- ============================================================================
 -- TIPOS ENUMERADOS (ENUMS)
@@ -97,9 +103,15 @@ CREATE TABLE usuario (
     activo BOOLEAN DEFAULT TRUE,
     fecha_registro TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     ultima_conexion TIMESTAMP,
+    sesiones_revocadas_desde TIMESTAMP,  -- Tokens emitidos antes de esta fecha (UTC) dejan de ser válidos
     fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     
-    CONSTRAINT email_valido CHECK (email ~* '^[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}$')
+    -- Misma expresión que PATRON_EMAIL en backend/src/utils/validacion.rs (RFC 5322 / 5321)
+    CONSTRAINT email_valido CHECK (
+        char_length(email) <= 254
+        AND char_length(substring(email from '^(.*)@')) <= 64
+        AND email ~ '^(?:[A-Za-z0-9!#$%&''*+/=?^_`{|}~-]+(?:\.[A-Za-z0-9!#$%&''*+/=?^_`{|}~-]+)*|"(?:[ !#-\[\]-~\t]|\\[\t -~])*")@(?:[A-Za-z0-9!#$%&''*+/=?^_`{|}~-]{1,63}(?:\.[A-Za-z0-9!#$%&''*+/=?^_`{|}~-]{1,63})*|\[[!-Z^-~]+\])$'
+    )
 );
 
 CREATE INDEX idx_usuario_email ON usuario(email);
@@ -146,6 +158,99 @@ CREATE TABLE administrador (
     FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE CASCADE
 );
 
+-- ============================================================================
+
+CREATE TABLE token_recuperacion (
+    id_token SERIAL PRIMARY KEY,
+    id_usuario INTEGER NOT NULL,
+    token_hash CHAR(64) NOT NULL UNIQUE,  -- SHA-256 del token enviado por email
+    fecha_expiracion TIMESTAMP NOT NULL,
+    usado BOOLEAN DEFAULT FALSE,
+    fecha_uso TIMESTAMP,
+    ip_solicitud VARCHAR(45),
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_token_recuperacion_usuario ON token_recuperacion(id_usuario, fecha_creacion DESC);
+
+COMMENT ON TABLE token_recuperacion IS 'Tokens de un solo uso para restablecer contraseña - solo se guarda el hash';
+
+CREATE TABLE autenticacion_dos_factores (
+    id_usuario INTEGER PRIMARY KEY,
+    secreto VARCHAR(64) NOT NULL,           -- Secreto TOTP en base32 (RFC 4648, sin padding)
+    activo BOOLEAN DEFAULT FALSE,           -- FALSE mientras el enrolamiento no se confirma
+    ultimo_paso_usado BIGINT,               -- Último time-step aceptado (evita reutilizar un código)
+    intentos_fallidos INTEGER DEFAULT 0,
+    bloqueado_hasta TIMESTAMP,
+    fecha_activacion TIMESTAMP,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE CASCADE
+);
+
+COMMENT ON TABLE autenticacion_dos_factores IS 'Segundo factor TOTP (RFC 6238) de administradores';
+
+CREATE TABLE codigo_recuperacion_2fa (
+    id_codigo SERIAL PRIMARY KEY,
+    id_usuario INTEGER NOT NULL,
+    codigo_hash CHAR(64) NOT NULL,          -- SHA-256 del código normalizado
+    usado BOOLEAN DEFAULT FALSE,
+    fecha_uso TIMESTAMP,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_codigo_recuperacion_2fa_usuario ON codigo_recuperacion_2fa(id_usuario, usado);
+
+COMMENT ON TABLE codigo_recuperacion_2fa IS 'Códigos de recuperación 2FA de un solo uso - solo se guarda el hash';
+
+CREATE TYPE estado_solicitud_eliminacion AS ENUM ('pendiente', 'cancelada', 'ejecutada');
+
+CREATE TABLE solicitud_eliminacion_cuenta (
+    id_solicitud SERIAL PRIMARY KEY,
+    id_usuario INTEGER NOT NULL,
+    estado estado_solicitud_eliminacion NOT NULL DEFAULT 'pendiente',
+    motivo TEXT,
+    fecha_solicitud TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    fecha_programada TIMESTAMP NOT NULL,    -- Fin del periodo de gracia (UTC)
+    fecha_cancelacion TIMESTAMP,
+    fecha_ejecucion TIMESTAMP,
+    ip_solicitud VARCHAR(45),
+    
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE RESTRICT
+);
+
+-- Solo una solicitud pendiente por usuario
+CREATE UNIQUE INDEX idx_solicitud_eliminacion_pendiente ON solicitud_eliminacion_cuenta(id_usuario) WHERE estado = 'pendiente';
+CREATE INDEX idx_solicitud_eliminacion_programada ON solicitud_eliminacion_cuenta(fecha_programada) WHERE estado = 'pendiente';
+
+COMMENT ON TABLE solicitud_eliminacion_cuenta IS 'Solicitudes de eliminación de cuenta (Ley 29733) - la cuenta se anonimiza al vencer el periodo de gracia';
+
+CREATE TABLE api_key (
+    id_api_key SERIAL PRIMARY KEY,
+    nombre VARCHAR(100) NOT NULL,            -- Integración que la usa (ERP, marketplace...)
+    prefijo VARCHAR(16) NOT NULL UNIQUE,     -- Parte visible de la clave, para identificarla
+    clave_hash CHAR(64) NOT NULL UNIQUE,     -- SHA-256 de la clave completa
+    scopes TEXT[] NOT NULL,                  -- inventario:leer, inventario:escribir, ventas:leer, ventas:escribir
+    ips_permitidas TEXT[],                   -- IPs o CIDR; NULL = cualquier IP
+    limite_por_minuto INTEGER NOT NULL DEFAULT 60 CHECK (limite_por_minuto > 0),
+    fecha_expiracion TIMESTAMP,              -- NULL = no expira
+    activo BOOLEAN DEFAULT TRUE,
+    ultimo_uso TIMESTAMP,
+    ultima_ip VARCHAR(45),
+    total_usos BIGINT DEFAULT 0,
+    creado_por INTEGER,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    fecha_revocacion TIMESTAMP,
+    
+    FOREIGN KEY (creado_por) REFERENCES usuario(id_usuario) ON DELETE SET NULL
+);
+
+COMMENT ON TABLE api_key IS 'Claves de API para integraciones servidor a servidor (header X-Api-Key) - solo se guarda el hash';
+
 -- ============================================================================
 -- TABLAS: CATÁLOGO DE PRODUCTOS
 -- ============================================================================
@@ -315,20 +420,104 @@ CREATE INDEX idx_producto_detalle_precio ON producto_detalle(precio_venta);
 CREATE INDEX idx_producto_detalle_ventas ON producto_detalle(total_vendidos DESC);
 CREATE INDEX idx_producto_detalle_slug ON producto_detalle(slug);
 
+-- Búsqueda del catálogo. documento_busqueda pondera nombre (A) > marca, modelo y SKU (B)
+-- > keywords del producto (C) > descripción (D); texto_busqueda (sin tildes, en minúsculas)
+-- sirve al respaldo por trigramas (operador <%, con este índice) cuando el texto completo no encuentra nada.
+-- Los triggers los mantienen al día.
+ALTER TABLE producto_detalle
+    ADD COLUMN documento_busqueda TSVECTOR,
+    ADD COLUMN texto_busqueda TEXT;
+
+CREATE INDEX idx_producto_detalle_documento ON producto_detalle USING gin(documento_busqueda);
+CREATE INDEX idx_producto_detalle_texto_trgm ON producto_detalle USING gin(texto_busqueda gin_trgm_ops);
+
+CREATE FUNCTION indexar_producto_detalle() RETURNS TRIGGER AS $$
+DECLARE
+    v_marca TEXT;
+    v_keywords TEXT;
+BEGIN
+    SELECT nombre INTO v_marca FROM marca WHERE id_marca = NEW.id_marca;
+    SELECT keywords INTO v_keywords FROM producto WHERE id_producto = NEW.id_producto;
+
+    NEW.documento_busqueda :=
+        setweight(to_tsvector('es_unaccent', COALESCE(NEW.nombre, '')), 'A') ||
+        setweight(to_tsvector('es_unaccent', concat_ws(' ', v_marca, NEW.modelo, NEW.sku)), 'B') ||
+        setweight(to_tsvector('es_unaccent', COALESCE(v_keywords, '')), 'C') ||
+        setweight(to_tsvector('es_unaccent', COALESCE(NEW.descripcion, '')), 'D');
+    NEW.texto_busqueda := lower(unaccent(concat_ws(' ', NEW.nombre, v_marca, NEW.modelo, NEW.sku)));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_producto_detalle_indexar
+    BEFORE INSERT OR UPDATE OF nombre, descripcion, modelo, sku, id_marca, id_producto, documento_busqueda
+    ON producto_detalle
+    FOR EACH ROW EXECUTE FUNCTION indexar_producto_detalle();
+
+-- Cambios en producto o marca reindexan sus variantes (documento_busqueda = NULL dispara el trigger)
+CREATE FUNCTION reindexar_variantes_producto() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE producto_detalle SET documento_busqueda = NULL WHERE id_producto = NEW.id_producto;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_producto_reindexar
+    AFTER UPDATE OF keywords ON producto
+    FOR EACH ROW EXECUTE FUNCTION reindexar_variantes_producto();
+
+CREATE FUNCTION reindexar_variantes_marca() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE producto_detalle SET documento_busqueda = NULL WHERE id_marca = NEW.id_marca;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_marca_reindexar
+    AFTER UPDATE OF nombre ON marca
+    FOR EACH ROW EXECUTE FUNCTION reindexar_variantes_marca();
+
 -- ============================================================================
 
+-- Esquema de especificaciones de cada categoría: qué atributos se cargan en sus
+-- productos, de qué tipo y si sirven para filtrar (facetas) o comparar
+CREATE TABLE atributo_especificacion (
+    id_atributo SERIAL PRIMARY KEY,
+    id_categoria INTEGER NOT NULL,
+    nombre VARCHAR(100) NOT NULL,  -- clave del atributo ("socket", "nucleos")
+    etiqueta VARCHAR(100) NOT NULL,  -- nombre visible ("Socket", "Núcleos")
+    unidad_medida VARCHAR(50),
+    tipo_dato VARCHAR(20) NOT NULL DEFAULT 'texto' CHECK (tipo_dato IN ('texto', 'numero', 'booleano', 'opcion')),
+    valores_permitidos TEXT[],  -- opciones válidas cuando tipo_dato = 'opcion'
+    obligatorio BOOLEAN NOT NULL DEFAULT FALSE,
+    filtrable BOOLEAN NOT NULL DEFAULT FALSE,
+    comparable BOOLEAN NOT NULL DEFAULT TRUE,
+    orden INTEGER DEFAULT 0,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (id_categoria) REFERENCES categoria(id_categoria) ON DELETE CASCADE,
+    UNIQUE (id_categoria, nombre)
+);
+
+CREATE INDEX idx_atributo_especificacion_categoria ON atributo_especificacion(id_categoria);
+
 CREATE TABLE especificacion_producto (
     id_especificacion SERIAL PRIMARY KEY,
     id_producto_detalle INTEGER NOT NULL,
+    id_atributo INTEGER,
     nombre_atributo VARCHAR(100) NOT NULL,
     valor_atributo TEXT NOT NULL,
     unidad_medida VARCHAR(50),
     orden INTEGER DEFAULT 0,
     
-    FOREIGN KEY (id_producto_detalle) REFERENCES producto_detalle(id_producto_detalle) ON DELETE CASCADE
+    FOREIGN KEY (id_producto_detalle) REFERENCES producto_detalle(id_producto_detalle) ON DELETE CASCADE,
+    FOREIGN KEY (id_atributo) REFERENCES atributo_especificacion(id_atributo) ON DELETE RESTRICT,
+    UNIQUE (id_producto_detalle, nombre_atributo)
 );
 
 CREATE INDEX idx_especificacion_producto_detalle ON especificacion_producto(id_producto_detalle);
+CREATE INDEX idx_especificacion_producto_atributo ON especificacion_producto(id_atributo);
 
 -- ============================================================================
 
@@ -887,14 +1076,87 @@ CREATE TABLE lista_deseos (
 CREATE INDEX idx_lista_deseos_usuario ON lista_deseos(id_usuario);
 CREATE INDEX idx_lista_deseos_producto ON lista_deseos(id_producto_detalle);
 
+-- ============================================================================
+-- TABLAS: ARMA TU PC
+-- ============================================================================
+
+-- Ranuras de un armado: cada una admite productos de una categoría
+CREATE TABLE ranura_armado (
+    id_ranura SERIAL PRIMARY KEY,
+    codigo VARCHAR(50) NOT NULL UNIQUE,  -- "cpu", "placa_madre", "fuente"
+    nombre VARCHAR(100) NOT NULL,
+    id_categoria INTEGER NOT NULL,
+    obligatoria BOOLEAN NOT NULL DEFAULT TRUE,  -- necesaria para considerar el armado completo
+    cantidad_maxima INTEGER NOT NULL DEFAULT 1 CHECK (cantidad_maxima > 0),
+    orden INTEGER DEFAULT 0,
+
+    FOREIGN KEY (id_categoria) REFERENCES categoria(id_categoria) ON DELETE RESTRICT
+);
+
+-- Reglas de compatibilidad entre especificaciones de las ranuras:
+--   igual             valor de origen = valor de destino (sin distinguir mayúsculas)
+--   incluido          valor de origen está en la lista separada por comas del destino
+--   menor_igual       origen * (1 + margen) <= destino
+--   suma_menor_igual  suma de los atributos de origen en todo el armado * (1 + margen) <= destino
+CREATE TABLE regla_compatibilidad (
+    id_regla SERIAL PRIMARY KEY,
+    codigo VARCHAR(50) NOT NULL UNIQUE,
+    descripcion VARCHAR(255) NOT NULL,
+    ranura_origen VARCHAR(50),  -- NULL solo en suma_menor_igual: suma en todas las ranuras
+    atributos_origen TEXT[] NOT NULL,
+    ranura_destino VARCHAR(50) NOT NULL,
+    atributo_destino VARCHAR(100) NOT NULL,
+    operador VARCHAR(20) NOT NULL CHECK (operador IN ('igual', 'incluido', 'menor_igual', 'suma_menor_igual')),
+    margen_porcentaje DECIMAL(5,2) NOT NULL DEFAULT 0,
+    severidad VARCHAR(20) NOT NULL DEFAULT 'error' CHECK (severidad IN ('error', 'advertencia')),
+    activo BOOLEAN NOT NULL DEFAULT TRUE,
+
+    FOREIGN KEY (ranura_origen) REFERENCES ranura_armado(codigo) ON DELETE CASCADE,
+    FOREIGN KEY (ranura_destino) REFERENCES ranura_armado(codigo) ON DELETE CASCADE,
+    CONSTRAINT origen_requerido CHECK (ranura_origen IS NOT NULL OR operador = 'suma_menor_igual')
+);
+
+CREATE TABLE armado_pc (
+    id_armado SERIAL PRIMARY KEY,
+    id_usuario INTEGER NOT NULL,
+    nombre VARCHAR(150) NOT NULL,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_armado_pc_usuario ON armado_pc(id_usuario);
+
+-- Un producto por ranura
+CREATE TABLE armado_pc_detalle (
+    id_armado_detalle SERIAL PRIMARY KEY,
+    id_armado INTEGER NOT NULL,
+    id_ranura INTEGER NOT NULL,
+    id_producto_detalle INTEGER NOT NULL,
+    cantidad INTEGER NOT NULL DEFAULT 1 CHECK (cantidad > 0),
+    fecha_agregado TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (id_armado) REFERENCES armado_pc(id_armado) ON DELETE CASCADE,
+    FOREIGN KEY (id_ranura) REFERENCES ranura_armado(id_ranura) ON DELETE CASCADE,
+    FOREIGN KEY (id_producto_detalle) REFERENCES producto_detalle(id_producto_detalle) ON DELETE CASCADE,
+    UNIQUE (id_armado, id_ranura)
+);
+
+CREATE INDEX idx_armado_pc_detalle_armado ON armado_pc_detalle(id_armado);
+
+COMMENT ON TABLE regla_compatibilidad IS 'Reglas del verificador de compatibilidad de "arma tu PC" sobre las especificaciones de cada ranura';
+
 -- ============================================================================
 -- TABLAS: LOGS Y AUDITORÍA
 -- ============================================================================
 
 CREATE TYPE nivel_log AS ENUM ('info', 'warning', 'error', 'success', 'security');
 
+CREATE SEQUENCE log_auditoria_id_log_seq;
+
 CREATE TABLE log_auditoria (
-    id_log SERIAL PRIMARY KEY,
+    id_log INTEGER PRIMARY KEY,  -- Lo asigna el trigger de encadenamiento, en orden de la cadena
     nivel nivel_log NOT NULL DEFAULT 'info',
     accion VARCHAR(200) NOT NULL,
     detalles TEXT,
@@ -903,19 +1165,179 @@ CREATE TABLE log_auditoria (
     email_usuario VARCHAR(255),  -- Snapshot del email para auditoría
     ip_cliente VARCHAR(45),
     user_agent TEXT,
+    origen VARCHAR(10) NOT NULL DEFAULT 'servidor' CHECK (origen IN ('servidor', 'cliente')),
+    metodo_http VARCHAR(10),
+    ruta TEXT,
+    entidad VARCHAR(50),
+    id_entidad VARCHAR(100),
+    cambios JSONB,  -- {"campo": {"antes": ..., "despues": ...}}
+    codigo_respuesta SMALLINT,
     fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    hash_anterior CHAR(64),  -- hash de la entrada previa (NULL solo en la primera de la cadena)
+    hash CHAR(64) NOT NULL,
+    hash_datos_personales CHAR(64) NOT NULL,  -- digest de las columnas con datos personales al insertar
+    redactado BOOLEAN NOT NULL DEFAULT FALSE,  -- datos personales eliminados (derecho al olvido)
     
     FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE SET NULL
 );
 
+ALTER SEQUENCE log_auditoria_id_log_seq OWNED BY log_auditoria.id_log;
+
 CREATE INDEX idx_log_nivel ON log_auditoria(nivel);
 CREATE INDEX idx_log_modulo ON log_auditoria(modulo);
 CREATE INDEX idx_log_usuario ON log_auditoria(id_usuario);
 CREATE INDEX idx_log_fecha ON log_auditoria(fecha_creacion DESC);
 CREATE INDEX idx_log_accion ON log_auditoria(accion);
+CREATE INDEX idx_log_entidad ON log_auditoria(entidad, id_entidad);
+CREATE INDEX idx_log_ip ON log_auditoria(ip_cliente varchar_pattern_ops);
+-- Reglas de detección de anomalías: logins por IP dentro de una ventana
+CREATE INDEX idx_log_login ON log_auditoria(accion, ip_cliente, fecha_creacion) WHERE origen = 'servidor';
+CREATE INDEX idx_log_email_trgm ON log_auditoria USING GIN (email_usuario gin_trgm_ops);
+CREATE INDEX idx_log_busqueda ON log_auditoria
+    USING GIN (to_tsvector('spanish', accion || ' ' || COALESCE(detalles, '')));
 
 COMMENT ON TABLE log_auditoria IS 'Tabla de logs para auditoría del sistema';
 COMMENT ON COLUMN log_auditoria.email_usuario IS 'Snapshot del email - se mantiene aunque el usuario sea eliminado';
+COMMENT ON COLUMN log_auditoria.origen IS 'servidor: registrado por el backend; cliente: enviado por el frontend a POST /api/logs (no confiable)';
+COMMENT ON COLUMN log_auditoria.cambios IS 'Diferencias de la fila afectada antes y después de la solicitud (middleware de auditoría)';
+COMMENT ON COLUMN log_auditoria.hash IS 'SHA-256 del contenido de la entrada y de hash_anterior: editar o borrar una entrada rompe la cadena';
+COMMENT ON COLUMN log_auditoria.hash_datos_personales IS 'SHA-256 de los datos personales al insertar; se conserva al redactar para que el hash siga verificando el resto';
+
+-- Digest de las columnas con datos personales, las únicas que cambia la redacción
+CREATE FUNCTION hash_datos_personales_log(l log_auditoria) RETURNS CHAR(64) AS $$
+    SELECT encode(sha256(convert_to(
+        jsonb_build_object(
+            'email_usuario', l.email_usuario,
+            'ip_cliente', l.ip_cliente,
+            'user_agent', l.user_agent,
+            'detalles', l.detalles,
+            'cambios', l.cambios
+        )::text,
+        'UTF8'
+    )), 'hex')
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Hash de una entrada: contenido canónico (jsonb ordena las claves) sin los datos
+-- personales, más su digest guardado, encadenado con el hash previo. Así el resto
+-- de la entrada se sigue verificando después de redactarla.
+CREATE FUNCTION hash_log_auditoria(l log_auditoria) RETURNS CHAR(64) AS $$
+    SELECT encode(sha256(convert_to(
+        COALESCE(l.hash_anterior, '') || jsonb_build_object(
+            'id_log', l.id_log,
+            'nivel', l.nivel,
+            'accion', l.accion,
+            'modulo', l.modulo,
+            'id_usuario', l.id_usuario,
+            'origen', l.origen,
+            'metodo_http', l.metodo_http,
+            'ruta', l.ruta,
+            'entidad', l.entidad,
+            'id_entidad', l.id_entidad,
+            'codigo_respuesta', l.codigo_respuesta,
+            'fecha_creacion', l.fecha_creacion
+        )::text || l.hash_datos_personales,
+        'UTF8'
+    )), 'hex')
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Encadenar cada nueva entrada. El lock serializa las inserciones para que el orden
+-- de id_log coincida con el de la cadena; el id se asigna aquí, después del lock.
+CREATE FUNCTION encadenar_log_auditoria() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('log_auditoria'));
+
+    NEW.id_log := nextval('log_auditoria_id_log_seq');
+    NEW.fecha_creacion := COALESCE(NEW.fecha_creacion, CURRENT_TIMESTAMP);
+    NEW.redactado := FALSE;
+    NEW.hash_anterior := (SELECT hash FROM log_auditoria ORDER BY id_log DESC LIMIT 1);
+    IF NEW.hash_anterior IS NULL THEN
+        -- Tras archivar todo el log, la cadena continúa desde el último archivo
+        NEW.hash_anterior := (SELECT hash_final FROM archivo_log_auditoria ORDER BY hasta_id DESC LIMIT 1);
+    END IF;
+    NEW.hash_datos_personales := hash_datos_personales_log(NEW);
+    NEW.hash := hash_log_auditoria(NEW);
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Solo se permite: redactar datos personales y purgar entradas ya archivadas
+-- desde el proceso de retención (kronos.purga_auditoria). La redacción borra todas
+-- las columnas con datos personales (email_usuario, ip_cliente, user_agent, detalles,
+-- cambios) y no toca nada más: el hash sigue cubriendo el resto de la fila, y una
+-- entrada redactada no puede quedar con contenido distinto del original.
+CREATE FUNCTION proteger_log_auditoria() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        IF current_setting('kronos.purga_auditoria', TRUE) = 'on' THEN
+            RETURN OLD;
+        END IF;
+        RAISE EXCEPTION 'log_auditoria es de solo inserción: use la política de retención';
+    END IF;
+
+    IF NOT NEW.redactado THEN
+        RAISE EXCEPTION 'log_auditoria es de solo inserción: solo se permite redactar datos personales';
+    END IF;
+
+    IF NEW.email_usuario IS NOT NULL OR NEW.ip_cliente IS NOT NULL OR NEW.user_agent IS NOT NULL
+       OR NEW.detalles IS NOT NULL OR NEW.cambios IS NOT NULL THEN
+        RAISE EXCEPTION 'log_auditoria: la redacción debe borrar email_usuario, ip_cliente, user_agent, detalles y cambios';
+    END IF;
+
+    IF (NEW.id_log, NEW.nivel, NEW.accion, NEW.modulo, NEW.id_usuario, NEW.origen, NEW.metodo_http,
+        NEW.ruta, NEW.entidad, NEW.id_entidad, NEW.codigo_respuesta, NEW.fecha_creacion,
+        NEW.hash_anterior, NEW.hash, NEW.hash_datos_personales)
+       IS DISTINCT FROM
+       (OLD.id_log, OLD.nivel, OLD.accion, OLD.modulo, OLD.id_usuario, OLD.origen, OLD.metodo_http,
+        OLD.ruta, OLD.entidad, OLD.id_entidad, OLD.codigo_respuesta, OLD.fecha_creacion,
+        OLD.hash_anterior, OLD.hash, OLD.hash_datos_personales) THEN
+        RAISE EXCEPTION 'log_auditoria: la redacción solo puede borrar los datos personales';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_log_auditoria_encadenar
+    BEFORE INSERT ON log_auditoria
+    FOR EACH ROW EXECUTE FUNCTION encadenar_log_auditoria();
+
+CREATE TRIGGER trg_log_auditoria_proteger
+    BEFORE UPDATE OR DELETE ON log_auditoria
+    FOR EACH ROW EXECUTE FUNCTION proteger_log_auditoria();
+
+-- Archivos JSONL comprimidos con las entradas purgadas por la política de retención
+CREATE TABLE archivo_log_auditoria (
+    id_archivo SERIAL PRIMARY KEY,
+    nombre_archivo VARCHAR(255) NOT NULL,
+    desde_id INTEGER NOT NULL,
+    hasta_id INTEGER NOT NULL,
+    total_entradas INTEGER NOT NULL,
+    hash_final CHAR(64) NOT NULL,  -- hash de la entrada hasta_id: ancla de la cadena que sigue en BD
+    sha256_archivo CHAR(64) NOT NULL,
+    fecha_desde TIMESTAMP,
+    fecha_hasta TIMESTAMP,
+    creado_por INTEGER,  -- NULL si lo ejecutó la tarea programada
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (creado_por) REFERENCES usuario(id_usuario) ON DELETE SET NULL
+);
+
+COMMENT ON TABLE archivo_log_auditoria IS 'Entradas de log_auditoria archivadas en disco antes de purgarlas';
+
+-- Avance del reenvío de log_auditoria al SIEM (una sola fila)
+CREATE TABLE reenvio_siem (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    ultimo_id_log INTEGER NOT NULL,  -- última entrada enviada
+    fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Avance de la detección de anomalías sobre log_auditoria (una sola fila)
+CREATE TABLE deteccion_anomalias (
+    id SMALLINT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    ultimo_id_log INTEGER NOT NULL,  -- última entrada evaluada
+    fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
 
 -- ============================================================================
 -- TABLAS: CONFIGURACIÓN DEL SISTEMA
@@ -925,7 +1347,7 @@ CREATE TABLE configuracion_sistema (
     id_config SERIAL PRIMARY KEY,
     clave VARCHAR(100) NOT NULL UNIQUE,
     valor TEXT NOT NULL,
-    tipo VARCHAR(20) NOT NULL DEFAULT 'string', -- string, number, boolean, json
+    tipo VARCHAR(20) NOT NULL DEFAULT 'string' CHECK (tipo IN ('string', 'number', 'boolean', 'json')),
     descripcion TEXT,
     categoria VARCHAR(50) NOT NULL DEFAULT 'general',
     fecha_actualizacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
@@ -937,4 +1359,113 @@ CREATE TABLE configuracion_sistema (
 CREATE INDEX idx_config_clave ON configuracion_sistema(clave);
 CREATE INDEX idx_config_categoria ON configuracion_sistema(categoria);
 
-COMMENT ON TABLE configuracion_sistema IS 'Configuración global del sistema - editable desde el panel de admin';
\ No newline at end of file
+-- Avisar a los servidores (LISTEN configuracion_sistema) qué clave cambió para que recarguen su cache
+CREATE FUNCTION notificar_configuracion() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('configuracion_sistema', COALESCE(NEW.clave, OLD.clave));
+    IF TG_OP = 'UPDATE' AND NEW.clave <> OLD.clave THEN
+        PERFORM pg_notify('configuracion_sistema', OLD.clave);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notificar_configuracion
+    AFTER INSERT OR UPDATE OR DELETE ON configuracion_sistema
+    FOR EACH ROW EXECUTE FUNCTION notificar_configuracion();
+
+-- Versiones de cada clave. Las escribe el trigger, así que también quedan los cambios
+-- hechos por SQL. El servidor indica autor, motivo y lote con variables de la transacción
+-- (kronos.config_usuario, kronos.config_motivo, kronos.config_lote, kronos.config_revierte_*).
+CREATE SEQUENCE historial_configuracion_lote_seq;
+
+CREATE TABLE historial_configuracion (
+    id_historial SERIAL PRIMARY KEY,
+    id_lote INTEGER NOT NULL,  -- cambios aplicados juntos (una solicitud o una transacción)
+    clave VARCHAR(100) NOT NULL,
+    valor_anterior TEXT,  -- NULL: alta de la clave
+    valor_nuevo TEXT,  -- NULL: baja de la clave
+    motivo TEXT,
+    id_usuario INTEGER,
+    revierte_historial INTEGER,  -- versión restaurada por un rollback de clave
+    revierte_lote INTEGER,  -- lote deshecho por un rollback de lote
+    fecha_cambio TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (id_usuario) REFERENCES usuario(id_usuario) ON DELETE SET NULL,
+    FOREIGN KEY (revierte_historial) REFERENCES historial_configuracion(id_historial)
+);
+
+CREATE INDEX idx_historial_config_clave ON historial_configuracion(clave, id_historial DESC);
+CREATE INDEX idx_historial_config_lote ON historial_configuracion(id_lote);
+
+CREATE FUNCTION versionar_configuracion() RETURNS TRIGGER AS $$
+DECLARE
+    lote INTEGER := NULLIF(current_setting('kronos.config_lote', true), '')::INTEGER;
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.clave = OLD.clave AND NEW.valor IS NOT DISTINCT FROM OLD.valor THEN
+        RETURN NULL;
+    END IF;
+
+    -- Sin lote indicado, todos los cambios de la transacción comparten uno nuevo
+    IF lote IS NULL THEN
+        lote := nextval('historial_configuracion_lote_seq');
+        PERFORM set_config('kronos.config_lote', lote::TEXT, true);
+    END IF;
+
+    INSERT INTO historial_configuracion (
+        id_lote, clave, valor_anterior, valor_nuevo, motivo, id_usuario, revierte_historial, revierte_lote
+    ) VALUES (
+        lote,
+        COALESCE(NEW.clave, OLD.clave),
+        CASE WHEN TG_OP = 'INSERT' THEN NULL ELSE OLD.valor END,
+        CASE WHEN TG_OP = 'DELETE' THEN NULL ELSE NEW.valor END,
+        NULLIF(current_setting('kronos.config_motivo', true), ''),
+        NULLIF(current_setting('kronos.config_usuario', true), '')::INTEGER,
+        NULLIF(current_setting('kronos.config_revierte_historial', true), '')::INTEGER,
+        NULLIF(current_setting('kronos.config_revierte_lote', true), '')::INTEGER
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER versionar_configuracion
+    AFTER INSERT OR UPDATE OR DELETE ON configuracion_sistema
+    FOR EACH ROW EXECUTE FUNCTION versionar_configuracion();
+
+COMMENT ON TABLE historial_configuracion IS 'Versiones de configuracion_sistema: valor anterior y nuevo, autor, motivo y lote';
+
+COMMENT ON TABLE configuracion_sistema IS 'Configuración global del sistema - editable desde el panel de admin';
+
+-- ============================================================================
+-- TÉRMINOS DE BÚSQUEDA
+-- ============================================================================
+
+-- Búsquedas del catálogo agregadas por término normalizado (minúsculas, sin tildes).
+-- No guarda usuario, IP ni sesión: solo cuántas veces se buscó y con qué resultado.
+CREATE TABLE termino_busqueda (
+    termino VARCHAR(100) PRIMARY KEY,
+    total_busquedas INTEGER NOT NULL DEFAULT 0,
+    total_buscadores INTEGER NOT NULL DEFAULT 0,  -- clientes distintos (ver buscador_termino)
+    bloqueado BOOLEAN NOT NULL DEFAULT FALSE,  -- un admin lo excluyó de las sugerencias
+    busquedas_sin_resultados INTEGER NOT NULL DEFAULT 0,
+    ultimos_resultados INTEGER NOT NULL DEFAULT 0,  -- resultados de la búsqueda más reciente
+    primera_busqueda TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    ultima_busqueda TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_termino_busqueda_prefijo ON termino_busqueda(termino text_pattern_ops);
+CREATE INDEX idx_termino_busqueda_popular ON termino_busqueda(total_busquedas DESC);
+
+-- Quién buscó cada término, para contar clientes distintos. La huella es un hash de
+-- la IP con una sal que solo existe en memoria: no se puede volver a la IP.
+CREATE TABLE buscador_termino (
+    termino VARCHAR(100) NOT NULL,
+    huella CHAR(64) NOT NULL,
+    fecha_creacion TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (termino, huella),
+    FOREIGN KEY (termino) REFERENCES termino_busqueda(termino) ON DELETE CASCADE
+);
+
+COMMENT ON TABLE termino_busqueda IS 'Términos buscados en el catálogo (anonimizados) para sugerencias y análisis de búsquedas sin resultados';
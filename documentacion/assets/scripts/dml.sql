@@ -60,7 +60,10 @@ INSERT INTO categoria (nombre, descripcion, id_familia, orden, estado) VALUES
 ('Memoria RAM', 'DDR4 y DDR5', 1, 4, 'activo'),
 ('SSD', 'Unidades de estado sólido', 2, 1, 'activo'),
 ('Teclados', 'Teclados mecánicos y gaming', 3, 1, 'activo'),
-('Mouse', 'Mouse gaming', 3, 2, 'activo');
+('Mouse', 'Mouse gaming', 3, 2, 'activo'),
+('Placas Madre', 'Motherboards Intel y AMD', 1, 3, 'activo'),
+('Fuentes de Poder', 'Fuentes certificadas 80 Plus', 1, 5, 'activo'),
+('Gabinetes', 'Cases ATX, Micro-ATX y Mini-ITX', 1, 6, 'activo');
 
 INSERT INTO subcategoria (nombre, id_categoria, orden, estado) VALUES
 ('Intel Core i5', 1, 1, 'activo'),
@@ -99,7 +102,12 @@ INSERT INTO producto (nombre, descripcion, id_categoria, id_subcategoria, especi
 ('Corsair Vengeance DDR5', 'Memoria RAM DDR5 alto rendimiento', 3, NULL, '{"tipo": "DDR5", "frecuencia": "5600 MHz"}', 4.6, 78, 'activo'),
 ('Samsung 980 PRO', 'SSD NVMe PCIe 4.0', 4, NULL, '{"interfaz": "NVMe PCIe 4.0", "lectura": "7000 MB/s"}', 4.8, 124, 'activo'),
 ('Logitech G Pro X', 'Teclado mecánico gaming', 5, NULL, '{"tipo_switch": "GX", "iluminacion": "RGB"}', 4.6, 89, 'activo'),
-('Razer DeathAdder V3', 'Mouse gaming ergonómico', 6, NULL, '{"sensor": "Focus Pro 30K", "dpi_max": 30000}', 4.7, 112, 'activo');
+('Razer DeathAdder V3', 'Mouse gaming ergonómico', 6, NULL, '{"sensor": "Focus Pro 30K", "dpi_max": 30000}', 4.7, 112, 'activo'),
+('ASUS TUF Gaming B760-PLUS', 'Placa madre Intel B760 para 12ª y 13ª gen', 7, NULL, '{"socket": "LGA1700", "tipo_memoria": "DDR5"}', 4.5, 21, 'activo'),
+('MSI MAG B650M Mortar', 'Placa madre AMD B650 Micro-ATX', 7, NULL, '{"socket": "AM5", "tipo_memoria": "DDR5"}', 4.6, 17, 'activo'),
+('Corsair RMe', 'Fuente modular 80 Plus Gold', 8, NULL, '{"certificacion": "80 Plus Gold"}', 4.7, 40, 'activo'),
+('Corsair 4000D Airflow', 'Gabinete ATX de alto flujo de aire', 9, NULL, '{"formatos_soportados": "ATX, Micro-ATX, Mini-ITX"}', 4.8, 56, 'activo'),
+('MSI MAG Forge M100R', 'Gabinete Micro-ATX compacto', 9, NULL, '{"formatos_soportados": "Micro-ATX, Mini-ITX"}', 4.3, 12, 'activo');
 
 -- ============================================================================
 -- 6. PRODUCTOS DETALLE
@@ -126,7 +134,86 @@ INSERT INTO producto_detalle (nombre, modelo, sku, id_producto, id_marca, precio
 -- Teclado gaming
 ('Logitech G Pro X TKL', 'G-PKB-003', 'KBD-LOGI-GPROX-TKL', 9, 8, 799.00, 749.00, 600.00, 0.8, 24, TRUE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587829741301-dc798b83add3?w=400&h=300&fit=crop'),
 -- Mouse gaming
-('Razer DeathAdder V3 Pro', 'RZ01-04630100-R3U1', 'MOU-RAZ-DAV3-PRO', 10, 9, 649.00, 599.00, 450.00, 0.06, 24, TRUE, FALSE, 'activo', 'https://images.unsplash.com/photo-1527864550417-7fd91fc51a46?w=400&h=300&fit=crop');
+('Razer DeathAdder V3 Pro', 'RZ01-04630100-R3U1', 'MOU-RAZ-DAV3-PRO', 10, 9, 649.00, 599.00, 450.00, 0.06, 24, TRUE, FALSE, 'activo', 'https://images.unsplash.com/photo-1527864550417-7fd91fc51a46?w=400&h=300&fit=crop'),
+-- Placas madre
+('ASUS TUF Gaming B760-PLUS WiFi', '90MB1DB0-M0EAY0', 'MB-ASUS-B760-TUF', 11, 4, 999.00, 949.00, 750.00, 1.3, 36, FALSE, TRUE, 'activo', 'https://images.unsplash.com/photo-1518770660439-4636190af475?w=400&h=300&fit=crop'),
+('MSI MAG B650M Mortar WiFi', '7D76-001R', 'MB-MSI-B650M-MORTAR', 12, 5, 1049.00, 999.00, 800.00, 1.1, 36, FALSE, TRUE, 'activo', 'https://images.unsplash.com/photo-1518770660439-4636190af475?w=400&h=300&fit=crop'),
+-- Fuentes de poder
+('Corsair RM650e', 'CP-9020262-NA', 'PSU-CORS-RM650E', 13, 6, 449.00, 419.00, 320.00, 1.6, 84, FALSE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587202372634-32705e3bf49c?w=400&h=300&fit=crop'),
+('Corsair RM850e', 'CP-9020263-NA', 'PSU-CORS-RM850E', 13, 6, 599.00, 559.00, 430.00, 1.7, 84, FALSE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587202372634-32705e3bf49c?w=400&h=300&fit=crop'),
+('Corsair RM450e', 'CP-9020261-NA', 'PSU-CORS-RM450E', 13, 6, 349.00, 329.00, 250.00, 1.5, 84, FALSE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587202372634-32705e3bf49c?w=400&h=300&fit=crop'),
+-- Gabinetes
+('Corsair 4000D Airflow Negro', 'CC-9011200-WW', 'CASE-CORS-4000D', 14, 6, 449.00, 429.00, 320.00, 7.8, 24, TRUE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587202372775-e229f172b9d7?w=400&h=300&fit=crop'),
+('MSI MAG Forge M100R', '306-7G18R21-W57', 'CASE-MSI-FORGE-M100R', 15, 5, 259.00, 239.00, 170.00, 4.9, 12, FALSE, FALSE, 'activo', 'https://images.unsplash.com/photo-1587202372775-e229f172b9d7?w=400&h=300&fit=crop');
+
+-- Esquema de especificaciones por categoría
+INSERT INTO atributo_especificacion (id_categoria, nombre, etiqueta, unidad_medida, tipo_dato, valores_permitidos, obligatorio, filtrable, comparable, orden) VALUES
+-- Procesadores
+(1, 'socket', 'Socket', NULL, 'opcion', ARRAY['LGA1700', 'AM5', 'AM4'], TRUE, TRUE, TRUE, 1),
+(1, 'nucleos', 'Núcleos', NULL, 'numero', NULL, TRUE, TRUE, TRUE, 2),
+(1, 'hilos', 'Hilos', NULL, 'numero', NULL, FALSE, FALSE, TRUE, 3),
+(1, 'tdp', 'TDP', 'W', 'numero', NULL, FALSE, FALSE, TRUE, 4),
+(1, 'tipo_memoria', 'Memoria compatible', NULL, 'opcion', ARRAY['DDR4', 'DDR5'], FALSE, TRUE, TRUE, 5),
+-- Tarjetas gráficas
+(2, 'memoria', 'Memoria', NULL, 'texto', NULL, TRUE, TRUE, TRUE, 1),
+(2, 'cuda_cores', 'Núcleos CUDA', NULL, 'numero', NULL, FALSE, FALSE, TRUE, 2),
+(2, 'consumo', 'Consumo', 'W', 'numero', NULL, FALSE, FALSE, TRUE, 3),
+(2, 'largo', 'Largo', 'mm', 'numero', NULL, FALSE, FALSE, TRUE, 4),
+-- Memoria RAM
+(3, 'tipo', 'Tipo', NULL, 'opcion', ARRAY['DDR4', 'DDR5'], TRUE, TRUE, TRUE, 1),
+(3, 'capacidad', 'Capacidad', 'GB', 'numero', NULL, TRUE, TRUE, TRUE, 2),
+(3, 'frecuencia', 'Frecuencia', 'MHz', 'numero', NULL, FALSE, TRUE, TRUE, 3),
+-- SSD
+(4, 'interfaz', 'Interfaz', NULL, 'opcion', ARRAY['SATA III', 'NVMe PCIe 3.0', 'NVMe PCIe 4.0', 'NVMe PCIe 5.0'], TRUE, TRUE, TRUE, 1),
+(4, 'capacidad', 'Capacidad', 'GB', 'numero', NULL, TRUE, TRUE, TRUE, 2),
+(4, 'lectura', 'Lectura secuencial', 'MB/s', 'numero', NULL, FALSE, FALSE, TRUE, 3),
+-- Teclados
+(5, 'tipo_switch', 'Switch', NULL, 'texto', NULL, FALSE, FALSE, TRUE, 1),
+(5, 'iluminacion', 'Iluminación', NULL, 'opcion', ARRAY['Ninguna', 'Blanca', 'RGB'], FALSE, TRUE, TRUE, 2),
+-- Mouse
+(6, 'sensor', 'Sensor', NULL, 'texto', NULL, FALSE, FALSE, TRUE, 1),
+(6, 'dpi_max', 'DPI máximo', NULL, 'numero', NULL, FALSE, FALSE, TRUE, 2),
+-- Placas madre
+(7, 'socket', 'Socket', NULL, 'opcion', ARRAY['LGA1700', 'AM5', 'AM4'], TRUE, TRUE, TRUE, 1),
+(7, 'chipset', 'Chipset', NULL, 'texto', NULL, FALSE, TRUE, TRUE, 2),
+(7, 'tipo_memoria', 'Memoria', NULL, 'opcion', ARRAY['DDR4', 'DDR5'], TRUE, TRUE, TRUE, 3),
+(7, 'formato', 'Formato', NULL, 'opcion', ARRAY['ATX', 'Micro-ATX', 'Mini-ITX'], TRUE, TRUE, TRUE, 4),
+-- Fuentes de poder
+(8, 'potencia', 'Potencia', 'W', 'numero', NULL, TRUE, TRUE, TRUE, 1),
+(8, 'certificacion', 'Certificación', NULL, 'opcion', ARRAY['80 Plus', '80 Plus Bronze', '80 Plus Gold', '80 Plus Platinum'], FALSE, TRUE, TRUE, 2),
+-- Gabinetes
+(9, 'formatos_soportados', 'Placas soportadas', NULL, 'texto', NULL, TRUE, FALSE, TRUE, 1),
+(9, 'largo_max_gpu', 'Largo máximo de GPU', 'mm', 'numero', NULL, FALSE, FALSE, TRUE, 2);
+
+-- Especificaciones de cada variante
+INSERT INTO especificacion_producto (id_producto_detalle, id_atributo, nombre_atributo, valor_atributo, unidad_medida, orden)
+SELECT v.id_producto_detalle, a.id_atributo, a.nombre, v.valor, a.unidad_medida, a.orden
+FROM (VALUES
+    (1, 'socket', 'LGA1700'), (1, 'nucleos', '14'), (1, 'hilos', '20'), (1, 'tdp', '125'), (1, 'tipo_memoria', 'DDR5'),
+    (2, 'socket', 'LGA1700'), (2, 'nucleos', '16'), (2, 'hilos', '24'), (2, 'tdp', '125'), (2, 'tipo_memoria', 'DDR5'),
+    (3, 'socket', 'AM5'), (3, 'nucleos', '6'), (3, 'hilos', '12'), (3, 'tdp', '105'), (3, 'tipo_memoria', 'DDR5'),
+    (4, 'socket', 'AM5'), (4, 'nucleos', '8'), (4, 'hilos', '16'), (4, 'tdp', '105'), (4, 'tipo_memoria', 'DDR5'),
+    (5, 'memoria', '8GB GDDR6'), (5, 'cuda_cores', '3072'), (5, 'consumo', '115'), (5, 'largo', '301'),
+    (6, 'memoria', '12GB GDDR6X'), (6, 'cuda_cores', '5888'), (6, 'consumo', '200'), (6, 'largo', '336'),
+    (7, 'memoria', '8GB GDDR6'), (7, 'cuda_cores', '3072'), (7, 'consumo', '115'), (7, 'largo', '247'),
+    (8, 'memoria', '12GB GDDR6X'), (8, 'cuda_cores', '5888'), (8, 'consumo', '200'), (8, 'largo', '338'),
+    (9, 'tipo', 'DDR5'), (9, 'capacidad', '32'), (9, 'frecuencia', '5600'),
+    (10, 'tipo', 'DDR5'), (10, 'capacidad', '16'), (10, 'frecuencia', '5600'),
+    (11, 'interfaz', 'NVMe PCIe 4.0'), (11, 'capacidad', '1000'), (11, 'lectura', '7000'),
+    (12, 'interfaz', 'NVMe PCIe 4.0'), (12, 'capacidad', '2000'), (12, 'lectura', '7000'),
+    (13, 'tipo_switch', 'GX'), (13, 'iluminacion', 'RGB'),
+    (14, 'sensor', 'Focus Pro 30K'), (14, 'dpi_max', '30000'),
+    (15, 'socket', 'LGA1700'), (15, 'chipset', 'B760'), (15, 'tipo_memoria', 'DDR5'), (15, 'formato', 'ATX'),
+    (16, 'socket', 'AM5'), (16, 'chipset', 'B650'), (16, 'tipo_memoria', 'DDR5'), (16, 'formato', 'Micro-ATX'),
+    (17, 'potencia', '650'), (17, 'certificacion', '80 Plus Gold'),
+    (18, 'potencia', '850'), (18, 'certificacion', '80 Plus Gold'),
+    (19, 'potencia', '450'), (19, 'certificacion', '80 Plus Gold'),
+    (20, 'formatos_soportados', 'ATX, Micro-ATX, Mini-ITX'), (20, 'largo_max_gpu', '360'),
+    (21, 'formatos_soportados', 'Micro-ATX, Mini-ITX'), (21, 'largo_max_gpu', '330')
+) AS v(id_producto_detalle, nombre, valor)
+JOIN producto_detalle pd ON pd.id_producto_detalle = v.id_producto_detalle
+JOIN producto p ON p.id_producto = pd.id_producto
+JOIN atributo_especificacion a ON a.id_categoria = p.id_categoria AND a.nombre = v.nombre;
 
 -- ============================================================================
 -- 7. INVENTARIO
@@ -146,7 +233,35 @@ INSERT INTO inventario (id_producto_detalle, cantidad_disponible, cantidad_minim
 (11, 35, 8, 150, 'D-01-SSD'),
 (12, 20, 5, 100, 'D-01-SSD'),
 (13, 28, 5, 100, 'E-01-PER'),
-(14, 32, 5, 100, 'E-02-PER');
+(14, 32, 5, 100, 'E-02-PER'),
+(15, 14, 3, 50, 'A-03-MB'),
+(16, 11, 3, 50, 'A-03-MB'),
+(17, 20, 5, 80, 'F-01-PSU'),
+(18, 16, 5, 80, 'F-01-PSU'),
+(19, 9, 3, 50, 'F-01-PSU'),
+(20, 10, 2, 40, 'G-01-CASE'),
+(21, 7, 2, 40, 'G-01-CASE');
+
+-- ============================================================================
+-- ARMA TU PC
+-- ============================================================================
+
+INSERT INTO ranura_armado (codigo, nombre, id_categoria, obligatoria, cantidad_maxima, orden) VALUES
+('cpu', 'Procesador', 1, TRUE, 1, 1),
+('placa_madre', 'Placa madre', 7, TRUE, 1, 2),
+('ram', 'Memoria RAM', 3, TRUE, 4, 3),
+('gpu', 'Tarjeta gráfica', 2, FALSE, 1, 4),
+('almacenamiento', 'Almacenamiento', 4, TRUE, 4, 5),
+('fuente', 'Fuente de poder', 8, TRUE, 1, 6),
+('gabinete', 'Gabinete', 9, TRUE, 1, 7);
+
+INSERT INTO regla_compatibilidad (codigo, descripcion, ranura_origen, atributos_origen, ranura_destino, atributo_destino, operador, margen_porcentaje, severidad) VALUES
+('socket_cpu_placa', 'El socket del procesador debe coincidir con el de la placa madre', 'cpu', ARRAY['socket'], 'placa_madre', 'socket', 'igual', 0, 'error'),
+('memoria_ram_placa', 'La generación de la memoria RAM debe coincidir con la de la placa madre', 'ram', ARRAY['tipo'], 'placa_madre', 'tipo_memoria', 'igual', 0, 'error'),
+('memoria_ram_cpu', 'El procesador debe soportar la generación de la memoria RAM', 'ram', ARRAY['tipo'], 'cpu', 'tipo_memoria', 'igual', 0, 'advertencia'),
+('formato_placa_gabinete', 'El gabinete debe admitir el formato de la placa madre', 'placa_madre', ARRAY['formato'], 'gabinete', 'formatos_soportados', 'incluido', 0, 'error'),
+('largo_gpu_gabinete', 'La tarjeta gráfica debe caber en el gabinete', 'gpu', ARRAY['largo'], 'gabinete', 'largo_max_gpu', 'menor_igual', 0, 'error'),
+('potencia_fuente', 'La fuente debe cubrir el consumo del procesador y la tarjeta gráfica con 40% de margen', NULL, ARRAY['tdp', 'consumo'], 'fuente', 'potencia', 'suma_menor_igual', 40, 'error');
 
 -- ============================================================================
 -- 8. DESCUENTOS
@@ -246,6 +361,7 @@ INSERT INTO configuracion_sistema (clave, valor, tipo, descripcion, categoria) V
 ('site_description', 'Tu tienda de tecnología de confianza', 'string', 'Descripción del sitio', 'general'),
 ('support_email', 'soporte@kronostech.com', 'string', 'Email de soporte', 'general'),
 ('contact_phone', '+51 999 999 999', 'string', 'Teléfono de contacto', 'general'),
+('site_url', 'http://localhost:5173', 'string', 'URL pública de la tienda (enlaces en emails)', 'general'),
 
 -- E-commerce
 ('currency', 'PEN', 'string', 'Moneda del sistema', 'ecommerce'),
@@ -253,6 +369,11 @@ INSERT INTO configuracion_sistema (clave, valor, tipo, descripcion, categoria) V
 ('free_shipping_threshold', '100', 'number', 'Umbral para envío gratis (S/.)', 'ecommerce'),
 ('low_stock_threshold', '10', 'number', 'Umbral de alerta de stock bajo', 'ecommerce'),
 
+-- Catálogo
+('facet_spec_attributes', '["socket", "nucleos", "memoria", "tipo", "interfaz"]', 'json', 'Atributos de especificación que se muestran como facetas en el listado de productos', 'catalogo'),
+('facet_price_ranges', '[500, 1000, 2000, 5000]', 'json', 'Límites de los tramos de precio de la faceta de precios (S/.)', 'catalogo'),
+('compare_max_products', '4', 'number', 'Máximo de productos que se pueden comparar a la vez', 'catalogo'),
+
 -- Envío
 ('default_shipping_cost', '15', 'number', 'Costo de envío estándar (S/.)', 'envio'),
 ('express_shipping_cost', '35', 'number', 'Costo de envío express (S/.)', 'envio'),
@@ -262,6 +383,35 @@ INSERT INTO configuracion_sistema (clave, valor, tipo, descripcion, categoria) V
 ('session_timeout', '24', 'number', 'Duración de sesión en horas', 'seguridad'),
 ('max_login_attempts', '5', 'number', 'Máximo intentos de login antes de bloqueo', 'seguridad'),
 ('password_min_length', '6', 'number', 'Longitud mínima de contraseña', 'seguridad'),
+('password_require_uppercase', 'true', 'boolean', 'La contraseña debe incluir mayúsculas', 'seguridad'),
+('password_require_lowercase', 'true', 'boolean', 'La contraseña debe incluir minúsculas', 'seguridad'),
+('password_require_number', 'true', 'boolean', 'La contraseña debe incluir números', 'seguridad'),
+('password_require_symbol', 'false', 'boolean', 'La contraseña debe incluir símbolos', 'seguridad'),
+('password_reset_ttl_minutes', '30', 'number', 'Vigencia del enlace de recuperación de contraseña (minutos)', 'seguridad'),
+('password_reset_max_requests', '3', 'number', 'Máximo de solicitudes de recuperación por cuenta por hora', 'seguridad'),
+('require_2fa_admin', 'false', 'boolean', 'Exigir autenticación en dos pasos (TOTP) a administradores', 'seguridad'),
+('password_hash_memory_kib', '19456', 'number', 'Memoria de Argon2id para hash de contraseñas (KiB)', 'seguridad'),
+('password_hash_iterations', '2', 'number', 'Iteraciones de Argon2id para hash de contraseñas', 'seguridad'),
+('password_hash_parallelism', '1', 'number', 'Paralelismo de Argon2id para hash de contraseñas', 'seguridad'),
+('account_deletion_grace_days', '30', 'number', 'Días de gracia antes de anonimizar una cuenta cuya eliminación fue solicitada', 'seguridad'),
+('impersonation_max_minutes', '30', 'number', 'Vigencia máxima de un token de suplantación de cliente (minutos)', 'seguridad'),
+('trusted_proxies', '127.0.0.1,::1', 'string', 'Proxies confiables (IPs o CIDR separados por coma) cuyo X-Forwarded-For se acepta', 'seguridad'),
+('rate_limit_login', '{"por_ip": 10, "periodo_segundos": 60}', 'json', 'Límite de intentos de login', 'seguridad'),
+('rate_limit_registro', '{"por_ip": 5, "periodo_segundos": 3600}', 'json', 'Límite de registros de cuenta', 'seguridad'),
+('rate_limit_valoraciones', '{"por_ip": 20, "por_usuario": 5, "periodo_segundos": 3600}', 'json', 'Límite de valoraciones publicadas', 'seguridad'),
+('rate_limit_checkout', '{"por_ip": 20, "por_usuario": 10, "periodo_segundos": 600}', 'json', 'Límite de pedidos procesados', 'seguridad'),
+('audit_retention_days', '365', 'number', 'Días que se conservan los logs de auditoría en BD antes de archivarlos (0 = sin límite, mínimo 90; solo super_admin)', 'seguridad'),
+('audit_archive_dir', 'archivos/auditoria', 'string', 'Directorio donde se guardan los logs archivados (JSONL comprimido), relativo a AUDIT_ARCHIVE_ROOT (solo super_admin)', 'seguridad'),
+('siem_forwarding_enabled', 'false', 'boolean', 'Reenviar los logs de auditoría a un SIEM', 'seguridad'),
+('siem_format', 'syslog', 'string', 'Formato del reenvío al SIEM: syslog (RFC 5424) o jsonl', 'seguridad'),
+('siem_destination', 'udp:127.0.0.1:514', 'string', 'Destino del reenvío (solo super_admin): file:archivo dentro de SIEM_FILE_DIR, udp:127.0.0.1:puerto, tcp:127.0.0.1:puerto o unix:/ruta', 'seguridad'),
+('anomaly_detection_enabled', 'true', 'boolean', 'Detectar anomalías de seguridad en los logs y alertar a los administradores', 'seguridad'),
+('anomaly_window_minutes', '15', 'number', 'Ventana (minutos) en la que se cuentan los logins fallidos', 'seguridad'),
+('anomaly_failed_logins_per_ip', '10', 'number', 'Logins fallidos desde una IP dentro de la ventana que generan una alerta', 'seguridad'),
+('anomaly_accounts_per_ip', '5', 'number', 'Cuentas distintas con logins fallidos desde una IP dentro de la ventana (credential stuffing)', 'seguridad'),
+('anomaly_coupon_max_percentage', '50', 'number', 'Porcentaje de descuento a partir del cual un cupón genera una alerta', 'seguridad'),
+('anomaly_coupon_max_amount', '500', 'number', 'Monto fijo de descuento a partir del cual un cupón genera una alerta', 'seguridad'),
+('anomaly_coupon_mass_assignment', '100', 'number', 'Usuarios asignados a un cupón de una vez que generan una alerta', 'seguridad'),
 
 -- Funcionalidades
 ('allow_guest_checkout', 'true', 'boolean', 'Permitir compra sin registro', 'funcionalidades'),
@@ -269,6 +419,13 @@ INSERT INTO configuracion_sistema (clave, valor, tipo, descripcion, categoria) V
 ('enable_reviews', 'true', 'boolean', 'Habilitar valoraciones', 'funcionalidades'),
 ('enable_wishlist', 'true', 'boolean', 'Habilitar lista de deseos', 'funcionalidades'),
 ('enable_coupons', 'true', 'boolean', 'Habilitar cupones', 'funcionalidades'),
+('feature_flag_rules', '{"enable_reviews": {"porcentaje": 100, "roles": []}, "enable_wishlist": {"porcentaje": 100, "roles": []}, "enable_coupons": {"porcentaje": 100, "roles": []}, "allow_guest_checkout": {"porcentaje": 100, "roles": []}}', 'json', 'Despliegue de cada funcionalidad: porcentaje de usuarios (0-100) y roles a los que se limita (cliente, administrador, super_admin, invitado; vacío = todos)', 'funcionalidades'),
+
+-- Mantenimiento
+('maintenance_mode', 'off', 'string', 'Modo mantenimiento: off, read_only (sin cambios en carrito ni compras) o closed (tienda cerrada)', 'mantenimiento'),
+('maintenance_message', 'Estamos realizando tareas de mantenimiento. Vuelve en unos minutos.', 'string', 'Mensaje que se muestra durante el mantenimiento', 'mantenimiento'),
+('maintenance_start', '', 'string', 'Inicio programado del mantenimiento (RFC 3339, vacío = inmediato)', 'mantenimiento'),
+('maintenance_end', '', 'string', 'Fin programado del mantenimiento (RFC 3339, vacío = hasta desactivarlo)', 'mantenimiento'),
 
 -- Email
 ('email_enabled', 'true', 'boolean', 'Habilitar envío de emails', 'email'),
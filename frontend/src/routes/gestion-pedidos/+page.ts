@@ -1,4 +1,8 @@
 import type { PageLoad } from './$types';
+import { getToken } from '$lib/services/auth';
+
+// El token de administrador está en localStorage: cargar solo en el navegador
+export const ssr = false;
 
 export interface Order {
     id_venta: number;
@@ -32,7 +36,9 @@ export const load: PageLoad = async ({ fetch, url }) => {
     if (fecha_fin) params.append('fecha_fin', fecha_fin);
 
     try {
-        const response = await fetch(`http://localhost:3000/api/ventas?${params.toString()}`);
+        const response = await fetch(`http://localhost:3000/api/ventas?${params.toString()}`, {
+            headers: { Authorization: `Bearer ${getToken()}` }
+        });
         if (!response.ok) {
             throw new Error('Failed to fetch orders');
         }
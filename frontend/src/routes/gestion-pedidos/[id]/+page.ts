@@ -1,4 +1,8 @@
 import type { PageLoad } from './$types';
+import { getToken } from '$lib/services/auth';
+
+// El token de administrador está en localStorage: cargar solo en el navegador
+export const ssr = false;
 
 export interface OrderDetail {
     id_venta: number;
@@ -68,7 +72,9 @@ export interface OrderComplete {
 
 export const load: PageLoad = async ({ params, fetch }) => {
     try {
-        const response = await fetch(`http://localhost:3000/api/ventas/${params.id}`);
+        const response = await fetch(`http://localhost:3000/api/ventas/${params.id}`, {
+            headers: { Authorization: `Bearer ${getToken()}` }
+        });
 
         if (!response.ok) {
             throw new Error('Failed to fetch order details');